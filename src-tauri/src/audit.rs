@@ -0,0 +1,172 @@
+// Chain-of-custody log of analyst actions
+// Every command run against a project is recorded in the project's auxiliary database
+
+use crate::io::atomic_write_sync;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+
+/// Name of the sled tree holding audit entries
+const AUDIT_TREE: &str = "audit_log";
+
+/// One recorded command invocation
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub command: String,
+    pub args: serde_json::Value,
+    /// "ok", or the error the command returned
+    pub result_summary: String,
+    pub duration_ms: u64,
+}
+
+/// Append-only audit log backed by a sled tree
+/// Keys are sled-generated IDs, so iteration order is the order commands were logged
+pub struct AuditLogger {
+    db: sled::Db,
+    tree: sled::Tree,
+}
+
+impl AuditLogger {
+    pub fn open(db: &sled::Db) -> anyhow::Result<Self> {
+        Ok(Self {
+            db: db.clone(),
+            tree: db.open_tree(AUDIT_TREE)?,
+        })
+    }
+
+    /// Record a command invocation
+    pub fn log_command(
+        &self,
+        command_name: &str,
+        args: &serde_json::Value,
+        result: &str,
+        duration_ms: u64,
+    ) -> anyhow::Result<()> {
+        let entry = AuditEntry {
+            timestamp: Utc::now(),
+            command: command_name.to_string(),
+            args: args.clone(),
+            result_summary: result.to_string(),
+            duration_ms,
+        };
+        let key = self.db.generate_id()?.to_be_bytes();
+        self.tree.insert(key, serde_json::to_vec(&entry)?)?;
+        self.tree.flush()?;
+        Ok(())
+    }
+
+    /// The most recent entries, newest first
+    pub fn recent(&self, limit: usize) -> anyhow::Result<Vec<AuditEntry>> {
+        self.tree
+            .iter()
+            .rev()
+            .take(limit)
+            .map(|item| Ok(serde_json::from_slice(&item?.1)?))
+            .collect()
+    }
+
+    /// Every entry, oldest first
+    pub fn entries(&self) -> anyhow::Result<Vec<AuditEntry>> {
+        self.tree
+            .iter()
+            .map(|item| Ok(serde_json::from_slice(&item?.1)?))
+            .collect()
+    }
+
+    /// Add entries from another project's log, skipping ones already recorded
+    /// Nothing is ever dropped; the log is rewritten in one batch so it stays in timestamp
+    /// order. Returns the number of entries added
+    pub fn merge(&self, entries: Vec<AuditEntry>) -> anyhow::Result<usize> {
+        let mut merged = self.entries()?;
+        let before = merged.len();
+        for entry in entries {
+            if !merged.contains(&entry) {
+                merged.push(entry);
+            }
+        }
+        let added = merged.len() - before;
+        if added == 0 {
+            return Ok(0);
+        }
+        merged.sort_by_key(|entry| entry.timestamp);
+
+        let mut batch = sled::Batch::default();
+        for key in self.tree.iter().keys() {
+            batch.remove(key?);
+        }
+        for entry in &merged {
+            let key = self.db.generate_id()?.to_be_bytes();
+            batch.insert(&key, serde_json::to_vec(entry)?);
+        }
+        self.tree.apply_batch(batch)?;
+        self.tree.flush()?;
+        Ok(added)
+    }
+
+    /// Write the whole log to a CSV file, oldest first; returns the number of entries
+    pub fn export_csv(&self, output_path: &Path) -> anyhow::Result<u64> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(["timestamp", "command", "args", "result", "duration_ms"])?;
+
+        let mut count = 0u64;
+        for item in self.tree.iter() {
+            let entry: AuditEntry = serde_json::from_slice(&item?.1)?;
+            writer.write_record([
+                entry.timestamp.to_rfc3339(),
+                entry.command,
+                entry.args.to_string(),
+                entry.result_summary,
+                entry.duration_ms.to_string(),
+            ])?;
+            count += 1;
+        }
+
+        atomic_write_sync(output_path, &writer.into_inner()?)?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_entries_are_listed_newest_first_and_exported() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = sled::open(temp_dir.path().join("aux")).unwrap();
+        let log = AuditLogger::open(&db).unwrap();
+
+        log.log_command(
+            "search_database",
+            &serde_json::json!({ "query": "invoice" }),
+            "ok",
+            12,
+        )
+        .unwrap();
+        log.log_command(
+            "export_files",
+            &serde_json::json!({ "outDir": "/cases/out" }),
+            "error: No database open",
+            3,
+        )
+        .unwrap();
+        log.log_command("gc_index", &serde_json::json!({}), "ok", 40)
+            .unwrap();
+
+        let recent = log.recent(2).unwrap();
+        let commands: Vec<_> = recent.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, vec!["gc_index", "export_files"]);
+        assert_eq!(recent[1].result_summary, "error: No database open");
+
+        let csv_path = temp_dir.path().join("audit.csv");
+        assert_eq!(log.export_csv(&csv_path).unwrap(), 3);
+        let mut reader = csv::Reader::from_path(&csv_path).unwrap();
+        let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(&rows[0][1], "search_database");
+        assert_eq!(&rows[0][2], r#"{"query":"invoice"}"#);
+        assert_eq!(&rows[2][4], "40");
+    }
+}
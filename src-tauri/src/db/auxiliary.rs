@@ -1,10 +1,16 @@
+use crate::audit::{AuditEntry, AuditLogger};
+use crate::index::{ArchiveSettings, PreviewConfig};
+use crate::io::atomic_write_sync;
+use chrono::{DateTime, Utc};
 use image::EncodableLayout;
 use sled::IVec;
-use std::io::Read;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
 
 pub struct AuxiliaryProjectDb {
     db: sled::Db,
+    /// Set on the stand-in used while another instance holds the project's db
+    detached: bool,
 }
 
 #[derive(serde::Serialize)]
@@ -15,61 +21,627 @@ pub struct Group {
     pub content: Vec<(String, String)>,
 }
 
+/// Case details recorded by the examiner for a project
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaseMetadata {
+    pub case_number: String,
+    pub case_name: String,
+    pub examiner_name: String,
+    pub examiner_agency: String,
+    pub acquisition_date: DateTime<Utc>,
+    pub notes: String,
+    pub evidence_description: String,
+}
+
+const CASE_METADATA_KEY: &str = "case_metadata";
+const ARCHIVE_SETTINGS_KEY: &str = "archive_settings";
+const PREVIEW_CONFIG_KEY: &str = "preview_config";
+
+/// Sled tree holding document tags, keyed `<tag>\0<doc id>` so a tag's documents share a prefix
+const TAGS_TREE: &str = "doc_tags";
+
+/// Bumped whenever the backup layout changes
+const BACKUP_VERSION: u32 = 2;
+
+/// How imported data is combined with what the project already holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    /// Replace all existing groups, tags and case metadata with the backup
+    /// The audit log is only ever added to, whatever the strategy
+    Overwrite,
+    /// Add everything from the backup; backup values win on conflicts
+    Merge,
+    /// Add only what doesn't exist yet; existing values win on conflicts
+    SkipExisting,
+}
+
+/// Entity counts written by an export
+#[derive(Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportStats {
+    pub groups: usize,
+    pub group_entries: usize,
+    pub tags: usize,
+    pub case_metadata: usize,
+    pub audit_entries: usize,
+}
+
+/// Entity counts applied by an import
+#[derive(Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportStats {
+    pub groups: usize,
+    pub group_entries: usize,
+    pub tags: usize,
+    pub case_metadata: usize,
+    /// Audit entries not already in this project's log
+    pub audit_entries: usize,
+    /// Entries left alone because they already existed (SkipExisting only)
+    pub skipped: usize,
+}
+
+/// Portable JSON snapshot of the auxiliary database
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuxiliaryBackup {
+    version: u32,
+    exported_at: DateTime<Utc>,
+    case_metadata: Option<CaseMetadata>,
+    groups: Vec<GroupBackup>,
+    /// Absent from version 1 backups
+    #[serde(default)]
+    tags: Vec<TagBackup>,
+    /// Oldest first; absent from version 1 backups
+    #[serde(default)]
+    audit_log: Vec<AuditEntry>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupBackup {
+    /// Raw sled tree name, kept as-is so name/color round-trip exactly
+    tree: String,
+    entries: Vec<GroupEntryBackup>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TagBackup {
+    tag: String,
+    doc_id: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupEntryBackup {
+    /// Hex-encoded sled key
+    key: String,
+    value: (String, String),
+}
+
 impl AuxiliaryProjectDb {
     pub fn init(path: PathBuf) -> anyhow::Result<Self> {
         let db = sled::open(path)?;
-        Ok(AuxiliaryProjectDb { db })
+        Ok(AuxiliaryProjectDb {
+            db,
+            detached: false,
+        })
+    }
+
+    /// Empty stand-in for a project db that another instance holds open
+    /// Reads find nothing and writes fail, rather than landing in a copy that is thrown away
+    pub fn detached() -> anyhow::Result<Self> {
+        let db = sled::Config::new().temporary(true).open()?;
+        Ok(AuxiliaryProjectDb { db, detached: true })
+    }
+
+    /// Fail on the detached stand-in, before anything is written to it
+    fn ensure_writable(&self) -> anyhow::Result<()> {
+        if self.detached {
+            anyhow::bail!(
+                "Groups, tags and case details are open in another instance and can't be changed here"
+            );
+        }
+        Ok(())
+    }
+
+    /// Sled tree name for a group; dashes in the name are stored as `#` so the
+    /// first dash after the prefix always separates the name from the color
+    fn group_tree_name(name: &str, color: &str) -> String {
+        format!("g-{}-{}", name.replace("-", "#"), color)
     }
 
     pub fn create_group(&self, name: String, color: String) -> anyhow::Result<()> {
+        self.ensure_writable()?;
         let tree = self
             .db
-            .open_tree(format!("g-{}-{}", name.replace("-", "#"), color).as_bytes())?;
+            .open_tree(Self::group_tree_name(&name, &color).as_bytes())?;
         tree.flush().expect("failed to write group to disk");
         println!("Group created {name}");
         Ok(())
     }
 
     pub fn delete_group(&self, name: String, color: String) -> anyhow::Result<()> {
+        self.ensure_writable()?;
         self.db
-            .drop_tree(format!("g-{}-{}", name.replace("-", "#"), color).as_bytes())?;
+            .drop_tree(Self::group_tree_name(&name, &color).as_bytes())?;
         println!("Group dropped {name}");
         Ok(())
     }
 
-    fn get_trees(&self) -> Vec<String> {
-        let names = self.db.tree_names();
-        let groups = names
-            .iter()
-            .map(|x| {
-                let mut str = String::new();
-                x.as_bytes().read_to_string(&mut str).unwrap();
-                str.replace("#", "-")
-            })
-            .collect::<Vec<String>>();
-        groups
+    /// Open an existing group's tree without creating it
+    fn existing_group(&self, name: &str, color: &str) -> anyhow::Result<sled::Tree> {
+        let tree_name = Self::group_tree_name(name, color);
+        if !self
+            .db
+            .tree_names()
             .iter()
-            .filter(|x| x != &&"__sled__default".to_string())
-            .map(|x| x.clone())
-            .collect::<Vec<String>>()
+            .any(|existing| existing == tree_name.as_bytes())
+        {
+            anyhow::bail!("Group {name} does not exist");
+        }
+        Ok(self.db.open_tree(tree_name.as_bytes())?)
+    }
+
+    /// Add a file to a group; adding the same file id again replaces its path
+    pub fn add_file_to_group(
+        &self,
+        group_name: String,
+        group_color: String,
+        file_id: String,
+        file_path: String,
+    ) -> anyhow::Result<()> {
+        self.ensure_writable()?;
+        let tree = self.existing_group(&group_name, &group_color)?;
+        tree.insert(
+            file_id.as_bytes(),
+            bincode::serialize(&(file_id.clone(), file_path))?,
+        )?;
+        tree.flush()?;
+        Ok(())
+    }
+
+    pub fn remove_file_from_group(
+        &self,
+        group_name: String,
+        group_color: String,
+        file_id: String,
+    ) -> anyhow::Result<()> {
+        self.ensure_writable()?;
+        let tree = self.existing_group(&group_name, &group_color)?;
+        tree.remove(file_id.as_bytes())?;
+        tree.flush()?;
+        Ok(())
     }
 
-    pub fn get_groups(&self) -> Vec<Group> {
+    pub fn get_groups(&self) -> anyhow::Result<Vec<Group>> {
         let mut result = Vec::new();
-        for grp in self.get_trees().iter().filter(|x| x.starts_with("g-")) {
-            let entries = self.db.open_tree(grp).expect("failed to open tree");
-            let parts = grp.split("--").collect::<Vec<&str>>();
+        for tree_name in self.group_tree_names() {
+            let raw = String::from_utf8_lossy(&tree_name);
+            let Some((name, color)) = raw["g-".len()..].split_once('-') else {
+                continue;
+            };
+
+            let mut content = Vec::new();
+            for value in self.db.open_tree(&tree_name)?.iter().values() {
+                content.push(bincode::deserialize::<(String, String)>(value?.as_bytes())?);
+            }
+
             result.push(Group {
-                name: parts[0].to_string().replace("g-", ""),
-                color: parts[1].to_string(),
-                content: entries
-                    .iter()
-                    .values()
-                    .map(|x| x.unwrap())
-                    .map(|x| bincode::deserialize::<(String, String)>(x.as_bytes()).unwrap())
-                    .collect(),
+                name: name.replace("#", "-"),
+                color: color.to_string(),
+                content,
+            })
+        }
+        Ok(result)
+    }
+
+    pub fn set_case_metadata(&self, metadata: CaseMetadata) -> anyhow::Result<()> {
+        self.ensure_writable()?;
+        let bytes = bincode::serialize(&metadata)?;
+        self.db.insert(CASE_METADATA_KEY, bytes)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Raw names of the sled trees that hold groups
+    fn group_tree_names(&self) -> Vec<IVec> {
+        self.db
+            .tree_names()
+            .into_iter()
+            .filter(|name| name.starts_with(b"g-"))
+            .collect()
+    }
+
+    /// Write groups, tags, case metadata and the audit log to a single JSON file
+    pub fn export_auxiliary(&self, output_path: &Path) -> anyhow::Result<ExportStats> {
+        let mut stats = ExportStats::default();
+        let mut groups = Vec::new();
+
+        for tree_name in self.group_tree_names() {
+            let tree = self.db.open_tree(&tree_name)?;
+            let mut entries = Vec::new();
+            for item in tree.iter() {
+                let (key, value) = item?;
+                entries.push(GroupEntryBackup {
+                    key: hex::encode(key),
+                    value: bincode::deserialize(value.as_bytes())?,
+                });
+            }
+            stats.group_entries += entries.len();
+            groups.push(GroupBackup {
+                tree: String::from_utf8_lossy(&tree_name).to_string(),
+                entries,
+            });
+        }
+        stats.groups = groups.len();
+
+        let mut tags = Vec::new();
+        for key in self.db.open_tree(TAGS_TREE)?.iter().keys() {
+            let key = key?;
+            if let Some(end) = key.iter().position(|&b| b == 0) {
+                tags.push(TagBackup {
+                    tag: String::from_utf8_lossy(&key[..end]).to_string(),
+                    doc_id: String::from_utf8_lossy(&key[end + 1..]).to_string(),
+                });
+            }
+        }
+        stats.tags = tags.len();
+
+        let audit_log = self.audit_log()?.entries()?;
+        stats.audit_entries = audit_log.len();
+
+        let case_metadata = self.get_case_metadata()?;
+        stats.case_metadata = usize::from(case_metadata.is_some());
+
+        let backup = AuxiliaryBackup {
+            version: BACKUP_VERSION,
+            exported_at: Utc::now(),
+            case_metadata,
+            groups,
+            tags,
+            audit_log,
+        };
+        atomic_write_sync(output_path, &serde_json::to_vec_pretty(&backup)?)?;
+
+        Ok(stats)
+    }
+
+    /// Load a backup written by export_auxiliary
+    pub fn import_auxiliary(
+        &self,
+        input_path: &Path,
+        merge_strategy: MergeStrategy,
+    ) -> anyhow::Result<ImportStats> {
+        self.ensure_writable()?;
+        let backup: AuxiliaryBackup = serde_json::from_slice(&std::fs::read(input_path)?)?;
+        if backup.version > BACKUP_VERSION {
+            anyhow::bail!(
+                "Backup version {} is newer than supported version {}",
+                backup.version,
+                BACKUP_VERSION
+            );
+        }
+
+        if merge_strategy == MergeStrategy::Overwrite {
+            for tree_name in self.group_tree_names() {
+                self.db.drop_tree(tree_name)?;
+            }
+            self.db.drop_tree(TAGS_TREE)?;
+            self.db.remove(CASE_METADATA_KEY)?;
+        }
+
+        let mut stats = ImportStats::default();
+
+        for group in backup.groups {
+            let tree = self.db.open_tree(group.tree.as_bytes())?;
+            stats.groups += 1;
+            for entry in group.entries {
+                let key = hex::decode(&entry.key)?;
+                if merge_strategy == MergeStrategy::SkipExisting && tree.contains_key(&key)? {
+                    stats.skipped += 1;
+                    continue;
+                }
+                tree.insert(key, bincode::serialize(&entry.value)?)?;
+                stats.group_entries += 1;
+            }
+            tree.flush()?;
+        }
+
+        let tags_tree = self.db.open_tree(TAGS_TREE)?;
+        for entry in backup.tags {
+            let key = Self::tag_key(&entry.tag, &entry.doc_id);
+            if tags_tree.contains_key(&key)? {
+                if merge_strategy == MergeStrategy::SkipExisting {
+                    stats.skipped += 1;
+                }
+                continue;
+            }
+            tags_tree.insert(key, IVec::default())?;
+            stats.tags += 1;
+        }
+        tags_tree.flush()?;
+
+        stats.audit_entries = self.audit_log()?.merge(backup.audit_log)?;
+
+        if let Some(metadata) = backup.case_metadata {
+            if merge_strategy == MergeStrategy::SkipExisting && self.get_case_metadata()?.is_some()
+            {
+                stats.skipped += 1;
+            } else {
+                self.set_case_metadata(metadata)?;
+                stats.case_metadata = 1;
+            }
+        }
+
+        self.db.flush()?;
+        Ok(stats)
+    }
+
+    /// Tag every document with every tag; returns the number of new (document, tag) pairs
+    pub fn add_tags_bulk(&self, doc_ids: Vec<String>, tags: Vec<String>) -> anyhow::Result<usize> {
+        self.ensure_writable()?;
+        let mut keys = BTreeSet::new();
+        for tag in tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+        {
+            if tag.contains('\0') {
+                anyhow::bail!("Tag contains a NUL character: {:?}", tag);
+            }
+            keys.extend(doc_ids.iter().map(|doc_id| Self::tag_key(tag, doc_id)));
+        }
+
+        let tree = self.db.open_tree(TAGS_TREE)?;
+        let mut batch = sled::Batch::default();
+        let mut added = 0;
+        for key in keys {
+            if !tree.contains_key(&key)? {
+                batch.insert(key, IVec::default());
+                added += 1;
+            }
+        }
+
+        tree.apply_batch(batch)?;
+        tree.flush()?;
+        Ok(added)
+    }
+
+    /// IDs of the documents carrying a tag
+    pub fn docs_with_tag(&self, tag: &str) -> anyhow::Result<HashSet<String>> {
+        let prefix = Self::tag_key(tag, "");
+        self.db
+            .open_tree(TAGS_TREE)?
+            .scan_prefix(&prefix)
+            .keys()
+            .map(|key| Ok(String::from_utf8_lossy(&key?[prefix.len()..]).to_string()))
+            .collect()
+    }
+
+    /// Every tag in use, sorted
+    pub fn get_all_tags(&self) -> anyhow::Result<Vec<String>> {
+        let mut tags = BTreeSet::new();
+        for key in self.db.open_tree(TAGS_TREE)?.iter().keys() {
+            let key = key?;
+            if let Some(end) = key.iter().position(|&b| b == 0) {
+                tags.insert(String::from_utf8_lossy(&key[..end]).to_string());
+            }
+        }
+        Ok(tags.into_iter().collect())
+    }
+
+    fn tag_key(tag: &str, doc_id: &str) -> Vec<u8> {
+        format!("{}\0{}", tag, doc_id).into_bytes()
+    }
+
+    /// Log of commands run against this project
+    pub fn audit_log(&self) -> anyhow::Result<AuditLogger> {
+        AuditLogger::open(&self.db)
+    }
+
+    pub fn get_case_metadata(&self) -> anyhow::Result<Option<CaseMetadata>> {
+        let Some(bytes) = self.db.get(CASE_METADATA_KEY)? else {
+            return Ok(None);
+        };
+        Ok(Some(bincode::deserialize(bytes.as_bytes())?))
+    }
+
+    /// Remember the project's archive settings so reopening it restores them
+    pub fn save_archive_settings(&self, settings: &ArchiveSettings) -> anyhow::Result<()> {
+        self.ensure_writable()?;
+        self.db
+            .insert(ARCHIVE_SETTINGS_KEY, bincode::serialize(settings)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    pub fn load_archive_settings(&self) -> Option<ArchiveSettings> {
+        self.db
+            .get(ARCHIVE_SETTINGS_KEY)
+            .ok()
+            .flatten()
+            .and_then(|x| bincode::deserialize::<ArchiveSettings>(x.as_bytes()).ok())
+    }
+
+    /// Remember the project's image preview settings so reopening it restores them
+    pub fn save_preview_config(&self, config: &PreviewConfig) -> anyhow::Result<()> {
+        self.ensure_writable()?;
+        self.db
+            .insert(PREVIEW_CONFIG_KEY, bincode::serialize(config)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    pub fn load_preview_config(&self) -> Option<PreviewConfig> {
+        self.db
+            .get(PREVIEW_CONFIG_KEY)
+            .ok()
+            .flatten()
+            .and_then(|x| bincode::deserialize::<PreviewConfig>(x.as_bytes()).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn open_with_group(dir: &Path, entry: (&str, &str)) -> AuxiliaryProjectDb {
+        let db = AuxiliaryProjectDb::init(dir.to_path_buf()).unwrap();
+        db.create_group("suspects".to_string(), "#ff0000".to_string())
+            .unwrap();
+        let tree = db.db.open_tree(&db.group_tree_names()[0]).unwrap();
+        let value = (entry.0.to_string(), entry.1.to_string());
+        tree.insert(entry.0, bincode::serialize(&value).unwrap())
+            .unwrap();
+        db
+    }
+
+    fn group_values(db: &AuxiliaryProjectDb) -> Vec<(String, String)> {
+        db.group_tree_names()
+            .into_iter()
+            .flat_map(|name| db.db.open_tree(name).unwrap().iter().values())
+            .map(|value| bincode::deserialize(value.unwrap().as_bytes()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_export_import_round_trip_with_merge_strategies() {
+        let temp_dir = TempDir::new().unwrap();
+        let backup_path = temp_dir.path().join("case-notes.json");
+
+        let source = open_with_group(
+            &temp_dir.path().join("source"),
+            ("/evidence/a.db", "note a"),
+        );
+        source
+            .set_case_metadata(CaseMetadata {
+                case_number: "2024-017".to_string(),
+                case_name: "Harbor".to_string(),
+                examiner_name: "J. Doe".to_string(),
+                examiner_agency: "Lab".to_string(),
+                acquisition_date: Utc::now(),
+                notes: String::new(),
+                evidence_description: String::new(),
             })
+            .unwrap();
+        source
+            .add_tags_bulk(vec!["doc-a".to_string()], vec!["suspect".to_string()])
+            .unwrap();
+        source
+            .audit_log()
+            .unwrap()
+            .log_command("tag_documents", &serde_json::json!({}), "ok", 2)
+            .unwrap();
+        let exported = source.export_auxiliary(&backup_path).unwrap();
+        assert_eq!(exported.groups, 1);
+        assert_eq!(exported.group_entries, 1);
+        assert_eq!(exported.tags, 1);
+        assert_eq!(exported.case_metadata, 1);
+        assert_eq!(exported.audit_entries, 1);
+
+        let target = open_with_group(&temp_dir.path().join("target"), ("/evidence/a.db", "mine"));
+        target
+            .add_tags_bulk(vec!["doc-b".to_string()], vec!["stale".to_string()])
+            .unwrap();
+        target
+            .audit_log()
+            .unwrap()
+            .log_command("search_database", &serde_json::json!({}), "ok", 5)
+            .unwrap();
+        let stats = target
+            .import_auxiliary(&backup_path, MergeStrategy::SkipExisting)
+            .unwrap();
+        assert_eq!(stats.skipped, 1);
+        assert_eq!(stats.tags, 1);
+        assert_eq!(stats.audit_entries, 1);
+        assert_eq!(group_values(&target)[0].1, "mine");
+        assert_eq!(target.get_all_tags().unwrap(), vec!["stale", "suspect"]);
+        assert_eq!(
+            target.get_case_metadata().unwrap().unwrap().case_number,
+            "2024-017"
+        );
+
+        let stats = target
+            .import_auxiliary(&backup_path, MergeStrategy::Overwrite)
+            .unwrap();
+        // The audit log keeps both projects' entries, oldest first, without duplicates
+        assert_eq!(stats.audit_entries, 0);
+        let commands: Vec<String> = target
+            .audit_log()
+            .unwrap()
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.command)
+            .collect();
+        assert_eq!(commands, vec!["tag_documents", "search_database"]);
+        assert_eq!(
+            group_values(&target),
+            vec![("/evidence/a.db".to_string(), "note a".to_string())]
+        );
+        assert_eq!(target.get_all_tags().unwrap(), vec!["suspect"]);
+        assert_eq!(
+            target.docs_with_tag("suspect").unwrap(),
+            HashSet::from(["doc-a".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_detached_db_rejects_writes() {
+        let db = AuxiliaryProjectDb::detached().unwrap();
+        assert!(db
+            .create_group("suspects".to_string(), "#ff0000".to_string())
+            .is_err());
+        assert!(db
+            .add_tags_bulk(vec!["1".to_string()], vec!["wire".to_string()])
+            .is_err());
+        assert!(db.get_groups().unwrap().is_empty());
+        assert!(db.get_all_tags().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_group_files_added_and_removed() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = AuxiliaryProjectDb::init(temp_dir.path().join("aux")).unwrap();
+        db.create_group("red-flags".to_string(), "#ff0000".to_string())
+            .unwrap();
+
+        for (id, path) in [("1", "/evidence/a.db"), ("2", "/evidence/b.db")] {
+            db.add_file_to_group(
+                "red-flags".to_string(),
+                "#ff0000".to_string(),
+                id.to_string(),
+                path.to_string(),
+            )
+            .unwrap();
         }
-        result
+        db.remove_file_from_group(
+            "red-flags".to_string(),
+            "#ff0000".to_string(),
+            "1".to_string(),
+        )
+        .unwrap();
+
+        let groups = db.get_groups().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "red-flags");
+        assert_eq!(groups[0].color, "#ff0000");
+        assert_eq!(
+            groups[0].content,
+            vec![("2".to_string(), "/evidence/b.db".to_string())]
+        );
+
+        assert!(db
+            .add_file_to_group(
+                "missing".to_string(),
+                "#000000".to_string(),
+                "3".to_string(),
+                "/evidence/c.db".to_string()
+            )
+            .is_err());
     }
 }
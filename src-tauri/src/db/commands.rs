@@ -1,20 +1,40 @@
-use crate::db::auxiliary::Group;
-use crate::index::{IndexStats, MasterIndexer, Query, QueryResult};
+use crate::audit::AuditEntry;
+use crate::db::auxiliary::{CaseMetadata, ExportStats, Group, ImportStats, MergeStrategy};
+use crate::db::sqlite_diff::{diff_sqlite_tables, SqliteDiff};
+use crate::db::sqlite_pool::SqliteConnectionPool;
+use crate::index::{
+    ArchiveEntry, ArchiveExtractor, ArchiveSettings, CarvingLimits, CarvingReport, CarvingTarget,
+    DetectedFileType, DirectoryCount, ExtractorInfo, ExtractorRegistry, FileCarver,
+    FileExportReport, FileTypeDetector, GcStats, InFileMatch, IndexError, IndexProgress,
+    IndexStats, IndexedBatch, MasterIndexer, PreviewConfig, Query, QueryResult, RecoveredRow,
+    SearchFilter, SimilarityResult, SqliteRecovery, StructuredCatalog, TypedHit,
+};
 use crate::io::types::FileInfo;
+use crate::report::{generate_html_report, ReportOptions};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::future::Future;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tauri::ipc::Channel;
 use tauri::State;
 use tokio::sync::RwLock;
 
+/// Shortest gap between two audit entries for a command run on every keystroke
+const SUGGEST_AUDIT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
 /// Global database state
 pub struct DatabaseState {
     current_db: Arc<RwLock<Option<Arc<MasterIndexer>>>>,
+    /// Per throttled command: when it was last logged, and how many calls went unlogged since
+    throttled_audits: parking_lot::Mutex<HashMap<&'static str, (std::time::Instant, u64)>>,
 }
 
 impl DatabaseState {
     pub fn new() -> Self {
         Self {
             current_db: Arc::new(RwLock::new(None)),
+            throttled_audits: parking_lot::Mutex::new(HashMap::new()),
         }
     }
 
@@ -26,6 +46,95 @@ impl DatabaseState {
     pub async fn get_db(&self) -> Option<Arc<MasterIndexer>> {
         self.current_db.read().await.clone()
     }
+
+    /// Close the current project, handing back the indexer if nothing else is using it
+    /// While it is still shared the project stays open and None is returned
+    pub async fn take_unshared_db(&self) -> Option<MasterIndexer> {
+        let mut current = self.current_db.write().await;
+        let db = current.take()?;
+        match Arc::try_unwrap(db) {
+            Ok(db) => Some(db),
+            Err(db) => {
+                *current = Some(db);
+                None
+            }
+        }
+    }
+
+    /// Run a command and record it in the open project's audit log
+    /// Nothing is recorded while no project is open, since the log lives in the project
+    pub async fn audited<T>(
+        &self,
+        command: &str,
+        args: serde_json::Value,
+        run: impl Future<Output = Result<T, String>>,
+    ) -> Result<T, String> {
+        let start = std::time::Instant::now();
+        let result = run.await;
+
+        // A read-only handle can't write the log; the instance holding the project keeps it
+        if let Some(db) = self.get_db().await.filter(|db| !db.is_read_only()) {
+            let summary = match &result {
+                Ok(_) => "ok".to_string(),
+                Err(e) => format!("error: {}", e),
+            };
+            let logged = db.get_auxiliary_db().audit_log().and_then(|log| {
+                log.log_command(command, &args, &summary, start.elapsed().as_millis() as u64)
+            });
+            if let Err(e) = logged {
+                eprintln!("Failed to record {} in audit log: {}", command, e);
+            }
+        }
+
+        result
+    }
+
+    /// Like `audited`, for commands too frequent to log every call (e.g. per keystroke)
+    /// At most one entry is written per `interval`; it records how many calls since the
+    /// previous entry went unlogged, so the log still accounts for every call
+    pub async fn audited_throttled<T>(
+        &self,
+        command: &'static str,
+        mut args: serde_json::Value,
+        interval: std::time::Duration,
+        run: impl Future<Output = Result<T, String>>,
+    ) -> Result<T, String> {
+        let unlogged = {
+            let mut throttled = self.throttled_audits.lock();
+            match throttled.get_mut(command) {
+                Some((last, unlogged)) if last.elapsed() < interval => {
+                    *unlogged += 1;
+                    None
+                }
+                entry => {
+                    let unlogged = entry.map_or(0, |(_, unlogged)| *unlogged);
+                    throttled.insert(command, (std::time::Instant::now(), 0));
+                    Some(unlogged)
+                }
+            }
+        };
+
+        match unlogged {
+            Some(unlogged) => {
+                args["unloggedSinceLastEntry"] = unlogged.into();
+                self.audited(command, args, run).await
+            }
+            None => run.await,
+        }
+    }
+}
+
+/// Pooled connections for the SQLite viewer commands
+pub struct SqliteState {
+    pool: SqliteConnectionPool,
+}
+
+impl SqliteState {
+    pub fn new() -> Self {
+        Self {
+            pool: SqliteConnectionPool::new(),
+        }
+    }
 }
 
 #[tauri::command]
@@ -33,43 +142,509 @@ pub async fn create_project_database(
     evidence_path: String,
     state: State<'_, DatabaseState>,
 ) -> Result<String, String> {
-    let path = PathBuf::from(&evidence_path);
+    state
+        .audited(
+            "create_project_database",
+            serde_json::json!({ "evidencePath": evidence_path }),
+            async {
+                let path = PathBuf::from(&evidence_path);
+
+                match MasterIndexer::get_or_init_from_project_path(&path) {
+                    Ok(db) => {
+                        state.set_db(db).await;
+                        Ok(path.to_string_lossy().to_string())
+                    }
+                    Err(e) => Err(e.to_string()),
+                }
+            },
+        )
+        .await
+}
+
+/// Open a project's index for searching while another instance of the app indexes it
+/// Call reload_project_index to see the batches it has committed since
+#[tauri::command]
+pub async fn open_project_read_only(
+    evidence_path: String,
+    state: State<'_, DatabaseState>,
+) -> Result<String, String> {
+    state
+        .audited(
+            "open_project_read_only",
+            serde_json::json!({ "evidencePath": evidence_path }),
+            async {
+                let path = PathBuf::from(&evidence_path);
+                let db = MasterIndexer::open_read_only_from_project_path(&path)
+                    .map_err(|e| e.to_string())?;
+                state.set_db(db).await;
+                Ok(path.to_string_lossy().to_string())
+            },
+        )
+        .await
+}
 
-    match MasterIndexer::get_or_init_from_project_path(&path) {
-        Ok(db) => {
-            state.set_db(db).await;
-            Ok(path.to_string_lossy().to_string())
-        }
-        Err(e) => Err(e.to_string()),
-    }
+/// Make everything committed to the open index so far visible to searches
+#[tauri::command]
+pub async fn reload_project_index(state: State<'_, DatabaseState>) -> Result<(), String> {
+    state
+        .audited("reload_project_index", serde_json::json!({}), async {
+            let db = state.get_db().await.ok_or("No database open")?;
+            db.reload().map_err(|e| e.to_string())
+        })
+        .await
 }
 
 #[tauri::command]
 pub async fn get_project_metadata(state: State<'_, DatabaseState>) -> Result<IndexStats, String> {
-    let db = state.get_db().await.ok_or("No database open")?;
-    db.stats().map_err(|e| e.to_string())
+    state
+        .audited("get_project_metadata", serde_json::json!({}), async {
+            let db = state.get_db().await.ok_or("No database open")?;
+            db.stats().map_err(|e| e.to_string())
+        })
+        .await
 }
 
+/// Index a directory tree; on_batch receives each batch of files once it is searchable,
+/// starting with those matching the priority patterns
 #[tauri::command]
 pub async fn index_directory(
     file_tree: FileInfo,
+    on_batch: Channel<IndexedBatch>,
+    state: State<'_, DatabaseState>,
+) -> Result<IndexStats, String> {
+    state
+        .audited(
+            "index_directory",
+            serde_json::json!({ "path": file_tree.path }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                let index = db
+                    .index_directory_streaming(file_tree.path.as_path(), &mut |batch| {
+                        on_batch.send(batch).map_err(anyhow::Error::from)
+                    })
+                    .map_err(|x| x.to_string())?;
+                Ok(index)
+            },
+        )
+        .await
+}
+
+/// Retry extraction of a single file (e.g. after it timed out during indexing)
+#[tauri::command]
+pub async fn retry_file_extraction(
+    path: String,
+    state: State<'_, DatabaseState>,
+) -> Result<Option<IndexError>, String> {
+    state
+        .audited(
+            "retry_file_extraction",
+            serde_json::json!({ "path": path }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.retry_file(&PathBuf::from(path))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Find the evidence file whose content has the given Keccak-256 hash (e.g. from a blockchain)
+#[tauri::command]
+pub async fn lookup_keccak256(
+    hash: String,
+    state: State<'_, DatabaseState>,
+) -> Result<Option<TypedHit>, String> {
+    state
+        .audited(
+            "lookup_keccak256",
+            serde_json::json!({ "hash": hash }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.query_planner()
+                    .lookup_keccak256(&hash)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Search one file's content directly, e.g. the database open in the viewer
+#[tauri::command]
+pub async fn search_in_file(
+    path: String,
+    pattern: String,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<InFileMatch>, String> {
+    state
+        .audited(
+            "search_in_file",
+            serde_json::json!({ "path": path, "pattern": pattern }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.search_within_file(&PathBuf::from(path), &pattern)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Rescan the given root and re-extract every file found, replacing existing documents
+/// See rebuild_index_from_cache to re-extract without touching the filesystem tree
+#[tauri::command]
+pub async fn rebuild_index_from_scan(
+    path: String,
     state: State<'_, DatabaseState>,
 ) -> Result<IndexStats, String> {
-    let db = state.get_db().await.ok_or("No database open")?;
-    let index = db
-        .index_directory(file_tree.path.as_path())
-        .map_err(|x| x.to_string())?;
-    Ok(index)
+    state
+        .audited(
+            "rebuild_index_from_scan",
+            serde_json::json!({ "path": path }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.rebuild(&PathBuf::from(path)).map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Re-extract every file already in the project without rescanning the filesystem, e.g.
+/// after an extractor upgrade; on_progress gets a report after each batch
+#[tauri::command]
+pub async fn rebuild_index_from_cache(
+    on_progress: Channel<IndexProgress>,
+    state: State<'_, DatabaseState>,
+) -> Result<IndexStats, String> {
+    state
+        .audited("rebuild_index_from_cache", serde_json::json!({}), async {
+            let db = state.get_db().await.ok_or("No database open")?;
+            db.rebuild_index_with_progress(&mut |progress| {
+                // A closed channel only means the UI stopped listening; keep rebuilding
+                let _ = on_progress.send(progress);
+            })
+            .map_err(|e| e.to_string())
+        })
+        .await
+}
+
+/// Delete thumbnails and unpacked archives left behind by deleted or re-indexed files
+#[tauri::command]
+pub async fn gc_index(state: State<'_, DatabaseState>) -> Result<GcStats, String> {
+    state
+        .audited("gc_index", serde_json::json!({}), async {
+            let db = state.get_db().await.ok_or("No database open")?;
+            db.gc().map_err(|e| e.to_string())
+        })
+        .await
+}
+
+/// Copy the committed search index to backup_path, replacing any backup already there
+/// The UI should confirm with the user before overwriting an existing backup
+#[tauri::command]
+pub async fn backup_index(
+    backup_path: String,
+    state: State<'_, DatabaseState>,
+) -> Result<(), String> {
+    state
+        .audited(
+            "backup_index",
+            serde_json::json!({ "backupPath": backup_path }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.backup_index(&PathBuf::from(&backup_path))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Replace the open project's search index with the backup at backup_path
+/// Destructive: the current index is discarded, so the UI must get the user's confirmation
+/// before calling this. Run rebuild_index_from_scan afterwards to pick up files changed since the backup
+/// If the restore fails the project is left closed and has to be reopened
+#[tauri::command]
+pub async fn restore_index(
+    backup_path: String,
+    state: State<'_, DatabaseState>,
+) -> Result<(), String> {
+    state
+        .audited(
+            "restore_index",
+            serde_json::json!({ "backupPath": backup_path }),
+            async {
+                if state.get_db().await.is_none() {
+                    return Err("No database open".to_string());
+                }
+                let db = state
+                    .take_unshared_db()
+                    .await
+                    .ok_or("The index is in use; try again once running tasks finish")?;
+
+                let restored = db
+                    .restore_index(&PathBuf::from(&backup_path))
+                    .map_err(|e| e.to_string())?;
+                state.set_db(restored).await;
+                Ok(())
+            },
+        )
+        .await
+}
+
+/// Copy the files behind the given documents to out_dir, with a chain-of-custody manifest
+#[tauri::command]
+pub async fn export_files(
+    doc_ids: Vec<String>,
+    out_dir: String,
+    preserve_structure: bool,
+    state: State<'_, DatabaseState>,
+) -> Result<FileExportReport, String> {
+    state
+        .audited(
+            "export_files",
+            serde_json::json!({
+                "docIds": doc_ids,
+                "outDir": out_dir,
+                "preserveStructure": preserve_structure,
+            }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.export_files(&doc_ids, &PathBuf::from(out_dir), preserve_structure)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
 }
 
 #[tauri::command]
 pub async fn search_database(
     query: Query,
+    modified_after: Option<DateTime<Utc>>,
+    modified_before: Option<DateTime<Utc>>,
     state: State<'_, DatabaseState>,
 ) -> Result<QueryResult, String> {
-    let db = state.get_db().await.ok_or("No database open")?;
-    let qp = db.query_planner();
-    qp.execute(&query).map_err(|e| e.to_string())
+    state
+        .audited(
+            "search_database",
+            serde_json::json!({
+                "query": query,
+                "modifiedAfter": modified_after,
+                "modifiedBefore": modified_before,
+            }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                let filter = SearchFilter {
+                    modified_after,
+                    modified_before,
+                    ..SearchFilter::default()
+                };
+                db.query_planner()
+                    .execute_filtered(&query, &filter)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Files modified between start and end (inclusive), earliest first
+#[tauri::command]
+pub async fn get_files_modified_between(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    limit: Option<usize>,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<TypedHit>, String> {
+    state
+        .audited(
+            "get_files_modified_between",
+            serde_json::json!({ "start": start, "end": end, "limit": limit }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.get_files_modified_between(start, end, limit.unwrap_or(1000))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Files created between start and end (inclusive), earliest first
+#[tauri::command]
+pub async fn get_files_created_between(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    limit: Option<usize>,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<TypedHit>, String> {
+    state
+        .audited(
+            "get_files_created_between",
+            serde_json::json!({ "start": start, "end": end, "limit": limit }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.get_files_created_between(start, end, limit.unwrap_or(1000))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Events sent while streaming search results
+#[derive(Clone, serde::Serialize)]
+#[serde(
+    tag = "event",
+    rename_all = "lowercase",
+    rename_all_fields = "camelCase"
+)]
+pub enum SearchStreamEvent {
+    /// A batch of hits, in rank order
+    Batch { hits: Vec<TypedHit> },
+    /// All hits have been sent
+    Done { total: usize, query_time_ms: u64 },
+}
+
+/// Search the database, sending hits over the channel in batches
+#[tauri::command]
+pub async fn search_database_streaming(
+    query: Query,
+    batch_size: Option<usize>,
+    on_event: Channel<SearchStreamEvent>,
+    state: State<'_, DatabaseState>,
+) -> Result<(), String> {
+    state
+        .audited(
+            "search_database_streaming",
+            serde_json::json!({ "query": query, "batchSize": batch_size }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                let start = std::time::Instant::now();
+
+                let qp = db.query_planner();
+                let total = qp
+                    .execute_streaming(&query, batch_size.unwrap_or(200), &mut |hits| {
+                        on_event
+                            .send(SearchStreamEvent::Batch { hits })
+                            .map_err(anyhow::Error::from)
+                    })
+                    .map_err(|e| e.to_string())?;
+
+                on_event
+                    .send(SearchStreamEvent::Done {
+                        total,
+                        query_time_ms: start.elapsed().as_millis() as u64,
+                    })
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// List SQLite tables and columns across all indexed databases
+#[tauri::command]
+pub async fn get_structured_catalog(
+    state: State<'_, DatabaseState>,
+) -> Result<StructuredCatalog, String> {
+    state
+        .audited("get_structured_catalog", serde_json::json!({}), async {
+            let db = state.get_db().await.ok_or("No database open")?;
+            db.structured_catalog().map_err(|e| e.to_string())
+        })
+        .await
+}
+
+/// Count the files matching query per containing folder, for a folder-grouped results view
+#[tauri::command]
+pub async fn get_directory_breakdown(
+    query: String,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<DirectoryCount>, String> {
+    state
+        .audited(
+            "get_directory_breakdown",
+            serde_json::json!({ "query": query }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.directory_breakdown(&query).map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Indexed documents with content similar to file_id, e.g. copies or drafts of a report
+#[tauri::command]
+pub async fn find_similar(
+    file_id: String,
+    limit: usize,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<SimilarityResult>, String> {
+    state
+        .audited(
+            "find_similar",
+            serde_json::json!({ "fileId": file_id, "limit": limit }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.find_similar(&file_id, limit).map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Thumbnail of an indexed image as a base64 JPEG data URL
+#[tauri::command]
+pub async fn get_thumbnail(
+    doc_id: String,
+    state: State<'_, DatabaseState>,
+) -> Result<String, String> {
+    state
+        .audited(
+            "get_thumbnail",
+            serde_json::json!({ "docId": doc_id }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.get_thumbnail(&doc_id).map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Autocomplete a search term from the index, e.g. table or column names as the user types
+/// field is an index field such as "tables", "columns" or "paths"
+/// Runs on every keystroke, so its audit entries are throttled
+#[tauri::command]
+pub async fn suggest(
+    prefix: String,
+    field: String,
+    limit: Option<usize>,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<String>, String> {
+    state
+        .audited_throttled(
+            "suggest",
+            serde_json::json!({ "prefix": prefix, "field": field, "limit": limit }),
+            SUGGEST_AUDIT_INTERVAL,
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.suggest(&prefix, &field, limit.unwrap_or(10))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Export the index into a SQLite database readable by other tools
+#[tauri::command]
+pub async fn export_index_to_sqlite(
+    output_path: String,
+    state: State<'_, DatabaseState>,
+) -> Result<u64, String> {
+    state
+        .audited(
+            "export_index_to_sqlite",
+            serde_json::json!({ "outputPath": output_path }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.export_to_sqlite(&PathBuf::from(output_path))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
 }
 
 #[derive(serde::Serialize)]
@@ -89,6 +664,9 @@ pub struct SqliteTableInfo {
     pub columns: Vec<SqliteColumnInfo>,
     pub row_count: u64,
     pub indexes: Vec<String>,
+    /// CREATE TABLE statement
+    #[serde(default)]
+    pub sql: Option<String>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -109,73 +687,73 @@ pub struct SqliteDatabaseInfo {
     pub total_rows: u64,
 }
 
-#[tauri::command]
-pub async fn query_sqlite_info(db_path: String) -> Result<SqliteDatabaseInfo, String> {
-    use rusqlite::{Connection, OpenFlags};
-    use std::path::Path;
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteViewInfo {
+    pub name: String,
+    pub sql: Option<String>,
+}
 
-    // Validate path exists
-    let path = Path::new(&db_path);
-    if !path.exists() {
-        return Err(format!("Database file does not exist: {}", db_path));
-    }
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteTriggerInfo {
+    pub name: String,
+    pub table_name: String,
+    pub sql: Option<String>,
+}
 
-    if !path.is_file() {
-        return Err(format!("Path is not a file: {}", db_path));
-    }
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteIndexInfo {
+    pub name: String,
+    pub table_name: String,
+    pub sql: Option<String>,
+}
 
-    // Check file header to ensure it's a SQLite database
-    if let Ok(mut file) = std::fs::File::open(path) {
-        use std::io::Read;
-        let mut header = [0u8; 16];
-        if file.read(&mut header).is_ok() {
-            let header_str = String::from_utf8_lossy(&header);
-            if !header_str.starts_with("SQLite format 3") {
-                return Err(format!(
-                    "File does not appear to be a valid SQLite database. Header: '{}'",
-                    header_str.escape_default()
-                ));
-            }
-        }
-    }
+/// Every schema object in a database, with the statements that created them
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteFullSchema {
+    pub tables: Vec<SqliteTableInfo>,
+    pub views: Vec<SqliteViewInfo>,
+    pub triggers: Vec<SqliteTriggerInfo>,
+    pub indexes: Vec<SqliteIndexInfo>,
+}
 
-    // Try to open with multiple flag combinations
-    let conn = Connection::open_with_flags(
-        &db_path,
-        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .or_else(|e1| {
-        // Try without NO_MUTEX flag (might help with locked files)
-        eprintln!("First open attempt failed ({}), trying without NO_MUTEX...", e1);
-        Connection::open_with_flags(
-            &db_path,
-            OpenFlags::SQLITE_OPEN_READ_WRITE,
-        )
-    })
-    .map_err(|e| format!("Failed to open database '{}': {}. File might be locked, corrupted, or not a valid SQLite3 database.", db_path, e))?;
-
-    let version: String = conn
-        .query_row("SELECT sqlite_version()", [], |row| row.get(0))
-        .unwrap_or_else(|_| "unknown".to_string());
-
-    let page_size: u32 = conn
-        .pragma_query_value(None, "page_size", |row| row.get(0))
-        .unwrap_or(4096);
+/// A row of sqlite_master: (name, tbl_name, sql)
+type SqliteMasterEntry = (String, String, Option<String>);
 
+/// List schema objects of one type ('table', 'view', 'trigger' or 'index'), skipping internal ones
+fn query_sqlite_master(
+    conn: &rusqlite::Connection,
+    object_type: &str,
+) -> Result<Vec<SqliteMasterEntry>, String> {
     let mut stmt = conn
-        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'")
+        .prepare(
+            "SELECT name, tbl_name, sql FROM sqlite_master \
+             WHERE type = ?1 AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
         .map_err(|e| e.to_string())?;
 
-    let table_names: Vec<String> = stmt
-        .query_map([], |row| row.get(0))
+    let entries = stmt
+        .query_map([object_type], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
 
+    Ok(entries)
+}
+
+/// Tables with their columns, row counts and indexes, plus the total row count
+pub(super) fn read_sqlite_tables(
+    conn: &rusqlite::Connection,
+) -> Result<(Vec<SqliteTableInfo>, u64), String> {
     let mut tables = Vec::new();
     let mut total_rows = 0u64;
 
-    for table_name in table_names {
+    for (table_name, _, sql) in query_sqlite_master(conn, "table")? {
         let mut col_stmt = conn
             .prepare(&format!("PRAGMA table_info('{}')", table_name))
             .map_err(|e| e.to_string())?;
@@ -218,15 +796,139 @@ pub async fn query_sqlite_info(db_path: String) -> Result<SqliteDatabaseInfo, St
             columns,
             row_count: row_count as u64,
             indexes,
+            sql,
         });
     }
 
-    Ok(SqliteDatabaseInfo {
-        version,
-        page_size,
-        tables,
-        total_rows,
-    })
+    Ok((tables, total_rows))
+}
+
+/// Check that db_path is a SQLite database and get its pooled read-only connection
+fn open_pooled_sqlite(
+    db_path: &str,
+    state: &SqliteState,
+) -> Result<Arc<parking_lot::Mutex<rusqlite::Connection>>, String> {
+    use std::path::Path;
+
+    let path = Path::new(db_path);
+    if !path.exists() {
+        return Err(format!("Database file does not exist: {}", db_path));
+    }
+
+    if !path.is_file() {
+        return Err(format!("Path is not a file: {}", db_path));
+    }
+
+    // Check file header to ensure it's a SQLite database
+    if let Ok(mut file) = std::fs::File::open(path) {
+        use std::io::Read;
+        let mut header = [0u8; 16];
+        if file.read(&mut header).is_ok() {
+            let header_str = String::from_utf8_lossy(&header);
+            if !header_str.starts_with("SQLite format 3") {
+                return Err(format!(
+                    "File does not appear to be a valid SQLite database. Header: '{}'",
+                    header_str.escape_default()
+                ));
+            }
+        }
+    }
+
+    let conn = state.pool.get(path).map_err(|e| {
+        format!(
+            "Failed to open database '{}': {}. \
+             File might be locked, corrupted, or not a valid SQLite3 database.",
+            db_path, e
+        )
+    })?;
+    Ok(conn)
+}
+
+#[tauri::command]
+pub async fn query_sqlite_info(
+    db_path: String,
+    state: State<'_, SqliteState>,
+    audit: State<'_, DatabaseState>,
+) -> Result<SqliteDatabaseInfo, String> {
+    audit
+        .audited(
+            "query_sqlite_info",
+            serde_json::json!({ "dbPath": db_path }),
+            async {
+                let conn = open_pooled_sqlite(&db_path, &state)?;
+                let conn = conn.lock();
+
+                let version: String = conn
+                    .query_row("SELECT sqlite_version()", [], |row| row.get(0))
+                    .unwrap_or_else(|_| "unknown".to_string());
+
+                let page_size: u32 = conn
+                    .pragma_query_value(None, "page_size", |row| row.get(0))
+                    .unwrap_or(4096);
+
+                let (tables, total_rows) = read_sqlite_tables(&conn)?;
+
+                Ok(SqliteDatabaseInfo {
+                    version,
+                    page_size,
+                    tables,
+                    total_rows,
+                })
+            },
+        )
+        .await
+}
+
+/// List tables, views, triggers and indexes of a SQLite database with their CREATE statements
+/// Views and triggers show how an application reads and rewrites its data
+#[tauri::command]
+pub async fn query_sqlite_full_schema(
+    db_path: String,
+    state: State<'_, SqliteState>,
+    audit: State<'_, DatabaseState>,
+) -> Result<SqliteFullSchema, String> {
+    audit
+        .audited(
+            "query_sqlite_full_schema",
+            serde_json::json!({ "dbPath": db_path }),
+            async {
+                let conn = open_pooled_sqlite(&db_path, &state)?;
+                let conn = conn.lock();
+
+                let (tables, _) = read_sqlite_tables(&conn)?;
+
+                let views = query_sqlite_master(&conn, "view")?
+                    .into_iter()
+                    .map(|(name, _, sql)| SqliteViewInfo { name, sql })
+                    .collect();
+
+                let triggers = query_sqlite_master(&conn, "trigger")?
+                    .into_iter()
+                    .map(|(name, table_name, sql)| SqliteTriggerInfo {
+                        name,
+                        table_name,
+                        sql,
+                    })
+                    .collect();
+
+                let indexes = query_sqlite_master(&conn, "index")?
+                    .into_iter()
+                    .map(|(name, table_name, sql)| SqliteIndexInfo {
+                        name,
+                        table_name,
+                        sql,
+                    })
+                    .collect();
+
+                Ok(SqliteFullSchema {
+                    tables,
+                    views,
+                    triggers,
+                    indexes,
+                })
+            },
+        )
+        .await
 }
 
 #[derive(serde::Serialize)]
@@ -241,55 +943,107 @@ pub async fn query_sqlite_table(
     table_name: String,
     limit: Option<u32>,
     offset: Option<u32>,
+    state: State<'_, SqliteState>,
+    audit: State<'_, DatabaseState>,
 ) -> Result<Vec<QueryResultRow>, String> {
-    use rusqlite::{Connection, OpenFlags};
-
-    let conn = Connection::open_with_flags(
-        &db_path,
-        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .map_err(|e| format!("Failed to open database: {}", e))?;
-
-    let limit = limit.unwrap_or(100);
-    let offset = offset.unwrap_or(0);
-
-    let query = format!(
-        "SELECT * FROM '{}' LIMIT {} OFFSET {}",
-        table_name, limit, offset
-    );
-
-    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
-
-    let column_count = stmt.column_count();
-
-    let rows: Vec<QueryResultRow> = stmt
-        .query_map([], |row| {
-            let mut values = Vec::new();
-            for i in 0..column_count {
-                // Try different types and convert to JSON value
-                let value = if let Ok(s) = row.get::<_, String>(i) {
-                    serde_json::Value::String(s)
-                } else if let Ok(n) = row.get::<_, i64>(i) {
-                    serde_json::Value::Number(n.into())
-                } else if let Ok(f) = row.get::<_, f64>(i) {
-                    serde_json::json!(f)
-                } else if let Ok(b) = row.get::<_, bool>(i) {
-                    serde_json::Value::Bool(b)
-                } else if let Ok(bytes) = row.get::<_, Vec<u8>>(i) {
-                    // Convert bytes to hex string
-                    serde_json::Value::String(format!("0x{}", hex::encode(bytes)))
-                } else {
-                    serde_json::Value::Null
-                };
-                values.push(value);
-            }
-            Ok(QueryResultRow { values })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?;
+    audit
+        .audited(
+            "query_sqlite_table",
+            serde_json::json!({
+                "dbPath": db_path,
+                "tableName": table_name,
+                "limit": limit,
+                "offset": offset,
+            }),
+            async {
+                let conn = state
+                    .pool
+                    .get(std::path::Path::new(&db_path))
+                    .map_err(|e| format!("Failed to open database: {}", e))?;
+                let conn = conn.lock();
+
+                let limit = limit.unwrap_or(100);
+                let offset = offset.unwrap_or(0);
+
+                let query = format!(
+                    "SELECT * FROM '{}' LIMIT {} OFFSET {}",
+                    table_name, limit, offset
+                );
+
+                let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+
+                let column_count = stmt.column_count();
+
+                let rows: Vec<QueryResultRow> = stmt
+                    .query_map([], |row| {
+                        let mut values = Vec::new();
+                        for i in 0..column_count {
+                            // Try different types and convert to JSON value
+                            let value = if let Ok(s) = row.get::<_, String>(i) {
+                                serde_json::Value::String(s)
+                            } else if let Ok(n) = row.get::<_, i64>(i) {
+                                serde_json::Value::Number(n.into())
+                            } else if let Ok(f) = row.get::<_, f64>(i) {
+                                serde_json::json!(f)
+                            } else if let Ok(b) = row.get::<_, bool>(i) {
+                                serde_json::Value::Bool(b)
+                            } else if let Ok(bytes) = row.get::<_, Vec<u8>>(i) {
+                                // Convert bytes to hex string
+                                serde_json::Value::String(format!("0x{}", hex::encode(bytes)))
+                            } else {
+                                serde_json::Value::Null
+                            };
+                            values.push(value);
+                        }
+                        Ok(QueryResultRow { values })
+                    })
+                    .map_err(|e| e.to_string())?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| e.to_string())?;
+
+                Ok(rows)
+            },
+        )
+        .await
+}
+
+/// Compare the tables, columns and row counts of two SQLite databases
+/// Both are opened read-only through the connection pool
+#[tauri::command]
+pub async fn diff_sqlite(
+    db_a: String,
+    db_b: String,
+    state: State<'_, SqliteState>,
+    audit: State<'_, DatabaseState>,
+) -> Result<SqliteDiff, String> {
+    audit
+        .audited(
+            "diff_sqlite",
+            serde_json::json!({ "dbA": db_a, "dbB": db_b }),
+            async {
+                // One at a time: the same path twice would hand back the same pooled connection
+                let (tables_a, _) = read_sqlite_tables(&open_pooled_sqlite(&db_a, &state)?.lock())?;
+                let (tables_b, _) = read_sqlite_tables(&open_pooled_sqlite(&db_b, &state)?.lock())?;
+                Ok(diff_sqlite_tables(tables_a, tables_b))
+            },
+        )
+        .await
+}
 
-    Ok(rows)
+/// Close the pooled connection for a database (e.g. before the file is moved)
+#[tauri::command]
+pub async fn close_sqlite_connection(
+    db_path: String,
+    state: State<'_, SqliteState>,
+    audit: State<'_, DatabaseState>,
+) -> Result<bool, String> {
+    audit
+        .audited(
+            "close_sqlite_connection",
+            serde_json::json!({ "dbPath": db_path }),
+            async { Ok(state.pool.evict(std::path::Path::new(&db_path))) },
+        )
+        .await
 }
 
 #[derive(serde::Serialize)]
@@ -301,38 +1055,168 @@ pub struct LevelDbInfo {
 }
 
 #[tauri::command]
-pub async fn query_leveldb_info(db_path: String) -> Result<LevelDbInfo, String> {
-    use std::path::Path;
-
-    let path = Path::new(&db_path);
-
-    if !path.is_dir() {
-        return Err("LevelDB path must be a directory".to_string());
-    }
+pub async fn query_leveldb_info(
+    db_path: String,
+    audit: State<'_, DatabaseState>,
+) -> Result<LevelDbInfo, String> {
+    audit
+        .audited(
+            "query_leveldb_info",
+            serde_json::json!({ "dbPath": db_path }),
+            async {
+                use std::path::Path;
+
+                let path = Path::new(&db_path);
+
+                if !path.is_dir() {
+                    return Err("LevelDB path must be a directory".to_string());
+                }
 
-    let mut total_size = 0u64;
-    let mut files = Vec::new();
+                let mut total_size = 0u64;
+                let mut files = Vec::new();
 
-    if let Ok(entries) = std::fs::read_dir(path) {
-        for entry in entries.flatten() {
-            if let Ok(metadata) = entry.metadata() {
-                if metadata.is_file() {
-                    total_size += metadata.len();
-                    if let Some(name) = entry.file_name().to_str() {
-                        files.push(name.to_string());
+                if let Ok(entries) = std::fs::read_dir(path) {
+                    for entry in entries.flatten() {
+                        if let Ok(metadata) = entry.metadata() {
+                            if metadata.is_file() {
+                                total_size += metadata.len();
+                                if let Some(name) = entry.file_name().to_str() {
+                                    files.push(name.to_string());
+                                }
+                            }
+                        }
                     }
                 }
-            }
-        }
-    }
 
-    let key_count = total_size / 100;
+                let key_count = total_size / 100;
+
+                Ok(LevelDbInfo {
+                    key_count,
+                    approximate_size: total_size,
+                    files,
+                })
+            },
+        )
+        .await
+}
+
+/// Identify a file's type from its magic bytes without indexing it
+#[tauri::command]
+pub async fn detect_file_type(
+    path: String,
+    audit: State<'_, DatabaseState>,
+) -> Result<DetectedFileType, String> {
+    audit
+        .audited(
+            "detect_file_type",
+            serde_json::json!({ "path": path }),
+            async {
+                FileTypeDetector::detect(std::path::Path::new(&path)).map_err(|e| match e.kind() {
+                    std::io::ErrorKind::NotFound => format!("File not found: {}", path),
+                    std::io::ErrorKind::PermissionDenied => format!("Permission denied: {}", path),
+                    _ => format!("Failed to read {}: {}", path, e),
+                })
+            },
+        )
+        .await
+}
+
+/// Catalog of the built-in extractors and the file types each can deeply extract
+#[tauri::command]
+pub async fn get_supported_extractors(
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<ExtractorInfo>, String> {
+    state
+        .audited("get_supported_extractors", serde_json::json!({}), async {
+            Ok(ExtractorRegistry::new().list())
+        })
+        .await
+}
+
+/// List the files inside a zip or tar archive without unpacking it
+#[tauri::command]
+pub async fn list_archive_entries(
+    archive_path: String,
+    audit: State<'_, DatabaseState>,
+) -> Result<Vec<ArchiveEntry>, String> {
+    audit
+        .audited(
+            "list_archive_entries",
+            serde_json::json!({ "archivePath": archive_path }),
+            async {
+                ArchiveExtractor::new(ArchiveSettings::default())
+                    .list_entries(std::path::Path::new(&archive_path))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Read one file from inside a zip or tar archive, for previewing without unpacking
+#[tauri::command]
+pub async fn read_archive_entry(
+    archive_path: String,
+    entry_path: String,
+    audit: State<'_, DatabaseState>,
+) -> Result<Vec<u8>, String> {
+    audit
+        .audited(
+            "read_archive_entry",
+            serde_json::json!({ "archivePath": archive_path, "entryPath": entry_path }),
+            async {
+                ArchiveExtractor::new(ArchiveSettings::default())
+                    .read_entry(std::path::Path::new(&archive_path), &entry_path)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Recover JPEG, PNG, PDF and ZIP files embedded in a disk image or binary dump
+/// targets defaults to all four built-in signatures, limits to the default budgets
+#[tauri::command]
+pub async fn carve_files(
+    path: String,
+    output_dir: String,
+    targets: Option<Vec<CarvingTarget>>,
+    limits: Option<CarvingLimits>,
+    audit: State<'_, DatabaseState>,
+) -> Result<CarvingReport, String> {
+    audit
+        .audited(
+            "carve_files",
+            serde_json::json!({ "path": path, "outputDir": output_dir }),
+            async {
+                let targets = targets.unwrap_or_else(CarvingTarget::all);
+                FileCarver::carve(
+                    std::path::Path::new(&path),
+                    std::path::Path::new(&output_dir),
+                    &targets,
+                    &limits.unwrap_or_default(),
+                )
+                .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
 
-    Ok(LevelDbInfo {
-        key_count,
-        approximate_size: total_size,
-        files,
-    })
+/// Recover deleted rows from the freelist pages of a SQLite database
+/// The file is parsed directly, so it doesn't need to be a database SQLite can still open
+#[tauri::command]
+pub async fn recover_deleted_rows(
+    path: String,
+    audit: State<'_, DatabaseState>,
+) -> Result<Vec<RecoveredRow>, String> {
+    audit
+        .audited(
+            "recover_deleted_rows",
+            serde_json::json!({ "path": path }),
+            async {
+                SqliteRecovery::recover_deleted_rows(std::path::Path::new(&path))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
 }
 
 #[derive(serde::Serialize)]
@@ -344,66 +1228,77 @@ pub struct IndexedDbInfo {
 }
 
 #[tauri::command]
-pub async fn query_indexeddb_info(db_path: String) -> Result<IndexedDbInfo, String> {
-    use std::path::Path;
-
-    let path = Path::new(&db_path);
-
-    if !path.is_dir() {
-        return Err("IndexedDB path must be a directory".to_string());
-    }
+pub async fn query_indexeddb_info(
+    db_path: String,
+    audit: State<'_, DatabaseState>,
+) -> Result<IndexedDbInfo, String> {
+    audit
+        .audited(
+            "query_indexeddb_info",
+            serde_json::json!({ "dbPath": db_path }),
+            async {
+                use std::path::Path;
+
+                let path = Path::new(&db_path);
+
+                if !path.is_dir() {
+                    return Err("IndexedDB path must be a directory".to_string());
+                }
 
-    let mut databases = Vec::new();
-    let mut subdirectories = Vec::new();
-    let mut total_keys = 0u64;
-
-    if let Ok(entries) = std::fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let entry_path = entry.path();
-            if entry_path.is_dir() {
-                if let Some(name) = entry.file_name().to_str() {
-                    subdirectories.push(name.to_string());
-
-                    if name.ends_with(".indexeddb") {
-                        databases.push(name.replace(".indexeddb", ""));
-
-                        if let Ok(dir_entries) = std::fs::read_dir(&entry_path) {
-                            let mut dir_size = 0u64;
-                            for file_entry in dir_entries.flatten() {
-                                if let Ok(metadata) = file_entry.metadata() {
-                                    if metadata.is_file() {
-                                        dir_size += metadata.len();
+                let mut databases = Vec::new();
+                let mut subdirectories = Vec::new();
+                let mut total_keys = 0u64;
+
+                if let Ok(entries) = std::fs::read_dir(path) {
+                    for entry in entries.flatten() {
+                        let entry_path = entry.path();
+                        if entry_path.is_dir() {
+                            if let Some(name) = entry.file_name().to_str() {
+                                subdirectories.push(name.to_string());
+
+                                if name.ends_with(".indexeddb") {
+                                    databases.push(name.replace(".indexeddb", ""));
+
+                                    if let Ok(dir_entries) = std::fs::read_dir(&entry_path) {
+                                        let mut dir_size = 0u64;
+                                        for file_entry in dir_entries.flatten() {
+                                            if let Ok(metadata) = file_entry.metadata() {
+                                                if metadata.is_file() {
+                                                    dir_size += metadata.len();
+                                                }
+                                            }
+                                        }
+                                        total_keys += dir_size / 100;
                                     }
                                 }
                             }
-                            total_keys += dir_size / 100;
                         }
                     }
                 }
-            }
-        }
-    }
 
-    if databases.is_empty() {
-        databases.push("indexeddb".to_string());
-        if let Ok(entries) = std::fs::read_dir(path) {
-            let mut total_size = 0u64;
-            for entry in entries.flatten() {
-                if let Ok(metadata) = entry.metadata() {
-                    if metadata.is_file() {
-                        total_size += metadata.len();
+                if databases.is_empty() {
+                    databases.push("indexeddb".to_string());
+                    if let Ok(entries) = std::fs::read_dir(path) {
+                        let mut total_size = 0u64;
+                        for entry in entries.flatten() {
+                            if let Ok(metadata) = entry.metadata() {
+                                if metadata.is_file() {
+                                    total_size += metadata.len();
+                                }
+                            }
+                        }
+                        total_keys = total_size / 100;
                     }
                 }
-            }
-            total_keys = total_size / 100;
-        }
-    }
 
-    Ok(IndexedDbInfo {
-        databases,
-        total_keys,
-        subdirectories,
-    })
+                Ok(IndexedDbInfo {
+                    databases,
+                    total_keys,
+                    subdirectories,
+                })
+            },
+        )
+        .await
 }
 
 #[tauri::command]
@@ -412,14 +1307,22 @@ pub async fn create_group(
     color: String,
     state: State<'_, DatabaseState>,
 ) -> Result<(), String> {
-    let state = state
-        .get_db()
+    state
+        .audited(
+            "create_group",
+            serde_json::json!({ "name": name, "color": color }),
+            async {
+                let state = state
+                    .get_db()
+                    .await
+                    .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
+                    .map_err(|y| y.to_string())?;
+                let db = state.get_auxiliary_db();
+                db.create_group(name, color).map_err(|e| e.to_string())?;
+                Ok(())
+            },
+        )
         .await
-        .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
-        .map_err(|y| y.to_string())?;
-    let db = state.get_auxiliary_db();
-    db.create_group(name, color).map_err(|e| e.to_string())?;
-    Ok(())
 }
 
 #[tauri::command]
@@ -428,25 +1331,250 @@ pub async fn delete_group(
     color: String,
     state: State<'_, DatabaseState>,
 ) -> Result<(), String> {
-    let state = state
-        .get_db()
+    state
+        .audited(
+            "delete_group",
+            serde_json::json!({ "name": name, "color": color }),
+            async {
+                let state = state
+                    .get_db()
+                    .await
+                    .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
+                    .map_err(|y| y.to_string())?;
+                let db = state.get_auxiliary_db();
+                db.delete_group(name, color).map_err(|e| e.to_string())?;
+                Ok(())
+            },
+        )
         .await
-        .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
-        .map_err(|y| y.to_string())?;
-    let db = state.get_auxiliary_db();
-    db.delete_group(name, color).map_err(|e| e.to_string())?;
-    Ok(())
 }
 
 #[tauri::command]
 pub async fn get_groups(state: State<'_, DatabaseState>) -> Result<Vec<Group>, String> {
-    let state = state
-        .get_db()
+    state
+        .audited("get_groups", serde_json::json!({}), async {
+            let state = state
+                .get_db()
+                .await
+                .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
+                .map_err(|y| y.to_string())?;
+            let db = state.get_auxiliary_db();
+            db.get_groups().map_err(|e| e.to_string())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn add_file_to_group(
+    group_name: String,
+    group_color: String,
+    file_id: String,
+    file_path: String,
+    state: State<'_, DatabaseState>,
+) -> Result<(), String> {
+    state
+        .audited(
+            "add_file_to_group",
+            serde_json::json!({
+                "groupName": group_name,
+                "groupColor": group_color,
+                "fileId": file_id,
+                "filePath": file_path,
+            }),
+            async {
+                let state = state.get_db().await.ok_or("No database open")?;
+                state
+                    .get_auxiliary_db()
+                    .add_file_to_group(group_name, group_color, file_id, file_path)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn remove_file_from_group(
+    group_name: String,
+    group_color: String,
+    file_id: String,
+    state: State<'_, DatabaseState>,
+) -> Result<(), String> {
+    state
+        .audited(
+            "remove_file_from_group",
+            serde_json::json!({
+                "groupName": group_name,
+                "groupColor": group_color,
+                "fileId": file_id,
+            }),
+            async {
+                let state = state.get_db().await.ok_or("No database open")?;
+                state
+                    .get_auxiliary_db()
+                    .remove_file_from_group(group_name, group_color, file_id)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Tag many documents at once (e.g. every result of a query); returns the number of new tags
+#[tauri::command]
+pub async fn add_tags_bulk(
+    doc_ids: Vec<String>,
+    tags: Vec<String>,
+    state: State<'_, DatabaseState>,
+) -> Result<usize, String> {
+    state
+        .audited(
+            "add_tags_bulk",
+            serde_json::json!({ "docIds": doc_ids, "tags": tags }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.get_auxiliary_db()
+                    .add_tags_bulk(doc_ids, tags)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn get_all_tags(state: State<'_, DatabaseState>) -> Result<Vec<String>, String> {
+    state
+        .audited("get_all_tags", serde_json::json!({}), async {
+            let db = state.get_db().await.ok_or("No database open")?;
+            db.get_auxiliary_db()
+                .get_all_tags()
+                .map_err(|e| e.to_string())
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn set_case_metadata(
+    metadata: CaseMetadata,
+    state: State<'_, DatabaseState>,
+) -> Result<(), String> {
+    state
+        .audited(
+            "set_case_metadata",
+            serde_json::json!({ "metadata": metadata }),
+            async {
+                let state = state
+                    .get_db()
+                    .await
+                    .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
+                    .map_err(|y| y.to_string())?;
+                let db = state.get_auxiliary_db();
+                db.set_case_metadata(metadata).map_err(|e| e.to_string())?;
+                Ok(())
+            },
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn get_case_metadata(
+    state: State<'_, DatabaseState>,
+) -> Result<Option<CaseMetadata>, String> {
+    state
+        .audited("get_case_metadata", serde_json::json!({}), async {
+            let state = state
+                .get_db()
+                .await
+                .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
+                .map_err(|y| y.to_string())?;
+            let db = state.get_auxiliary_db();
+            db.get_case_metadata().map_err(|e| e.to_string())
+        })
+        .await
+}
+
+/// Change the open project's archive unpacking settings; they persist across restarts
+#[tauri::command]
+pub async fn update_archive_settings(
+    settings: ArchiveSettings,
+    state: State<'_, DatabaseState>,
+) -> Result<(), String> {
+    state
+        .audited(
+            "update_archive_settings",
+            serde_json::json!({ "settings": settings }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.update_archive_settings(settings)
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Change the open project's image preview config; it persists across restarts
+#[tauri::command]
+pub async fn update_preview_config(
+    config: PreviewConfig,
+    state: State<'_, DatabaseState>,
+) -> Result<(), String> {
+    state
+        .audited(
+            "update_preview_config",
+            serde_json::json!({ "config": config }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                db.update_preview_config(config).map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Back up groups and case metadata to a portable JSON file
+#[tauri::command]
+pub async fn export_auxiliary(
+    output_path: String,
+    state: State<'_, DatabaseState>,
+) -> Result<ExportStats, String> {
+    state
+        .audited(
+            "export_auxiliary",
+            serde_json::json!({ "outputPath": output_path }),
+            async {
+                let state = state
+                    .get_db()
+                    .await
+                    .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
+                    .map_err(|y| y.to_string())?;
+                let db = state.get_auxiliary_db();
+                db.export_auxiliary(&PathBuf::from(output_path))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Restore groups and case metadata from a backup made by export_auxiliary
+#[tauri::command]
+pub async fn import_auxiliary(
+    input_path: String,
+    merge_strategy: MergeStrategy,
+    state: State<'_, DatabaseState>,
+) -> Result<ImportStats, String> {
+    state
+        .audited(
+            "import_auxiliary",
+            serde_json::json!({ "inputPath": input_path, "mergeStrategy": merge_strategy }),
+            async {
+                let state = state
+                    .get_db()
+                    .await
+                    .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
+                    .map_err(|y| y.to_string())?;
+                let db = state.get_auxiliary_db();
+                db.import_auxiliary(&PathBuf::from(input_path), merge_strategy)
+                    .map_err(|e| e.to_string())
+            },
+        )
         .await
-        .ok_or(anyhow::Error::msg("Failed to get db".to_string()))
-        .map_err(|y| y.to_string())?;
-    let db = state.get_auxiliary_db();
-    Ok(db.get_groups())
 }
 
 /// Check if a specific path is indexed
@@ -455,28 +1583,36 @@ pub async fn is_path_indexed(
     path: String,
     state: State<'_, DatabaseState>,
 ) -> Result<bool, String> {
-    let db = state.get_db().await.ok_or("No database open")?;
-    let path_buf = PathBuf::from(path);
-
-    // Query the index to see if this path exists
-    let query = Query::Metadata {
-        category: None,
-        mime_type: None,
-        min_size: None,
-        max_size: None,
-        extension: None,
-        path_prefix: Some(path_buf.to_string_lossy().to_string()),
-    };
-
-    let qp = db.query_planner();
-    match qp.execute(&query) {
-        Ok(result) => Ok(!result.hits.is_empty()),
-        Err(_) => Ok(false),
-    }
+    state
+        .audited(
+            "is_path_indexed",
+            serde_json::json!({ "path": path }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                let path_buf = PathBuf::from(path);
+
+                // Query the index to see if this path exists
+                let query = Query::Metadata {
+                    category: None,
+                    mime_type: None,
+                    min_size: None,
+                    max_size: None,
+                    extension: None,
+                    path_prefix: Some(path_buf.to_string_lossy().to_string()),
+                };
+
+                let qp = db.query_planner();
+                match qp.execute(&query) {
+                    Ok(result) => Ok(!result.hits.is_empty()),
+                    Err(_) => Ok(false),
+                }
+            },
+        )
+        .await
 }
 
 /// Get detailed index status for a path
-#[derive(Serialize)]
+#[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PathIndexStatus {
     pub path: String,
@@ -492,85 +1628,94 @@ pub async fn get_path_index_status(
     path: String,
     state: State<'_, DatabaseState>,
 ) -> Result<PathIndexStatus, String> {
-    let db = state.get_db().await.ok_or("No database open")?;
-    let path_buf = PathBuf::from(&path);
-
-    // Check if path is a directory or file
-    let is_dir = path_buf.is_dir();
-
-    if is_dir {
-        // For directories, count files and indexed files
-        let query = Query::Metadata {
-            category: None,
-            mime_type: None,
-            min_size: None,
-            max_size: None,
-            extension: None,
-            path_prefix: Some(path_buf.to_string_lossy().to_string()),
-        };
-
-        let qp = db.query_planner();
-        match qp.execute(&query) {
-            Ok(result) => {
-                let indexed_count = result.hits.len() as u64;
-
-                // Count total files in directory (simplified)
-                let total_files = count_files_in_dir(&path_buf).unwrap_or(0);
-
-                let status = if indexed_count == 0 {
-                    "not_indexed"
-                } else if indexed_count < total_files {
-                    "partial"
-                } else {
-                    "indexed"
-                };
-
-                Ok(PathIndexStatus {
-                    path,
-                    indexed: indexed_count > 0,
-                    indexed_at: None,
-                    file_count: Some(total_files),
-                    indexed_file_count: Some(indexed_count),
-                    status: status.to_string(),
-                })
-            }
-            Err(e) => Err(e.to_string()),
-        }
-    } else {
-        // For files, check if it exists in index
-        let query = Query::Metadata {
-            category: None,
-            mime_type: None,
-            min_size: None,
-            max_size: None,
-            extension: None,
-            path_prefix: Some(path_buf.to_string_lossy().to_string()),
-        };
-
-        let qp = db.query_planner();
-        match qp.execute(&query) {
-            Ok(result) => {
-                let indexed = !result.hits.is_empty();
-                let indexed_at = if indexed {
-                    result.hits.first().and_then(|hit| {
-                        hit.metadata.indexed_at.map(|dt| dt.to_rfc3339())
-                    })
+    state
+        .audited(
+            "get_path_index_status",
+            serde_json::json!({ "path": path }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                let path_buf = PathBuf::from(&path);
+
+                // Check if path is a directory or file
+                let is_dir = path_buf.is_dir();
+
+                if is_dir {
+                    // For directories, count files and indexed files
+                    let query = Query::Metadata {
+                        category: None,
+                        mime_type: None,
+                        min_size: None,
+                        max_size: None,
+                        extension: None,
+                        path_prefix: Some(path_buf.to_string_lossy().to_string()),
+                    };
+
+                    let qp = db.query_planner();
+                    match qp.execute(&query) {
+                        Ok(result) => {
+                            let indexed_count = result.hits.len() as u64;
+
+                            // Count total files in directory (simplified)
+                            let total_files = count_files_in_dir(&path_buf).unwrap_or(0);
+
+                            let status = if indexed_count == 0 {
+                                "not_indexed"
+                            } else if indexed_count < total_files {
+                                "partial"
+                            } else {
+                                "indexed"
+                            };
+
+                            Ok(PathIndexStatus {
+                                path,
+                                indexed: indexed_count > 0,
+                                indexed_at: None,
+                                file_count: Some(total_files),
+                                indexed_file_count: Some(indexed_count),
+                                status: status.to_string(),
+                            })
+                        }
+                        Err(e) => Err(e.to_string()),
+                    }
                 } else {
-                    None
-                };
-
-                Ok(PathIndexStatus {
-                    path,
-                    indexed,
-                    indexed_at,
-                    file_count: Some(1),
-                    indexed_file_count: if indexed { Some(1) } else { Some(0) },
-                    status: if indexed { "indexed" } else { "not_indexed" }.to_string(),
-                })
-            }
-            Err(e) => Err(e.to_string()),
-        }
-    }
+                    // For files, check if it exists in index
+                    let query = Query::Metadata {
+                        category: None,
+                        mime_type: None,
+                        min_size: None,
+                        max_size: None,
+                        extension: None,
+                        path_prefix: Some(path_buf.to_string_lossy().to_string()),
+                    };
+
+                    let qp = db.query_planner();
+                    match qp.execute(&query) {
+                        Ok(result) => {
+                            let indexed = !result.hits.is_empty();
+                            let indexed_at = if indexed {
+                                result.hits.first().and_then(|hit| {
+                                    let metadata = hit.metadata.as_ref()?;
+                                    metadata.indexed_at.map(|dt| dt.to_rfc3339())
+                                })
+                            } else {
+                                None
+                            };
+
+                            Ok(PathIndexStatus {
+                                path,
+                                indexed,
+                                indexed_at,
+                                file_count: Some(1),
+                                indexed_file_count: if indexed { Some(1) } else { Some(0) },
+                                status: if indexed { "indexed" } else { "not_indexed" }.to_string(),
+                            })
+                        }
+                        Err(e) => Err(e.to_string()),
+                    }
+                }
+            },
+        )
+        .await
 }
 
 /// Helper function to count files in a directory
@@ -604,3 +1749,86 @@ fn count_files_in_dir(dir: &PathBuf) -> Result<u64, std::io::Error> {
     count_recursive(dir, &mut count)?;
     Ok(count)
 }
+
+/// Most recent audit log entries, newest first
+#[tauri::command]
+pub async fn get_audit_log(
+    limit: usize,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<AuditEntry>, String> {
+    state
+        .audited(
+            "get_audit_log",
+            serde_json::json!({ "limit": limit }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                let log = db
+                    .get_auxiliary_db()
+                    .audit_log()
+                    .map_err(|e| e.to_string())?;
+                log.recent(limit).map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Write the project's audit log to a CSV file; returns the number of entries written
+#[tauri::command]
+pub async fn export_audit_log_csv(
+    output_path: String,
+    state: State<'_, DatabaseState>,
+) -> Result<u64, String> {
+    state
+        .audited(
+            "export_audit_log_csv",
+            serde_json::json!({ "outputPath": output_path }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                let log = db
+                    .get_auxiliary_db()
+                    .audit_log()
+                    .map_err(|e| e.to_string())?;
+                log.export_csv(&PathBuf::from(&output_path))
+                    .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
+
+/// Run a query and write its results as an HTML report for the case file
+/// Requires case metadata, which fills the report header
+#[tauri::command]
+pub async fn export_html_report(
+    query: Query,
+    output_path: String,
+    options: Option<ReportOptions>,
+    state: State<'_, DatabaseState>,
+) -> Result<(), String> {
+    state
+        .audited(
+            "export_html_report",
+            serde_json::json!({ "query": query, "outputPath": output_path, "options": options }),
+            async {
+                let db = state.get_db().await.ok_or("No database open")?;
+                let case_metadata = db
+                    .get_auxiliary_db()
+                    .get_case_metadata()
+                    .map_err(|e| e.to_string())?
+                    .ok_or("Set the case metadata before exporting a report")?;
+                let query_result = db
+                    .query_planner()
+                    .execute(&query)
+                    .map_err(|e| e.to_string())?;
+
+                generate_html_report(
+                    &query,
+                    &query_result,
+                    &case_metadata,
+                    &PathBuf::from(&output_path),
+                    options.unwrap_or_default(),
+                )
+                .map_err(|e| e.to_string())
+            },
+        )
+        .await
+}
@@ -1,5 +1,7 @@
 mod auxiliary;
 pub mod commands;
+mod sqlite_diff;
+mod sqlite_pool;
 
-pub use auxiliary::AuxiliaryProjectDb;
-pub use commands::DatabaseState;
+pub use auxiliary::{AuxiliaryProjectDb, CaseMetadata};
+pub use commands::{DatabaseState, SqliteState};
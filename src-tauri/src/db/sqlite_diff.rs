@@ -0,0 +1,152 @@
+use super::commands::{SqliteColumnInfo, SqliteTableInfo};
+use std::collections::BTreeMap;
+
+/// Structural differences between two SQLite databases (e.g. two snapshots of an app's DB)
+/// "a" is the baseline; deltas are b minus a
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteDiff {
+    pub tables_only_in_a: Vec<String>,
+    pub tables_only_in_b: Vec<String>,
+    /// Every table present in both, including unchanged ones
+    pub shared_tables: Vec<SqliteTableDiff>,
+}
+
+/// Differences in one table present in both databases
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteTableDiff {
+    pub name: String,
+    pub columns_only_in_a: Vec<String>,
+    pub columns_only_in_b: Vec<String>,
+    /// Columns present in both whose declared type or constraints differ
+    pub changed_columns: Vec<SqliteColumnChange>,
+    pub row_count_a: u64,
+    pub row_count_b: u64,
+    pub row_delta: i64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteColumnChange {
+    pub name: String,
+    pub a: SqliteColumnInfo,
+    pub b: SqliteColumnInfo,
+}
+
+/// Compare the tables of two databases, as read by read_sqlite_tables
+pub fn diff_sqlite_tables(a: Vec<SqliteTableInfo>, b: Vec<SqliteTableInfo>) -> SqliteDiff {
+    let mut b: BTreeMap<String, SqliteTableInfo> = b
+        .into_iter()
+        .map(|table| (table.name.clone(), table))
+        .collect();
+
+    let mut tables_only_in_a = Vec::new();
+    let mut shared_tables = Vec::new();
+    for table_a in a {
+        match b.remove(&table_a.name) {
+            Some(table_b) => shared_tables.push(diff_table(table_a, table_b)),
+            None => tables_only_in_a.push(table_a.name),
+        }
+    }
+    tables_only_in_a.sort();
+    shared_tables.sort_by(|x, y| x.name.cmp(&y.name));
+
+    SqliteDiff {
+        tables_only_in_a,
+        // Whatever is left was never matched by a table in a
+        tables_only_in_b: b.into_keys().collect(),
+        shared_tables,
+    }
+}
+
+fn diff_table(a: SqliteTableInfo, b: SqliteTableInfo) -> SqliteTableDiff {
+    let mut columns_b: BTreeMap<String, SqliteColumnInfo> = b
+        .columns
+        .into_iter()
+        .map(|column| (column.name.clone(), column))
+        .collect();
+
+    let mut columns_only_in_a = Vec::new();
+    let mut changed_columns = Vec::new();
+    for column_a in a.columns {
+        match columns_b.remove(&column_a.name) {
+            Some(column_b) if !same_column(&column_a, &column_b) => {
+                changed_columns.push(SqliteColumnChange {
+                    name: column_a.name.clone(),
+                    a: column_a,
+                    b: column_b,
+                })
+            }
+            Some(_) => {}
+            None => columns_only_in_a.push(column_a.name),
+        }
+    }
+
+    SqliteTableDiff {
+        name: a.name,
+        columns_only_in_a,
+        columns_only_in_b: columns_b.into_keys().collect(),
+        changed_columns,
+        row_count_a: a.row_count,
+        row_count_b: b.row_count,
+        row_delta: b.row_count as i64 - a.row_count as i64,
+    }
+}
+
+/// SQLite type names are case-insensitive
+fn same_column(a: &SqliteColumnInfo, b: &SqliteColumnInfo) -> bool {
+    a.data_type.eq_ignore_ascii_case(&b.data_type)
+        && a.nullable == b.nullable
+        && a.primary_key == b.primary_key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::commands::read_sqlite_tables;
+    use rusqlite::{Connection, OpenFlags};
+    use tempfile::TempDir;
+
+    fn tables_of(dir: &TempDir, name: &str, setup: &str) -> Vec<SqliteTableInfo> {
+        let path = dir.path().join(name);
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(setup)
+            .unwrap();
+        let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap();
+        read_sqlite_tables(&conn).unwrap().0
+    }
+
+    #[test]
+    fn test_diff_reports_missing_table_and_added_column() {
+        let dir = TempDir::new().unwrap();
+        let a = tables_of(
+            &dir,
+            "before.db",
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE TABLE logs (id INTEGER, message TEXT);
+             INSERT INTO users (name) VALUES ('alice');",
+        );
+        let b = tables_of(
+            &dir,
+            "after.db",
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT);
+             INSERT INTO users (name) VALUES ('alice'), ('bob'), ('carol');",
+        );
+
+        let diff = diff_sqlite_tables(a, b);
+
+        assert_eq!(diff.tables_only_in_a, vec!["logs"]);
+        assert!(diff.tables_only_in_b.is_empty());
+        assert_eq!(diff.shared_tables.len(), 1);
+
+        let users = &diff.shared_tables[0];
+        assert_eq!(users.name, "users");
+        assert!(users.columns_only_in_a.is_empty());
+        assert_eq!(users.columns_only_in_b, vec!["email"]);
+        assert!(users.changed_columns.is_empty());
+        assert_eq!((users.row_count_a, users.row_count_b), (1, 3));
+        assert_eq!(users.row_delta, 2);
+    }
+}
@@ -0,0 +1,113 @@
+use parking_lot::Mutex;
+use rusqlite::{Connection, OpenFlags};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Maximum number of SQLite connections kept open at once
+const MAX_CONNECTIONS: usize = 10;
+
+/// Read-only SQLite connections kept open between viewer calls
+/// Least recently used connections are closed once MAX_CONNECTIONS is reached
+pub struct SqliteConnectionPool {
+    inner: Mutex<PoolInner>,
+}
+
+#[derive(Default)]
+struct PoolInner {
+    connections: HashMap<PathBuf, Arc<Mutex<Connection>>>,
+    /// Paths from least to most recently used
+    recency: VecDeque<PathBuf>,
+}
+
+impl SqliteConnectionPool {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(PoolInner::default()),
+        }
+    }
+
+    /// Get the connection for a database, opening it read-only if needed
+    pub fn get(&self, path: &Path) -> rusqlite::Result<Arc<Mutex<Connection>>> {
+        let mut inner = self.inner.lock();
+
+        if let Some(conn) = inner.connections.get(path).cloned() {
+            inner.recency.retain(|p| p != path);
+            inner.recency.push_back(path.to_path_buf());
+            return Ok(conn);
+        }
+
+        let conn = Arc::new(Mutex::new(Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?));
+
+        while inner.connections.len() >= MAX_CONNECTIONS {
+            let Some(oldest) = inner.recency.pop_front() else {
+                break;
+            };
+            inner.connections.remove(&oldest);
+        }
+
+        inner
+            .connections
+            .insert(path.to_path_buf(), Arc::clone(&conn));
+        inner.recency.push_back(path.to_path_buf());
+        Ok(conn)
+    }
+
+    /// Close the connection for a database; returns whether one was open
+    /// Calls still holding the connection finish before it is dropped
+    pub fn evict(&self, path: &Path) -> bool {
+        let mut inner = self.inner.lock();
+        inner.recency.retain(|p| p != path);
+        inner.connections.remove(path).is_some()
+    }
+}
+
+impl Default for SqliteConnectionPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reuses_connections_and_evicts_least_recently_used() {
+        let temp_dir = TempDir::new().unwrap();
+        let paths: Vec<PathBuf> = (0..=MAX_CONNECTIONS)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("{}.db", i));
+                Connection::open(&path)
+                    .unwrap()
+                    .execute_batch("CREATE TABLE t (x INTEGER);")
+                    .unwrap();
+                path
+            })
+            .collect();
+
+        let pool = SqliteConnectionPool::new();
+        let first = pool.get(&paths[0]).unwrap();
+        assert!(Arc::ptr_eq(&first, &pool.get(&paths[0]).unwrap()));
+
+        for path in &paths[1..MAX_CONNECTIONS] {
+            pool.get(path).unwrap();
+        }
+        // Touch the first database so the second becomes least recently used
+        pool.get(&paths[0]).unwrap();
+        pool.get(&paths[MAX_CONNECTIONS]).unwrap();
+
+        assert_eq!(pool.inner.lock().connections.len(), MAX_CONNECTIONS);
+        assert!(Arc::ptr_eq(&first, &pool.get(&paths[0]).unwrap()));
+        assert!(pool.evict(&paths[0]));
+        assert!(!pool.evict(&paths[1]));
+
+        // Read-only: writes through a pooled connection are refused
+        let conn = pool.get(&paths[2]).unwrap();
+        assert!(conn.lock().execute("INSERT INTO t VALUES (1)", []).is_err());
+    }
+}
@@ -1,12 +1,58 @@
-use super::archive_settings::{ArchiveFormat, ArchiveSettings, UnpackedArchiveInfo};
+use super::archive_settings::{ArchiveEntry, ArchiveFormat, ArchiveSettings, UnpackedArchiveInfo};
 use anyhow::{Context, Result};
+use bzip2::read::BzDecoder;
 use flate2::read::GzDecoder;
 use std::fs::{self, File};
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use tar::Archive as TarArchive;
+use thiserror::Error;
+use zip::result::ZipError;
 use zip::ZipArchive;
 
+/// Archive formats that were recognised but can't be unpacked
+/// Surfaced per-file so the investigator knows which archives were not looked into
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error(
+        "RAR archives are not supported; extract {path} with an external unrar tool and re-index"
+    )]
+    RarUnsupported { path: PathBuf },
+
+    #[error("Unsupported archive format {format:?}: {path}")]
+    UnsupportedFormat {
+        format: ArchiveFormat,
+        path: PathBuf,
+    },
+
+    #[error("No entry {entry} in archive {archive}")]
+    EntryNotFound { archive: PathBuf, entry: String },
+
+    #[error("Entry {entry} in archive {archive} is larger than the {limit} byte limit")]
+    EntryTooLarge {
+        archive: PathBuf,
+        entry: String,
+        limit: u64,
+    },
+}
+
+impl ArchiveError {
+    fn unsupported(format: ArchiveFormat, path: &Path) -> Self {
+        match format {
+            ArchiveFormat::Rar => Self::RarUnsupported {
+                path: path.to_path_buf(),
+            },
+            _ => Self::UnsupportedFormat {
+                format,
+                path: path.to_path_buf(),
+            },
+        }
+    }
+}
+
+/// Offset of the "ustar" magic in a tar header
+const TAR_MAGIC_OFFSET: usize = 257;
+
 /// Archive extractor that unpacks various archive formats
 pub struct ArchiveExtractor {
     settings: ArchiveSettings,
@@ -47,7 +93,7 @@ impl ArchiveExtractor {
         let format = self.detect_format(archive_path)?;
 
         if !format.is_supported() {
-            anyhow::bail!("Unsupported archive format: {:?}", format);
+            return Err(ArchiveError::unsupported(format, archive_path).into());
         }
 
         // Determine extraction directory
@@ -61,9 +107,11 @@ impl ArchiveExtractor {
             ArchiveFormat::Zip => self.extract_zip(archive_path, &extract_dir)?,
             ArchiveFormat::Tar => self.extract_tar(archive_path, &extract_dir)?,
             ArchiveFormat::TarGz => self.extract_tar_gz(archive_path, &extract_dir)?,
+            ArchiveFormat::TarBz2 => self.extract_tar_bz2(archive_path, &extract_dir)?,
             ArchiveFormat::Gzip => self.extract_gzip(archive_path, &extract_dir)?,
+            ArchiveFormat::Bzip2 => self.extract_bzip2(archive_path, &extract_dir)?,
             ArchiveFormat::SevenZ => self.extract_7z(archive_path, &extract_dir)?,
-            _ => anyhow::bail!("Unsupported format: {:?}", format),
+            _ => return Err(ArchiveError::unsupported(format, archive_path).into()),
         };
 
         Ok(UnpackedArchiveInfo {
@@ -78,15 +126,13 @@ impl ArchiveExtractor {
 
     /// Detect archive format from file
     fn detect_format(&self, path: &Path) -> Result<ArchiveFormat> {
-        // Try extension first
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            if let Some(format) = ArchiveFormat::from_extension(ext) {
-                return Ok(format);
-            }
-        }
-
-        // Check full extension (e.g., .tar.gz)
-        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        // Check the compound suffix first (e.g., .tar.gz); the last extension alone
+        // would classify a tarball as a bare compressed file
+        let filename = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_lowercase();
 
         if filename.ends_with(".tar.gz") {
             return Ok(ArchiveFormat::TarGz);
@@ -96,6 +142,15 @@ impl ArchiveExtractor {
             return Ok(ArchiveFormat::TarXz);
         }
 
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            match ArchiveFormat::from_extension(ext) {
+                // A bare .bz2 may still be a compressed tarball
+                Some(ArchiveFormat::Bzip2) => return Ok(Self::classify_bzip2(path)),
+                Some(format) => return Ok(format),
+                None => {}
+            }
+        }
+
         // Read magic bytes
         let mut file = File::open(path)?;
         let mut magic = [0u8; 8];
@@ -116,9 +171,30 @@ impl ArchiveExtractor {
             return Ok(ArchiveFormat::SevenZ);
         }
 
+        // Bzip2: BZh
+        if &magic[0..3] == b"BZh" {
+            return Ok(Self::classify_bzip2(path));
+        }
+
         anyhow::bail!("Could not detect archive format for {:?}", path)
     }
 
+    /// Tell a bzip2-compressed tarball from a single bzip2-compressed file
+    /// Decompresses just the first tar header and looks for the ustar magic
+    fn classify_bzip2(path: &Path) -> ArchiveFormat {
+        let mut header = [0u8; TAR_MAGIC_OFFSET + 5];
+        let is_tar = File::open(path)
+            .and_then(|file| BzDecoder::new(file).read_exact(&mut header))
+            .is_ok()
+            && &header[TAR_MAGIC_OFFSET..] == b"ustar";
+
+        if is_tar {
+            ArchiveFormat::TarBz2
+        } else {
+            ArchiveFormat::Bzip2
+        }
+    }
+
     /// Get extraction directory based on settings
     fn get_extract_directory(
         &self,
@@ -190,33 +266,23 @@ impl ArchiveExtractor {
     /// Extract TAR archive
     fn extract_tar(&self, archive_path: &Path, extract_dir: &Path) -> Result<(usize, u64)> {
         let file = File::open(archive_path)?;
-        let mut archive = TarArchive::new(file);
-
-        let mut file_count = 0;
-        let mut total_size = 0u64;
-
-        for entry_result in archive.entries()? {
-            let mut entry = entry_result?;
-            let path = entry.path()?;
-            let outpath = extract_dir.join(path);
-
-            entry.unpack(&outpath)?;
-
-            if entry.header().entry_type().is_file() {
-                file_count += 1;
-                total_size += entry.header().size()?;
-            }
-        }
-
-        Ok((file_count, total_size))
+        Self::unpack_tar(TarArchive::new(file), extract_dir)
     }
 
     /// Extract TAR.GZ archive
     fn extract_tar_gz(&self, archive_path: &Path, extract_dir: &Path) -> Result<(usize, u64)> {
         let file = File::open(archive_path)?;
-        let decoder = GzDecoder::new(file);
-        let mut archive = TarArchive::new(decoder);
+        Self::unpack_tar(TarArchive::new(GzDecoder::new(file)), extract_dir)
+    }
+
+    /// Extract TAR.BZ2 archive
+    fn extract_tar_bz2(&self, archive_path: &Path, extract_dir: &Path) -> Result<(usize, u64)> {
+        let file = File::open(archive_path)?;
+        Self::unpack_tar(TarArchive::new(BzDecoder::new(file)), extract_dir)
+    }
 
+    /// Unpack every entry of a tar stream, counting regular files and their bytes
+    fn unpack_tar<R: Read>(mut archive: TarArchive<R>, extract_dir: &Path) -> Result<(usize, u64)> {
         let mut file_count = 0;
         let mut total_size = 0u64;
 
@@ -255,6 +321,22 @@ impl ArchiveExtractor {
         Ok((1, size))
     }
 
+    /// Extract BZIP2 file (single file compression)
+    fn extract_bzip2(&self, archive_path: &Path, extract_dir: &Path) -> Result<(usize, u64)> {
+        let mut decoder = BzDecoder::new(File::open(archive_path)?);
+
+        // Get output filename (remove .bz2 extension)
+        let stem = archive_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("decompressed");
+
+        let mut outfile = File::create(extract_dir.join(stem))?;
+        let size = io::copy(&mut decoder, &mut outfile)?;
+
+        Ok((1, size))
+    }
+
     /// Extract 7z archive
     fn extract_7z(&self, archive_path: &Path, extract_dir: &Path) -> Result<(usize, u64)> {
         use sevenz_rust::decompress_file;
@@ -289,6 +371,121 @@ impl ArchiveExtractor {
         Ok((file_count, total_size))
     }
 
+    /// List the entries of a zip or tar archive without extracting it
+    pub fn list_entries(&self, archive_path: &Path) -> Result<Vec<ArchiveEntry>> {
+        let format = self.detect_format(archive_path)?;
+        let file = File::open(archive_path)?;
+
+        match format {
+            ArchiveFormat::Zip => {
+                let mut archive = ZipArchive::new(file)?;
+                let mut entries = Vec::with_capacity(archive.len());
+                for i in 0..archive.len() {
+                    // Raw access reads only the headers, nothing is decompressed
+                    let entry = archive.by_index_raw(i)?;
+                    entries.push(ArchiveEntry {
+                        path: entry.name().to_string(),
+                        size: entry.size(),
+                        is_dir: entry.is_dir(),
+                    });
+                }
+                Ok(entries)
+            }
+            ArchiveFormat::Tar => Self::list_tar_entries(TarArchive::new(file)),
+            ArchiveFormat::TarGz => Self::list_tar_entries(TarArchive::new(GzDecoder::new(file))),
+            ArchiveFormat::TarBz2 => Self::list_tar_entries(TarArchive::new(BzDecoder::new(file))),
+            _ => Err(ArchiveError::unsupported(format, archive_path).into()),
+        }
+    }
+
+    /// Read a single entry of a zip or tar archive into memory
+    /// Nothing is written to disk, so files can be previewed without unpacking the archive
+    /// Entries over max_archive_size fail with EntryTooLarge, whatever their header claims
+    pub fn read_entry(&self, archive_path: &Path, entry_path: &str) -> Result<Vec<u8>> {
+        let format = self.detect_format(archive_path)?;
+        let file = File::open(archive_path)?;
+        let not_found = || ArchiveError::EntryNotFound {
+            archive: archive_path.to_path_buf(),
+            entry: entry_path.to_string(),
+        };
+        let limit = self.settings.max_archive_size.unwrap_or(u64::MAX);
+
+        let bytes = match format {
+            ArchiveFormat::Zip => {
+                let mut archive = ZipArchive::new(file)?;
+                let mut entry = match archive.by_name(entry_path) {
+                    Ok(entry) if !entry.is_dir() => entry,
+                    Ok(_) | Err(ZipError::FileNotFound) => return Err(not_found().into()),
+                    Err(e) => return Err(e.into()),
+                };
+                let declared_size = entry.size();
+                Some(Self::read_capped(&mut entry, declared_size, limit)?)
+            }
+            ArchiveFormat::Tar => Self::read_tar_entry(TarArchive::new(file), entry_path, limit)?,
+            ArchiveFormat::TarGz => {
+                Self::read_tar_entry(TarArchive::new(GzDecoder::new(file)), entry_path, limit)?
+            }
+            ArchiveFormat::TarBz2 => {
+                Self::read_tar_entry(TarArchive::new(BzDecoder::new(file)), entry_path, limit)?
+            }
+            _ => return Err(ArchiveError::unsupported(format, archive_path).into()),
+        };
+
+        let bytes = bytes.ok_or_else(not_found)?;
+        if bytes.len() as u64 > limit {
+            return Err(ArchiveError::EntryTooLarge {
+                archive: archive_path.to_path_buf(),
+                entry: entry_path.to_string(),
+                limit,
+            }
+            .into());
+        }
+        Ok(bytes)
+    }
+
+    /// Read at most limit + 1 bytes, so an oversized entry is noticed without reading it all
+    /// The size from the entry's header only pre-sizes the buffer, and never beyond limit
+    fn read_capped(entry: impl Read, declared_size: u64, limit: u64) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(declared_size.min(limit) as usize);
+        entry
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn list_tar_entries<R: Read>(mut archive: TarArchive<R>) -> Result<Vec<ArchiveEntry>> {
+        let mut entries = Vec::new();
+        for entry_result in archive.entries()? {
+            let entry = entry_result?;
+            entries.push(ArchiveEntry {
+                path: entry.path()?.to_string_lossy().to_string(),
+                size: entry.header().size()?,
+                is_dir: entry.header().entry_type().is_dir(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Scan a tar stream for a regular file entry; tar has no index, so this reads up to it
+    fn read_tar_entry<R: Read>(
+        mut archive: TarArchive<R>,
+        entry_path: &str,
+        limit: u64,
+    ) -> Result<Option<Vec<u8>>> {
+        let wanted = entry_path.trim_start_matches("./");
+        for entry_result in archive.entries()? {
+            let mut entry = entry_result?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            if entry.path()?.to_string_lossy().trim_start_matches("./") == wanted {
+                let declared_size = entry.header().size()?;
+                return Ok(Some(Self::read_capped(&mut entry, declared_size, limit)?));
+            }
+        }
+        Ok(None)
+    }
+
     /// Check if path is an archive based on settings
     pub fn is_archive(&self, path: &Path) -> bool {
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -300,3 +497,166 @@ impl ArchiveExtractor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rar_reports_specific_unsupported_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let rar_path = temp_dir.path().join("evidence.rar");
+        std::fs::write(&rar_path, b"Rar!\x1a\x07\x00rest-of-archive").unwrap();
+
+        let extractor = ArchiveExtractor::new(ArchiveSettings::default());
+        assert!(extractor.is_archive(&rar_path));
+
+        let err = extractor.unpack(&rar_path, temp_dir.path(), 0).unwrap_err();
+        match err.downcast_ref::<ArchiveError>() {
+            Some(ArchiveError::RarUnsupported { path }) => assert_eq!(path, &rar_path),
+            other => panic!("expected RarUnsupported, got {:?}", other),
+        }
+        assert!(err.to_string().contains("unrar"));
+    }
+
+    #[test]
+    fn test_zip_entries_listed_and_read_without_extracting() {
+        let temp_dir = TempDir::new().unwrap();
+        let zip_path = temp_dir.path().join("evidence.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("docs/", options).unwrap();
+        writer.start_file("docs/notes.txt", options).unwrap();
+        writer.write_all(b"meet at the docks").unwrap();
+        writer.start_file("photo.jpg", options).unwrap();
+        writer.write_all(&[0xFF, 0xD8, 0xFF]).unwrap();
+        writer.finish().unwrap();
+
+        let extractor = ArchiveExtractor::new(ArchiveSettings::default());
+        let entries = extractor.list_entries(&zip_path).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ArchiveEntry {
+                    path: "docs/".to_string(),
+                    size: 0,
+                    is_dir: true,
+                },
+                ArchiveEntry {
+                    path: "docs/notes.txt".to_string(),
+                    size: 17,
+                    is_dir: false,
+                },
+                ArchiveEntry {
+                    path: "photo.jpg".to_string(),
+                    size: 3,
+                    is_dir: false,
+                },
+            ]
+        );
+
+        let bytes = extractor.read_entry(&zip_path, "docs/notes.txt").unwrap();
+        assert_eq!(bytes, b"meet at the docks");
+        let capped = ArchiveExtractor::new(ArchiveSettings {
+            max_archive_size: Some(8),
+            ..ArchiveSettings::default()
+        });
+        let err = capped.read_entry(&zip_path, "docs/notes.txt").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ArchiveError>(),
+            Some(ArchiveError::EntryTooLarge { limit: 8, .. })
+        ));
+        let err = extractor.read_entry(&zip_path, "missing.txt").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ArchiveError>(),
+            Some(ArchiveError::EntryNotFound { .. })
+        ));
+
+        // Only the archive itself is on disk
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_tar_gz_entry_read_by_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let tar_path = temp_dir.path().join("logs.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(&tar_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "var/log/auth.log", &b"login"[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let extractor = ArchiveExtractor::new(ArchiveSettings::default());
+        let entries = extractor.list_entries(&tar_path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "var/log/auth.log");
+        assert_eq!(
+            extractor.read_entry(&tar_path, "var/log/auth.log").unwrap(),
+            b"login"
+        );
+    }
+
+    #[test]
+    fn test_bzip2_single_file_and_tarball_classified_by_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let extractor = ArchiveExtractor::new(ArchiveSettings::default());
+
+        let single_path = temp_dir.path().join("notes.bz2");
+        let mut encoder = bzip2::write::BzEncoder::new(
+            File::create(&single_path).unwrap(),
+            bzip2::Compression::default(),
+        );
+        encoder.write_all(b"meeting at noon").unwrap();
+        encoder.finish().unwrap();
+        assert_eq!(
+            extractor.detect_format(&single_path).unwrap(),
+            ArchiveFormat::Bzip2
+        );
+
+        let write_tar_bz2 = |path: &Path| {
+            let encoder = bzip2::write::BzEncoder::new(
+                File::create(path).unwrap(),
+                bzip2::Compression::default(),
+            );
+            let mut builder = tar::Builder::new(encoder);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(5);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, "home/user/.bash_history", &b"ls -a"[..])
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        };
+
+        let tar_path = temp_dir.path().join("home.tar.bz2");
+        write_tar_bz2(&tar_path);
+        assert_eq!(
+            extractor.detect_format(&tar_path).unwrap(),
+            ArchiveFormat::TarBz2
+        );
+
+        // Mis-extensioned tarballs are recognised from their content
+        for name in ["home.bz2", "home.bin"] {
+            let path = temp_dir.path().join(name);
+            write_tar_bz2(&path);
+            assert_eq!(
+                extractor.detect_format(&path).unwrap(),
+                ArchiveFormat::TarBz2
+            );
+            assert_eq!(
+                extractor
+                    .read_entry(&path, "home/user/.bash_history")
+                    .unwrap(),
+                b"ls -a"
+            );
+        }
+    }
+}
@@ -69,6 +69,19 @@ pub struct UnpackedArchiveInfo {
     pub format: ArchiveFormat,
 }
 
+/// A file or directory inside an archive, listed without unpacking it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    /// Path inside the archive, as stored by the archiver
+    pub path: String,
+
+    /// Uncompressed size in bytes
+    pub size: u64,
+
+    pub is_dir: bool,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ArchiveFormat {
@@ -106,7 +119,7 @@ impl ArchiveFormat {
     /// Check if format is supported
     pub fn is_supported(&self) -> bool {
         match self {
-            Self::Zip | Self::Tar | Self::TarGz | Self::TarBz2 | Self::Gzip => true,
+            Self::Zip | Self::Tar | Self::TarGz | Self::TarBz2 | Self::Gzip | Self::Bzip2 => true,
             Self::SevenZ => true,
             Self::Rar => false, // RAR requires proprietary library
             _ => false,
@@ -0,0 +1,495 @@
+use super::detector::{JPEG_MAGIC, PDF_MAGIC, PNG_MAGIC, ZIP_MAGIC};
+use anyhow::{Context, Result};
+use memchr::memmem::Finder;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Default size cap for carved images
+const MAX_IMAGE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// Default size cap for carved documents and archives
+const MAX_CONTAINER_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Default cap on the number of files carved in one run
+const DEFAULT_MAX_CARVED_FILES: usize = 10_000;
+
+/// Default cap on the bytes written in one run
+const DEFAULT_MAX_TOTAL_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Bytes read per step while scanning for a signature, so memory use doesn't grow with the image
+const SCAN_CHUNK_LEN: usize = 1024 * 1024;
+
+/// Signature of a file type to recover from raw data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarvingTarget {
+    /// Bytes every file of this type starts with
+    pub magic_start: Vec<u8>,
+
+    /// Bytes that mark the end of the file (the carved region includes them)
+    /// Without one, max_size bytes are taken from each match
+    pub magic_end: Option<Vec<u8>>,
+
+    /// Largest region carved for a single match, in bytes
+    pub max_size: u64,
+
+    /// Extension given to carved files, without the dot
+    pub output_extension: String,
+
+    /// Bytes after magic_end that still belong to the file
+    /// (e.g. the rest of a ZIP end-of-central-directory record)
+    #[serde(default)]
+    pub trailer_size: u64,
+}
+
+impl CarvingTarget {
+    pub fn jpeg() -> Self {
+        Self {
+            // Start-of-image plus the first segment's marker byte, to cut down on false hits
+            magic_start: [JPEG_MAGIC, b"\xFF"].concat(),
+            magic_end: Some(vec![0xFF, 0xD9]),
+            max_size: MAX_IMAGE_SIZE,
+            output_extension: "jpg".to_string(),
+            trailer_size: 0,
+        }
+    }
+
+    pub fn png() -> Self {
+        Self {
+            magic_start: PNG_MAGIC.to_vec(),
+            // IEND chunk type followed by its CRC
+            magic_end: Some(b"IEND\xAE\x42\x60\x82".to_vec()),
+            max_size: MAX_IMAGE_SIZE,
+            output_extension: "png".to_string(),
+            trailer_size: 0,
+        }
+    }
+
+    pub fn pdf() -> Self {
+        Self {
+            magic_start: [PDF_MAGIC, b"-"].concat(),
+            magic_end: Some(b"%%EOF".to_vec()),
+            max_size: MAX_CONTAINER_SIZE,
+            output_extension: "pdf".to_string(),
+            trailer_size: 0,
+        }
+    }
+
+    pub fn zip() -> Self {
+        Self {
+            magic_start: ZIP_MAGIC.to_vec(),
+            magic_end: Some(b"PK\x05\x06".to_vec()),
+            max_size: MAX_CONTAINER_SIZE,
+            output_extension: "zip".to_string(),
+            // Fixed part of the end-of-central-directory record after its signature
+            trailer_size: 18,
+        }
+    }
+
+    /// Every built-in target
+    pub fn all() -> Vec<Self> {
+        vec![Self::jpeg(), Self::png(), Self::pdf(), Self::zip()]
+    }
+}
+
+/// Bounds on one carving run, since an image can hold far more signature hits than files
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarvingLimits {
+    /// Most files written before the scan stops
+    pub max_files: usize,
+
+    /// Most bytes written before the scan stops
+    pub max_total_bytes: u64,
+
+    /// Carve hits whose end marker isn't within max_size, cut at max_size and marked
+    /// truncated; off by default, since most of them are noise rather than partial files
+    #[serde(default)]
+    pub keep_truncated: bool,
+}
+
+impl Default for CarvingLimits {
+    fn default() -> Self {
+        Self {
+            max_files: DEFAULT_MAX_CARVED_FILES,
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+            keep_truncated: false,
+        }
+    }
+}
+
+/// What a carving run recovered, and what it left out
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CarvingReport {
+    pub files: Vec<CarvedFile>,
+
+    /// Hits skipped because their end marker wasn't found (keep_truncated off)
+    pub truncated_skipped: usize,
+
+    /// The scan stopped at max_files or max_total_bytes, so later hits weren't carved
+    pub limit_reached: bool,
+}
+
+/// A region recovered from the scanned file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarvedFile {
+    /// Where the carved file was written
+    pub path: PathBuf,
+
+    /// Byte offset of the region in the scanned file
+    pub offset: u64,
+
+    /// Size of the carved region in bytes
+    pub size: u64,
+
+    pub extension: String,
+
+    /// The end marker wasn't found within max_size, so the file is probably incomplete
+    pub truncated: bool,
+}
+
+/// Recovers files embedded in disk images, memory dumps and other binary blobs
+pub struct FileCarver;
+
+impl FileCarver {
+    /// Scan path for each target's signature and write the matching regions to output_dir
+    /// Files are named after their offset so they can be traced back to the source.
+    /// Once a complete file is carved the scan resumes after it, so e.g. the entries inside
+    /// a ZIP are not carved again as separate archives. The image is read a chunk at a
+    /// time and regions are copied straight to disk, so images larger than memory work
+    pub fn carve(
+        path: &Path,
+        output_dir: &Path,
+        targets: &[CarvingTarget],
+        limits: &CarvingLimits,
+    ) -> Result<CarvingReport> {
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let len = file.metadata()?.len();
+        let mut report = CarvingReport::default();
+        if len == 0 {
+            return Ok(report);
+        }
+
+        fs::create_dir_all(output_dir).with_context(|| {
+            format!("Failed to create output directory {}", output_dir.display())
+        })?;
+
+        // All start signatures are searched in one pass; the chunk overlaps the next by
+        // the longest signature minus one so a match across a boundary isn't missed
+        let targets: Vec<&CarvingTarget> = targets
+            .iter()
+            .filter(|target| !target.magic_start.is_empty())
+            .collect();
+        let finders: Vec<Finder> = targets
+            .iter()
+            .map(|target| Finder::new(&target.magic_start))
+            .collect();
+        let overlap = targets
+            .iter()
+            .map(|target| target.magic_start.len() - 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut chunk = vec![0u8; SCAN_CHUNK_LEN + overlap];
+        let mut end_buffer = Vec::new();
+        let mut hits: Vec<(u64, usize)> = Vec::new();
+        let mut resume_at = vec![0u64; targets.len()];
+        let mut total_bytes = 0u64;
+        let mut chunk_start = 0u64;
+        'scan: while !targets.is_empty() && chunk_start < len {
+            let chunk_len = chunk.len().min((len - chunk_start) as usize);
+            file.seek(SeekFrom::Start(chunk_start))
+                .and_then(|_| file.read_exact(&mut chunk[..chunk_len]))
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+
+            // Matches starting in the overlap are picked up by the next chunk instead
+            let is_last = chunk_start + chunk_len as u64 == len;
+            let advance = if is_last {
+                chunk_len
+            } else {
+                chunk_len - overlap
+            };
+
+            hits.clear();
+            for (index, finder) in finders.iter().enumerate() {
+                hits.extend(
+                    finder
+                        .find_iter(&chunk[..chunk_len])
+                        .take_while(|&found| found < advance)
+                        .map(|found| (chunk_start + found as u64, index)),
+                );
+            }
+            hits.sort_unstable();
+
+            for &(start, index) in &hits {
+                let target = targets[index];
+                if start < resume_at[index] {
+                    continue;
+                }
+
+                let (end, truncated) =
+                    Self::region_end(&mut file, start, len, target, &mut end_buffer)
+                        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+                if truncated && !limits.keep_truncated {
+                    report.truncated_skipped += 1;
+                    resume_at[index] = start + target.magic_start.len() as u64;
+                    continue;
+                }
+                if report.files.len() >= limits.max_files
+                    || total_bytes + (end - start) > limits.max_total_bytes
+                {
+                    report.limit_reached = true;
+                    break 'scan;
+                }
+                total_bytes += end - start;
+
+                let output_path =
+                    output_dir.join(format!("carved_{:012x}.{}", start, target.output_extension));
+                copy_range(&mut file, start, end, &output_path)
+                    .with_context(|| format!("Failed to write {}", output_path.display()))?;
+
+                report.files.push(CarvedFile {
+                    path: output_path,
+                    offset: start,
+                    size: end - start,
+                    extension: target.output_extension.clone(),
+                    truncated,
+                });
+
+                resume_at[index] = if truncated || target.magic_end.is_none() {
+                    start + target.magic_start.len() as u64
+                } else {
+                    end
+                };
+            }
+
+            chunk_start += advance as u64;
+        }
+
+        report.files.sort_by_key(|file| file.offset);
+        Ok(report)
+    }
+
+    /// End of the region starting at start, and whether it was cut short of the end marker
+    fn region_end(
+        file: &mut File,
+        start: u64,
+        len: u64,
+        target: &CarvingTarget,
+        buffer: &mut Vec<u8>,
+    ) -> io::Result<(u64, bool)> {
+        let limit = start.saturating_add(target.max_size).min(len);
+
+        let Some(magic_end) = &target.magic_end else {
+            return Ok((limit, false));
+        };
+
+        let search_from = (start + target.magic_start.len() as u64).min(limit);
+        Ok(
+            match find_in_range(file, search_from, limit, magic_end, buffer)? {
+                Some(found) => {
+                    let end = found + magic_end.len() as u64;
+                    (end.saturating_add(target.trailer_size).min(len), false)
+                }
+                None => (limit, true),
+            },
+        )
+    }
+}
+
+/// Offset of the first occurrence of needle in file[from..to], reading into buffer
+fn find_in_range(
+    file: &mut File,
+    from: u64,
+    to: u64,
+    needle: &[u8],
+    buffer: &mut Vec<u8>,
+) -> io::Result<Option<u64>> {
+    if needle.is_empty() {
+        return Ok(None);
+    }
+
+    // Chunks overlap by needle.len() - 1 bytes so a match across a boundary isn't missed
+    buffer.resize(SCAN_CHUNK_LEN + needle.len() - 1, 0);
+    let finder = Finder::new(needle);
+    let mut chunk_start = from;
+    while chunk_start.saturating_add(needle.len() as u64) <= to {
+        let chunk_len = buffer.len().min((to - chunk_start) as usize);
+        file.seek(SeekFrom::Start(chunk_start))?;
+        file.read_exact(&mut buffer[..chunk_len])?;
+
+        if let Some(found) = finder.find(&buffer[..chunk_len]) {
+            return Ok(Some(chunk_start + found as u64));
+        }
+        chunk_start += (chunk_len - (needle.len() - 1)) as u64;
+    }
+    Ok(None)
+}
+
+/// Copy file[start..end] to a new file at output_path
+fn copy_range(file: &mut File, start: u64, end: u64, output_path: &Path) -> io::Result<()> {
+    file.seek(SeekFrom::Start(start))?;
+    let mut output = File::create(output_path)?;
+    io::copy(&mut file.take(end - start), &mut output)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_carves_embedded_files_by_signature() {
+        let dir = TempDir::new().unwrap();
+        let jpeg = [&[0xFF, 0xD8, 0xFF, 0xE0][..], b"jfif data", &[0xFF, 0xD9]].concat();
+        let pdf = b"%PDF-1.7 body %%EOF".to_vec();
+
+        let mut dump = vec![0u8; 100];
+        dump.extend_from_slice(&jpeg);
+        dump.extend_from_slice(&[0u8; 50]);
+        dump.extend_from_slice(&pdf);
+        dump.extend_from_slice(&[0u8; 20]);
+        // PNG header with no IEND before the end of the dump
+        dump.extend_from_slice(b"\x89PNG\r\n\x1a\nbroken");
+        let source = dir.path().join("disk.img");
+        std::fs::write(&source, &dump).unwrap();
+
+        let output = dir.path().join("carved");
+        let limits = CarvingLimits {
+            keep_truncated: true,
+            ..CarvingLimits::default()
+        };
+        let carved = FileCarver::carve(&source, &output, &CarvingTarget::all(), &limits)
+            .unwrap()
+            .files;
+
+        assert_eq!(carved.len(), 3);
+
+        assert_eq!(carved[0].offset, 100);
+        assert_eq!(carved[0].extension, "jpg");
+        assert!(!carved[0].truncated);
+        assert_eq!(std::fs::read(&carved[0].path).unwrap(), jpeg);
+
+        assert_eq!(carved[1].offset, 100 + jpeg.len() as u64 + 50);
+        assert_eq!(std::fs::read(&carved[1].path).unwrap(), pdf);
+
+        assert_eq!(carved[2].extension, "png");
+        assert!(carved[2].truncated);
+        assert_eq!(carved[2].size, 14);
+    }
+
+    #[test]
+    fn test_carves_jpeg_spanning_scan_chunks() {
+        let dir = TempDir::new().unwrap();
+        // Large enough that neither the scan nor the end-marker search fits in one chunk
+        let body: Vec<u8> = (0..SCAN_CHUNK_LEN + 1000)
+            .map(|i| (i % 251) as u8 & 0x7F)
+            .collect();
+        let jpeg = [&[0xFF, 0xD8, 0xFF, 0xE1][..], &body, &[0xFF, 0xD9]].concat();
+
+        let mut image = vec![0u8; SCAN_CHUNK_LEN - 2];
+        image.extend_from_slice(&jpeg);
+        image.extend_from_slice(&[0u8; 512]);
+        let source = dir.path().join("disk.dd");
+        std::fs::write(&source, &image).unwrap();
+
+        // Scanning for every signature at once must still catch the JPEG across the boundary
+        let carved = FileCarver::carve(
+            &source,
+            dir.path().join("out").as_path(),
+            &CarvingTarget::all(),
+            &CarvingLimits::default(),
+        )
+        .unwrap()
+        .files;
+
+        assert_eq!(carved.len(), 1);
+        assert_eq!(carved[0].offset, SCAN_CHUNK_LEN as u64 - 2);
+        assert_eq!(carved[0].size, jpeg.len() as u64);
+        assert!(!carved[0].truncated);
+        assert_eq!(std::fs::read(&carved[0].path).unwrap(), jpeg);
+    }
+
+    #[test]
+    fn test_carved_zip_includes_end_record_and_skips_inner_entries() {
+        let dir = TempDir::new().unwrap();
+        let mut zip = Vec::new();
+        {
+            let mut writer = zip::ZipWriter::new(std::io::Cursor::new(&mut zip));
+            let options = zip::write::SimpleFileOptions::default();
+            for name in ["a.txt", "b.txt"] {
+                writer.start_file(name, options).unwrap();
+                std::io::Write::write_all(&mut writer, b"evidence").unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let mut dump = vec![0xAAu8; 64];
+        dump.extend_from_slice(&zip);
+        dump.extend_from_slice(&[0xAAu8; 64]);
+        let source = dir.path().join("memory.dmp");
+        std::fs::write(&source, &dump).unwrap();
+
+        let carved = FileCarver::carve(
+            &source,
+            dir.path().join("out").as_path(),
+            &[CarvingTarget::zip()],
+            &CarvingLimits::default(),
+        )
+        .unwrap()
+        .files;
+
+        assert_eq!(carved.len(), 1);
+        assert_eq!(std::fs::read(&carved[0].path).unwrap(), zip);
+        let archive = zip::ZipArchive::new(File::open(&carved[0].path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 2);
+    }
+
+    #[test]
+    fn test_truncated_hits_skipped_and_budget_enforced() {
+        let dir = TempDir::new().unwrap();
+        let jpeg = [&[0xFF, 0xD8, 0xFF, 0xE0][..], b"jfif data", &[0xFF, 0xD9]].concat();
+        let mut dump = Vec::new();
+        for _ in 0..3 {
+            dump.extend_from_slice(&[0u8; 32]);
+            dump.extend_from_slice(&jpeg);
+        }
+        // Start of a JPEG whose end marker never comes
+        dump.extend_from_slice(&[0xFF, 0xD8, 0xFF, 0xE0, 1, 2, 3]);
+        let source = dir.path().join("disk.img");
+        std::fs::write(&source, &dump).unwrap();
+        let output = dir.path().join("out");
+
+        let report = FileCarver::carve(
+            &source,
+            &output,
+            &[CarvingTarget::jpeg()],
+            &CarvingLimits::default(),
+        )
+        .unwrap();
+        assert_eq!(report.files.len(), 3);
+        assert_eq!(report.truncated_skipped, 1);
+        assert!(!report.limit_reached);
+
+        let by_count = CarvingLimits {
+            max_files: 2,
+            ..CarvingLimits::default()
+        };
+        let report =
+            FileCarver::carve(&source, &output, &[CarvingTarget::jpeg()], &by_count).unwrap();
+        assert_eq!(report.files.len(), 2);
+        assert!(report.limit_reached);
+
+        let by_size = CarvingLimits {
+            max_total_bytes: jpeg.len() as u64,
+            ..CarvingLimits::default()
+        };
+        let report =
+            FileCarver::carve(&source, &output, &[CarvingTarget::jpeg()], &by_size).unwrap();
+        assert_eq!(report.files.len(), 1);
+        assert!(report.limit_reached);
+    }
+}
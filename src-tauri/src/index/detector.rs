@@ -1,17 +1,74 @@
+use super::extractors::pst::{PST_MAGIC, PST_MIME};
 use super::schema::FileCategory;
+use serde::Serialize;
 use std::fs::File;
 use std::io::{self, Read};
 use std::path::Path;
 
+/// OLE2 compound document magic (D0 CF 11 E0 A1 B1 1A E1)
+const OLE2_MAGIC: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+/// ZIP local file header, also the start of Office Open XML documents
+pub const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+pub const PDF_MAGIC: &[u8] = b"%PDF";
+
+pub const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// JPEG start-of-image marker
+pub const JPEG_MAGIC: &[u8] = b"\xFF\xD8";
+
+/// Generic MIME type for OLE2 containers that aren't a known Office format
+const OLE2_MIME: &str = "application/x-ole-storage";
+
+/// Single RFC 2822 message
+pub const EML_MIME: &str = "message/rfc822";
+
+/// Unix mailbox of concatenated messages, each starting with a `From ` line
+pub const MBOX_MIME: &str = "application/mbox";
+
+/// Outlook message saved on its own (an OLE2 container, not a PST)
+pub const MSG_MIME: &str = "application/vnd.ms-outlook-msg";
+
+/// Prefix of the MAPI subject property streams that every MSG file carries
+const MSG_SUBJECT_STREAM_PREFIX: &str = "__substg1.0_0037";
+
+/// Bytes scanned for RFC 2822 headers at the start of a message
+const EMAIL_HEADER_SAMPLE_LEN: usize = 200;
+
+/// Header names that open an email; a single match is too weak a signal
+const EMAIL_HEADERS: [&str; 8] = [
+    "from:",
+    "to:",
+    "date:",
+    "subject:",
+    "mime-version:",
+    "received:",
+    "return-path:",
+    "message-id:",
+];
+
+/// Detection confidence levels (0.0-1.0)
+/// Magic byte signatures are reliable; content heuristics can misfire
+/// (e.g. a Python file starting with `{` looks like JSON)
+const CONFIDENCE_MAGIC: f32 = 1.0;
+const CONFIDENCE_STRUCTURED_HEURISTIC: f32 = 0.7;
+const CONFIDENCE_TEXT: f32 = 0.6;
+const CONFIDENCE_CSV: f32 = 0.5;
+const CONFIDENCE_FALLBACK: f32 = 0.3;
+
 /// File type detection using magic bytes (like libmagic)
 /// Never trust file extensions - always check the actual content
 pub struct FileTypeDetector;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DetectedFileType {
     pub mime_type: String,
     pub category: FileCategory,
     pub magic_header: String,
+    /// How certain the detection is (1.0 = magic byte match)
+    pub confidence: f32,
 }
 
 impl FileTypeDetector {
@@ -28,33 +85,70 @@ impl FileTypeDetector {
             hex::encode(&buffer[..bytes_read])
         };
 
-        let (mime_type, category) = Self::identify_type(&buffer[..bytes_read]);
+        let (mut mime_type, mut category, confidence) = Self::identify_type(&buffer[..bytes_read]);
+
+        // OLE2 containers need their stream directory inspected to tell formats apart
+        if mime_type == OLE2_MIME {
+            if let Some((specific, specific_category)) = Self::identify_ole2_document(path) {
+                mime_type = specific;
+                category = specific_category;
+            }
+        }
 
         Ok(DetectedFileType {
             mime_type: mime_type.to_string(),
             category,
             magic_header,
+            confidence,
         })
     }
 
+    /// MIME type from a magic byte signature alone, or None if the file has no known one
+    /// Cheaper than detect (no header hex, no OLE2 directory walk) for callers like the file
+    /// browser that only want a type for files whose extension says nothing
+    pub fn sniff_mime(path: &Path) -> Option<&'static str> {
+        let mut buffer = [0u8; 512];
+        let bytes_read = File::open(path).ok()?.read(&mut buffer).ok()?;
+
+        let (mime_type, _, confidence) = Self::identify_type(&buffer[..bytes_read]);
+        (confidence == CONFIDENCE_MAGIC).then_some(mime_type)
+    }
+
     /// Identify file type from magic bytes
-    fn identify_type(bytes: &[u8]) -> (&'static str, FileCategory) {
+    fn identify_type(bytes: &[u8]) -> (&'static str, FileCategory, f32) {
         if bytes.is_empty() {
-            return ("application/octet-stream", FileCategory::Binary);
+            return (
+                "application/octet-stream",
+                FileCategory::Binary,
+                CONFIDENCE_FALLBACK,
+            );
         }
 
         // SQLite database
         if bytes.len() >= 16 && &bytes[0..16] == b"SQLite format 3\0" {
-            return ("application/vnd.sqlite3", FileCategory::Database);
+            return (
+                "application/vnd.sqlite3",
+                FileCategory::Database,
+                CONFIDENCE_MAGIC,
+            );
         }
 
         // LevelDB
         if bytes.len() >= 8 && &bytes[0..8] == b"leveldb/" {
-            return ("application/x-leveldb", FileCategory::Database);
+            return (
+                "application/x-leveldb",
+                FileCategory::Database,
+                CONFIDENCE_MAGIC,
+            );
+        }
+
+        // Outlook PST/OST mailbox
+        if bytes.len() >= 4 && &bytes[0..4] == PST_MAGIC {
+            return (PST_MIME, FileCategory::Database, CONFIDENCE_MAGIC);
         }
 
         // ZIP/Office formats (XLSX, DOCX, etc.)
-        if bytes.len() >= 4 && &bytes[0..4] == b"PK\x03\x04" {
+        if bytes.starts_with(ZIP_MAGIC) {
             // Check if it's an Office file
             if bytes.len() >= 30 {
                 if Self::contains_sequence(bytes, b"[Content_Types].xml") {
@@ -63,18 +157,28 @@ impl FileTypeDetector {
                         return (
                             "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
                             FileCategory::Document,
+                            CONFIDENCE_MAGIC,
                         );
                     } else if Self::contains_sequence(bytes, b"word/") {
-                        return ("application/vnd.openxmlformats-officedocument.wordprocessingml.document", FileCategory::Document);
+                        return (
+                            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+                            FileCategory::Document,
+                            CONFIDENCE_MAGIC,
+                        );
                     }
                 }
             }
-            return ("application/zip", FileCategory::Archive);
+            return ("application/zip", FileCategory::Archive, CONFIDENCE_MAGIC);
+        }
+
+        // OLE2 compound document (legacy .doc, .xls, .ppt)
+        if bytes.len() >= 8 && bytes[0..8] == OLE2_MAGIC {
+            return (OLE2_MIME, FileCategory::Document, CONFIDENCE_MAGIC);
         }
 
         // PDF
-        if bytes.len() >= 4 && &bytes[0..4] == b"%PDF" {
-            return ("application/pdf", FileCategory::Document);
+        if bytes.starts_with(PDF_MAGIC) {
+            return ("application/pdf", FileCategory::Document, CONFIDENCE_MAGIC);
         }
 
         // Parquet
@@ -82,36 +186,57 @@ impl FileTypeDetector {
             return (
                 "application/vnd.apache.parquet",
                 FileCategory::StructuredData,
+                CONFIDENCE_MAGIC,
             );
         }
 
         // Images
         if bytes.len() >= 8 {
             // PNG
-            if &bytes[0..8] == b"\x89PNG\r\n\x1a\n" {
-                return ("image/png", FileCategory::Media);
+            if bytes.starts_with(PNG_MAGIC) {
+                return ("image/png", FileCategory::Media, CONFIDENCE_MAGIC);
             }
             // JPEG
-            if &bytes[0..2] == b"\xFF\xD8" {
-                return ("image/jpeg", FileCategory::Media);
+            if bytes.starts_with(JPEG_MAGIC) {
+                return ("image/jpeg", FileCategory::Media, CONFIDENCE_MAGIC);
             }
             // GIF
             if &bytes[0..6] == b"GIF87a" || &bytes[0..6] == b"GIF89a" {
-                return ("image/gif", FileCategory::Media);
+                return ("image/gif", FileCategory::Media, CONFIDENCE_MAGIC);
             }
             // WebP
             if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
-                return ("image/webp", FileCategory::Media);
+                return ("image/webp", FileCategory::Media, CONFIDENCE_MAGIC);
             }
         }
 
+        // Mailboxes and messages are text, but would otherwise pass for plain text or CSV
+        if Self::looks_like_mbox(bytes) {
+            return (
+                MBOX_MIME,
+                FileCategory::Email,
+                CONFIDENCE_STRUCTURED_HEURISTIC,
+            );
+        }
+        if Self::looks_like_email(bytes) {
+            return (
+                EML_MIME,
+                FileCategory::Email,
+                CONFIDENCE_STRUCTURED_HEURISTIC,
+            );
+        }
+
         // JSON (heuristic - starts with { or [, allowing whitespace)
         if let Some(&first_non_ws) = bytes.iter().find(|&&b| !b.is_ascii_whitespace()) {
             if first_non_ws == b'{' || first_non_ws == b'[' {
                 // Try to confirm it's JSON by checking for valid JSON structure
                 if let Ok(s) = std::str::from_utf8(bytes) {
                     if s.trim_start().starts_with('{') || s.trim_start().starts_with('[') {
-                        return ("application/json", FileCategory::StructuredData);
+                        return (
+                            "application/json",
+                            FileCategory::StructuredData,
+                            CONFIDENCE_STRUCTURED_HEURISTIC,
+                        );
                     }
                 }
             }
@@ -121,45 +246,133 @@ impl FileTypeDetector {
         if bytes.len() >= 5 {
             if let Ok(s) = std::str::from_utf8(&bytes[..bytes.len().min(100)]) {
                 if s.trim_start().starts_with("<?xml") || s.trim_start().starts_with('<') {
-                    return ("application/xml", FileCategory::StructuredData);
+                    return (
+                        "application/xml",
+                        FileCategory::StructuredData,
+                        CONFIDENCE_STRUCTURED_HEURISTIC,
+                    );
                 }
             }
         }
 
         // CSV (heuristic - check for common patterns)
         if Self::looks_like_csv(bytes) {
-            return ("text/csv", FileCategory::StructuredData);
+            return ("text/csv", FileCategory::StructuredData, CONFIDENCE_CSV);
         }
 
         // ELF binary (Unix executable)
         if bytes.len() >= 4 && &bytes[0..4] == b"\x7FELF" {
-            return ("application/x-executable", FileCategory::Binary);
+            return (
+                "application/x-executable",
+                FileCategory::Binary,
+                CONFIDENCE_MAGIC,
+            );
         }
 
         // Mach-O binary (macOS executable)
         if bytes.len() >= 4 {
             let magic = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
             if magic == 0xFEEDFACE || magic == 0xFEEDFACF || magic == 0xCAFEBABE {
-                return ("application/x-mach-binary", FileCategory::Binary);
+                return (
+                    "application/x-mach-binary",
+                    FileCategory::Binary,
+                    CONFIDENCE_MAGIC,
+                );
             }
         }
 
         // PE binary (Windows executable)
         if bytes.len() >= 2 && &bytes[0..2] == b"MZ" {
-            return ("application/x-dosexec", FileCategory::Binary);
+            return (
+                "application/x-dosexec",
+                FileCategory::Binary,
+                CONFIDENCE_MAGIC,
+            );
         }
 
         // Check if it's text
         if Self::is_text(bytes) {
-            return ("text/plain", FileCategory::Text);
+            return ("text/plain", FileCategory::Text, CONFIDENCE_TEXT);
         }
 
         // Default to binary
-        ("application/octet-stream", FileCategory::Binary)
+        (
+            "application/octet-stream",
+            FileCategory::Binary,
+            CONFIDENCE_FALLBACK,
+        )
+    }
+
+    /// Distinguish legacy Office formats and Outlook messages by the streams in the
+    /// compound document
+    fn identify_ole2_document(path: &Path) -> Option<(&'static str, FileCategory)> {
+        let compound = cfb::open(path).ok()?;
+
+        if compound.is_stream("/WordDocument") {
+            Some(("application/msword", FileCategory::Document))
+        } else if compound.is_stream("/Workbook") || compound.is_stream("/Book") {
+            Some(("application/vnd.ms-excel", FileCategory::Document))
+        } else if compound.is_stream("/PowerPoint Document") {
+            Some(("application/vnd.ms-powerpoint", FileCategory::Document))
+        } else if compound
+            .read_root_storage()
+            .any(|entry| entry.name().starts_with(MSG_SUBJECT_STREAM_PREFIX))
+        {
+            Some((MSG_MIME, FileCategory::Email))
+        } else {
+            None
+        }
+    }
+
+    /// Check if bytes start like an mbox: a `From ` separator line followed by a Date header
+    fn looks_like_mbox(bytes: &[u8]) -> bool {
+        if !bytes.starts_with(b"From ") {
+            return false;
+        }
+        let text = String::from_utf8_lossy(bytes);
+        let mut lines = text.lines();
+        let separator_has_sender = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .is_some_and(|sender| sender.contains('@') || sender == "MAILER-DAEMON");
+        separator_has_sender
+            && lines
+                .take_while(|line| !line.is_empty())
+                .any(|line| line.to_ascii_lowercase().starts_with("date:"))
+    }
+
+    /// Check if bytes start with RFC 2822 headers
+    /// The first line has to be a header, and at least two well-known ones must appear
+    fn looks_like_email(bytes: &[u8]) -> bool {
+        let sample = &bytes[..bytes.len().min(EMAIL_HEADER_SAMPLE_LEN)];
+        if sample.contains(&0) {
+            return false;
+        }
+        // The sample may cut a multi-byte character in half, so decode lossily
+        let text = String::from_utf8_lossy(sample);
+
+        let starts_with_header = text.lines().next().is_some_and(|line| {
+            line.split_once(':')
+                .is_some_and(|(name, _)| !name.is_empty() && !name.contains(char::is_whitespace))
+        });
+        let known_headers = text
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .filter(|line| {
+                let line = line.to_ascii_lowercase();
+                EMAIL_HEADERS.iter().any(|header| line.starts_with(header))
+            })
+            .count();
+
+        starts_with_header && known_headers >= 2
     }
 
     /// Check if bytes look like CSV
     fn looks_like_csv(bytes: &[u8]) -> bool {
+        if bytes.is_empty() {
+            return false;
+        }
+
         if let Ok(s) = std::str::from_utf8(&bytes[..bytes.len().min(1024)]) {
             let lines: Vec<&str> = s.lines().take(5).collect();
             if lines.len() >= 2 {
@@ -186,6 +399,11 @@ impl FileTypeDetector {
 
     /// Check if bytes are valid UTF-8 text
     fn is_text(bytes: &[u8]) -> bool {
+        // Nothing to judge, and the printable ratio below would be 0/0
+        if bytes.is_empty() {
+            return false;
+        }
+
         // Check if valid UTF-8
         if std::str::from_utf8(bytes).is_err() {
             return false;
@@ -224,6 +442,30 @@ mod tests {
         let detected = FileTypeDetector::detect(file.path()).unwrap();
         assert_eq!(detected.mime_type, "application/vnd.sqlite3");
         assert_eq!(detected.category, FileCategory::Database);
+        assert_eq!(detected.confidence, 1.0);
+    }
+
+    #[test]
+    fn test_detect_pst_mailbox() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"!BDN\0\0\0\0SM\x17\0").unwrap();
+        file.flush().unwrap();
+
+        let detected = FileTypeDetector::detect(file.path()).unwrap();
+        assert_eq!(detected.mime_type, "application/vnd.ms-outlook");
+        assert_eq!(detected.category, FileCategory::Database);
+    }
+
+    #[test]
+    fn test_detect_png_magic_header() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        file.flush().unwrap();
+
+        let detected = FileTypeDetector::detect(file.path()).unwrap();
+        assert_eq!(detected.mime_type, "image/png");
+        assert_eq!(detected.category, FileCategory::Media);
+        assert_eq!(detected.magic_header, "89504e470d0a1a0a0000000d49484452");
     }
 
     #[test]
@@ -235,6 +477,94 @@ mod tests {
         let detected = FileTypeDetector::detect(file.path()).unwrap();
         assert_eq!(detected.mime_type, "application/json");
         assert_eq!(detected.category, FileCategory::StructuredData);
+        assert!(detected.confidence < 1.0);
+    }
+
+    #[test]
+    fn test_detect_ole2_word_document() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("legacy.doc");
+        {
+            let mut compound = cfb::create(&path).unwrap();
+            compound
+                .create_stream("/WordDocument")
+                .unwrap()
+                .write_all(b"word data")
+                .unwrap();
+            compound.flush().unwrap();
+        }
+
+        let detected = FileTypeDetector::detect(&path).unwrap();
+        assert_eq!(detected.mime_type, "application/msword");
+        assert_eq!(detected.category, FileCategory::Document);
+    }
+
+    #[test]
+    fn test_detect_ole2_unknown_container() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&OLE2_MAGIC).unwrap();
+        file.write_all(&[0u8; 64]).unwrap();
+        file.flush().unwrap();
+
+        let detected = FileTypeDetector::detect(file.path()).unwrap();
+        assert_eq!(detected.mime_type, OLE2_MIME);
+        assert_eq!(detected.category, FileCategory::Document);
+    }
+
+    #[test]
+    fn test_detect_email_formats() {
+        let mut eml = NamedTempFile::new().unwrap();
+        eml.write_all(
+            b"Return-Path: <alice@example.com>\r\nFrom: Alice <alice@example.com>\r\n\
+              To: bob@example.com\r\nSubject: Invoice, final\r\nDate: Mon, 1 Jan 2024 10:00:00 +0000\r\n\
+              MIME-Version: 1.0\r\n\r\nSee attached.\r\n",
+        )
+        .unwrap();
+        eml.flush().unwrap();
+        let detected = FileTypeDetector::detect(eml.path()).unwrap();
+        assert_eq!(detected.mime_type, EML_MIME);
+        assert_eq!(detected.category, FileCategory::Email);
+
+        let mut mbox = NamedTempFile::new().unwrap();
+        mbox.write_all(
+            b"From alice@example.com Mon Jan  1 10:00:00 2024\nFrom: alice@example.com\n\
+              Date: Mon, 1 Jan 2024 10:00:00 +0000\n\nHello\n",
+        )
+        .unwrap();
+        mbox.flush().unwrap();
+        let detected = FileTypeDetector::detect(mbox.path()).unwrap();
+        assert_eq!(detected.mime_type, MBOX_MIME);
+        assert_eq!(detected.category, FileCategory::Email);
+
+        // A prose line that merely starts with "From " is still text
+        let mut prose = NamedTempFile::new().unwrap();
+        prose
+            .write_all(b"From the top: notes on the case\nDate: tbd\n")
+            .unwrap();
+        prose.flush().unwrap();
+        assert_eq!(
+            FileTypeDetector::detect(prose.path()).unwrap().mime_type,
+            "text/plain"
+        );
+    }
+
+    #[test]
+    fn test_detect_outlook_msg() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("message.msg");
+        {
+            let mut compound = cfb::create(&path).unwrap();
+            compound
+                .create_stream("/__substg1.0_0037001F")
+                .unwrap()
+                .write_all(b"S\0u\0b\0j\0")
+                .unwrap();
+            compound.flush().unwrap();
+        }
+
+        let detected = FileTypeDetector::detect(&path).unwrap();
+        assert_eq!(detected.mime_type, MSG_MIME);
+        assert_eq!(detected.category, FileCategory::Email);
     }
 
     #[test]
@@ -247,4 +577,28 @@ mod tests {
         assert_eq!(detected.mime_type, "text/plain");
         assert_eq!(detected.category, FileCategory::Text);
     }
+
+    #[test]
+    fn test_detect_empty_file() {
+        let file = NamedTempFile::new().unwrap();
+
+        let detected = FileTypeDetector::detect(file.path()).unwrap();
+        assert_eq!(detected.mime_type, "application/octet-stream");
+        assert_eq!(detected.category, FileCategory::Binary);
+        assert!(!FileTypeDetector::is_text(&[]));
+    }
+
+    #[test]
+    fn test_text_heuristics_reject_empty_input() {
+        assert!(!FileTypeDetector::is_text(b""));
+        assert!(!FileTypeDetector::looks_like_csv(b""));
+        assert_eq!(
+            FileTypeDetector::identify_type(b""),
+            (
+                "application/octet-stream",
+                FileCategory::Binary,
+                CONFIDENCE_FALLBACK
+            )
+        );
+    }
 }
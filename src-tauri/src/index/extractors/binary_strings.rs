@@ -0,0 +1,242 @@
+use super::{Extractor, ExtractorOutput, SupportedFormat};
+use crate::index::schema::FileCategory;
+use anyhow::{Context, Result};
+use memchr::memmem::Finder;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+/// Shortest run of printable characters reported as a string (same default as `strings`)
+const MIN_STRING_LEN: usize = 4;
+
+/// Cap on the extracted strings indexed per file
+const MAX_STRINGS_CONTENT: usize = 2 * 1024 * 1024;
+
+/// Bytes read per step when locating a term in a file
+const LOCATE_CHUNK_LEN: usize = 64 * 1024;
+
+/// Longest printable run held while locating a term before it is searched and cut
+const MAX_HELD_RUN: usize = 1024 * 1024;
+
+/// A printable string found in a binary, with the byte offset it starts at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedString {
+    pub offset: u64,
+    pub text: String,
+}
+
+/// Extracts printable ASCII strings from binaries so their text is searchable
+pub struct BinaryStringsExtractor;
+
+impl Extractor for BinaryStringsExtractor {
+    fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
+        let bytes = fs::read(path).context("Failed to read binary file")?;
+        let strings = extract_strings(&bytes);
+        let content = strings_to_content(&strings);
+
+        let mut fields = HashMap::new();
+        fields.insert("format".to_string(), "binary".to_string());
+        fields.insert("string_count".to_string(), strings.len().to_string());
+
+        let preview = format!(
+            "Binary file: {} bytes, {} strings. {}",
+            bytes.len(),
+            strings.len(),
+            content.replace('\n', " ")
+        );
+
+        Ok(ExtractorOutput {
+            structured: None,
+            content: Some(content),
+            preview: preview.chars().take(500).collect(),
+            fields,
+        })
+    }
+
+    fn can_handle(&self, category: FileCategory, _mime_type: &str) -> bool {
+        category == FileCategory::Binary
+    }
+
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(FileCategory::Binary, &[])]
+    }
+
+    fn name(&self) -> &'static str {
+        "binary_strings"
+    }
+}
+
+/// Find runs of printable ASCII (plus tab) at least MIN_STRING_LEN long
+pub fn extract_strings(bytes: &[u8]) -> Vec<ExtractedString> {
+    extract_strings_with_min_len(bytes, MIN_STRING_LEN)
+}
+
+/// Find runs of printable ASCII (plus tab) at least min_len long
+pub fn extract_strings_with_min_len(bytes: &[u8], min_len: usize) -> Vec<ExtractedString> {
+    let mut strings = Vec::new();
+    let mut start = None;
+
+    for (i, &byte) in bytes.iter().enumerate() {
+        match (is_printable(byte), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                push_run(&mut strings, bytes, s, i, min_len);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        push_run(&mut strings, bytes, s, bytes.len(), min_len);
+    }
+
+    strings
+}
+
+fn is_printable(byte: u8) -> bool {
+    byte == b'\t' || (0x20..=0x7E).contains(&byte)
+}
+
+fn push_run(
+    strings: &mut Vec<ExtractedString>,
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+    min_len: usize,
+) {
+    if end - start >= min_len {
+        strings.push(ExtractedString {
+            offset: start as u64,
+            // Printable ASCII is always valid UTF-8
+            text: String::from_utf8_lossy(&bytes[start..end]).to_string(),
+        });
+    }
+}
+
+/// One string per line, stopping before MAX_STRINGS_CONTENT is exceeded
+pub fn strings_to_content(strings: &[ExtractedString]) -> String {
+    let mut content = String::new();
+    for string in strings {
+        if content.len() + string.text.len() + 1 > MAX_STRINGS_CONTENT {
+            break;
+        }
+        content.push_str(&string.text);
+        content.push('\n');
+    }
+    content
+}
+
+/// Re-scan a binary file for the byte offset of a search term
+/// Used to point the hex viewer at a content match. The match is case-insensitive and
+/// within a string extract_strings would report; the file is streamed and only the
+/// current printable run is held
+pub fn locate_in_file(path: &Path, term: &str) -> Option<u64> {
+    let term = term.to_ascii_lowercase();
+    if term.is_empty() {
+        return None;
+    }
+    let finder = Finder::new(term.as_bytes());
+    let search = |run: &[u8], run_start: u64, long: bool| {
+        (long || run.len() >= MIN_STRING_LEN)
+            .then(|| finder.find(run))
+            .flatten()
+            .map(|found| run_start + found as u64)
+    };
+
+    let mut file = File::open(path).ok()?;
+    let mut buffer = vec![0u8; LOCATE_CHUNK_LEN];
+    let mut run = Vec::new();
+    let mut run_start = 0u64;
+    // Set once part of the current run was cut, since it is then long enough to count
+    let mut long = false;
+    let mut offset = 0u64;
+    loop {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        for &byte in &buffer[..read] {
+            if is_printable(byte) {
+                if run.is_empty() {
+                    run_start = offset;
+                }
+                run.push(byte.to_ascii_lowercase());
+                if run.len() >= MAX_HELD_RUN {
+                    if let Some(found) = search(&run, run_start, long) {
+                        return Some(found);
+                    }
+                    // Keep enough of the tail for a match that straddles the cut
+                    let cut = run.len() - (term.len() - 1);
+                    run.drain(..cut);
+                    run_start += cut as u64;
+                    long = true;
+                }
+            } else if !run.is_empty() || long {
+                if let Some(found) = search(&run, run_start, long) {
+                    return Some(found);
+                }
+                run.clear();
+                long = false;
+            }
+            offset += 1;
+        }
+    }
+    search(&run, run_start, long)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matched_string_reports_byte_offset() {
+        let mut bytes = vec![0u8; 16];
+        bytes.extend_from_slice(b"GetProcAddress");
+        bytes.extend_from_slice(&[0x00, 0xFF, b'a', b'b', 0x00]);
+        bytes.extend_from_slice(b"admin_password=hunter2");
+        bytes.push(0x00);
+
+        let strings = extract_strings(&bytes);
+
+        assert_eq!(
+            strings,
+            vec![
+                ExtractedString {
+                    offset: 16,
+                    text: "GetProcAddress".to_string(),
+                },
+                ExtractedString {
+                    offset: 35,
+                    text: "admin_password=hunter2".to_string(),
+                },
+            ]
+        );
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("sample.bin");
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(locate_in_file(&path, "PASSWORD"), Some(41));
+        assert_eq!(locate_in_file(&path, "missing"), None);
+    }
+
+    #[test]
+    fn test_locate_in_file_streams_across_chunks() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("sample.bin");
+        let mut bytes = vec![0u8; LOCATE_CHUNK_LEN - 5];
+        bytes.extend_from_slice(b"abc");
+        bytes.push(0x00);
+        bytes.extend_from_slice(b"Admin_Password=hunter2");
+        bytes.push(0xFF);
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert_eq!(
+            locate_in_file(&path, "password"),
+            Some(LOCATE_CHUNK_LEN as u64 - 1 + 6)
+        );
+        // Too short to be a string on its own, as with extract_strings
+        assert_eq!(locate_in_file(&path, "abc"), None);
+        assert_eq!(locate_in_file(&path, "missing"), None);
+    }
+}
@@ -0,0 +1,233 @@
+// Recognition of messaging-app databases by their table layout
+// Pulls message text, sender and time out of known chat schemas so conversations are searchable
+
+use super::binary_strings;
+use crate::index::schema::TableInfo;
+use chrono::{DateTime, Utc};
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+
+/// Most messages read from a single chat database
+pub(super) const MAX_CHAT_MESSAGES: usize = 50_000;
+
+/// Cap on the message transcript indexed per database
+const MAX_CHAT_CONTENT_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimestampUnit {
+    Seconds,
+    Millis,
+}
+
+/// A known messages table and how to read a message out of it
+pub struct ChatSchema {
+    pub app: &'static str,
+    table: &'static str,
+    /// Columns that must all be present for the table to match
+    columns: &'static [&'static str],
+    /// Other tables the database must have, for layouts whose messages table is too generic
+    /// to tell the app apart on its own
+    companion_tables: &'static [&'static str],
+    /// SQL expression yielding the sender ("me" for outgoing messages)
+    sender: &'static str,
+    body: &'static str,
+    /// Whether the body is a serialized blob that only yields printable strings
+    binary_body: bool,
+    timestamp: &'static str,
+    unit: TimestampUnit,
+}
+
+const CHAT_SCHEMAS: &[ChatSchema] = &[
+    // WhatsApp Android msgstore.db, pre-2022 layout
+    ChatSchema {
+        app: "whatsapp",
+        table: "messages",
+        columns: &["key_remote_jid", "key_from_me", "data", "timestamp"],
+        companion_tables: &[],
+        sender: "CASE WHEN key_from_me = 1 THEN 'me' ELSE key_remote_jid END",
+        body: "data",
+        binary_body: false,
+        timestamp: "timestamp",
+        unit: TimestampUnit::Millis,
+    },
+    // WhatsApp Android msgstore.db, `message` + `chat` layout
+    ChatSchema {
+        app: "whatsapp",
+        table: "message",
+        columns: &["chat_row_id", "from_me", "text_data", "timestamp"],
+        companion_tables: &[],
+        sender: "CASE WHEN from_me = 1 THEN 'me' ELSE CAST(chat_row_id AS TEXT) END",
+        body: "text_data",
+        binary_body: false,
+        timestamp: "timestamp",
+        unit: TimestampUnit::Millis,
+    },
+    // Signal Android (decrypted signal.db)
+    ChatSchema {
+        app: "signal",
+        table: "message",
+        columns: &["thread_id", "from_recipient_id", "body", "date_sent"],
+        companion_tables: &[],
+        sender: "CAST(from_recipient_id AS TEXT)",
+        body: "body",
+        binary_body: false,
+        timestamp: "date_sent",
+        unit: TimestampUnit::Millis,
+    },
+    // Signal Android, older `sms` layout; Android's own mmssms.db has an `sms` table with
+    // the same columns, but its threads table is `threads` rather than Signal's `thread`
+    ChatSchema {
+        app: "signal",
+        table: "sms",
+        columns: &["thread_id", "address", "body", "date_sent"],
+        companion_tables: &["thread"],
+        sender: "address",
+        body: "body",
+        binary_body: false,
+        timestamp: "date_sent",
+        unit: TimestampUnit::Millis,
+    },
+    // Telegram Android cache4.db; message text sits inside a serialized blob
+    ChatSchema {
+        app: "telegram",
+        table: "messages_v2",
+        columns: &["mid", "uid", "date", "data", "out"],
+        companion_tables: &[],
+        sender: "CASE WHEN out = 1 THEN 'me' ELSE CAST(uid AS TEXT) END",
+        body: "data",
+        binary_body: true,
+        timestamp: "date",
+        unit: TimestampUnit::Seconds,
+    },
+];
+
+/// A single chat message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    pub sender: String,
+    pub body: String,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// Messages read from a chat database, oldest first
+#[derive(Debug)]
+pub struct ChatTranscript {
+    pub messages: Vec<ChatMessage>,
+    /// Whether messages were left out to stay within the limits
+    pub truncated: bool,
+}
+
+impl ChatSchema {
+    /// Find the chat schema matching a database's tables, if any
+    pub fn detect(tables: &[TableInfo]) -> Option<&'static ChatSchema> {
+        CHAT_SCHEMAS.iter().find(|schema| {
+            tables.iter().any(|table| {
+                table.name == schema.table
+                    && schema
+                        .columns
+                        .iter()
+                        .all(|column| table.columns.iter().any(|c| c.name == *column))
+            }) && schema
+                .companion_tables
+                .iter()
+                .all(|name| tables.iter().any(|table| table.name == *name))
+        })
+    }
+
+    /// Read messages in time order, bounded by MAX_CHAT_MESSAGES and MAX_CHAT_CONTENT_BYTES
+    /// Rows are read newest first, so a truncated transcript keeps the latest messages
+    pub fn read_messages(&self, conn: &Connection) -> rusqlite::Result<ChatTranscript> {
+        let sql = format!(
+            "SELECT {}, \"{}\", \"{}\" FROM \"{}\" WHERE \"{}\" IS NOT NULL \
+             ORDER BY \"{}\" DESC LIMIT {}",
+            self.sender,
+            self.body,
+            self.timestamp,
+            self.table,
+            self.body,
+            self.timestamp,
+            MAX_CHAT_MESSAGES + 1
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query([])?;
+
+        let mut messages = Vec::new();
+        let mut content_bytes = 0;
+        let mut truncated = false;
+
+        while let Some(row) = rows.next()? {
+            if messages.len() == MAX_CHAT_MESSAGES {
+                truncated = true;
+                break;
+            }
+
+            let body = match row.get_ref(1)? {
+                ValueRef::Text(text) => String::from_utf8_lossy(text).to_string(),
+                ValueRef::Blob(bytes) if self.binary_body => binary_strings::extract_strings(bytes)
+                    .into_iter()
+                    .map(|string| string.text)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => continue,
+            };
+            if body.trim().is_empty() {
+                continue;
+            }
+
+            content_bytes += body.len();
+            if content_bytes > MAX_CHAT_CONTENT_BYTES {
+                truncated = true;
+                break;
+            }
+
+            let sender = match row.get_ref(0)? {
+                ValueRef::Text(text) => String::from_utf8_lossy(text).to_string(),
+                ValueRef::Integer(n) => n.to_string(),
+                _ => "unknown".to_string(),
+            };
+            let sent_at = row
+                .get::<_, Option<i64>>(2)
+                .ok()
+                .flatten()
+                .and_then(|value| match self.unit {
+                    TimestampUnit::Seconds => DateTime::from_timestamp(value, 0),
+                    TimestampUnit::Millis => DateTime::from_timestamp_millis(value),
+                });
+
+            messages.push(ChatMessage {
+                sender,
+                body,
+                sent_at,
+            });
+        }
+
+        messages.reverse();
+        Ok(ChatTranscript {
+            messages,
+            truncated,
+        })
+    }
+}
+
+impl ChatTranscript {
+    /// One line per message: `<time> <sender>: <body>`
+    pub fn to_content(&self) -> String {
+        let mut content = String::new();
+        for message in &self.messages {
+            let time = message
+                .sent_at
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_else(|| "unknown-time".to_string());
+            content.push_str(&format!("{} {}: {}\n", time, message.sender, message.body));
+        }
+        content
+    }
+
+    pub fn first_sent(&self) -> Option<DateTime<Utc>> {
+        self.messages.iter().filter_map(|m| m.sent_at).min()
+    }
+
+    pub fn last_sent(&self) -> Option<DateTime<Utc>> {
+        self.messages.iter().filter_map(|m| m.sent_at).max()
+    }
+}
@@ -1,46 +1,146 @@
-use super::{Extractor, ExtractorOutput};
+use super::{Extractor, ExtractorOutput, SupportedFormat};
 use crate::index::schema::{ColumnSchema, FileCategory, StructuredData};
 use anyhow::{Context, Result};
-use csv::ReaderBuilder;
+use chardetng::EncodingDetector;
+use csv::{ReaderBuilder, StringRecord};
+use encoding_rs::{CoderResult, Decoder, Encoding, UTF_8};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 
-pub struct CsvExtractor;
+/// Number of rows sampled for type inference
+const SCHEMA_SAMPLE_ROWS: usize = 100;
+
+/// Bytes read from the start of the file to detect the encoding and delimiter
+const SAMPLE_BYTES: usize = 4 * 1024;
+
+/// Bytes read from the file at a time while detecting or decoding a non-UTF-8 encoding
+const DECODE_BUFFER_BYTES: usize = 64 * 1024;
+
+/// Most lines compared when detecting the delimiter
+const DELIMITER_SAMPLE_LINES: usize = 20;
+
+/// Delimiters recognised, in order of preference when counts tie
+const CANDIDATE_DELIMITERS: [u8; 4] = [b',', b'\t', b'|', b';'];
+
+/// Overrides for files that auto-detection gets wrong
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvOptions {
+    /// Use this delimiter instead of detecting one (must be ASCII)
+    #[serde(default)]
+    pub delimiter: Option<char>,
+
+    /// Whether the first row holds column names (None = assume it does)
+    /// Headerless files get generated names: column_1, column_2, ...
+    #[serde(default)]
+    pub has_headers: Option<bool>,
+
+    /// Quote character around fields containing delimiters or newlines, instead of `"`
+    /// (e.g. `'` for some exported logs; must be ASCII)
+    #[serde(default)]
+    pub quote: Option<char>,
+}
+
+/// CSV/TSV extractor
+/// Tolerates ragged rows and malformed records so one bad line doesn't abort extraction
+#[derive(Default)]
+pub struct CsvExtractor {
+    options: CsvOptions,
+}
 
 impl Extractor for CsvExtractor {
     fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
-        let file = File::open(path).context("Failed to open CSV file")?;
+        let source = Utf8Source::open(path)?;
+
+        let quote = match self.options.quote {
+            Some(quote) => u8::try_from(quote).context("CSV quote must be an ASCII character")?,
+            None => b'"',
+        };
 
-        // Try to detect delimiter
-        let delimiter = self.detect_delimiter(path).unwrap_or(b',');
+        // A forced delimiter wins; otherwise detect it from the start of the file
+        let delimiter = match self.options.delimiter {
+            Some(delimiter) => {
+                u8::try_from(delimiter).context("CSV delimiter must be an ASCII character")?
+            }
+            None => Self::detect_delimiter(&source.sample, quote),
+        };
+        let has_headers = self.options.has_headers.unwrap_or(true);
 
         let mut reader = ReaderBuilder::new()
             .delimiter(delimiter)
-            .has_headers(true)
-            .from_reader(file);
+            .quote(quote)
+            .flexible(true)
+            .has_headers(has_headers)
+            .from_reader(source.reader);
 
-        // Get headers
-        let headers: Vec<String> = reader
-            .headers()
-            .context("Failed to read CSV headers")?
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
+        // Get headers; without a header row the first record only sets the column count
+        let first_row = reader.headers().context("Failed to read CSV headers")?;
+        let headers: Vec<String> = if has_headers {
+            first_row.iter().map(|s| s.to_string()).collect()
+        } else {
+            (1..=first_row.len())
+                .map(|i| format!("column_{}", i))
+                .collect()
+        };
 
-        // Infer schema by sampling first 100 rows
-        let schema = self.infer_schema(&mut reader, &headers)?;
+        // Single pass: count parsed rows, note bad ones, keep a sample for the schema
+        let mut samples = Vec::new();
+        let mut row_count = 0u64;
+        let mut ragged_rows = 0u64;
+        let mut error_rows = 0u64;
+        let mut first_error = None;
+
+        for result in reader.records() {
+            match result {
+                Ok(record) => {
+                    row_count += 1;
+                    if record.len() != headers.len() {
+                        ragged_rows += 1;
+                    }
+                    if samples.len() < SCHEMA_SAMPLE_ROWS {
+                        samples.push(record);
+                    }
+                }
+                Err(e) => {
+                    error_rows += 1;
+                    let is_io = matches!(e.kind(), csv::ErrorKind::Io(_));
+                    first_error.get_or_insert_with(|| e.to_string());
+                    // The underlying reader failed; further records can't be trusted
+                    if is_io {
+                        break;
+                    }
+                }
+            }
+        }
 
-        // Count total rows
-        let row_count = reader.into_records().count() as u64;
+        let schema = self.infer_schema(&samples, &headers);
 
         // Build searchable fields
         let mut fields = HashMap::new();
         fields.insert("format".to_string(), "csv".to_string());
         fields.insert("delimiter".to_string(), (delimiter as char).to_string());
+        fields.insert(
+            "source_encoding".to_string(),
+            source.encoding.name().to_string(),
+        );
         fields.insert("column_count".to_string(), headers.len().to_string());
         fields.insert("row_count".to_string(), row_count.to_string());
         fields.insert("columns".to_string(), headers.join(", "));
+        if !has_headers {
+            fields.insert("has_headers".to_string(), "false".to_string());
+        }
+        if ragged_rows > 0 {
+            fields.insert("ragged_rows".to_string(), ragged_rows.to_string());
+        }
+        if error_rows > 0 {
+            fields.insert("error_rows".to_string(), error_rows.to_string());
+        }
+        if let Some(error) = first_error {
+            fields.insert("first_error".to_string(), error);
+        }
 
         // Create preview
         let preview = format!(
@@ -67,51 +167,94 @@ impl Extractor for CsvExtractor {
         category == FileCategory::StructuredData && mime_type == "text/csv"
     }
 
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(
+            FileCategory::StructuredData,
+            &["text/csv"],
+        )]
+    }
+
     fn name(&self) -> &'static str {
         "csv"
     }
 }
 
 impl CsvExtractor {
-    fn detect_delimiter(&self, path: &Path) -> Result<u8> {
-        let file = File::open(path)?;
-        let mut reader = ReaderBuilder::new().has_headers(false).from_reader(file);
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        if let Some(result) = reader.records().next() {
-            let record = result?;
-            let line = record.as_slice();
+    /// Override delimiter detection and header handling
+    pub fn with_options(mut self, options: CsvOptions) -> Self {
+        self.options = options;
+        self
+    }
 
-            // Count occurrences of common delimiters
-            let comma_count = line.matches(',').count();
-            let tab_count = line.matches('\t').count();
-            let pipe_count = line.matches('|').count();
-            let semicolon_count = line.matches(';').count();
+    /// Pick the delimiter from the start of the (UTF-8) file
+    /// Each sampled line is split outside quotes, so quoted cells with commas or newlines
+    /// don't skew it; the delimiter giving the most lines the same field count wins
+    fn detect_delimiter(sample: &[u8], quote: u8) -> u8 {
+        let truncated = sample.len() == SAMPLE_BYTES;
 
-            // Return most common delimiter
-            let max = comma_count
-                .max(tab_count)
-                .max(pipe_count)
-                .max(semicolon_count);
+        // Delimiter counts per line, for each candidate
+        let mut lines: Vec<[usize; CANDIDATE_DELIMITERS.len()]> = Vec::new();
+        let mut counts = [0usize; CANDIDATE_DELIMITERS.len()];
+        let mut in_quotes = false;
+        let mut line_has_content = false;
+        for &byte in sample {
+            match byte {
+                // A doubled quote inside a field toggles twice, leaving the state unchanged
+                _ if byte == quote => in_quotes = !in_quotes,
+                b'\n' if !in_quotes => {
+                    if line_has_content {
+                        lines.push(counts);
+                    }
+                    if lines.len() == DELIMITER_SAMPLE_LINES {
+                        break;
+                    }
+                    counts = [0; CANDIDATE_DELIMITERS.len()];
+                    line_has_content = false;
+                    continue;
+                }
+                b'\r' => continue,
+                _ if !in_quotes => {
+                    if let Some(idx) = CANDIDATE_DELIMITERS.iter().position(|&d| d == byte) {
+                        counts[idx] += 1;
+                    }
+                }
+                _ => {}
+            }
+            line_has_content = true;
+        }
+        // A final line cut off by the sample limit would skew the comparison
+        if line_has_content && !truncated && lines.len() < DELIMITER_SAMPLE_LINES {
+            lines.push(counts);
+        }
 
-            if max == comma_count {
-                return Ok(b',');
-            } else if max == tab_count {
-                return Ok(b'\t');
-            } else if max == pipe_count {
-                return Ok(b'|');
-            } else if max == semicolon_count {
-                return Ok(b';');
+        // Score each candidate by how many lines share its most common non-zero count,
+        // then by that count; ties go to the earlier candidate
+        let mut best = (0, (0, 0));
+        for idx in 0..CANDIDATE_DELIMITERS.len() {
+            let mut frequency: HashMap<usize, usize> = HashMap::new();
+            for line in &lines {
+                if line[idx] > 0 {
+                    *frequency.entry(line[idx]).or_default() += 1;
+                }
+            }
+            let score = frequency
+                .into_iter()
+                .map(|(count, lines)| (lines, count))
+                .max()
+                .unwrap_or((0, 0));
+            if score > best.1 {
+                best = (idx, score);
             }
         }
 
-        Ok(b',')
+        CANDIDATE_DELIMITERS[best.0]
     }
 
-    fn infer_schema(
-        &self,
-        reader: &mut csv::Reader<File>,
-        headers: &[String],
-    ) -> Result<Vec<ColumnSchema>> {
+    fn infer_schema(&self, samples: &[StringRecord], headers: &[String]) -> Vec<ColumnSchema> {
         let mut schema: Vec<ColumnSchema> = headers
             .iter()
             .map(|name| ColumnSchema {
@@ -121,28 +264,26 @@ impl CsvExtractor {
             })
             .collect();
 
-        // Sample first 100 rows to infer types
+        // Use the sampled rows to infer types
         let mut has_values = vec![false; headers.len()];
         let mut all_numeric = vec![true; headers.len()];
         let mut all_integer = vec![true; headers.len()];
 
-        for result in reader.records().take(100) {
-            if let Ok(record) = result {
-                for (idx, field) in record.iter().enumerate() {
-                    if idx >= schema.len() {
-                        break;
-                    }
+        for record in samples {
+            for (idx, field) in record.iter().enumerate() {
+                if idx >= schema.len() {
+                    break;
+                }
 
-                    if !field.is_empty() {
-                        has_values[idx] = true;
+                if !field.is_empty() {
+                    has_values[idx] = true;
 
-                        // Check if numeric
-                        if field.parse::<f64>().is_err() {
-                            all_numeric[idx] = false;
-                            all_integer[idx] = false;
-                        } else if field.parse::<i64>().is_err() {
-                            all_integer[idx] = false;
-                        }
+                    // Check if numeric
+                    if field.parse::<f64>().is_err() {
+                        all_numeric[idx] = false;
+                        all_integer[idx] = false;
+                    } else if field.parse::<i64>().is_err() {
+                        all_integer[idx] = false;
                     }
                 }
             }
@@ -157,6 +298,434 @@ impl CsvExtractor {
             }
         }
 
-        Ok(schema)
+        schema
+    }
+}
+
+/// A CSV file's contents as UTF-8, whatever encoding it was written in
+struct Utf8Source {
+    reader: Box<dyn Read>,
+    /// Start of the UTF-8 contents, up to SAMPLE_BYTES
+    sample: Vec<u8>,
+    /// Encoding the file was read as
+    encoding: &'static Encoding,
+}
+
+impl Utf8Source {
+    /// Detect the file's encoding and read it as UTF-8
+    /// Files that start as valid UTF-8 are streamed as they are; anything else is streamed
+    /// through a decoder, since the csv crate only reads UTF-8
+    fn open(path: &Path) -> Result<Self> {
+        let mut file = File::open(path).context("Failed to open CSV file")?;
+        let mut sample = Vec::with_capacity(SAMPLE_BYTES);
+        (&mut file)
+            .take(SAMPLE_BYTES as u64)
+            .read_to_end(&mut sample)?;
+        file.rewind()?;
+
+        let encoding = match Encoding::for_bom(&sample) {
+            Some((encoding, _)) => encoding,
+            None if is_utf8_sample(&sample, sample.len() < SAMPLE_BYTES) => {
+                return Ok(Self {
+                    reader: Box::new(file),
+                    sample,
+                    encoding: UTF_8,
+                });
+            }
+            None => {
+                let encoding = detect_encoding(&mut file)?;
+                file.rewind()?;
+                encoding
+            }
+        };
+
+        // Strips the BOM, if any, along with decoding
+        let mut reader = DecodingReader::new(file, encoding);
+        let mut sample = Vec::with_capacity(SAMPLE_BYTES);
+        (&mut reader)
+            .take(SAMPLE_BYTES as u64)
+            .read_to_end(&mut sample)?;
+        Ok(Self {
+            reader: Box::new(Cursor::new(sample.clone()).chain(reader)),
+            sample,
+            encoding,
+        })
+    }
+}
+
+/// Whether the start of a file is valid UTF-8 (plain ASCII included)
+fn is_utf8_sample(sample: &[u8], is_whole_file: bool) -> bool {
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        // A multi-byte character cut off by the end of the sample isn't an error
+        Err(e) => e.error_len().is_none() && !is_whole_file,
+    }
+}
+
+/// Encoding of a file whose start isn't valid UTF-8, read through to the end
+/// It stays UTF-8 unless invalid sequences outnumber the valid multi-byte characters, so
+/// a stray corrupt byte doesn't send a UTF-8 export through a legacy decoder; otherwise
+/// chardetng's best guess
+fn detect_encoding(reader: &mut impl Read) -> std::io::Result<&'static Encoding> {
+    let mut detector = EncodingDetector::new();
+    let mut buf = vec![0; DECODE_BUFFER_BYTES];
+    // Bytes of a multi-byte character split across two reads
+    let mut pending = Vec::new();
+    let (mut valid, mut invalid) = (0u64, 0u64);
+
+    loop {
+        let read = reader.read(&mut buf)?;
+        detector.feed(&buf[..read], read == 0);
+        if read == 0 {
+            if !pending.is_empty() {
+                invalid += 1;
+            }
+            break;
+        }
+        pending.extend_from_slice(&buf[..read]);
+        let consumed = tally_utf8(&pending, &mut valid, &mut invalid);
+        pending.drain(..consumed);
+    }
+
+    Ok(if invalid > valid {
+        detector.guess(None, true)
+    } else {
+        UTF_8
+    })
+}
+
+/// Count valid multi-byte characters and invalid sequences in `bytes`
+/// Returns how many bytes were consumed; an incomplete character at the end is left over
+fn tally_utf8(mut bytes: &[u8], valid: &mut u64, invalid: &mut u64) -> usize {
+    let total = bytes.len();
+    loop {
+        let (good, error_len) = match std::str::from_utf8(bytes) {
+            Ok(_) => (bytes, None),
+            Err(e) => (&bytes[..e.valid_up_to()], Some(e.error_len())),
+        };
+        // Lead bytes of multi-byte characters; continuation bytes are below 0xC0
+        *valid += good.iter().filter(|&&b| b >= 0xC0).count() as u64;
+        match error_len {
+            None => return total,
+            Some(Some(len)) => {
+                *invalid += 1;
+                bytes = &bytes[good.len() + len..];
+            }
+            Some(None) => return total - (bytes.len() - good.len()),
+        }
+    }
+}
+
+/// Reads another reader's bytes as UTF-8, decoding them as they are read so a legacy
+/// encoded file is never held in memory whole; malformed sequences become U+FFFD
+struct DecodingReader<R> {
+    inner: R,
+    decoder: Decoder,
+    input: Vec<u8>,
+    input_start: usize,
+    input_end: usize,
+    output: Vec<u8>,
+    output_start: usize,
+    output_end: usize,
+    eof: bool,
+    finished: bool,
+}
+
+impl<R: Read> DecodingReader<R> {
+    fn new(inner: R, encoding: &'static Encoding) -> Self {
+        Self {
+            inner,
+            // Sniffs and removes a BOM, which may override `encoding`
+            decoder: encoding.new_decoder(),
+            input: vec![0; DECODE_BUFFER_BYTES],
+            input_start: 0,
+            input_end: 0,
+            output: vec![0; DECODE_BUFFER_BYTES * 3],
+            output_start: 0,
+            output_end: 0,
+            eof: false,
+            finished: false,
+        }
+    }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            if self.output_start < self.output_end {
+                let len = (self.output_end - self.output_start).min(buf.len());
+                buf[..len]
+                    .copy_from_slice(&self.output[self.output_start..self.output_start + len]);
+                self.output_start += len;
+                return Ok(len);
+            }
+            if self.finished {
+                return Ok(0);
+            }
+
+            if self.input_start == self.input_end && !self.eof {
+                self.input_end = self.inner.read(&mut self.input)?;
+                self.input_start = 0;
+                self.eof = self.input_end == 0;
+            }
+            let (result, read, written, _) = self.decoder.decode_to_utf8(
+                &self.input[self.input_start..self.input_end],
+                &mut self.output,
+                self.eof,
+            );
+            self.input_start += read;
+            self.output_start = 0;
+            self.output_end = written;
+            self.finished = self.eof && result == CoderResult::InputEmpty;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_quoted_fields_and_ragged_rows() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("export.csv");
+        let mut data = Vec::new();
+        data.extend_from_slice(b"name,address,amount\n");
+        data.extend_from_slice(b"\"Smith, John\",\"1 Main St, Springfield\",100\n");
+        data.extend_from_slice(b"Jane,\"Line one\nLine two\",200\n");
+        data.extend_from_slice(b"Bob,short\n");
+        data.extend_from_slice(b"\xff\xfe,not utf8,1\n");
+        data.extend_from_slice(b"Eve,Elm St,3,extra\n");
+        std::fs::write(&path, data).unwrap();
+
+        let output = CsvExtractor::new().extract(&path).unwrap();
+
+        match output.structured {
+            Some(StructuredData::Csv {
+                headers,
+                row_count,
+                schema,
+                ..
+            }) => {
+                assert_eq!(headers, vec!["name", "address", "amount"]);
+                // The non-UTF-8 row is transcoded rather than rejected
+                assert_eq!(row_count, 5);
+                assert_eq!(schema[2].data_type, "integer");
+            }
+            other => panic!("expected CSV structure, got {:?}", other),
+        }
+        assert_eq!(output.fields["ragged_rows"], "2");
+        assert_ne!(output.fields["source_encoding"], "UTF-8");
+        assert!(!output.fields.contains_key("error_rows"));
+    }
+
+    #[test]
+    fn test_delimiter_ignores_quoted_commas_and_newlines() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("mailbox.csv");
+        std::fs::write(
+            &path,
+            "\"Subject, thread\";\"From, display\";Date\n\
+             \"Re: invoice, final\";\"Doe, Jane\";2024-01-02\n\
+             \"Meeting\";\"Roe, Rick\nroe@example.com\";2024-01-03\n",
+        )
+        .unwrap();
+
+        let output = CsvExtractor::new().extract(&path).unwrap();
+
+        assert_eq!(output.fields["delimiter"], ";");
+        match output.structured {
+            Some(StructuredData::Csv {
+                headers, row_count, ..
+            }) => {
+                assert_eq!(headers, vec!["Subject, thread", "From, display", "Date"]);
+                assert_eq!(row_count, 2);
+            }
+            other => panic!("expected CSV structure, got {:?}", other),
+        }
+        assert!(!output.fields.contains_key("ragged_rows"));
+    }
+
+    #[test]
+    fn test_configured_quote_character() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("export.log.csv");
+        std::fs::write(
+            &path,
+            "'time, local',user,action\n\
+             '2024-01-02 10:00, CET',alice,login\n\
+             '2024-01-02 11:00, CET',bob,logout\n",
+        )
+        .unwrap();
+
+        let output = CsvExtractor::new()
+            .with_options(CsvOptions {
+                quote: Some('\''),
+                ..Default::default()
+            })
+            .extract(&path)
+            .unwrap();
+
+        assert_eq!(output.fields["delimiter"], ",");
+        match output.structured {
+            Some(StructuredData::Csv {
+                headers, row_count, ..
+            }) => {
+                assert_eq!(headers, vec!["time, local", "user", "action"]);
+                assert_eq!(row_count, 2);
+            }
+            other => panic!("expected CSV structure, got {:?}", other),
+        }
+        assert!(!output.fields.contains_key("ragged_rows"));
+    }
+
+    #[test]
+    fn test_headerless_tsv_gets_generated_column_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("events.tsv");
+        std::fs::write(
+            &path,
+            "2024-01-01\t10.5\tlogin, console\n\
+             2024-01-02\t3\tlogout\n\
+             2024-01-03\t7.25\tlogin, ssh, key\n",
+        )
+        .unwrap();
+
+        let extractor = CsvExtractor::new().with_options(CsvOptions {
+            has_headers: Some(false),
+            ..Default::default()
+        });
+        let output = extractor.extract(&path).unwrap();
+
+        assert_eq!(output.fields["delimiter"], "\t");
+        match output.structured {
+            Some(StructuredData::Csv {
+                headers,
+                row_count,
+                schema,
+                ..
+            }) => {
+                assert_eq!(headers, vec!["column_1", "column_2", "column_3"]);
+                assert_eq!(row_count, 3);
+                assert_eq!(schema[0].data_type, "string");
+                assert_eq!(schema[1].data_type, "number");
+            }
+            other => panic!("expected CSV structure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_semicolon_csv_with_decimal_commas() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("ledger.csv");
+        std::fs::write(
+            &path,
+            "id;amount;memo\n\
+             1;1,50;coffee, cake, tip, tax\n\
+             2;2,75;fuel\n\
+             3;10,00;rent, march, april, may, june\n",
+        )
+        .unwrap();
+
+        let output = CsvExtractor::new().extract(&path).unwrap();
+
+        assert_eq!(output.fields["delimiter"], ";");
+        match output.structured {
+            Some(StructuredData::Csv {
+                headers,
+                row_count,
+                schema,
+                ..
+            }) => {
+                assert_eq!(headers, vec!["id", "amount", "memo"]);
+                assert_eq!(row_count, 3);
+                assert_eq!(schema[0].data_type, "integer");
+                assert_eq!(schema[1].data_type, "string");
+            }
+            other => panic!("expected CSV structure, got {:?}", other),
+        }
+
+        // A forced delimiter skips detection entirely
+        let forced = CsvExtractor::new()
+            .with_options(CsvOptions {
+                delimiter: Some(','),
+                ..Default::default()
+            })
+            .extract(&path)
+            .unwrap();
+        assert_eq!(forced.fields["delimiter"], ",");
+    }
+
+    #[test]
+    fn test_windows_1252_and_utf16_files_are_transcoded() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // "Müller" and "Café" as written by a Western European Windows export
+        let path = temp_dir.path().join("kunden.csv");
+        std::fs::write(
+            &path,
+            b"name;ort;betrag\nM\xfcller;K\xf6ln;12\nCaf\xe9 Stra\xdfe;M\xfcnchen;7\n",
+        )
+        .unwrap();
+        let output = CsvExtractor::new().extract(&path).unwrap();
+        assert_eq!(output.fields["source_encoding"], "windows-1252");
+        assert_eq!(output.fields["delimiter"], ";");
+        assert_eq!(output.fields["row_count"], "2");
+        assert!(!output.fields.contains_key("error_rows"));
+
+        // Excel's "Unicode text" export: UTF-16LE with a BOM, tab separated
+        let path = temp_dir.path().join("export.txt");
+        let mut data = vec![0xFF, 0xFE];
+        for unit in "Straße\tBetrag\nHauptstraße\t5\n".encode_utf16() {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        std::fs::write(&path, data).unwrap();
+        let output = CsvExtractor::new().extract(&path).unwrap();
+        assert_eq!(output.fields["source_encoding"], "UTF-16LE");
+        assert_eq!(output.fields["delimiter"], "\t");
+        match output.structured {
+            Some(StructuredData::Csv {
+                headers, row_count, ..
+            }) => {
+                assert_eq!(headers, vec!["Straße", "Betrag"]);
+                assert_eq!(row_count, 1);
+            }
+            other => panic!("expected CSV structure, got {:?}", other),
+        }
+
+        let path = temp_dir.path().join("plain.csv");
+        std::fs::write(&path, "a,b\n1,2\n").unwrap();
+        let output = CsvExtractor::new().extract(&path).unwrap();
+        assert_eq!(output.fields["source_encoding"], "UTF-8");
+    }
+
+    #[test]
+    fn test_stray_byte_keeps_utf8_and_large_legacy_files_stream() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // One corrupt byte among genuine UTF-8 characters is still UTF-8
+        let path = temp_dir.path().join("damaged.csv");
+        std::fs::write(
+            &path,
+            b"name,city\nM\xc3\xbcller,K\xc3\xb6ln\nBad\xff,M\xc3\xbcnchen\n",
+        )
+        .unwrap();
+        let output = CsvExtractor::new().extract(&path).unwrap();
+        assert_eq!(output.fields["source_encoding"], "UTF-8");
+        assert_eq!(output.fields["row_count"], "2");
+        assert!(!output.fields.contains_key("error_rows"));
+
+        // Decoded across many buffer refills, with no row lost at the seams
+        let path = temp_dir.path().join("large.csv");
+        let mut data = b"name;ort\n".to_vec();
+        for _ in 0..20_000 {
+            data.extend_from_slice(b"M\xfcller;K\xf6ln\n");
+        }
+        std::fs::write(&path, data).unwrap();
+        let output = CsvExtractor::new().extract(&path).unwrap();
+        assert_eq!(output.fields["source_encoding"], "windows-1252");
+        assert_eq!(output.fields["row_count"], "20000");
+        assert!(!output.fields.contains_key("error_rows"));
     }
 }
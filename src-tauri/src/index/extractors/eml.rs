@@ -0,0 +1,221 @@
+// Single RFC 822 messages (.eml), as exported by most mail clients
+// Multipart bodies and quoted-printable/base64 transfer encodings are decoded by mail-parser
+
+use super::{Extractor, ExtractorOutput, SupportedFormat};
+use crate::index::detector::EML_MIME;
+use crate::index::schema::FileCategory;
+use anyhow::{Context, Result};
+use mail_parser::{Address, MessageParser, MimeHeaders};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Body text shown in the preview after the sender and subject
+const PREVIEW_BODY_CHARS: usize = 300;
+
+/// Bytes of a message read for parsing; past this is usually the tail of a large attachment
+const MAX_EML_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Extracts headers, decoded body text and attachment names from .eml messages
+pub struct EmlExtractor;
+
+impl EmlExtractor {
+    /// Addresses as `Name <address>`, or just the address when there is no display name
+    fn format_addresses(address: Option<&Address>) -> Option<String> {
+        let formatted: Vec<String> = address?
+            .iter()
+            .filter_map(|addr| {
+                let email = addr.address()?;
+                Some(match addr.name() {
+                    Some(name) => format!("{} <{}>", name, email),
+                    None => email.to_string(),
+                })
+            })
+            .collect();
+        (!formatted.is_empty()).then(|| formatted.join(", "))
+    }
+
+    /// Up to max_len bytes of the file, and whether anything was left unread
+    fn read_capped(path: &Path, max_len: u64) -> Result<(Vec<u8>, bool)> {
+        let mut data = Vec::new();
+        File::open(path)
+            .context("Failed to open email file")?
+            .take(max_len + 1)
+            .read_to_end(&mut data)
+            .context("Failed to read email file")?;
+        let truncated = data.len() as u64 > max_len;
+        data.truncate(max_len as usize);
+        Ok((data, truncated))
+    }
+}
+
+impl Extractor for EmlExtractor {
+    fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
+        let (data, truncated) = Self::read_capped(path, MAX_EML_BYTES)?;
+        let message = MessageParser::default()
+            .parse(&data)
+            .context("Failed to parse email")?;
+
+        let mut fields = HashMap::new();
+        if truncated {
+            fields.insert("truncated".to_string(), "true".to_string());
+        }
+        let subject = message.subject().unwrap_or_default().to_string();
+        if !subject.is_empty() {
+            fields.insert("subject".to_string(), subject.clone());
+        }
+        for (name, address) in [
+            ("from", message.from()),
+            ("to", message.to()),
+            ("cc", message.cc()),
+        ] {
+            if let Some(addresses) = Self::format_addresses(address) {
+                fields.insert(name.to_string(), addresses);
+            }
+        }
+        if let Some(date) = message.date() {
+            fields.insert("date".to_string(), date.to_rfc3339());
+        }
+        if let Some(message_id) = message.message_id() {
+            fields.insert("message_id".to_string(), message_id.to_string());
+        }
+
+        let attachments: Vec<&str> = message
+            .attachments()
+            .filter_map(|part| part.attachment_name())
+            .collect();
+        fields.insert(
+            "attachment_count".to_string(),
+            message.attachment_count().to_string(),
+        );
+        if !attachments.is_empty() {
+            fields.insert("attachments".to_string(), attachments.join(", "));
+        }
+
+        // HTML-only messages come back from body_text already converted to text
+        let body: Vec<String> = (0..message.text_body_count())
+            .filter_map(|i| message.body_text(i))
+            .map(|text| text.into_owned())
+            .collect();
+        let body = body.join("\n");
+
+        // Headers, body and attachment names are all searchable
+        let mut content = String::new();
+        for name in ["from", "to", "cc", "subject"] {
+            if let Some(value) = fields.get(name) {
+                content.push_str(value);
+                content.push('\n');
+            }
+        }
+        content.push_str(&body);
+        for attachment in &attachments {
+            content.push('\n');
+            content.push_str(attachment);
+        }
+
+        let sender = fields
+            .get("from")
+            .map(String::as_str)
+            .unwrap_or("unknown sender");
+        let mut preview = format!("Email from {}: {}", sender, subject);
+        let body_preview: String = body.chars().take(PREVIEW_BODY_CHARS).collect();
+        if !body_preview.trim().is_empty() {
+            preview.push_str(" - ");
+            preview.push_str(body_preview.trim());
+        }
+        if !attachments.is_empty() {
+            preview.push_str(&format!(" ({} attachments)", attachments.len()));
+        }
+
+        Ok(ExtractorOutput {
+            structured: None,
+            content: Some(content),
+            preview,
+            fields,
+        })
+    }
+
+    fn can_handle(&self, category: FileCategory, mime_type: &str) -> bool {
+        category == FileCategory::Email && mime_type == EML_MIME
+    }
+
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(FileCategory::Email, &[EML_MIME])]
+    }
+
+    fn name(&self) -> &'static str {
+        "eml"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::FileTypeDetector;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const MULTIPART_EML: &[u8] = b"From: Alice Example <alice@example.com>\r
+To: bob@example.com\r
+Subject: Wire transfer details\r
+Date: Mon, 1 Jan 2024 10:00:00 +0000\r
+Message-ID: <abc123@example.com>\r
+MIME-Version: 1.0\r
+Content-Type: multipart/mixed; boundary=\"XYZ\"\r
+\r
+--XYZ\r
+Content-Type: text/plain; charset=utf-8\r
+Content-Transfer-Encoding: quoted-printable\r
+\r
+Please send the funds to account 4471 by Friday =E2=80=94 do not call.\r
+--XYZ\r
+Content-Type: application/pdf; name=\"invoice.pdf\"\r
+Content-Disposition: attachment; filename=\"invoice.pdf\"\r
+Content-Transfer-Encoding: base64\r
+\r
+JVBERi0xLjQK\r
+--XYZ--\r
+";
+
+    #[test]
+    fn test_multipart_eml_extraction() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(MULTIPART_EML).unwrap();
+        file.flush().unwrap();
+
+        let detected = FileTypeDetector::detect(file.path()).unwrap();
+        assert!(EmlExtractor.can_handle(detected.category, &detected.mime_type));
+
+        let output = EmlExtractor.extract(file.path()).unwrap();
+        let content = output.content.unwrap();
+        assert!(content.contains("Wire transfer details"));
+        assert!(content.contains("account 4471 by Friday \u{2014} do not call"));
+        assert!(content.contains("invoice.pdf"));
+        assert!(!content.contains("JVBERi0xLjQK"));
+
+        assert_eq!(output.fields["subject"], "Wire transfer details");
+        assert_eq!(output.fields["from"], "Alice Example <alice@example.com>");
+        assert_eq!(output.fields["to"], "bob@example.com");
+        assert_eq!(output.fields["attachments"], "invoice.pdf");
+        assert_eq!(output.fields["attachment_count"], "1");
+        assert_eq!(output.fields["date"], "2024-01-01T10:00:00Z");
+        assert!(!output.fields.contains_key("truncated"));
+    }
+
+    #[test]
+    fn test_read_capped_stops_at_limit() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(MULTIPART_EML).unwrap();
+        file.flush().unwrap();
+
+        let (data, truncated) = EmlExtractor::read_capped(file.path(), 64).unwrap();
+        assert_eq!(data, &MULTIPART_EML[..64]);
+        assert!(truncated);
+
+        let len = MULTIPART_EML.len() as u64;
+        let (data, truncated) = EmlExtractor::read_capped(file.path(), len).unwrap();
+        assert_eq!(data, MULTIPART_EML);
+        assert!(!truncated);
+    }
+}
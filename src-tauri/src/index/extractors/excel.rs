@@ -1,36 +1,39 @@
-use super::{Extractor, ExtractorOutput};
+use super::{Extractor, ExtractorOutput, SupportedFormat};
 use crate::index::schema::{FileCategory, SheetInfo, StructuredData};
 use anyhow::{Context, Result};
-use calamine::{open_workbook, Data, Reader, Xlsx};
+use calamine::{open_workbook_auto, Cell, Data, DataRef, Dimensions, Reader, Sheets};
 use std::collections::HashMap;
 use std::path::Path;
 
+/// Rows read from a sheet before the row count falls back to its dimension record
+const MAX_SCANNED_ROWS: u32 = 10_000;
+
 pub struct ExcelExtractor;
 
+/// What was learned about a sheet without loading all of it
+struct SheetScan {
+    headers: Vec<String>,
+    row_count: u64,
+    truncated: bool,
+}
+
 impl Extractor for ExcelExtractor {
     fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
-        let mut workbook: Xlsx<_> = open_workbook(path).context("Failed to open Excel file")?;
+        // Auto-detect handles both OOXML (.xlsx) and legacy BIFF (.xls) workbooks
+        let mut workbook = open_workbook_auto(path).context("Failed to open Excel file")?;
 
         let mut sheets = Vec::new();
         let mut total_rows = 0u64;
 
         // Process each sheet
         for sheet_name in workbook.sheet_names().to_vec() {
-            if let Ok(range) = workbook.worksheet_range(&sheet_name) {
-                let row_count = range.height() as u64;
-                total_rows += row_count;
-
-                // Extract headers from first row
-                let headers: Vec<String> = range
-                    .rows()
-                    .next()
-                    .map(|row| row.iter().map(|cell| Self::cell_to_string(cell)).collect())
-                    .unwrap_or_default();
-
+            if let Ok(scan) = Self::scan_sheet(&mut workbook, &sheet_name) {
+                total_rows += scan.row_count;
                 sheets.push(SheetInfo {
                     name: sheet_name.clone(),
-                    headers,
-                    row_count,
+                    headers: scan.headers,
+                    row_count: scan.row_count,
+                    truncated: scan.truncated,
                 });
             }
         }
@@ -53,6 +56,15 @@ impl Extractor for ExcelExtractor {
         }
         fields.insert("columns".to_string(), all_headers.join(", "));
 
+        let truncated: Vec<&str> = sheets
+            .iter()
+            .filter(|s| s.truncated)
+            .map(|s| s.name.as_str())
+            .collect();
+        if !truncated.is_empty() {
+            fields.insert("truncated_sheets".to_string(), truncated.join(", "));
+        }
+
         // Create preview
         let preview = format!(
             "Excel workbook: {} sheets, {} total rows. Sheets: {}",
@@ -71,7 +83,18 @@ impl Extractor for ExcelExtractor {
 
     fn can_handle(&self, category: FileCategory, mime_type: &str) -> bool {
         category == FileCategory::Document
-            && mime_type.contains("vnd.openxmlformats-officedocument.spreadsheetml")
+            && (mime_type.contains("vnd.openxmlformats-officedocument.spreadsheetml")
+                || mime_type == "application/vnd.ms-excel")
+    }
+
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(
+            FileCategory::Document,
+            &[
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+                "application/vnd.ms-excel",
+            ],
+        )]
     }
 
     fn name(&self) -> &'static str {
@@ -80,6 +103,118 @@ impl Extractor for ExcelExtractor {
 }
 
 impl ExcelExtractor {
+    /// Read the headers and row count of a sheet
+    /// OOXML and binary workbooks are streamed cell by cell, so only the first
+    /// MAX_SCANNED_ROWS rows are ever parsed; other formats have no streaming reader
+    /// and are loaded whole
+    fn scan_sheet<RS: std::io::Read + std::io::Seek>(
+        workbook: &mut Sheets<RS>,
+        name: &str,
+    ) -> Result<SheetScan> {
+        match workbook {
+            Sheets::Xlsx(xlsx) => {
+                let mut cells = xlsx.worksheet_cells_reader(name)?;
+                let dimensions = cells.dimensions();
+                Self::scan_cells(dimensions, || Ok(cells.next_cell()?))
+            }
+            Sheets::Xlsb(xlsb) => {
+                let mut cells = xlsb.worksheet_cells_reader(name)?;
+                let dimensions = cells.dimensions();
+                Self::scan_cells(dimensions, || Ok(cells.next_cell()?))
+            }
+            _ => {
+                let range = workbook.worksheet_range(name)?;
+                // Extract headers from first row
+                let headers = range
+                    .rows()
+                    .next()
+                    .map(|row| row.iter().map(Self::cell_to_string).collect())
+                    .unwrap_or_default();
+                Ok(SheetScan {
+                    headers,
+                    row_count: range.height() as u64,
+                    truncated: false,
+                })
+            }
+        }
+    }
+
+    /// Walk cells in row order, taking the first used row as headers and stopping
+    /// once MAX_SCANNED_ROWS rows have been seen. Past that point the row count
+    /// comes from the sheet's declared dimensions instead of the cells themselves
+    fn scan_cells<'a>(
+        dimensions: Dimensions,
+        mut next_cell: impl FnMut() -> Result<Option<Cell<DataRef<'a>>>>,
+    ) -> Result<SheetScan> {
+        let mut first_row = None;
+        let mut last_row = 0;
+        let mut header_cells = Vec::new();
+        let mut truncated = false;
+
+        while let Some(cell) = next_cell()? {
+            if cell.get_value() == &DataRef::Empty {
+                continue;
+            }
+            let (row, col) = cell.get_position();
+            let first = *first_row.get_or_insert(row);
+            if row - first >= MAX_SCANNED_ROWS {
+                truncated = true;
+                break;
+            }
+            if row == first {
+                header_cells.push((col, Self::cell_ref_to_string(cell.get_value())));
+            }
+            last_row = row;
+        }
+
+        let Some(first_row) = first_row else {
+            return Ok(SheetScan {
+                headers: Vec::new(),
+                row_count: 0,
+                truncated: false,
+            });
+        };
+
+        let mut row_count = (last_row - first_row + 1) as u64;
+        if truncated {
+            // The dimension record is optional, so never report fewer rows than were seen
+            let declared = dimensions.end.0.saturating_sub(first_row) as u64 + 1;
+            row_count = row_count.max(declared);
+        }
+
+        // Place headers by column so gaps in the first row stay aligned
+        let first_col = header_cells.iter().map(|(col, _)| *col).min().unwrap_or(0);
+        let mut headers = Vec::new();
+        for (col, value) in header_cells {
+            let index = (col - first_col) as usize;
+            if headers.len() <= index {
+                headers.resize(index + 1, String::new());
+            }
+            headers[index] = value;
+        }
+
+        Ok(SheetScan {
+            headers,
+            row_count,
+            truncated,
+        })
+    }
+
+    fn cell_ref_to_string(cell: &DataRef) -> String {
+        match cell {
+            DataRef::SharedString(s) => s.to_string(),
+            DataRef::Int(i) => i.to_string(),
+            DataRef::Float(f) => f.to_string(),
+            DataRef::String(s) => s.clone(),
+            DataRef::Bool(b) => b.to_string(),
+            DataRef::DateTime(dt) => format!("{:?}", dt),
+            DataRef::Error(e) => format!("Error: {:?}", e),
+            DataRef::Empty => String::new(),
+            DataRef::DateTimeIso(s) => s.clone(),
+            DataRef::DurationIso(s) => s.clone(),
+        }
+    }
+
     fn cell_to_string(cell: &Data) -> String {
         match cell {
             Data::Int(i) => i.to_string(),
@@ -94,3 +229,91 @@ impl ExcelExtractor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Write a single-sheet workbook with a header row and `rows` numeric data rows
+    fn write_workbook(path: &Path, rows: u32) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        let parts = [
+            (
+                "[Content_Types].xml",
+                r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#,
+            ),
+            (
+                "_rels/.rels",
+                r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/workbook.xml",
+                r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Transactions" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#,
+            ),
+        ];
+        for (name, body) in parts {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(body.as_bytes()).unwrap();
+        }
+
+        zip.start_file("xl/worksheets/sheet1.xml", options).unwrap();
+        write!(
+            zip,
+            r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><dimension ref="A1:B{}"/><sheetData><row r="1"><c r="A1" t="inlineStr"><is><t>account</t></is></c><c r="B1" t="inlineStr"><is><t>amount</t></is></c></row>"#,
+            rows + 1
+        )
+        .unwrap();
+        for row in 2..=rows + 1 {
+            write!(
+                zip,
+                r#"<row r="{row}"><c r="A{row}"><v>{row}</v></c><c r="B{row}"><v>{}</v></c></row>"#,
+                row * 3
+            )
+            .unwrap();
+        }
+        zip.write_all(b"</sheetData></worksheet>").unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_large_sheet_row_count_comes_from_dimensions() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("ledger.xlsx");
+        write_workbook(&path, MAX_SCANNED_ROWS * 3);
+
+        let output = ExcelExtractor.extract(&path).unwrap();
+        let Some(StructuredData::Excel { sheets, total_rows }) = output.structured else {
+            panic!("expected excel data");
+        };
+
+        assert_eq!(sheets.len(), 1);
+        assert_eq!(sheets[0].headers, vec!["account", "amount"]);
+        assert!(sheets[0].truncated);
+        assert_eq!(sheets[0].row_count, MAX_SCANNED_ROWS as u64 * 3 + 1);
+        assert_eq!(total_rows, sheets[0].row_count);
+        assert_eq!(output.fields["truncated_sheets"], "Transactions");
+    }
+
+    #[test]
+    fn test_small_sheet_is_fully_scanned() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("small.xlsx");
+        write_workbook(&path, 5);
+
+        let output = ExcelExtractor.extract(&path).unwrap();
+        let Some(StructuredData::Excel { sheets, .. }) = output.structured else {
+            panic!("expected excel data");
+        };
+
+        assert!(!sheets[0].truncated);
+        assert_eq!(sheets[0].row_count, 6);
+        assert!(!output.fields.contains_key("truncated_sheets"));
+    }
+}
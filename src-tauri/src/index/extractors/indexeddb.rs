@@ -1,4 +1,4 @@
-use super::{Extractor, ExtractorOutput};
+use super::{Extractor, ExtractorOutput, SupportedFormat};
 use crate::index::schema::{FileCategory, StructuredData};
 use anyhow::{Context, Result};
 use std::collections::HashMap;
@@ -62,6 +62,10 @@ impl Extractor for IndexedDbExtractor {
         category == FileCategory::Database
     }
 
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(FileCategory::Database, &[])]
+    }
+
     fn name(&self) -> &'static str {
         "indexeddb"
     }
@@ -1,4 +1,4 @@
-use super::{Extractor, ExtractorOutput};
+use super::{Extractor, ExtractorOutput, SupportedFormat};
 use crate::index::schema::{FileCategory, JsonPath, JsonValueType, StructuredData};
 use anyhow::{Context, Result};
 use serde_json::Value;
@@ -8,12 +8,35 @@ use std::path::Path;
 
 pub struct JsonExtractor;
 
+/// Maximum number of distinct keys collected across the objects of one array
+const MAX_ARRAY_KEYS: usize = 1000;
+
+/// Number of values sampled from an array of primitives
+const MAX_ARRAY_SAMPLES: usize = 10;
+
+/// JSONL records whose keys are merged into the reported paths
+const JSONL_SAMPLE_RECORDS: usize = 100;
+
+/// Share of non-empty lines that must be JSON objects for a file to count as JSONL
+const JSONL_MIN_OBJECT_RATIO: f64 = 0.8;
+
 impl Extractor for JsonExtractor {
     fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
         let content = fs::read_to_string(path).context("Failed to read JSON file")?;
 
-        // Parse JSON
-        let value: Value = serde_json::from_str(&content).context("Failed to parse JSON")?;
+        // A single document, or failing that, one object per line (JSONL/NDJSON)
+        let value: Value = match serde_json::from_str(&content) {
+            Ok(value) => value,
+            Err(e) => {
+                return match self.extract_json_lines(&content) {
+                    Some(mut output) => {
+                        output.content = Some(content);
+                        Ok(output)
+                    }
+                    None => Err(e).context("Failed to parse JSON"),
+                }
+            }
+        };
 
         // Extract structure
         let paths = self.extract_paths(&value, "$");
@@ -30,12 +53,7 @@ impl Extractor for JsonExtractor {
         let path_strings: Vec<String> = paths.iter().map(|p| p.path.clone()).collect();
         fields.insert("paths".to_string(), path_strings.join(" "));
 
-        // Create preview
-        let preview = if content.len() > 500 {
-            format!("{}\n...", &content[..497])
-        } else {
-            content.clone()
-        };
+        let preview = Self::preview(&content);
 
         Ok(ExtractorOutput {
             structured: Some(StructuredData::Json {
@@ -55,22 +73,105 @@ impl Extractor for JsonExtractor {
             && (mime_type == "application/json" || mime_type == "text/json")
     }
 
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(
+            FileCategory::StructuredData,
+            &["application/json", "text/json"],
+        )]
+    }
+
     fn name(&self) -> &'static str {
         "json"
     }
 }
 
 impl JsonExtractor {
+    /// Treat content as JSONL if enough of its lines are JSON objects
+    /// Paths are the union of keys across the first JSONL_SAMPLE_RECORDS records
+    /// Content is left for the caller to fill in with the raw text
+    fn extract_json_lines(&self, content: &str) -> Option<ExtractorOutput> {
+        let mut line_count = 0usize;
+        let mut samples = Vec::new();
+        let mut record_count = 0usize;
+        let (mut depth, mut object_count, mut array_count) = (0, 0, 0);
+
+        for line in content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            line_count += 1;
+            let Ok(record @ Value::Object(_)) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            record_count += 1;
+
+            let (record_depth, objects, arrays) = self.analyze_structure(&record);
+            depth = depth.max(record_depth);
+            object_count += objects;
+            array_count += arrays;
+            if samples.len() < JSONL_SAMPLE_RECORDS {
+                samples.push(record);
+            }
+        }
+
+        if line_count == 0 || (record_count as f64) < line_count as f64 * JSONL_MIN_OBJECT_RATIO {
+            return None;
+        }
+
+        let mut paths = Vec::new();
+        let records: Vec<&Value> = samples.iter().collect();
+        self.extract_paths_recursive(&records, "$", &mut paths, 0);
+
+        let mut fields = HashMap::new();
+        fields.insert("format".to_string(), "jsonl".to_string());
+        fields.insert("record_count".to_string(), record_count.to_string());
+        if record_count < line_count {
+            fields.insert(
+                "invalid_lines".to_string(),
+                (line_count - record_count).to_string(),
+            );
+        }
+        fields.insert("depth".to_string(), depth.to_string());
+        fields.insert("object_count".to_string(), object_count.to_string());
+        fields.insert("array_count".to_string(), array_count.to_string());
+        let path_strings: Vec<String> = paths.iter().map(|p| p.path.clone()).collect();
+        fields.insert("paths".to_string(), path_strings.join(" "));
+
+        Some(ExtractorOutput {
+            structured: Some(StructuredData::Json {
+                paths,
+                depth,
+                object_count,
+                array_count,
+            }),
+            content: None,
+            preview: Self::preview(content),
+            fields,
+        })
+    }
+
+    /// First 500 characters of the raw text
+    fn preview(content: &str) -> String {
+        if content.chars().nth(500).is_some() {
+            format!("{}\n...", content.chars().take(497).collect::<String>())
+        } else {
+            content.to_string()
+        }
+    }
+
     /// Extract all JSON paths from the value
     fn extract_paths(&self, value: &Value, current_path: &str) -> Vec<JsonPath> {
         let mut paths = Vec::new();
-        self.extract_paths_recursive(value, current_path, &mut paths, 0);
+        self.extract_paths_recursive(&[value], current_path, &mut paths, 0);
         paths
     }
 
+    /// Extract paths from every value found at current_path
+    /// Array elements share one `[*]` path, so the keys of all objects in an array are merged
     fn extract_paths_recursive(
         &self,
-        value: &Value,
+        values: &[&Value],
         current_path: &str,
         paths: &mut Vec<JsonPath>,
         depth: usize,
@@ -80,32 +181,67 @@ impl JsonExtractor {
             return;
         }
 
-        match value {
-            Value::Object(map) => {
-                for (key, val) in map {
-                    let path = format!("{}.{}", current_path, key);
-                    paths.push(JsonPath {
-                        path: path.clone(),
-                        value_type: Self::get_value_type(val),
-                        sample: Self::get_sample(val),
-                    });
-                    self.extract_paths_recursive(val, &path, paths, depth + 1);
-                }
-            }
-            Value::Array(arr) => {
-                for (idx, val) in arr.iter().enumerate().take(3) {
-                    // Sample first 3 items
-                    let path = format!("{}[{}]", current_path, idx);
-                    paths.push(JsonPath {
-                        path: path.clone(),
-                        value_type: Self::get_value_type(val),
-                        sample: Self::get_sample(val),
-                    });
-                    self.extract_paths_recursive(val, &path, paths, depth + 1);
+        // Union of object keys, in order of first appearance
+        let mut keys: Vec<(&str, Vec<&Value>)> = Vec::new();
+        let mut key_index: HashMap<&str, usize> = HashMap::new();
+        let mut elements: Vec<&Value> = Vec::new();
+
+        for value in values {
+            match value {
+                Value::Object(map) => {
+                    for (key, val) in map {
+                        match key_index.get(key.as_str()) {
+                            Some(&idx) => keys[idx].1.push(val),
+                            None if keys.len() < MAX_ARRAY_KEYS => {
+                                key_index.insert(key, keys.len());
+                                keys.push((key, vec![val]));
+                            }
+                            None => {}
+                        }
+                    }
                 }
+                Value::Array(arr) => elements.extend(arr.iter()),
+                _ => {}
             }
-            _ => {}
         }
+
+        for (key, vals) in keys {
+            let path = format!("{}.{}", current_path, key);
+            paths.push(JsonPath {
+                path: path.clone(),
+                value_type: Self::get_value_type(vals[0]),
+                sample: vals.iter().find_map(|val| Self::get_sample(val)),
+            });
+            self.extract_paths_recursive(&vals, &path, paths, depth + 1);
+        }
+
+        if elements.is_empty() {
+            return;
+        }
+
+        let path = format!("{}[*]", current_path);
+        let primitives: Vec<&Value> = elements
+            .iter()
+            .copied()
+            .filter(|val| !val.is_object() && !val.is_array())
+            .collect();
+        if let Some(first) = primitives.first() {
+            let samples: Vec<String> = primitives
+                .iter()
+                .filter_map(|val| Self::get_sample(val))
+                .take(MAX_ARRAY_SAMPLES)
+                .collect();
+            paths.push(JsonPath {
+                path: path.clone(),
+                value_type: Self::get_value_type(first),
+                sample: Some(format!(
+                    "{} values: {}",
+                    primitives.len(),
+                    samples.join(", ")
+                )),
+            });
+        }
+        self.extract_paths_recursive(&elements, &path, paths, depth + 1);
     }
 
     fn get_value_type(value: &Value) -> JsonValueType {
@@ -178,3 +314,65 @@ impl JsonExtractor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_paths_union_keys_across_all_elements() {
+        let mut events: Vec<Value> = (0..50)
+            .map(|i| serde_json::json!({ "ts": i, "name": "tick" }))
+            .collect();
+        events.push(serde_json::json!({ "ts": 50, "url": "https://example.com/exfil" }));
+        let value = serde_json::json!({ "traceEvents": events, "tags": ["a", "b", "c"] });
+
+        let paths = JsonExtractor.extract_paths(&value, "$");
+        let find = |path: &str| paths.iter().find(|p| p.path == path);
+
+        assert!(find("$.traceEvents[*].ts").is_some());
+        assert!(find("$.traceEvents[*].name").is_some());
+        assert_eq!(
+            find("$.traceEvents[*].url").and_then(|p| p.sample.as_deref()),
+            Some("https://example.com/exfil")
+        );
+        assert_eq!(
+            find("$.tags[*]").and_then(|p| p.sample.as_deref()),
+            Some("3 values: a, b, c")
+        );
+        // One entry per distinct path, not per element
+        assert_eq!(paths.len(), 6);
+    }
+
+    #[test]
+    fn test_jsonl_records_merge_keys_and_count() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("requests.ndjson");
+        let mut lines: Vec<String> = (0..120)
+            .map(|i| format!(r#"{{"ts":{},"method":"GET","status":200}}"#, i))
+            .collect();
+        lines.push(r#"{"ts":120,"method":"POST","body":{"user":"admin"}}"#.to_string());
+        lines.push("truncated {\"ts\":".to_string());
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let output = JsonExtractor.extract(&path).unwrap();
+
+        assert_eq!(output.fields["format"], "jsonl");
+        assert_eq!(output.fields["record_count"], "121");
+        assert_eq!(output.fields["invalid_lines"], "1");
+        assert!(output.content.unwrap().starts_with(r#"{"ts":0,"#));
+        match output.structured {
+            Some(StructuredData::Json { paths, .. }) => {
+                // Only the first 100 records are sampled, so the late `body` key is absent
+                let mut names: Vec<_> = paths.iter().map(|p| p.path.as_str()).collect();
+                names.sort();
+                assert_eq!(names, vec!["$.method", "$.status", "$.ts"]);
+            }
+            other => panic!("expected JSON structure, got {:?}", other),
+        }
+
+        // Mostly non-JSON lines are not JSONL
+        std::fs::write(&path, "{\"a\":1}\nplain text\nmore text\n").unwrap();
+        assert!(JsonExtractor.extract(&path).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Extractor, ExtractorOutput};
+use super::{Extractor, ExtractorOutput, SupportedFormat};
 use crate::index::schema::{FileCategory, StructuredData};
 use anyhow::{Context, Result};
 use std::collections::HashMap;
@@ -48,6 +48,13 @@ impl Extractor for LevelDbExtractor {
             && (mime_type.contains("leveldb") || mime_type.contains("x-leveldb"))
     }
 
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(
+            FileCategory::Database,
+            &["application/x-leveldb"],
+        )]
+    }
+
     fn name(&self) -> &'static str {
         "leveldb"
     }
@@ -0,0 +1,232 @@
+// Detection and summarising of well-known log formats
+// Turns plain-text logs into time ranges, client IPs and severity counts
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::LazyLock;
+
+/// Number of leading lines used to recognise the format
+const DETECTION_LINES: usize = 5;
+
+/// Apache/nginx access log, Combined Log Format
+static APACHE_COMBINED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^(\S+) \S+ \S+ \[([^\]]+)\] "[^"]*" \d{3} \S+ "[^"]*" "[^"]*""#).unwrap()
+});
+
+/// nginx error log: `2024/01/15 10:00:00 [error] 123#0: *1 message, client: 1.2.3.4, ...`
+static NGINX_ERROR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4}/\d{2}/\d{2} \d{2}:\d{2}:\d{2}) \[(\w+)\] \d+#\d+: ").unwrap()
+});
+
+/// Client address inside an nginx error message
+static NGINX_CLIENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"client: ([0-9A-Fa-f:.]+)").unwrap());
+
+/// RFC 5424 syslog header: `<PRI>1 TIMESTAMP HOST APP PROCID MSGID`
+static SYSLOG_5424: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^<(\d{1,3})>1 (\S+) \S+ \S+ \S+ \S+").unwrap());
+
+/// Syslog severities, indexed by PRI % 8
+const SYSLOG_SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// Keys commonly used for timestamps, levels and addresses in JSON-lines logs
+const JSON_TIME_KEYS: &[&str] = &["timestamp", "@timestamp", "time", "ts", "datetime"];
+const JSON_LEVEL_KEYS: &[&str] = &["level", "severity", "lvl", "log.level"];
+const JSON_IP_KEYS: &[&str] = &["ip", "client_ip", "remote_addr", "src_ip", "clientip"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    ApacheCombined,
+    NginxError,
+    Syslog,
+    JsonLines,
+}
+
+impl LogFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogFormat::ApacheCombined => "apache_combined",
+            LogFormat::NginxError => "nginx_error",
+            LogFormat::Syslog => "syslog",
+            LogFormat::JsonLines => "jsonl",
+        }
+    }
+
+    /// Recognise the format from the first few non-empty lines (all must agree)
+    pub fn detect(content: &str) -> Option<Self> {
+        let sample: Vec<&str> = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(DETECTION_LINES)
+            .collect();
+        if sample.is_empty() {
+            return None;
+        }
+
+        [
+            LogFormat::JsonLines,
+            LogFormat::ApacheCombined,
+            LogFormat::NginxError,
+            LogFormat::Syslog,
+        ]
+        .into_iter()
+        .find(|format| sample.iter().all(|line| format.matches(line)))
+    }
+
+    fn matches(&self, line: &str) -> bool {
+        match self {
+            LogFormat::ApacheCombined => APACHE_COMBINED.is_match(line),
+            LogFormat::NginxError => NGINX_ERROR.is_match(line),
+            LogFormat::Syslog => SYSLOG_5424.is_match(line),
+            LogFormat::JsonLines => serde_json::from_str::<serde_json::Value>(line)
+                .map(|value| value.is_object())
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// What a recognised log tells us
+#[derive(Debug, Default)]
+pub struct LogSummary {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub unique_ips: BTreeSet<String>,
+    /// Entry count per severity level
+    pub levels: BTreeMap<String, u64>,
+}
+
+impl LogSummary {
+    /// Walk every line of a log in the given format; lines that don't parse are skipped
+    pub fn analyze(content: &str, format: LogFormat) -> Self {
+        let mut summary = LogSummary::default();
+
+        for line in content.lines() {
+            match format {
+                LogFormat::ApacheCombined => {
+                    if let Some(caps) = APACHE_COMBINED.captures(line) {
+                        summary.unique_ips.insert(caps[1].to_string());
+                        summary.record_time(
+                            DateTime::parse_from_str(&caps[2], "%d/%b/%Y:%H:%M:%S %z")
+                                .ok()
+                                .map(|t| t.with_timezone(&Utc)),
+                        );
+                    }
+                }
+                LogFormat::NginxError => {
+                    if let Some(caps) = NGINX_ERROR.captures(line) {
+                        summary.record_time(
+                            NaiveDateTime::parse_from_str(&caps[1], "%Y/%m/%d %H:%M:%S")
+                                .ok()
+                                .map(|t| t.and_utc()),
+                        );
+                        summary.record_level(&caps[2]);
+                        if let Some(client) = NGINX_CLIENT.captures(line) {
+                            summary.unique_ips.insert(client[1].to_string());
+                        }
+                    }
+                }
+                LogFormat::Syslog => {
+                    if let Some(caps) = SYSLOG_5424.captures(line) {
+                        if let Ok(pri) = caps[1].parse::<usize>() {
+                            summary.record_level(SYSLOG_SEVERITIES[pri % 8]);
+                        }
+                        summary.record_time(
+                            DateTime::parse_from_rfc3339(&caps[2])
+                                .ok()
+                                .map(|t| t.with_timezone(&Utc)),
+                        );
+                    }
+                }
+                LogFormat::JsonLines => {
+                    if let Ok(serde_json::Value::Object(entry)) = serde_json::from_str(line) {
+                        let text = |keys: &[&str]| {
+                            keys.iter()
+                                .find_map(|key| entry.get(*key).and_then(|v| v.as_str()))
+                        };
+                        summary.record_time(
+                            text(JSON_TIME_KEYS)
+                                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                                .map(|t| t.with_timezone(&Utc)),
+                        );
+                        if let Some(level) = text(JSON_LEVEL_KEYS) {
+                            summary.record_level(level);
+                        }
+                        if let Some(ip) = text(JSON_IP_KEYS) {
+                            summary.unique_ips.insert(ip.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        summary
+    }
+
+    fn record_time(&mut self, time: Option<DateTime<Utc>>) {
+        let Some(time) = time else {
+            return;
+        };
+        if self.start.is_none_or(|start| time < start) {
+            self.start = Some(time);
+        }
+        if self.end.is_none_or(|end| time > end) {
+            self.end = Some(time);
+        }
+    }
+
+    fn record_level(&mut self, level: &str) {
+        *self.levels.entry(level.to_lowercase()).or_insert(0) += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apache_combined_time_range_and_ips() {
+        let log = r#"10.0.0.5 - - [15/Jan/2024:10:00:00 +0000] "GET / HTTP/1.1" 200 512 "-" "curl/8.0"
+192.168.1.20 - admin [15/Jan/2024:09:30:00 +0000] "POST /login HTTP/1.1" 302 0 "-" "Mozilla/5.0"
+10.0.0.5 - - [15/Jan/2024:11:45:10 +0100] "GET /export HTTP/1.1" 200 9000 "-" "curl/8.0"
+"#;
+
+        assert_eq!(LogFormat::detect(log), Some(LogFormat::ApacheCombined));
+        let summary = LogSummary::analyze(log, LogFormat::ApacheCombined);
+
+        assert_eq!(
+            summary.start.unwrap().to_rfc3339(),
+            "2024-01-15T09:30:00+00:00"
+        );
+        assert_eq!(
+            summary.end.unwrap().to_rfc3339(),
+            "2024-01-15T10:45:10+00:00"
+        );
+        assert_eq!(
+            summary.unique_ips.into_iter().collect::<Vec<_>>(),
+            vec!["10.0.0.5", "192.168.1.20"]
+        );
+    }
+
+    #[test]
+    fn test_jsonl_and_syslog_level_distribution() {
+        let jsonl = r#"{"time":"2024-03-01T12:00:00Z","level":"INFO","msg":"start"}
+{"time":"2024-03-01T12:00:05Z","level":"error","msg":"denied","ip":"203.0.113.9"}
+{"time":"2024-03-01T12:00:09Z","level":"ERROR","msg":"denied"}
+"#;
+        assert_eq!(LogFormat::detect(jsonl), Some(LogFormat::JsonLines));
+        let summary = LogSummary::analyze(jsonl, LogFormat::JsonLines);
+        assert_eq!(summary.levels["error"], 2);
+        assert_eq!(summary.levels["info"], 1);
+
+        let syslog = "<34>1 2024-03-01T12:00:00Z host sshd 42 - - Failed password\n\
+                      <38>1 2024-03-01T12:01:00Z host sshd 42 - - Accepted password\n";
+        assert_eq!(LogFormat::detect(syslog), Some(LogFormat::Syslog));
+        let summary = LogSummary::analyze(syslog, LogFormat::Syslog);
+        assert_eq!(summary.levels["crit"], 1);
+        assert_eq!(summary.levels["info"], 1);
+        assert!(LogFormat::detect("just some notes\nabout the case").is_none());
+    }
+}
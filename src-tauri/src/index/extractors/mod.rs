@@ -2,25 +2,37 @@
 // Each extractor knows how to extract searchable data from its file type
 
 use super::schema::{FileCategory, StructuredData};
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
 
+pub mod binary_strings;
+mod chat;
 mod csv_extractor;
+mod eml;
 mod excel;
 mod indexeddb;
 mod json;
 mod leveldb;
+mod log_format;
+pub mod pst;
 mod sqlite;
 mod text;
 mod xml;
 
-pub use csv_extractor::CsvExtractor;
+pub use binary_strings::BinaryStringsExtractor;
+pub use csv_extractor::{CsvExtractor, CsvOptions};
+pub use eml::EmlExtractor;
 pub use excel::ExcelExtractor;
 pub use indexeddb::IndexedDbExtractor;
 pub use json::JsonExtractor;
 pub use leveldb::LevelDbExtractor;
-pub use sqlite::SqliteExtractor;
+pub use pst::PstExtractor;
+pub use sqlite::{SqliteExtractor, SqliteOptions};
 pub use text::TextExtractor;
 pub use xml::XmlExtractor;
 
@@ -29,15 +41,67 @@ pub trait Extractor: Send + Sync {
     /// Extract structured data from a file
     fn extract(&self, path: &Path) -> Result<ExtractorOutput>;
 
+    /// Extract structure only, without reading the file's content
+    /// Used for files over their category's size limit; by default nothing is read
+    fn extract_metadata_only(&self, _path: &Path) -> Result<ExtractorOutput> {
+        Ok(ExtractorOutput {
+            structured: None,
+            content: None,
+            preview: format!("{} file (content not indexed)", self.name()),
+            fields: HashMap::new(),
+        })
+    }
+
     /// Check if this extractor can handle the file
     fn can_handle(&self, category: FileCategory, mime_type: &str) -> bool;
 
+    /// Formats advertised in the extractor catalog; matching still goes through can_handle
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        Vec::new()
+    }
+
     /// Get extractor name
     fn name(&self) -> &'static str;
 }
 
+/// How much of a file the extractor should read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractionMode {
+    /// Structure and full content
+    Full,
+
+    /// Structure only, for files over their category's size limit
+    MetadataOnly,
+}
+
+/// A file category an extractor handles, optionally narrowed to some MIME types
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportedFormat {
+    pub category: FileCategory,
+    /// Empty when every MIME type in the category is handled
+    pub mime_types: Vec<&'static str>,
+}
+
+impl SupportedFormat {
+    pub fn new(category: FileCategory, mime_types: &[&'static str]) -> Self {
+        Self {
+            category,
+            mime_types: mime_types.to_vec(),
+        }
+    }
+}
+
+/// Catalog entry for a registered extractor
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractorInfo {
+    pub name: &'static str,
+    pub formats: Vec<SupportedFormat>,
+}
+
 /// Output from an extractor
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExtractorOutput {
     /// Structured data (if applicable)
     pub structured: Option<StructuredData>,
@@ -52,39 +116,91 @@ pub struct ExtractorOutput {
     pub fields: HashMap<String, String>,
 }
 
+/// How long a single extraction may run before it is abandoned
+const DEFAULT_EXTRACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Per-format overrides for the built-in extractors
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractorConfig {
+    #[serde(default)]
+    pub csv: CsvOptions,
+    #[serde(default)]
+    pub sqlite: SqliteOptions,
+}
+
 /// Registry of all extractors
 pub struct ExtractorRegistry {
-    extractors: Vec<Box<dyn Extractor>>,
+    extractors: Vec<Arc<dyn Extractor>>,
+    config: ExtractorConfig,
+    /// Extractions running longer than this return a fallback output instead
+    extraction_timeout: Duration,
 }
 
 impl ExtractorRegistry {
     /// Create a new registry with all built-in extractors
     pub fn new() -> Self {
+        Self::with_config(ExtractorConfig::default())
+    }
+
+    /// Create a registry with all built-in extractors, applying the given overrides
+    pub fn with_config(config: ExtractorConfig) -> Self {
         let mut registry = Self {
             extractors: Vec::new(),
+            config: config.clone(),
+            extraction_timeout: DEFAULT_EXTRACTION_TIMEOUT,
         };
 
         // Register all extractors
-        registry.register(Box::new(SqliteExtractor));
+        registry.register(Box::new(SqliteExtractor::new().with_options(config.sqlite)));
         registry.register(Box::new(JsonExtractor));
-        registry.register(Box::new(CsvExtractor));
+        registry.register(Box::new(CsvExtractor::new().with_options(config.csv)));
         registry.register(Box::new(ExcelExtractor));
         registry.register(Box::new(XmlExtractor));
+        registry.register(Box::new(EmlExtractor));
         registry.register(Box::new(TextExtractor));
         registry.register(Box::new(LevelDbExtractor));
+        registry.register(Box::new(PstExtractor));
         registry.register(Box::new(IndexedDbExtractor));
+        registry.register(Box::new(BinaryStringsExtractor));
 
         registry
     }
 
+    /// Create a registry without any built-in extractors
+    pub fn empty() -> Self {
+        Self {
+            extractors: Vec::new(),
+            config: ExtractorConfig::default(),
+            extraction_timeout: DEFAULT_EXTRACTION_TIMEOUT,
+        }
+    }
+
+    /// Abandon extractions that run longer than this
+    pub fn with_extraction_timeout(mut self, timeout: Duration) -> Self {
+        self.extraction_timeout = timeout;
+        self
+    }
+
     /// Register a custom extractor
     pub fn register(&mut self, extractor: Box<dyn Extractor>) {
-        self.extractors.push(extractor);
+        self.extractors.push(Arc::from(extractor));
     }
 
-    /// Clone by creating a new registry
+    /// Clone by creating a new registry with the same overrides
     pub fn duplicate(&self) -> Self {
-        Self::new()
+        Self::with_config(self.config.clone()).with_extraction_timeout(self.extraction_timeout)
+    }
+
+    /// Every registered extractor with the formats it handles, in matching order
+    pub fn list(&self) -> Vec<ExtractorInfo> {
+        self.extractors
+            .iter()
+            .map(|extractor| ExtractorInfo {
+                name: extractor.name(),
+                formats: extractor.supported_formats(),
+            })
+            .collect()
     }
 
     /// Find an extractor for a file
@@ -106,22 +222,134 @@ impl ExtractorRegistry {
         category: FileCategory,
         mime_type: &str,
     ) -> Result<ExtractorOutput> {
-        if let Some(extractor) = self.find_extractor(category, mime_type) {
-            extractor.extract(path)
-        } else {
+        self.extract_with_mode(path, category, mime_type, ExtractionMode::Full)
+    }
+
+    /// Extract data using the appropriate extractor, limited to the given mode
+    /// The extractor runs on its own thread so a corrupt file can't hang the caller;
+    /// past the timeout the thread is abandoned and a fallback output is returned
+    pub fn extract_with_mode(
+        &self,
+        path: &Path,
+        category: FileCategory,
+        mime_type: &str,
+        mode: ExtractionMode,
+    ) -> Result<ExtractorOutput> {
+        let Some(extractor) = self
+            .extractors
+            .iter()
+            .find(|e| e.can_handle(category, mime_type))
+            .cloned()
+        else {
             // No specific extractor, return minimal output
-            Ok(ExtractorOutput {
+            return Ok(ExtractorOutput {
                 structured: None,
                 content: None,
                 preview: format!("{} file", mime_type),
                 fields: HashMap::new(),
+            });
+        };
+
+        let name = extractor.name();
+        let path_buf = path.to_path_buf();
+        let (tx, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name(format!("extractor-{}", name))
+            .spawn(move || {
+                let _ = tx.send(match mode {
+                    ExtractionMode::Full => extractor.extract(&path_buf),
+                    ExtractionMode::MetadataOnly => extractor.extract_metadata_only(&path_buf),
+                });
             })
+            .context("Failed to spawn extractor thread")?;
+
+        match rx.recv_timeout(self.extraction_timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                eprintln!(
+                    "Extractor {} timed out after {}ms on {}",
+                    name,
+                    self.extraction_timeout.as_millis(),
+                    path.display()
+                );
+                let mut fields = HashMap::new();
+                fields.insert("extraction_error".to_string(), "timeout".to_string());
+                Ok(ExtractorOutput {
+                    structured: None,
+                    content: None,
+                    preview: "Extraction timed out".to_string(),
+                    fields,
+                })
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                anyhow::bail!("Extractor {} panicked", name)
+            }
         }
     }
 }
 
+impl ExtractorOutput {
+    /// Whether this is the fallback returned for an extraction that timed out
+    pub fn timed_out(&self) -> bool {
+        self.fields.get("extraction_error").map(String::as_str) == Some("timeout")
+    }
+}
+
 impl Default for ExtractorRegistry {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_lists_builtin_extractors() {
+        let registry = ExtractorRegistry::new();
+        let catalog = registry.list();
+
+        let names: Vec<_> = catalog.iter().map(|info| info.name).collect();
+        for expected in [
+            "sqlite",
+            "json",
+            "csv",
+            "excel",
+            "xml",
+            "eml",
+            "text",
+            "pst",
+            "binary_strings",
+        ] {
+            assert!(names.contains(&expected), "missing {}", expected);
+        }
+
+        let text = catalog.iter().find(|info| info.name == "text").unwrap();
+        assert!(text
+            .formats
+            .contains(&SupportedFormat::new(FileCategory::Text, &[])));
+
+        // The catalog must agree with what the registry actually matches
+        for info in &catalog {
+            assert!(!info.formats.is_empty(), "{} advertises nothing", info.name);
+            for format in &info.formats {
+                let mime_types = if format.mime_types.is_empty() {
+                    vec!["application/octet-stream"]
+                } else {
+                    format.mime_types.clone()
+                };
+                for mime_type in mime_types {
+                    let extractor = registry.find_extractor(format.category, mime_type);
+                    assert_eq!(
+                        extractor.map(|e| e.name()),
+                        Some(info.name),
+                        "{:?} {} is claimed by another extractor",
+                        format.category,
+                        mime_type
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,1071 @@
+// Outlook PST/OST mailbox parsing, following [MS-PST]
+// Walks the node and block B-trees, then reads the property contexts of folders and messages
+// Unicode files with 512-byte or 4K pages are decoded in full when unencrypted or using
+// compressible (permute) encryption; with high (cyclic) encryption the B-trees still give
+// folder and message counts, since pages are never encrypted
+
+use super::{Extractor, ExtractorOutput, SupportedFormat};
+use crate::index::schema::{EmailFolder, FileCategory, StructuredData};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::ZlibDecoder;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Magic at the start of every PST and OST file
+pub const PST_MAGIC: &[u8; 4] = b"!BDN";
+
+/// MIME type reported by the detector for PST and OST files
+pub const PST_MIME: &str = "application/vnd.ms-outlook";
+
+/// Unicode files (Outlook 2003+); ANSI files use 14/15
+const VERSION_UNICODE: u16 = 23;
+
+/// Unicode OSTs with 4K pages (Outlook 2013+), whose data blocks may be deflated
+const VERSIONS_UNICODE_4K: [u16; 2] = [36, 37];
+
+/// Unicode header offsets: BREFNBT.ib, BREFBBT.ib and bCryptMethod
+const HEADER_NBT_ROOT: usize = 224;
+const HEADER_BBT_ROOT: usize = 240;
+const HEADER_CRYPT_METHOD: usize = 513;
+
+/// bCryptMethod values
+const CRYPT_NONE: u8 = 0;
+const CRYPT_PERMUTE: u8 = 1;
+const CRYPT_CYCLIC: u8 = 2;
+
+/// B-tree pages of 512-byte page files: entry count, entry size and level follow the entries
+const PAGE_SIZE: usize = 512;
+const PAGE_ENTRIES_END: usize = 488;
+
+/// B-tree pages of 4K page files, where the entry count takes two bytes
+const PAGE_SIZE_4K: usize = 4096;
+const PAGE_ENTRIES_END_4K: usize = 4056;
+
+const NID_TYPE_NORMAL_FOLDER: u32 = 0x02;
+const NID_TYPE_NORMAL_MESSAGE: u32 = 0x04;
+const NID_ROOT_FOLDER: u32 = 0x122;
+
+/// Bit marking blocks that hold block IDs (XBLOCK/XXBLOCK) rather than node data
+const BID_INTERNAL: u64 = 0x2;
+
+/// Deepest B-tree followed, so corrupt files can't recurse forever
+const MAX_TREE_DEPTH: usize = 16;
+
+/// Data trees are an XXBLOCK of XBLOCKs at most
+const MAX_DATA_TREE_DEPTH: usize = 2;
+
+/// Most blocks read for one node, so a corrupt data tree can't fan out without bound
+const MAX_NODE_BLOCKS: usize = 4096;
+
+/// Largest data block once inflated (4K page files)
+const MAX_INFLATED_BLOCK: u64 = 1 << 20;
+
+/// Subjects listed in the content and preview
+const MAX_SUBJECTS: usize = 100;
+
+/// Most messages whose properties are read for senders and dates; all are counted
+const MAX_MESSAGES_READ: usize = 100_000;
+
+const PROP_SUBJECT: u16 = 0x0037;
+const PROP_CLIENT_SUBMIT_TIME: u16 = 0x0039;
+const PROP_SENT_REPRESENTING_EMAIL: u16 = 0x0065;
+const PROP_SENDER_EMAIL: u16 = 0x0C1F;
+const PROP_MESSAGE_DELIVERY_TIME: u16 = 0x0E06;
+const PROP_DISPLAY_NAME: u16 = 0x3001;
+const PROP_SENDER_SMTP: u16 = 0x5D01;
+
+const PT_STRING8: u16 = 0x001E;
+const PT_UNICODE: u16 = 0x001F;
+const PT_SYSTIME: u16 = 0x0040;
+
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01
+const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+/// mpbbR from [MS-PST] 5.1: the byte substitution applied by compressible encryption
+#[rustfmt::skip]
+const PERMUTE_ENCODE: [u8; 256] = [
+    65, 54, 19, 98, 168, 33, 110, 187, 244, 22, 204, 4, 127, 100, 232, 93,
+    30, 242, 203, 42, 116, 197, 94, 53, 210, 149, 71, 158, 150, 45, 154, 136,
+    76, 125, 132, 63, 219, 172, 49, 182, 72, 95, 246, 196, 216, 57, 139, 231,
+    35, 59, 56, 142, 200, 193, 223, 37, 177, 32, 165, 70, 96, 78, 156, 251,
+    170, 211, 86, 81, 69, 124, 85, 0, 7, 201, 43, 157, 133, 155, 9, 160,
+    143, 173, 179, 15, 99, 171, 137, 75, 215, 167, 21, 90, 113, 102, 66, 191,
+    38, 74, 107, 152, 250, 234, 119, 83, 178, 112, 5, 44, 253, 89, 58, 134,
+    126, 206, 6, 235, 130, 120, 87, 199, 141, 67, 175, 180, 28, 212, 91, 205,
+    226, 233, 39, 79, 195, 8, 114, 128, 207, 176, 239, 245, 40, 109, 190, 48,
+    77, 52, 146, 213, 14, 60, 34, 50, 229, 228, 249, 159, 194, 209, 10, 129,
+    18, 225, 238, 145, 131, 118, 227, 151, 230, 97, 138, 23, 121, 164, 183, 220,
+    144, 122, 92, 140, 2, 166, 202, 105, 222, 80, 26, 17, 147, 185, 82, 135,
+    88, 252, 237, 29, 55, 73, 27, 106, 224, 41, 51, 153, 189, 108, 217, 148,
+    243, 64, 84, 111, 240, 198, 115, 184, 214, 62, 101, 24, 68, 31, 221, 103,
+    16, 241, 12, 25, 236, 174, 3, 161, 20, 123, 169, 11, 255, 248, 163, 192,
+    162, 1, 247, 46, 188, 36, 104, 117, 13, 254, 186, 47, 181, 208, 218, 61,
+];
+
+/// mpbbI: the inverse substitution, which decodes compressible encryption
+const PERMUTE_DECODE: [u8; 256] = invert_permutation(&PERMUTE_ENCODE);
+
+const fn invert_permutation(table: &[u8; 256]) -> [u8; 256] {
+    let mut inverse = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        inverse[table[i] as usize] = i as u8;
+        i += 1;
+    }
+    inverse
+}
+
+/// Extracts folders, senders, subjects and dates from Outlook PST/OST mailboxes
+pub struct PstExtractor;
+
+impl Extractor for PstExtractor {
+    fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
+        let mut pst = PstFile::open(path)?;
+        let summary = pst.summarize()?;
+
+        let mut fields = HashMap::new();
+        fields.insert("format".to_string(), summary.format.to_string());
+        fields.insert("encryption".to_string(), summary.encryption.to_string());
+        fields.insert(
+            "message_count".to_string(),
+            summary.message_count.to_string(),
+        );
+        fields.insert(
+            "folder_count".to_string(),
+            summary.folders.len().to_string(),
+        );
+        fields.insert(
+            "sender_count".to_string(),
+            summary.unique_senders.len().to_string(),
+        );
+        if let Some((first, last)) = summary.date_range {
+            fields.insert("first_message".to_string(), first.to_rfc3339());
+            fields.insert("last_message".to_string(), last.to_rfc3339());
+        }
+
+        // Folder names, senders and subjects are what investigators search a mailbox for
+        let mut content = String::new();
+        for folder in &summary.folders {
+            content.push_str(&format!("{} ({})\n", folder.path, folder.message_count));
+        }
+        for sender in &summary.unique_senders {
+            content.push_str(sender);
+            content.push('\n');
+        }
+        for subject in &summary.subjects {
+            content.push_str(subject);
+            content.push('\n');
+        }
+
+        let mut preview = format!(
+            "Outlook {} mailbox: {} folders, {} messages, {} senders",
+            summary.format.to_uppercase(),
+            summary.folders.len(),
+            summary.message_count,
+            summary.unique_senders.len()
+        );
+        if !summary.decoded {
+            preview.push_str(&format!(
+                " ({} encryption, contents not decoded)",
+                summary.encryption
+            ));
+        }
+        if !summary.subjects.is_empty() {
+            preview.push_str(". ");
+            preview.push_str(&summary.subjects.join("; "));
+        }
+
+        Ok(ExtractorOutput {
+            structured: Some(StructuredData::EmailDatabase {
+                format: summary.format.to_string(),
+                folder_count: summary.folders.len() as u64,
+                message_count: summary.message_count,
+                unique_senders: summary.unique_senders.into_iter().collect(),
+                date_range: summary.date_range,
+                folders: summary.folders,
+            }),
+            content: (!content.is_empty()).then_some(content),
+            preview: preview.chars().take(500).collect(),
+            fields,
+        })
+    }
+
+    fn can_handle(&self, category: FileCategory, mime_type: &str) -> bool {
+        category == FileCategory::Database && mime_type == PST_MIME
+    }
+
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(FileCategory::Database, &[PST_MIME])]
+    }
+
+    fn name(&self) -> &'static str {
+        "pst"
+    }
+}
+
+/// What a mailbox holds, as far as it could be decoded
+struct MailboxSummary {
+    format: &'static str,
+    encryption: &'static str,
+    /// Whether folder and message properties could be read
+    decoded: bool,
+    folders: Vec<EmailFolder>,
+    message_count: u64,
+    unique_senders: BTreeSet<String>,
+    subjects: Vec<String>,
+    date_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+/// Leaf entry of the node B-tree
+#[derive(Debug, Clone, Copy)]
+struct NodeEntry {
+    bid_data: u64,
+    nid_parent: u32,
+}
+
+/// Leaf entry of the block B-tree
+#[derive(Debug, Clone, Copy)]
+struct BlockEntry {
+    offset: u64,
+    size: u16,
+    /// Size once inflated; larger than size only for deflated blocks of 4K page files
+    inflated_size: u32,
+}
+
+/// Where the entry count, entry size and level sit in a B-tree page
+#[derive(Debug, Clone, Copy)]
+struct PageLayout {
+    page_size: usize,
+    entries_end: usize,
+    /// Entry count is a u16 rather than a byte
+    wide_count: bool,
+}
+
+impl PageLayout {
+    fn for_version(version: u16) -> Option<Self> {
+        if version == VERSION_UNICODE {
+            Some(Self {
+                page_size: PAGE_SIZE,
+                entries_end: PAGE_ENTRIES_END,
+                wide_count: false,
+            })
+        } else if VERSIONS_UNICODE_4K.contains(&version) {
+            Some(Self {
+                page_size: PAGE_SIZE_4K,
+                entries_end: PAGE_ENTRIES_END_4K,
+                wide_count: true,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Entry count, entry size and level of a page
+    fn trailer(&self, page: &[u8]) -> (usize, usize, u8) {
+        let end = self.entries_end;
+        if self.wide_count {
+            // cEnt, cEntMax, cbEnt, cLevel
+            let count = u16_at(page, end).unwrap_or(0) as usize;
+            (count, page[end + 4] as usize, page[end + 5])
+        } else {
+            (page[end] as usize, page[end + 2] as usize, page[end + 3])
+        }
+    }
+}
+
+struct PstFile {
+    file: File,
+    format: &'static str,
+    version: u16,
+    crypt_method: u8,
+    nbt_root: u64,
+    bbt_root: u64,
+}
+
+impl PstFile {
+    fn open(path: &Path) -> Result<Self> {
+        let mut file = File::open(path).context("Failed to open PST file")?;
+        let mut header = vec![0u8; HEADER_CRYPT_METHOD + 1];
+        file.read_exact(&mut header)
+            .context("PST file is shorter than its header")?;
+
+        if &header[0..4] != PST_MAGIC {
+            anyhow::bail!("Not a PST file (missing !BDN magic)");
+        }
+
+        // wMagicClient: "SM" for PST, "SO" for OST
+        let format = if &header[8..10] == b"SO" {
+            "ost"
+        } else {
+            "pst"
+        };
+
+        Ok(Self {
+            file,
+            format,
+            version: u16_at(&header, 10).unwrap_or(0),
+            crypt_method: header[HEADER_CRYPT_METHOD],
+            nbt_root: u64_at(&header, HEADER_NBT_ROOT).unwrap_or(0),
+            bbt_root: u64_at(&header, HEADER_BBT_ROOT).unwrap_or(0),
+        })
+    }
+
+    fn summarize(&mut self) -> Result<MailboxSummary> {
+        let encryption = match self.crypt_method {
+            CRYPT_NONE => "none",
+            CRYPT_PERMUTE => "compressible",
+            CRYPT_CYCLIC => "high",
+            _ => "unknown",
+        };
+        let mut summary = MailboxSummary {
+            format: self.format,
+            encryption,
+            decoded: false,
+            folders: Vec::new(),
+            message_count: 0,
+            unique_senders: BTreeSet::new(),
+            subjects: Vec::new(),
+            date_range: None,
+        };
+
+        // ANSI files use a different header and page format
+        let Some(layout) = PageLayout::for_version(self.version) else {
+            summary.encryption = "unsupported version";
+            return Ok(summary);
+        };
+
+        let mut nodes: Vec<(u32, NodeEntry)> = Vec::new();
+        self.walk_btree(layout, self.nbt_root, 0, &mut |entry| {
+            // NBTENTRY: nid (8, low 4 used), bidData, bidSub, nidParent
+            if let (Some(nid), Some(bid_data), Some(nid_parent)) =
+                (u32_at(entry, 0), u64_at(entry, 8), u32_at(entry, 24))
+            {
+                nodes.push((
+                    nid,
+                    NodeEntry {
+                        bid_data,
+                        nid_parent,
+                    },
+                ));
+            }
+        })?;
+        nodes.sort_by_key(|(nid, _)| *nid);
+
+        let folder_nids: Vec<u32> = nodes
+            .iter()
+            .filter(|(nid, _)| nid & 0x1F == NID_TYPE_NORMAL_FOLDER && *nid != NID_ROOT_FOLDER)
+            .map(|(nid, _)| *nid)
+            .collect();
+        let mut per_folder: HashMap<u32, u64> = HashMap::new();
+        for (nid, node) in &nodes {
+            if nid & 0x1F == NID_TYPE_NORMAL_MESSAGE {
+                *per_folder.entry(node.nid_parent).or_insert(0) += 1;
+                summary.message_count += 1;
+            }
+        }
+
+        if !matches!(self.crypt_method, CRYPT_NONE | CRYPT_PERMUTE) {
+            summary.folders = folder_nids
+                .iter()
+                .map(|nid| EmailFolder {
+                    path: format!("folder-{:x}", nid),
+                    message_count: per_folder.get(nid).copied().unwrap_or(0),
+                })
+                .collect();
+            return Ok(summary);
+        }
+
+        let mut blocks: HashMap<u64, BlockEntry> = HashMap::new();
+        let wide_pages = layout.wide_count;
+        self.walk_btree(layout, self.bbt_root, 0, &mut |entry| {
+            // BBTENTRY: BREF (bid, ib), cb, cRef; 4K page files add the inflated size
+            if let (Some(bid), Some(offset), Some(size)) =
+                (u64_at(entry, 0), u64_at(entry, 8), u16_at(entry, 16))
+            {
+                let inflated_size = match wide_pages {
+                    true => u32_at(entry, 20).unwrap_or(0),
+                    false => 0,
+                };
+                blocks.insert(
+                    bid & !1,
+                    BlockEntry {
+                        offset,
+                        size,
+                        inflated_size,
+                    },
+                );
+            }
+        })?;
+        summary.decoded = true;
+
+        let node_map: HashMap<u32, NodeEntry> = nodes.iter().copied().collect();
+
+        // Folder names first, then full paths by walking up to the root folder
+        let mut names: HashMap<u32, String> = HashMap::new();
+        for nid in &folder_nids {
+            let name = self
+                .property_context(&blocks, node_map[nid].bid_data)
+                .ok()
+                .and_then(|pc| pc.string(PROP_DISPLAY_NAME))
+                .unwrap_or_else(|| format!("folder-{:x}", nid));
+            names.insert(*nid, name);
+        }
+        for nid in &folder_nids {
+            summary.folders.push(EmailFolder {
+                path: folder_path(*nid, &names, &node_map),
+                message_count: per_folder.get(nid).copied().unwrap_or(0),
+            });
+        }
+        summary.folders.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut first: Option<DateTime<Utc>> = None;
+        let mut last: Option<DateTime<Utc>> = None;
+        let messages = nodes
+            .iter()
+            .filter(|(nid, _)| nid & 0x1F == NID_TYPE_NORMAL_MESSAGE)
+            .take(MAX_MESSAGES_READ);
+        for (_, node) in messages {
+            // A damaged message shouldn't stop the rest of the mailbox being read
+            let Ok(pc) = self.property_context(&blocks, node.bid_data) else {
+                continue;
+            };
+
+            let sender = [
+                PROP_SENDER_SMTP,
+                PROP_SENDER_EMAIL,
+                PROP_SENT_REPRESENTING_EMAIL,
+            ]
+            .into_iter()
+            .find_map(|id| pc.string(id).filter(|s| !s.is_empty()));
+            if let Some(sender) = sender {
+                summary.unique_senders.insert(sender.to_lowercase());
+            }
+
+            if let Some(time) = pc
+                .time(PROP_MESSAGE_DELIVERY_TIME)
+                .or_else(|| pc.time(PROP_CLIENT_SUBMIT_TIME))
+            {
+                first = Some(first.map_or(time, |t| t.min(time)));
+                last = Some(last.map_or(time, |t| t.max(time)));
+            }
+
+            if summary.subjects.len() < MAX_SUBJECTS {
+                if let Some(subject) = pc.string(PROP_SUBJECT).filter(|s| !s.is_empty()) {
+                    summary.subjects.push(strip_subject_prefix(&subject));
+                }
+            }
+        }
+        summary.date_range = first.zip(last);
+
+        Ok(summary)
+    }
+
+    fn read_at(&mut self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let mut buffer = vec![0u8; len];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file
+            .read_exact(&mut buffer)
+            .with_context(|| format!("Truncated PST data at offset {}", offset))?;
+        Ok(buffer)
+    }
+
+    /// Visit every leaf entry of the B-tree whose root page is at offset
+    fn walk_btree(
+        &mut self,
+        layout: PageLayout,
+        offset: u64,
+        depth: usize,
+        visit: &mut impl FnMut(&[u8]),
+    ) -> Result<()> {
+        if depth > MAX_TREE_DEPTH {
+            anyhow::bail!("PST B-tree is deeper than {} levels", MAX_TREE_DEPTH);
+        }
+
+        let page = self.read_at(offset, layout.page_size)?;
+        let (entry_count, entry_size, level) = layout.trailer(&page);
+        if entry_size == 0 || entry_count * entry_size > layout.entries_end {
+            anyhow::bail!("Corrupt PST B-tree page at offset {}", offset);
+        }
+
+        for entry in page[..entry_count * entry_size].chunks_exact(entry_size) {
+            if level > 0 {
+                // BTENTRY: btkey, then BREF (bid, ib) of the child page
+                let child = u64_at(entry, 16).context("Corrupt PST B-tree entry")?;
+                self.walk_btree(layout, child, depth + 1, visit)?;
+            } else {
+                visit(entry);
+            }
+        }
+        Ok(())
+    }
+
+    /// Data blocks of a node, in order; large nodes are split through XBLOCK/XXBLOCK trees
+    /// remaining_blocks bounds how many more blocks may be read for the node
+    fn data_blocks(
+        &mut self,
+        blocks: &HashMap<u64, BlockEntry>,
+        bid: u64,
+        depth: usize,
+        remaining_blocks: &mut usize,
+    ) -> Result<Vec<Vec<u8>>> {
+        if depth > MAX_DATA_TREE_DEPTH {
+            anyhow::bail!(
+                "PST data tree is deeper than {} levels",
+                MAX_DATA_TREE_DEPTH
+            );
+        }
+        if *remaining_blocks == 0 {
+            anyhow::bail!("PST node spans more than {} blocks", MAX_NODE_BLOCKS);
+        }
+        *remaining_blocks -= 1;
+
+        let entry = *blocks
+            .get(&(bid & !1))
+            .with_context(|| format!("PST block {:#x} not found", bid))?;
+        let data = self.read_at(entry.offset, entry.size as usize)?;
+        if bid & BID_INTERNAL == 0 {
+            return Ok(vec![self.decode_block(data, &entry)?]);
+        }
+
+        // XBLOCK/XXBLOCK: btype 0x01, cLevel, cEnt, lcbTotal, then child block IDs
+        if data.first() != Some(&0x01) {
+            anyhow::bail!("Unexpected PST internal block type");
+        }
+        let count = u16_at(&data, 2).unwrap_or(0) as usize;
+        let mut pages = Vec::new();
+        for i in 0..count {
+            let child = u64_at(&data, 8 + i * 8).context("Corrupt PST data tree")?;
+            pages.extend(self.data_blocks(blocks, child, depth + 1, remaining_blocks)?);
+        }
+        Ok(pages)
+    }
+
+    /// Undo deflation and compressible encryption of an external data block
+    /// Internal (XBLOCK) blocks are stored as-is
+    fn decode_block(&self, data: Vec<u8>, entry: &BlockEntry) -> Result<Vec<u8>> {
+        let mut data = if entry.inflated_size as usize > data.len() {
+            let limit = MAX_INFLATED_BLOCK.min(entry.inflated_size as u64);
+            let mut inflated = Vec::with_capacity(limit as usize);
+            ZlibDecoder::new(data.as_slice())
+                .take(limit)
+                .read_to_end(&mut inflated)
+                .context("Corrupt deflated PST block")?;
+            inflated
+        } else {
+            data
+        };
+
+        if self.crypt_method == CRYPT_PERMUTE {
+            for byte in &mut data {
+                *byte = PERMUTE_DECODE[*byte as usize];
+            }
+        }
+        Ok(data)
+    }
+
+    fn property_context(
+        &mut self,
+        blocks: &HashMap<u64, BlockEntry>,
+        bid: u64,
+    ) -> Result<PropertyContext> {
+        let mut remaining_blocks = MAX_NODE_BLOCKS;
+        PropertyContext::parse(Heap::new(self.data_blocks(
+            blocks,
+            bid,
+            0,
+            &mut remaining_blocks,
+        )?)?)
+    }
+}
+
+/// Folder path from the top of the mailbox, e.g. "Top of Personal Folders/Inbox"
+fn folder_path(nid: u32, names: &HashMap<u32, String>, nodes: &HashMap<u32, NodeEntry>) -> String {
+    let mut parts = Vec::new();
+    let mut current = nid;
+    while let Some(name) = names.get(&current) {
+        parts.push(name.as_str());
+        match nodes.get(&current) {
+            Some(node) if node.nid_parent != current && parts.len() < MAX_TREE_DEPTH * 4 => {
+                current = node.nid_parent;
+            }
+            _ => break,
+        }
+    }
+    parts.reverse();
+    parts.join("/")
+}
+
+/// Heap-on-node: allocations addressed by HID across one or more data blocks
+struct Heap {
+    pages: Vec<Vec<u8>>,
+}
+
+impl Heap {
+    fn new(pages: Vec<Vec<u8>>) -> Result<Self> {
+        // HNHDR: ibHnpm, bSig (0xEC), bClientSig, hidUserRoot, rgbFillLevel
+        match pages.first() {
+            Some(first) if first.len() >= 12 && first[2] == 0xEC => Ok(Self { pages }),
+            _ => anyhow::bail!("Not a PST heap-on-node"),
+        }
+    }
+
+    fn client_signature(&self) -> u8 {
+        self.pages[0][3]
+    }
+
+    fn user_root(&self) -> u32 {
+        u32_at(&self.pages[0], 4).unwrap_or(0)
+    }
+
+    /// Bytes of an allocation; None for NIDs (values kept in subnodes) or bad HIDs
+    fn get(&self, hid: u32) -> Option<&[u8]> {
+        if hid & 0x1F != 0 {
+            return None;
+        }
+        let index = ((hid >> 5) & 0x7FF) as usize;
+        let page = self.pages.get((hid >> 16) as usize)?;
+
+        // Every page starts with ibHnpm, the offset of its allocation map
+        let map = page.get(u16_at(page, 0)? as usize..)?;
+        let alloc_count = u16_at(map, 0)? as usize;
+        if index == 0 || index > alloc_count {
+            return None;
+        }
+        let start = u16_at(map, 4 + (index - 1) * 2)? as usize;
+        let end = u16_at(map, 4 + index * 2)? as usize;
+        page.get(start..end)
+    }
+}
+
+/// Property context: a B-tree on the heap mapping property IDs to values
+struct PropertyContext {
+    heap: Heap,
+    /// Property ID to (property type, inline value or HNID)
+    properties: HashMap<u16, (u16, u32)>,
+}
+
+impl PropertyContext {
+    fn parse(heap: Heap) -> Result<Self> {
+        if heap.client_signature() != 0xBC {
+            anyhow::bail!("PST node is not a property context");
+        }
+
+        // BTHHEADER: bType (0xB5), cbKey, cbEnt, bIdxLevels, hidRoot
+        let header = heap
+            .get(heap.user_root())
+            .filter(|h| h.len() >= 8 && h[0] == 0xB5)
+            .context("Missing property context header")?;
+        let (key_size, entry_size, levels) = (header[1], header[2], header[3]);
+        if key_size != 2 || entry_size != 6 {
+            anyhow::bail!("Unexpected property context record size");
+        }
+        let root = u32_at(header, 4).unwrap_or(0);
+
+        let mut properties = HashMap::new();
+        Self::collect(&heap, root, levels, 0, &mut properties)?;
+        Ok(Self { heap, properties })
+    }
+
+    fn collect(
+        heap: &Heap,
+        hid: u32,
+        level: u8,
+        depth: usize,
+        properties: &mut HashMap<u16, (u16, u32)>,
+    ) -> Result<()> {
+        if hid == 0 {
+            return Ok(());
+        }
+        if depth > MAX_TREE_DEPTH {
+            anyhow::bail!("Property context is deeper than {} levels", MAX_TREE_DEPTH);
+        }
+        let records = heap.get(hid).context("Missing property context records")?;
+
+        if level > 0 {
+            // Index records: key, then the HID of the next level
+            for record in records.chunks_exact(6) {
+                if let Some(child) = u32_at(record, 2) {
+                    Self::collect(heap, child, level - 1, depth + 1, properties)?;
+                }
+            }
+        } else {
+            // Leaf records: wPropId, wPropType, dwValueHnid
+            for record in records.chunks_exact(8) {
+                if let (Some(id), Some(kind), Some(value)) =
+                    (u16_at(record, 0), u16_at(record, 2), u32_at(record, 4))
+                {
+                    properties.insert(id, (kind, value));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn string(&self, id: u16) -> Option<String> {
+        let (kind, hnid) = *self.properties.get(&id)?;
+        let bytes = self.heap.get(hnid)?;
+        match kind {
+            PT_UNICODE => {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+            PT_STRING8 => Some(String::from_utf8_lossy(bytes).to_string()),
+            _ => None,
+        }
+    }
+
+    fn time(&self, id: u16) -> Option<DateTime<Utc>> {
+        let (kind, hnid) = *self.properties.get(&id)?;
+        if kind != PT_SYSTIME {
+            return None;
+        }
+        filetime_to_datetime(u64_at(self.heap.get(hnid)?, 0)?)
+    }
+}
+
+/// Subjects may start with 0x01 and a character giving the length of a prefix like "RE: "
+fn strip_subject_prefix(subject: &str) -> String {
+    match subject.strip_prefix('\u{1}') {
+        Some(rest) => rest.chars().skip(1).collect(),
+        None => subject.to_string(),
+    }
+}
+
+fn filetime_to_datetime(filetime: u64) -> Option<DateTime<Utc>> {
+    if filetime == 0 {
+        return None;
+    }
+    let secs = (filetime / 10_000_000) as i64 - FILETIME_UNIX_OFFSET;
+    let nanos = ((filetime % 10_000_000) * 100) as u32;
+    DateTime::from_timestamp(secs, nanos)
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    enum Value {
+        Text(&'static str),
+        Time(DateTime<Utc>),
+    }
+
+    /// A single-block heap holding a property context with the given properties
+    fn property_context_block(props: &[(u16, Value)]) -> Vec<u8> {
+        // Allocation 1: BTH header, 2: leaf records, 3..: variable-size values
+        let mut values = Vec::new();
+        let mut records = Vec::new();
+        for (id, value) in props {
+            let (kind, bytes) = match value {
+                Value::Text(text) => (
+                    PT_UNICODE,
+                    text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+                ),
+                Value::Time(time) => {
+                    let filetime = ((time.timestamp() + FILETIME_UNIX_OFFSET) as u64) * 10_000_000;
+                    (PT_SYSTIME, filetime.to_le_bytes().to_vec())
+                }
+            };
+            let hid = ((3 + values.len()) as u32) << 5;
+            records.extend_from_slice(&id.to_le_bytes());
+            records.extend_from_slice(&kind.to_le_bytes());
+            records.extend_from_slice(&hid.to_le_bytes());
+            values.push(bytes);
+        }
+
+        let mut header = vec![0xB5, 2, 6, 0];
+        header.extend_from_slice(&(2u32 << 5).to_le_bytes());
+        let mut allocations = vec![header, records];
+        allocations.extend(values);
+
+        let mut block = vec![0u8; 12];
+        block[2] = 0xEC;
+        block[3] = 0xBC;
+        block[4..8].copy_from_slice(&(1u32 << 5).to_le_bytes());
+        let mut offsets = vec![block.len() as u16];
+        for allocation in &allocations {
+            block.extend_from_slice(allocation);
+            offsets.push(block.len() as u16);
+        }
+        let map_offset = block.len() as u16;
+        block[0..2].copy_from_slice(&map_offset.to_le_bytes());
+        block.extend_from_slice(&(allocations.len() as u16).to_le_bytes());
+        block.extend_from_slice(&0u16.to_le_bytes());
+        for offset in offsets {
+            block.extend_from_slice(&offset.to_le_bytes());
+        }
+        block
+    }
+
+    fn btree_page(layout: PageLayout, entries: &[Vec<u8>], entry_size: u8) -> Vec<u8> {
+        let mut page = vec![0u8; layout.page_size];
+        for (i, entry) in entries.iter().enumerate() {
+            let start = i * entry_size as usize;
+            page[start..start + entry.len()].copy_from_slice(entry);
+        }
+        let end = layout.entries_end;
+        if layout.wide_count {
+            page[end..end + 2].copy_from_slice(&(entries.len() as u16).to_le_bytes());
+            page[end + 4] = entry_size;
+        } else {
+            page[end] = entries.len() as u8;
+            page[end + 2] = entry_size;
+        }
+        page
+    }
+
+    /// Minimal unencrypted Unicode PST: root folder, Inbox and the given messages
+    fn build_pst(messages: &[Vec<(u16, Value)>]) -> Vec<u8> {
+        build_pst_with(messages, VERSION_UNICODE, CRYPT_NONE)
+    }
+
+    /// Unicode PST or OST of the given version, with data blocks encoded by crypt_method
+    /// Blocks of 4K page files are deflated where that makes them smaller
+    fn build_pst_with(messages: &[Vec<(u16, Value)>], version: u16, crypt_method: u8) -> Vec<u8> {
+        let layout = PageLayout::for_version(version).unwrap();
+        let mut nodes = vec![
+            (
+                NID_ROOT_FOLDER,
+                NID_ROOT_FOLDER,
+                property_context_block(&[]),
+            ),
+            (
+                0x8022,
+                NID_ROOT_FOLDER,
+                property_context_block(&[(PROP_DISPLAY_NAME, Value::Text("Inbox"))]),
+            ),
+        ];
+        for (i, props) in messages.iter().enumerate() {
+            let nid = (((0x10000 + i) as u32) << 5) | NID_TYPE_NORMAL_MESSAGE;
+            nodes.push((nid, 0x8022, property_context_block(props)));
+        }
+
+        let page_size = layout.page_size as u64;
+        let (nbt_offset, bbt_offset, mut data_offset) =
+            (2 * page_size, 3 * page_size, 4 * page_size);
+        let mut file = vec![0u8; data_offset as usize];
+        file[0..4].copy_from_slice(PST_MAGIC);
+        file[8..10].copy_from_slice(if layout.wide_count { b"SO" } else { b"SM" });
+        file[10..12].copy_from_slice(&version.to_le_bytes());
+        file[HEADER_CRYPT_METHOD] = crypt_method;
+        file[HEADER_NBT_ROOT..HEADER_NBT_ROOT + 8].copy_from_slice(&nbt_offset.to_le_bytes());
+        file[HEADER_BBT_ROOT..HEADER_BBT_ROOT + 8].copy_from_slice(&bbt_offset.to_le_bytes());
+
+        let (mut nbt_entries, mut bbt_entries) = (Vec::new(), Vec::new());
+        for (i, (nid, parent, block)) in nodes.iter().enumerate() {
+            let bid = (i as u64 + 1) * 4;
+            let mut nbt_entry = (*nid as u64).to_le_bytes().to_vec();
+            nbt_entry.extend_from_slice(&bid.to_le_bytes());
+            nbt_entry.extend_from_slice(&0u64.to_le_bytes());
+            nbt_entry.extend_from_slice(&parent.to_le_bytes());
+            nbt_entries.push(nbt_entry);
+
+            let mut stored = block.clone();
+            if crypt_method == CRYPT_PERMUTE {
+                for byte in &mut stored {
+                    *byte = PERMUTE_ENCODE[*byte as usize];
+                }
+            }
+            if layout.wide_count {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                std::io::Write::write_all(&mut encoder, &stored).unwrap();
+                let deflated = encoder.finish().unwrap();
+                // Blocks that don't shrink are stored as they are
+                if deflated.len() < stored.len() {
+                    stored = deflated;
+                }
+            }
+
+            let mut bbt_entry = bid.to_le_bytes().to_vec();
+            bbt_entry.extend_from_slice(&data_offset.to_le_bytes());
+            bbt_entry.extend_from_slice(&(stored.len() as u16).to_le_bytes());
+            bbt_entry.extend_from_slice(&0u16.to_le_bytes());
+            bbt_entry.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            bbt_entries.push(bbt_entry);
+
+            file.extend_from_slice(&stored);
+            file.resize(file.len().next_multiple_of(64), 0);
+            data_offset = file.len() as u64;
+        }
+
+        let nbt_page = btree_page(layout, &nbt_entries, 32);
+        file[nbt_offset as usize..(nbt_offset + page_size) as usize].copy_from_slice(&nbt_page);
+        let bbt_page = btree_page(layout, &bbt_entries, 24);
+        file[bbt_offset as usize..(bbt_offset + page_size) as usize].copy_from_slice(&bbt_page);
+        file
+    }
+
+    #[test]
+    fn test_pst_folders_senders_and_date_range() {
+        let sent = |day| {
+            DateTime::parse_from_rfc3339(day)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let pst = build_pst(&[
+            vec![
+                (PROP_SUBJECT, Value::Text("Q3 wire transfer")),
+                (PROP_SENDER_SMTP, Value::Text("CFO@example.com")),
+                (
+                    PROP_MESSAGE_DELIVERY_TIME,
+                    Value::Time(sent("2023-03-01T09:00:00Z")),
+                ),
+            ],
+            vec![
+                (PROP_SUBJECT, Value::Text("\u{1}\u{4}RE: Q3 wire transfer")),
+                (PROP_SENDER_EMAIL, Value::Text("clerk@example.com")),
+                (
+                    PROP_CLIENT_SUBMIT_TIME,
+                    Value::Time(sent("2023-03-04T17:30:00Z")),
+                ),
+            ],
+        ]);
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("mailbox.pst");
+        std::fs::write(&path, pst).unwrap();
+
+        let output = PstExtractor.extract(&path).unwrap();
+
+        match output.structured {
+            Some(StructuredData::EmailDatabase {
+                format,
+                folder_count,
+                message_count,
+                unique_senders,
+                date_range,
+                folders,
+            }) => {
+                assert_eq!(format, "pst");
+                assert_eq!(folder_count, 1);
+                assert_eq!(message_count, 2);
+                assert_eq!(unique_senders, vec!["cfo@example.com", "clerk@example.com"]);
+                assert_eq!(
+                    date_range,
+                    Some((sent("2023-03-01T09:00:00Z"), sent("2023-03-04T17:30:00Z")))
+                );
+                assert_eq!(
+                    folders,
+                    vec![EmailFolder {
+                        path: "Inbox".to_string(),
+                        message_count: 2,
+                    }]
+                );
+            }
+            other => panic!("expected EmailDatabase, got {:?}", other),
+        }
+        assert!(output.content.unwrap().contains("RE: Q3 wire transfer"));
+        assert_eq!(output.fields["first_message"], "2023-03-01T09:00:00+00:00");
+    }
+
+    #[test]
+    fn test_permute_encrypted_pst_and_4k_ost_are_decoded() {
+        let message = || {
+            vec![
+                (PROP_SUBJECT, Value::Text("Shipment schedule")),
+                (PROP_SENDER_SMTP, Value::Text("dispatch@example.com")),
+            ]
+        };
+        let temp_dir = TempDir::new().unwrap();
+
+        for (name, version, crypt_method, format) in [
+            ("permute.pst", VERSION_UNICODE, CRYPT_PERMUTE, "pst"),
+            ("cached.ost", VERSIONS_UNICODE_4K[0], CRYPT_PERMUTE, "ost"),
+        ] {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, build_pst_with(&[message()], version, crypt_method)).unwrap();
+
+            let output = PstExtractor.extract(&path).unwrap();
+            assert_eq!(output.fields["format"], format);
+            assert_eq!(output.fields["encryption"], "compressible");
+            assert_eq!(output.fields["sender_count"], "1");
+            let content = output.content.unwrap();
+            assert!(content.contains("Inbox (1)"), "{name}: {content}");
+            assert!(content.contains("dispatch@example.com"));
+            assert!(content.contains("Shipment schedule"));
+        }
+    }
+
+    #[test]
+    fn test_data_tree_block_count_is_capped() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("mailbox.pst");
+        std::fs::write(&path, build_pst(&[])).unwrap();
+        let mut pst = PstFile::open(&path).unwrap();
+
+        // One XBLOCK listing itself, one listing the same data block 600 times
+        let (looping_bid, wide_bid, data_bid) =
+            (0x1000 | BID_INTERNAL, 0x2000 | BID_INTERNAL, 0x3000);
+        let xblock = |child: u64| {
+            let mut xblock = vec![0x01, 0x01];
+            xblock.extend_from_slice(&600u16.to_le_bytes());
+            xblock.extend_from_slice(&0u32.to_le_bytes());
+            for _ in 0..600 {
+                xblock.extend_from_slice(&child.to_le_bytes());
+            }
+            xblock
+        };
+        let mut blocks = HashMap::new();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        let mut offset = std::fs::metadata(&path).unwrap().len();
+        for (bid, data) in [
+            (looping_bid, xblock(looping_bid)),
+            (wide_bid, xblock(data_bid)),
+            (data_bid, vec![0u8; 16]),
+        ] {
+            std::io::Write::write_all(&mut file, &data).unwrap();
+            let entry = BlockEntry {
+                offset,
+                size: data.len() as u16,
+                inflated_size: 0,
+            };
+            blocks.insert(bid, entry);
+            offset += data.len() as u64;
+        }
+
+        let mut remaining_blocks = MAX_NODE_BLOCKS;
+        let err = pst
+            .data_blocks(&blocks, looping_bid, 0, &mut remaining_blocks)
+            .unwrap_err();
+        assert!(err.to_string().contains("deeper"));
+
+        let mut remaining_blocks = MAX_NODE_BLOCKS;
+        let pages = pst
+            .data_blocks(&blocks, wide_bid, 0, &mut remaining_blocks)
+            .unwrap();
+        assert_eq!(pages.len(), 600);
+
+        let mut remaining_blocks = 100;
+        let err = pst
+            .data_blocks(&blocks, wide_bid, 0, &mut remaining_blocks)
+            .unwrap_err();
+        assert!(err.to_string().contains("more than"));
+    }
+}
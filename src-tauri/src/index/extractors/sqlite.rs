@@ -1,14 +1,85 @@
-use super::{Extractor, ExtractorOutput};
+use super::chat::ChatSchema;
+use super::{ExtractionMode, Extractor, ExtractorOutput, SupportedFormat};
 use crate::index::schema::{ColumnInfo, FileCategory, StructuredData, TableInfo};
 use anyhow::{Context, Result};
-use rusqlite::{Connection, OpenFlags};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags, Row};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 
-pub struct SqliteExtractor;
+/// Row content is only indexed for databases up to this size
+const MAX_CONTENT_DB_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Tables with more rows than this are skipped for row content
+const MAX_CONTENT_TABLE_ROWS: u64 = 10_000;
+
+/// Cap on the total row content indexed per database
+const MAX_ROW_CONTENT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Upper bound on SqliteOptions::sample_values
+const MAX_SAMPLE_VALUES: usize = 20;
+
+/// Sampled text values are cut to this many characters
+const MAX_SAMPLE_CHARS: usize = 100;
+
+/// Shown instead of the bytes of a sampled binary value
+const BLOB_SAMPLE: &str = "<blob>";
+
+/// Overrides for SQLite extraction
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqliteOptions {
+    /// Distinct values sampled from each column for the schema view (0 = none, at most 20)
+    /// Each sampled column costs a query, which can scan the whole table
+    #[serde(default)]
+    pub sample_values: usize,
+}
+
+#[derive(Default)]
+pub struct SqliteExtractor {
+    options: SqliteOptions,
+}
 
 impl Extractor for SqliteExtractor {
     fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
+        self.extract_database(path, ExtractionMode::Full)
+    }
+
+    /// Schema and row counts only; no row content is read
+    fn extract_metadata_only(&self, path: &Path) -> Result<ExtractorOutput> {
+        self.extract_database(path, ExtractionMode::MetadataOnly)
+    }
+
+    fn can_handle(&self, category: FileCategory, mime_type: &str) -> bool {
+        category == FileCategory::Database
+            && (mime_type.contains("sqlite") || mime_type.contains("x-sqlite"))
+    }
+
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(
+            FileCategory::Database,
+            &["application/vnd.sqlite3"],
+        )]
+    }
+
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+}
+
+impl SqliteExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable column value sampling
+    pub fn with_options(mut self, options: SqliteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn extract_database(&self, path: &Path, mode: ExtractionMode) -> Result<ExtractorOutput> {
         // Open database in read-only mode
         let conn = Connection::open_with_flags(
             path,
@@ -27,7 +98,11 @@ impl Extractor for SqliteExtractor {
             .unwrap_or(4096);
 
         // Get all tables (excluding internal SQLite tables)
-        let tables = self.extract_tables(&conn)?;
+        let sample_values = match mode {
+            ExtractionMode::Full => self.options.sample_values.min(MAX_SAMPLE_VALUES),
+            ExtractionMode::MetadataOnly => 0,
+        };
+        let tables = self.extract_tables(&conn, sample_values)?;
 
         let total_rows: u64 = tables.iter().map(|t| t.row_count).sum();
 
@@ -51,14 +126,35 @@ impl Extractor for SqliteExtractor {
         }
         fields.insert("columns".to_string(), all_columns.join(", "));
 
+        // Small databases (messages, contacts, password managers) get their rows indexed too
+        let db_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(u64::MAX);
+        let include_rows = mode == ExtractionMode::Full && db_size <= MAX_CONTENT_DB_SIZE;
+        let (mut content, coverage) = if include_rows {
+            self.extract_row_content(&conn, &tables)
+        } else {
+            (None, "none")
+        };
+        fields.insert("content_indexed".to_string(), coverage.to_string());
+
         // Create preview
-        let preview = format!(
+        let mut preview = format!(
             "SQLite database: {} tables, {} total rows. Tables: {}",
             tables.len(),
             total_rows,
             table_names.join(", ")
         );
 
+        // Messaging app databases also get their conversation indexed as a transcript
+        if let Some(schema) = ChatSchema::detect(&tables) {
+            fields.insert("app".to_string(), schema.app.to_string());
+            if mode == ExtractionMode::Full {
+                if let Some(transcript) = self.extract_chat(&conn, schema, &mut fields) {
+                    preview = format!("{} chat: {}", schema.app, preview);
+                    content = Some(transcript + content.as_deref().unwrap_or(""));
+                }
+            }
+        }
+
         Ok(ExtractorOutput {
             structured: Some(StructuredData::Sqlite {
                 tables,
@@ -66,24 +162,55 @@ impl Extractor for SqliteExtractor {
                 page_size,
                 version,
             }),
-            content: None, // We don't index full DB content
+            content,
             preview: preview.chars().take(500).collect(),
             fields,
         })
     }
 
-    fn can_handle(&self, category: FileCategory, mime_type: &str) -> bool {
-        category == FileCategory::Database
-            && (mime_type.contains("sqlite") || mime_type.contains("x-sqlite"))
-    }
+    /// Read the messages of a recognised chat database, recording counts and time range
+    /// Returns None if the messages can't be read or there are none
+    fn extract_chat(
+        &self,
+        conn: &Connection,
+        schema: &ChatSchema,
+        fields: &mut HashMap<String, String>,
+    ) -> Option<String> {
+        let transcript = match schema.read_messages(conn) {
+            Ok(transcript) => transcript,
+            Err(e) => {
+                eprintln!("Failed to read {} messages: {}", schema.app, e);
+                return None;
+            }
+        };
+        if transcript.messages.is_empty() {
+            return None;
+        }
 
-    fn name(&self) -> &'static str {
-        "sqlite"
+        fields.insert(
+            "message_count".to_string(),
+            transcript.messages.len().to_string(),
+        );
+        fields.insert(
+            "chat_content".to_string(),
+            if transcript.truncated {
+                "partial"
+            } else {
+                "full"
+            }
+            .to_string(),
+        );
+        if let Some(first) = transcript.first_sent() {
+            fields.insert("first_message".to_string(), first.to_rfc3339());
+        }
+        if let Some(last) = transcript.last_sent() {
+            fields.insert("last_message".to_string(), last.to_rfc3339());
+        }
+
+        Some(transcript.to_content())
     }
-}
 
-impl SqliteExtractor {
-    fn extract_tables(&self, conn: &Connection) -> Result<Vec<TableInfo>> {
+    fn extract_tables(&self, conn: &Connection, sample_values: usize) -> Result<Vec<TableInfo>> {
         let mut stmt = conn.prepare(
             "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
         )?;
@@ -95,7 +222,15 @@ impl SqliteExtractor {
         let mut tables = Vec::new();
 
         for table_name in table_names {
-            let columns = self.extract_columns(conn, &table_name)?;
+            let mut columns = self.extract_columns(conn, &table_name)?;
+            if sample_values > 0 {
+                for column in &mut columns {
+                    // A column that can't be sampled keeps an empty list
+                    column.sample_values = self
+                        .sample_column(conn, &table_name, column, sample_values)
+                        .unwrap_or_default();
+                }
+            }
             let row_count = self.count_rows(conn, &table_name)?;
             let indexes = self.get_indexes(conn, &table_name)?;
 
@@ -110,6 +245,79 @@ impl SqliteExtractor {
         Ok(tables)
     }
 
+    /// Serialize every row of the small tables as one JSON object per line
+    /// Returns the content and whether it covers the tables "full"y, "partial"ly or not at all
+    fn extract_row_content(
+        &self,
+        conn: &Connection,
+        tables: &[TableInfo],
+    ) -> (Option<String>, &'static str) {
+        let mut content = String::new();
+        let mut skipped = false;
+
+        'tables: for table in tables {
+            if table.row_count > MAX_CONTENT_TABLE_ROWS {
+                skipped = true;
+                continue;
+            }
+
+            let columns: Vec<String> = table.columns.iter().map(|c| c.name.clone()).collect();
+            let Ok(mut stmt) = conn.prepare(&format!("SELECT * FROM '{}'", table.name)) else {
+                skipped = true;
+                continue;
+            };
+            let Ok(mut rows) = stmt.query([]) else {
+                skipped = true;
+                continue;
+            };
+
+            while let Ok(Some(row)) = rows.next() {
+                let line = Self::extract_sqlite_row_as_json(&table.name, &columns, row).to_string();
+                if content.len() + line.len() + 1 > MAX_ROW_CONTENT_BYTES {
+                    skipped = true;
+                    break 'tables;
+                }
+                content.push_str(&line);
+                content.push('\n');
+            }
+        }
+
+        if content.is_empty() {
+            (None, "none")
+        } else if skipped {
+            (Some(content), "partial")
+        } else {
+            (Some(content), "full")
+        }
+    }
+
+    /// Convert a row to a JSON object keyed by column name
+    /// Uses the same value conversion as the table viewer's query_sqlite_table
+    fn extract_sqlite_row_as_json(table: &str, columns: &[String], row: &Row) -> serde_json::Value {
+        let mut object = serde_json::Map::new();
+        object.insert(
+            "_table".to_string(),
+            serde_json::Value::String(table.to_string()),
+        );
+
+        for (i, name) in columns.iter().enumerate() {
+            let value = if let Ok(s) = row.get::<_, String>(i) {
+                serde_json::Value::String(s)
+            } else if let Ok(n) = row.get::<_, i64>(i) {
+                serde_json::Value::Number(n.into())
+            } else if let Ok(f) = row.get::<_, f64>(i) {
+                serde_json::json!(f)
+            } else if let Ok(bytes) = row.get::<_, Vec<u8>>(i) {
+                serde_json::Value::String(format!("0x{}", hex::encode(bytes)))
+            } else {
+                serde_json::Value::Null
+            };
+            object.insert(name.clone(), value);
+        }
+
+        serde_json::Value::Object(object)
+    }
+
     fn extract_columns(&self, conn: &Connection, table_name: &str) -> Result<Vec<ColumnInfo>> {
         let mut stmt = conn.prepare(&format!("PRAGMA table_info('{}')", table_name))?;
 
@@ -120,6 +328,7 @@ impl SqliteExtractor {
                     data_type: row.get(2)?,
                     nullable: row.get::<_, i32>(3)? == 0,
                     primary_key: row.get::<_, i32>(5)? == 1,
+                    sample_values: Vec::new(),
                 })
             })?
             .collect::<Result<_, _>>()?;
@@ -127,6 +336,47 @@ impl SqliteExtractor {
         Ok(columns)
     }
 
+    /// Up to limit distinct non-null values of a column
+    /// Columns declared BLOB are not read; binary values elsewhere show as a marker
+    fn sample_column(
+        &self,
+        conn: &Connection,
+        table_name: &str,
+        column: &ColumnInfo,
+        limit: usize,
+    ) -> Result<Vec<String>> {
+        if column.data_type.eq_ignore_ascii_case("blob") {
+            return Ok(vec![BLOB_SAMPLE.to_string()]);
+        }
+
+        let name = column.name.replace('"', "\"\"");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT DISTINCT \"{}\" FROM '{}' WHERE \"{}\" IS NOT NULL LIMIT {}",
+            name, table_name, name, limit
+        ))?;
+        let mut rows = stmt.query([])?;
+
+        let mut samples = Vec::new();
+        while let Some(row) = rows.next()? {
+            let sample = match row.get_ref(0)? {
+                ValueRef::Null => continue,
+                ValueRef::Integer(n) => n.to_string(),
+                ValueRef::Real(f) => f.to_string(),
+                ValueRef::Text(text) => String::from_utf8_lossy(text)
+                    .chars()
+                    .take(MAX_SAMPLE_CHARS)
+                    .collect(),
+                ValueRef::Blob(_) => BLOB_SAMPLE.to_string(),
+            };
+            // Distinct blobs all collapse into the one marker
+            if !samples.contains(&sample) {
+                samples.push(sample);
+            }
+        }
+
+        Ok(samples)
+    }
+
     fn count_rows(&self, conn: &Connection, table_name: &str) -> Result<u64> {
         let count: i64 = conn.query_row(
             &format!("SELECT COUNT(*) FROM '{}'", table_name),
@@ -146,3 +396,159 @@ impl SqliteExtractor {
         Ok(indexes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::inverted::InvertedIndex;
+    use crate::index::schema::FileDocument;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_small_tables_rows_indexed_as_json() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("messages.db");
+        let mut conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE messages (id INTEGER PRIMARY KEY, sender TEXT, body TEXT);
+             INSERT INTO messages (sender, body) VALUES ('alice', 'meet at the docks');
+             CREATE TABLE telemetry (id INTEGER PRIMARY KEY, value INTEGER);",
+        )
+        .unwrap();
+        let tx = conn.transaction().unwrap();
+        for i in 0..=MAX_CONTENT_TABLE_ROWS {
+            tx.execute("INSERT INTO telemetry (value) VALUES (?1)", [i as i64])
+                .unwrap();
+        }
+        tx.commit().unwrap();
+        drop(conn);
+
+        let output = SqliteExtractor::new().extract(&db_path).unwrap();
+        let content = output.content.unwrap();
+
+        assert!(content.contains("\"body\":\"meet at the docks\""));
+        assert!(!content.contains("\"_table\":\"telemetry\""));
+        assert_eq!(output.fields["content_indexed"], "partial");
+    }
+
+    #[test]
+    fn test_whatsapp_messages_tagged_and_searchable() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("msgstore.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE messages (_id INTEGER PRIMARY KEY, key_remote_jid TEXT,
+                 key_from_me INTEGER, data TEXT, timestamp INTEGER);
+             INSERT INTO messages (key_remote_jid, key_from_me, data, timestamp) VALUES
+                 ('4915112345678@s.whatsapp.net', 0, 'package is under the bridge', 1700000060000),
+                 ('4915112345678@s.whatsapp.net', 1, 'on my way', 1700000000000),
+                 ('4915112345678@s.whatsapp.net', 0, NULL, 1700000120000);
+             CREATE TABLE chat_list (_id INTEGER PRIMARY KEY, key_remote_jid TEXT);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let output = SqliteExtractor::new().extract(&db_path).unwrap();
+        assert_eq!(output.fields["app"], "whatsapp");
+        assert_eq!(output.fields["message_count"], "2");
+        assert_eq!(output.fields["first_message"], "2023-11-14T22:13:20+00:00");
+
+        let content = output.content.clone().unwrap();
+        assert!(content.starts_with("2023-11-14T22:13:20+00:00 me: on my way\n"));
+
+        let index = InvertedIndex::create(&temp_dir.path().join("index")).unwrap();
+        index
+            .add_document(&FileDocument {
+                structured: output.structured,
+                content: output.content,
+                preview: Some(output.preview),
+                ..FileDocument::text_fixture("msgstore", db_path.clone(), "")
+            })
+            .unwrap();
+        index.commit().unwrap();
+
+        let hits = index.search("bridge", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "msgstore");
+    }
+
+    #[test]
+    fn test_truncated_chat_keeps_latest_messages() {
+        use super::super::chat::MAX_CHAT_MESSAGES;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("msgstore.db");
+        let mut conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE messages (_id INTEGER PRIMARY KEY, key_remote_jid TEXT,
+                 key_from_me INTEGER, data TEXT, timestamp INTEGER);
+             CREATE TABLE chat_list (_id INTEGER PRIMARY KEY, key_remote_jid TEXT);",
+        )
+        .unwrap();
+        let tx = conn.transaction().unwrap();
+        for i in 0..=MAX_CHAT_MESSAGES as i64 {
+            tx.execute(
+                "INSERT INTO messages (key_remote_jid, key_from_me, data, timestamp)
+                 VALUES ('peer@s.whatsapp.net', 0, ?1, ?2)",
+                rusqlite::params![format!("message {}", i), 1_700_000_000_000 + i * 1000],
+            )
+            .unwrap();
+        }
+        tx.commit().unwrap();
+        drop(conn);
+
+        let output = SqliteExtractor::new().extract(&db_path).unwrap();
+        assert_eq!(output.fields["chat_content"], "partial");
+        assert_eq!(
+            output.fields["message_count"],
+            MAX_CHAT_MESSAGES.to_string()
+        );
+        // The oldest message is the one dropped, and the transcript still reads oldest first
+        assert_eq!(output.fields["first_message"], "2023-11-14T22:13:21+00:00");
+        let content = output.content.unwrap();
+        assert!(content.starts_with("2023-11-14T22:13:21+00:00 peer@s.whatsapp.net: message 1\n"));
+        assert!(content.ends_with(&format!(": message {}\n", MAX_CHAT_MESSAGES)));
+    }
+
+    #[test]
+    fn test_column_sample_values() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("contacts.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE contacts (id INTEGER PRIMARY KEY, col5 TEXT, avatar BLOB, extra);
+             INSERT INTO contacts (col5, avatar, extra) VALUES
+                 ('a@example.com', x'89504e47', x'00'),
+                 ('b@example.com', x'ffd8ff', NULL),
+                 ('a@example.com', NULL, NULL),
+                 (NULL, NULL, NULL),
+                 ('c@example.com', NULL, NULL);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let columns = |extractor: SqliteExtractor| -> Vec<ColumnInfo> {
+            match extractor.extract(&db_path).unwrap().structured {
+                Some(StructuredData::Sqlite { mut tables, .. }) => tables.remove(0).columns,
+                other => panic!("expected SQLite structure, got {:?}", other),
+            }
+        };
+
+        // Off unless configured
+        assert!(columns(SqliteExtractor::new())
+            .iter()
+            .all(|c| c.sample_values.is_empty()));
+
+        let sampled =
+            columns(SqliteExtractor::new().with_options(SqliteOptions { sample_values: 2 }));
+        assert_eq!(sampled[1].name, "col5");
+        assert_eq!(sampled[1].sample_values.len(), 2);
+        assert!(sampled[1]
+            .sample_values
+            .iter()
+            .all(|value| value.ends_with("@example.com")));
+        assert_ne!(sampled[1].sample_values[0], sampled[1].sample_values[1]);
+        assert_eq!(sampled[2].sample_values, vec!["<blob>"]);
+        assert_eq!(sampled[3].sample_values, vec!["<blob>"]);
+    }
+}
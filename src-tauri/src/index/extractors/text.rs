@@ -1,15 +1,47 @@
-use super::{Extractor, ExtractorOutput};
+use super::binary_strings;
+use super::log_format::{LogFormat, LogSummary};
+use super::{Extractor, ExtractorOutput, SupportedFormat};
+use crate::index::detector::MBOX_MIME;
 use crate::index::schema::FileCategory;
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
+/// Shortest printable run kept when carving strings out of a text file that isn't valid UTF-8
+const CARVED_MIN_STRING_LEN: usize = 6;
+
 pub struct TextExtractor;
 
+impl TextExtractor {
+    /// Text with embedded binary (padded streams, print spool files) is carved for strings
+    /// rather than dropped from the index
+    fn extract_carved(bytes: &[u8]) -> ExtractorOutput {
+        let strings = binary_strings::extract_strings_with_min_len(bytes, CARVED_MIN_STRING_LEN);
+        let content = binary_strings::strings_to_content(&strings);
+
+        let mut fields = HashMap::new();
+        fields.insert("format".to_string(), "text".to_string());
+        fields.insert("text_extraction_mode".to_string(), "carved".to_string());
+        fields.insert("binary_with_text".to_string(), "true".to_string());
+        fields.insert("string_count".to_string(), strings.len().to_string());
+
+        ExtractorOutput {
+            structured: None,
+            preview: content.replace('\n', " ").chars().take(500).collect(),
+            content: Some(content),
+            fields,
+        }
+    }
+}
+
 impl Extractor for TextExtractor {
     fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
-        let content = fs::read_to_string(path).context("Failed to read text file")?;
+        let bytes = fs::read(path).context("Failed to read text file")?;
+        let content = match String::from_utf8(bytes) {
+            Ok(content) => content,
+            Err(e) => return Ok(Self::extract_carved(e.as_bytes())),
+        };
 
         // Calculate stats
         let line_count = content.lines().count();
@@ -22,6 +54,30 @@ impl Extractor for TextExtractor {
         fields.insert("word_count".to_string(), word_count.to_string());
         fields.insert("char_count".to_string(), content.len().to_string());
 
+        // Recognised log formats get a structured summary on top of the plain text
+        if let Some(format) = LogFormat::detect(&content) {
+            let summary = LogSummary::analyze(&content, format);
+            fields.insert("log_format".to_string(), format.as_str().to_string());
+            if let Some(start) = summary.start {
+                fields.insert("log_start".to_string(), start.to_rfc3339());
+            }
+            if let Some(end) = summary.end {
+                fields.insert("log_end".to_string(), end.to_rfc3339());
+            }
+            if !summary.unique_ips.is_empty() {
+                let ips: Vec<String> = summary.unique_ips.into_iter().collect();
+                fields.insert("unique_ips".to_string(), ips.join(" "));
+            }
+            if !summary.levels.is_empty() {
+                let levels: Vec<String> = summary
+                    .levels
+                    .iter()
+                    .map(|(level, count)| format!("{}={}", level, count))
+                    .collect();
+                fields.insert("log_levels".to_string(), levels.join(", "));
+            }
+        }
+
         // Create preview
         let preview = if content.len() > 500 {
             format!("{}\n...", &content[..497])
@@ -37,11 +93,44 @@ impl Extractor for TextExtractor {
         })
     }
 
-    fn can_handle(&self, category: FileCategory, _mime_type: &str) -> bool {
+    fn can_handle(&self, category: FileCategory, mime_type: &str) -> bool {
+        // Mailboxes are plain text underneath; single messages go to the EML extractor
         category == FileCategory::Text
+            || (category == FileCategory::Email && mime_type == MBOX_MIME)
+    }
+
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![
+            SupportedFormat::new(FileCategory::Text, &[]),
+            SupportedFormat::new(FileCategory::Email, &[MBOX_MIME]),
+        ]
     }
 
     fn name(&self) -> &'static str {
         "text"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_invalid_utf8_text_is_carved_for_strings() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("spool.txt");
+        let mut bytes = b"PRINT JOB quarterly_report\n".to_vec();
+        bytes.extend_from_slice(&[0xFF, 0xFE, 0x00, 0x81]);
+        bytes.extend_from_slice(b"ab\x90short\x91page footer confidential");
+        std::fs::write(&path, bytes).unwrap();
+
+        let output = TextExtractor.extract(&path).unwrap();
+        assert_eq!(output.fields["text_extraction_mode"], "carved");
+        assert_eq!(output.fields["binary_with_text"], "true");
+        assert_eq!(
+            output.content.unwrap(),
+            "PRINT JOB quarterly_report\npage footer confidential\n"
+        );
+    }
+}
@@ -1,7 +1,7 @@
-use super::{Extractor, ExtractorOutput};
-use crate::index::schema::{FileCategory, StructuredData};
+use super::{Extractor, ExtractorOutput, SupportedFormat};
+use crate::index::schema::{FileCategory, StructuredData, XmlPath};
 use anyhow::{Context, Result};
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
 use std::collections::{HashMap, HashSet};
 use std::fs;
@@ -9,6 +9,12 @@ use std::path::Path;
 
 pub struct XmlExtractor;
 
+/// Maximum number of element/attribute paths recorded per file
+const MAX_XML_PATHS: usize = 1000;
+
+/// Maximum length of a recorded value
+const MAX_VALUE_LEN: usize = 100;
+
 impl Extractor for XmlExtractor {
     fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
         let content = fs::read_to_string(path).context("Failed to read XML file")?;
@@ -20,24 +26,55 @@ impl Extractor for XmlExtractor {
         let mut namespaces = HashSet::new();
         let mut element_count = 0;
 
+        // Current element path and the paths collected so far
+        let mut stack: Vec<String> = Vec::new();
+        let mut paths = Vec::new();
+        let mut seen_elements = HashSet::new();
+
         let mut buf = Vec::new();
 
         loop {
             match reader.read_event_into(&mut buf) {
-                Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                Ok(Event::Start(e)) => {
+                    element_count += 1;
+                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    if root_element.is_empty() {
+                        root_element = name.clone();
+                    }
+                    Self::record_element(
+                        &e,
+                        &stack,
+                        &mut paths,
+                        &mut seen_elements,
+                        &mut namespaces,
+                    );
+                    stack.push(name);
+                }
+                Ok(Event::Empty(e)) => {
+                    // Empty elements have no matching End event, so don't push them
                     element_count += 1;
-
-                    // Get root element
                     if root_element.is_empty() {
                         root_element = String::from_utf8_lossy(e.name().as_ref()).to_string();
                     }
-
-                    // Extract namespaces from attributes
-                    for attr in e.attributes().flatten() {
-                        let key = String::from_utf8_lossy(attr.key.as_ref());
-                        if key.starts_with("xmlns") {
-                            let ns = String::from_utf8_lossy(&attr.value).to_string();
-                            namespaces.insert(ns);
+                    Self::record_element(
+                        &e,
+                        &stack,
+                        &mut paths,
+                        &mut seen_elements,
+                        &mut namespaces,
+                    );
+                }
+                Ok(Event::End(_)) => {
+                    stack.pop();
+                }
+                Ok(Event::Text(e)) if paths.len() < MAX_XML_PATHS && !stack.is_empty() => {
+                    if let Ok(text) = e.unescape() {
+                        let text = text.trim();
+                        if !text.is_empty() {
+                            paths.push(XmlPath {
+                                path: Self::join_path(&stack),
+                                value: Some(Self::truncate_value(text)),
+                            });
                         }
                     }
                 }
@@ -63,6 +100,10 @@ impl Extractor for XmlExtractor {
         fields.insert("element_count".to_string(), element_count.to_string());
         fields.insert("namespaces".to_string(), namespace_vec.join(", "));
 
+        // Add element/attribute paths with values for searching
+        let path_strings: Vec<String> = paths.iter().map(XmlPath::to_search_string).collect();
+        fields.insert("paths".to_string(), path_strings.join(" "));
+
         // Create preview
         let preview = if content.len() > 500 {
             format!("{}\n...", &content[..497])
@@ -75,6 +116,7 @@ impl Extractor for XmlExtractor {
                 root_element,
                 namespaces: namespace_vec,
                 element_count,
+                paths,
             }),
             content: Some(content),
             preview,
@@ -87,7 +129,129 @@ impl Extractor for XmlExtractor {
             && (mime_type == "application/xml" || mime_type == "text/xml")
     }
 
+    fn supported_formats(&self) -> Vec<SupportedFormat> {
+        vec![SupportedFormat::new(
+            FileCategory::StructuredData,
+            &["application/xml", "text/xml"],
+        )]
+    }
+
     fn name(&self) -> &'static str {
         "xml"
     }
 }
+
+impl XmlExtractor {
+    /// Record an element's path and attribute values, collecting namespace declarations
+    fn record_element(
+        element: &BytesStart,
+        stack: &[String],
+        paths: &mut Vec<XmlPath>,
+        seen_elements: &mut HashSet<String>,
+        namespaces: &mut HashSet<String>,
+    ) {
+        let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+        let element_path = format!("{}/{}", Self::join_path(stack), name);
+
+        // Record element structure once per distinct path
+        if paths.len() < MAX_XML_PATHS && seen_elements.insert(element_path.clone()) {
+            paths.push(XmlPath {
+                path: element_path.clone(),
+                value: None,
+            });
+        }
+
+        for attr in element.attributes().flatten() {
+            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+            let value = String::from_utf8_lossy(&attr.value).to_string();
+
+            // Extract namespaces from attributes
+            if key.starts_with("xmlns") {
+                namespaces.insert(value);
+                continue;
+            }
+
+            if paths.len() < MAX_XML_PATHS {
+                paths.push(XmlPath {
+                    path: format!("{}/@{}", element_path, key),
+                    value: Some(Self::truncate_value(&value)),
+                });
+            }
+        }
+    }
+
+    fn join_path(stack: &[String]) -> String {
+        if stack.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", stack.join("/"))
+        }
+    }
+
+    fn truncate_value(value: &str) -> String {
+        if value.chars().count() > MAX_VALUE_LEN {
+            let truncated: String = value.chars().take(MAX_VALUE_LEN - 3).collect();
+            format!("{}...", truncated)
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::extractors::ExtractorRegistry;
+    use crate::index::inverted::InvertedIndex;
+    use crate::index::query::{Query, QueryPlanner, StructuredQueryType};
+    use crate::index::schema::FileDocument;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_attribute_value_searchable_via_structured_query() {
+        let temp_dir = TempDir::new().unwrap();
+        let xml_path = temp_dir.path().join("accounts.xml");
+        fs::write(
+            &xml_path,
+            r#"<?xml version="1.0"?>
+<accounts>
+  <account id="12345" type="savings"><owner>Alice</owner></account>
+  <account id="67890"/>
+</accounts>"#,
+        )
+        .unwrap();
+
+        let output = XmlExtractor.extract(&xml_path).unwrap();
+        let Some(StructuredData::Xml { paths, .. }) = &output.structured else {
+            panic!("expected XML structured data");
+        };
+        assert!(paths
+            .iter()
+            .any(|p| p.path == "/accounts/account/@id" && p.value.as_deref() == Some("12345")));
+        assert!(paths
+            .iter()
+            .any(|p| p.path == "/accounts/account/owner" && p.value.as_deref() == Some("Alice")));
+
+        let index = Arc::new(InvertedIndex::create(&temp_dir.path().join("index")).unwrap());
+        index
+            .add_document(&FileDocument {
+                structured: output.structured,
+                content: None,
+                ..FileDocument::text_fixture("accounts", xml_path.clone(), "")
+            })
+            .unwrap();
+        index.commit().unwrap();
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()));
+        let result = planner
+            .execute(&Query::Structured {
+                structured_type: StructuredQueryType::XmlPath,
+                query: "12345".to_string(),
+            })
+            .unwrap();
+
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].id, "accounts");
+    }
+}
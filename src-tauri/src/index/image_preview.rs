@@ -2,9 +2,14 @@ use anyhow::{Context, Result};
 use image::{DynamicImage, GenericImageView, ImageError, ImageFormat};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, Cursor, Read};
 use std::path::{Path, PathBuf};
 
+use crate::io::fs::atomic_temp_path;
+
+/// Bytes read from files without an image extension to look for an image signature
+const MAGIC_SAMPLE_LEN: usize = 16;
+
 /// Image preview configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PreviewConfig {
@@ -51,6 +56,10 @@ pub struct ImageInfo {
     pub has_alpha: bool,
     pub color_type: String,
     pub thumbnail_path: Option<PathBuf>,
+
+    /// The file was recognised by its signature; its extension isn't an image format
+    #[serde(default)]
+    pub extension_mismatch: bool,
 }
 
 /// Image preview generator
@@ -70,7 +79,13 @@ impl ImagePreviewGenerator {
     }
 
     /// Check if file is a supported image format
+    /// Files with another extension are still images if they start with an image signature,
+    /// so renamed pictures (e.g. `evidence.dat`) aren't missed
     pub fn is_image(&self, path: &Path) -> bool {
+        self.has_image_extension(path) || Self::sniff_format(path).is_some()
+    }
+
+    fn has_image_extension(&self, path: &Path) -> bool {
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
             self.config.supported_formats.contains(&ext.to_lowercase())
         } else {
@@ -78,6 +93,30 @@ impl ImagePreviewGenerator {
         }
     }
 
+    /// Image format named by the file's magic bytes, if any
+    fn sniff_format(path: &Path) -> Option<&'static str> {
+        let mut header = Vec::with_capacity(MAGIC_SAMPLE_LEN);
+        File::open(path)
+            .ok()?
+            .take(MAGIC_SAMPLE_LEN as u64)
+            .read_to_end(&mut header)
+            .ok()?;
+
+        if header.starts_with(b"\x89PNG") {
+            Some("png")
+        } else if header.starts_with(&[0xFF, 0xD8]) {
+            Some("jpeg")
+        } else if header.starts_with(b"GIF8") {
+            Some("gif")
+        } else if header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP") {
+            Some("webp")
+        } else if header.starts_with(b"BM") {
+            Some("bmp")
+        } else {
+            None
+        }
+    }
+
     /// Generate preview and extract metadata
     pub fn generate_preview(&self, image_path: &Path) -> Result<ImageInfo> {
         if !self.config.enabled {
@@ -94,6 +133,7 @@ impl ImagePreviewGenerator {
         let has_alpha = img.color().has_alpha();
 
         let format = self.detect_format(image_path)?;
+        let extension_mismatch = !self.has_image_extension(image_path);
 
         // Generate thumbnail
         let thumbnail_path =
@@ -110,11 +150,23 @@ impl ImagePreviewGenerator {
             has_alpha,
             color_type,
             thumbnail_path,
+            extension_mismatch,
         })
     }
 
     /// Load image with support for various formats
     fn load_image(&self, path: &Path) -> Result<DynamicImage> {
+        // The extension can't be trusted to pick a decoder, so go by the content
+        if !self.has_image_extension(path) {
+            return match Self::sniff_format(path) {
+                Some("webp") => self.load_webp(path),
+                Some(_) => Ok(image::ImageReader::open(path)?
+                    .with_guessed_format()?
+                    .decode()?),
+                None => Err(anyhow::anyhow!("Not an image: {}", path.display())),
+            };
+        }
+
         // Try standard loading
         match image::open(path) {
             Ok(img) => Ok(img),
@@ -190,15 +242,30 @@ impl ImagePreviewGenerator {
         let filename = self.generate_thumbnail_filename(original_path)?;
         let thumbnail_path = self.preview_dir.join(&filename);
 
-        // Save as JPEG
-        let file = File::create(&thumbnail_path)?;
-        let mut writer = BufWriter::new(file);
+        Self::write_jpeg(&thumbnail, &thumbnail_path)?;
+        Ok(thumbnail_path)
+    }
 
-        thumbnail
-            .write_to(&mut writer, ImageFormat::Jpeg)
-            .context("Failed to write thumbnail")?;
+    /// Save as JPEG through a temporary file, so a crash mid-write never leaves a
+    /// truncated thumbnail under the final name
+    fn write_jpeg(img: &DynamicImage, path: &Path) -> Result<()> {
+        // JPEG has no alpha channel, so flatten to RGB first
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(img.to_rgb8())
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .context("Failed to encode thumbnail")?;
+
+        let temp_path = atomic_temp_path(path);
+        if let Err(e) = fs::write(&temp_path, &jpeg).and_then(|_| fs::rename(&temp_path, path)) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e).context("Failed to write thumbnail");
+        }
+        Ok(())
+    }
 
-        Ok(thumbnail_path)
+    /// Whether an existing thumbnail is non-empty and decodes; older versions wrote in place
+    pub(crate) fn is_valid_thumbnail(path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|m| m.len() > 0) && image::open(path).is_ok()
     }
 
     /// Generate thumbnail filename
@@ -223,11 +290,18 @@ impl ImagePreviewGenerator {
             has_alpha: img.color().has_alpha(),
             color_type: format!("{:?}", img.color()),
             thumbnail_path: None,
+            extension_mismatch: !self.has_image_extension(path),
         })
     }
 
     /// Detect image format
     fn detect_format(&self, path: &Path) -> Result<String> {
+        if !self.has_image_extension(path) {
+            if let Some(format) = Self::sniff_format(path) {
+                return Ok(format.to_string());
+            }
+        }
+
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
             Ok(ext.to_lowercase())
         } else {
@@ -249,6 +323,30 @@ impl ImagePreviewGenerator {
         let filename = self.generate_thumbnail_filename(original_path)?;
         Ok(self.preview_dir.join(filename))
     }
+
+    /// Get the thumbnail for an image, creating it if it doesn't exist yet
+    /// A corrupt or truncated thumbnail is regenerated rather than served
+    /// Images already within thumbnail_size are re-encoded as JPEG at their own size
+    pub fn ensure_thumbnail(&self, image_path: &Path) -> Result<PathBuf> {
+        let thumbnail_path = self.get_thumbnail_path(image_path)?;
+        if thumbnail_path.exists() {
+            if Self::is_valid_thumbnail(&thumbnail_path) {
+                return Ok(thumbnail_path);
+            }
+            log::warn!(
+                "Regenerating corrupt thumbnail {}",
+                thumbnail_path.display()
+            );
+        }
+
+        let img = self.load_image(image_path)?;
+        if img.width() > self.config.thumbnail_size || img.height() > self.config.thumbnail_size {
+            return self.create_thumbnail(&img, image_path);
+        }
+
+        Self::write_jpeg(&img, &thumbnail_path)?;
+        Ok(thumbnail_path)
+    }
 }
 
 #[cfg(test)]
@@ -267,4 +365,68 @@ mod tests {
         assert!(generator.is_image(Path::new("test.webp")));
         assert!(!generator.is_image(Path::new("test.txt")));
     }
+
+    #[test]
+    fn test_renamed_image_detected_by_magic_bytes() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = PreviewConfig {
+            enabled: true,
+            thumbnail_size: 4,
+            ..PreviewConfig::default()
+        };
+        let generator =
+            ImagePreviewGenerator::new(config, temp_dir.path().join("previews")).unwrap();
+
+        let hidden = temp_dir.path().join("evidence.dat");
+        let mut jpeg = Vec::new();
+        image::RgbImage::from_pixel(8, 8, image::Rgb([10, 20, 30]))
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        fs::write(&hidden, jpeg).unwrap();
+        let text = temp_dir.path().join("notes.dat");
+        fs::write(&text, "not an image").unwrap();
+
+        assert!(generator.is_image(&hidden));
+        assert!(!generator.is_image(&text));
+
+        let info = generator.generate_preview(&hidden).unwrap();
+        assert_eq!(info.format, "jpeg");
+        assert!(info.extension_mismatch);
+        assert!(info.thumbnail_path.unwrap().exists());
+    }
+
+    #[test]
+    fn test_truncated_thumbnail_is_regenerated() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = PreviewConfig {
+            enabled: true,
+            thumbnail_size: 4,
+            ..PreviewConfig::default()
+        };
+        let generator =
+            ImagePreviewGenerator::new(config, temp_dir.path().join("previews")).unwrap();
+
+        let image_path = temp_dir.path().join("photo.png");
+        image::RgbImage::from_pixel(8, 8, image::Rgb([10, 20, 30]))
+            .save(&image_path)
+            .unwrap();
+
+        let thumbnail_path = generator.ensure_thumbnail(&image_path).unwrap();
+        let complete = fs::read(&thumbnail_path).unwrap();
+
+        // Simulate a crash halfway through writing the JPEG
+        fs::write(&thumbnail_path, &complete[..complete.len() / 2]).unwrap();
+        assert!(!ImagePreviewGenerator::is_valid_thumbnail(&thumbnail_path));
+
+        let regenerated = generator.ensure_thumbnail(&image_path).unwrap();
+        assert_eq!(regenerated, thumbnail_path);
+        assert!(ImagePreviewGenerator::is_valid_thumbnail(&regenerated));
+        assert_eq!(fs::read(&regenerated).unwrap(), complete);
+
+        // No temporary files are left next to the thumbnail
+        let entries = fs::read_dir(temp_dir.path().join("previews"))
+            .unwrap()
+            .count();
+        assert_eq!(entries, 1);
+    }
 }
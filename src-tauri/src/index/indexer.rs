@@ -1,22 +1,36 @@
-use super::archive_extractor::ArchiveExtractor;
-use super::archive_settings::ArchiveSettings;
+use super::archive_extractor::{ArchiveError, ArchiveExtractor};
+use super::archive_settings::{ArchiveSettings, UnpackedArchiveInfo};
 use super::detector::FileTypeDetector;
-use super::extractors::ExtractorRegistry;
+use super::extractors::{ExtractionMode, ExtractorConfig, ExtractorOutput, ExtractorRegistry};
 use super::image_preview::{ImagePreviewGenerator, PreviewConfig};
-use super::inverted::InvertedIndex;
+use super::inverted::{DirectoryCount, IndexSchemaOutdated, InvertedIndex};
 use super::query::QueryPlanner;
-use super::schema::{DocumentMetadata, FileDocument, ProjectDatabaseError};
-use super::watcher::{ChangeDetector, FileChange};
+use super::schema::{
+    ArchiveSource, DocumentMetadata, FileCategory, FileDocument, ProjectDatabaseError,
+    StructuredCatalog, TypedHit,
+};
+use super::similarity::{find_similar_documents, SimilarityResult};
+use super::watcher::{ChangeDetector, FileChange, FileState};
 use crate::db::AuxiliaryProjectDb;
+use crate::io::acl::read_acl;
+use crate::io::LocalFileSystem;
 use anyhow::{Context, Error, Result};
-use chrono::Utc;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// Gitignore-style exclusion file read from the root of indexed evidence
+pub const IGNORE_FILE_NAME: &str = ".forensicsignore";
 
 /// Main indexing orchestrator
 /// Coordinates file detection, extraction, and indexing
@@ -30,16 +44,241 @@ pub struct MasterIndexer {
     /// Change detector for incremental indexing
     change_detector: Arc<parking_lot::Mutex<ChangeDetector>>,
 
-    /// Archive extractor
-    archive_extractor: Option<Arc<ArchiveExtractor>>,
+    /// Archive extractor, replaced when the archive settings change
+    archive_extractor: parking_lot::RwLock<Option<Arc<ArchiveExtractor>>>,
 
-    /// Image preview generator
-    image_preview: Option<Arc<ImagePreviewGenerator>>,
+    /// Image preview generator, replaced when the preview config changes
+    image_preview: parking_lot::RwLock<Option<Arc<ImagePreviewGenerator>>>,
 
     /// Index directory
     index_dir: PathBuf,
 
     auxiliary_db: Arc<AuxiliaryProjectDb>,
+
+    /// Batching and throttling settings
+    indexing_config: IndexingConfig,
+
+    /// Bounded pool used for parallel extraction
+    thread_pool: Arc<rayon::ThreadPool>,
+
+    /// Per-file errors recorded during the current indexing run
+    index_errors: Arc<parking_lot::Mutex<Vec<IndexError>>>,
+
+    /// Extraction of each content hash first seen in the current batch, shared with
+    /// identical files in the same batch until the batch is committed
+    batch_extractions: Arc<parking_lot::Mutex<HashMap<String, SharedExtraction>>>,
+}
+
+/// Filled in once the file that claimed a content hash has been extracted
+/// None if that file failed before extracting, so copies extract themselves
+type SharedExtraction = Arc<OnceLock<Option<ExtractorOutput>>>;
+
+/// Claim on extracting a content hash for the current batch
+/// Dropping an unfulfilled claim releases anyone waiting on it
+struct ExtractionClaim(SharedExtraction);
+
+impl ExtractionClaim {
+    fn fulfil(&self, output: &ExtractorOutput) {
+        let _ = self.0.set(Some(output.clone()));
+    }
+}
+
+impl Drop for ExtractionClaim {
+    fn drop(&mut self) {
+        let _ = self.0.set(None);
+    }
+}
+
+/// Where a file's extraction comes from when duplicate extraction is skipped
+enum DuplicateExtraction {
+    /// An identical file was already extracted
+    Reuse(ExtractorOutput),
+    /// This file is the first with its content and extracts for the others
+    Claimed(ExtractionClaim),
+}
+
+/// Indexing throughput settings
+/// Defaults are conservative; SSDs can take larger batches and shorter pauses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexingConfig {
+    /// Number of files processed between commits
+    pub batch_size: usize,
+
+    /// Pause between batches (in milliseconds)
+    pub commit_interval_ms: u64,
+
+    /// Files larger than their category's limit are indexed by metadata only
+    #[serde(default)]
+    pub size_limits: SizeLimits,
+
+    /// Number of worker threads used for extraction
+    pub max_concurrent_extractions: usize,
+
+    /// Per-file extraction timeout (in milliseconds)
+    /// Files whose extractor exceeds this fall back to minimal extraction
+    pub extraction_timeout_ms: u64,
+
+    /// Only index files in these categories (None = everything)
+    /// Files outside the filter are detected but neither extracted nor indexed
+    #[serde(default)]
+    pub category_filter: Option<HashSet<FileCategory>>,
+
+    /// Index files with identical content once, recording the other paths as aliases
+    #[serde(default)]
+    pub deduplicate: bool,
+
+    /// Give files whose content hash is already indexed the existing extraction instead
+    /// of running the extractor again; each copy still gets its own document
+    /// Ignored when deduplicate is set, since copies then share one document anyway
+    #[serde(default)]
+    pub skip_duplicate_extraction: bool,
+
+    /// Overrides for extractors whose auto-detection can misfire (e.g. CSV delimiters)
+    #[serde(default)]
+    pub extractors: ExtractorConfig,
+
+    /// Gitignore-style patterns excluded from every scan
+    /// Applied before the evidence root's .forensicsignore, which can re-include with `!`
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+
+    /// Gitignore-style patterns for high-value artifacts (browser databases, shell history)
+    /// Matching files are indexed and committed in their own batches before everything else
+    #[serde(default)]
+    pub priority_patterns: Vec<String>,
+
+    /// Index symlinks as documents of their own, recording their target, instead of
+    /// reading through them to the file or folder they point to
+    #[serde(default)]
+    pub record_symlinks: bool,
+}
+
+impl Default for IndexingConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            commit_interval_ms: 10,
+            size_limits: SizeLimits::default(),
+            max_concurrent_extractions: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4),
+            extraction_timeout_ms: 60_000,
+            category_filter: None,
+            deduplicate: false,
+            skip_duplicate_extraction: false,
+            extractors: ExtractorConfig::default(),
+            exclude_patterns: Vec::new(),
+            priority_patterns: Vec::new(),
+            record_symlinks: false,
+        }
+    }
+}
+
+impl IndexingConfig {
+    /// Build a thread pool limited to the configured concurrency
+    fn build_thread_pool(&self) -> Result<rayon::ThreadPool> {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.max_concurrent_extractions.max(1))
+            .thread_name(|i| format!("indexer-{}", i))
+            .build()
+            .context("Failed to build indexing thread pool")
+    }
+
+    fn extraction_timeout(&self) -> Duration {
+        Duration::from_millis(self.extraction_timeout_ms)
+    }
+
+    /// Build the exclusion matcher for a scan of root
+    /// Combines the configured patterns with root's .forensicsignore, if present
+    fn ignore_matcher(&self, root: &Path) -> Result<Gitignore> {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in &self.exclude_patterns {
+            builder
+                .add_line(None, pattern)
+                .with_context(|| format!("Invalid exclude pattern: {}", pattern))?;
+        }
+
+        let ignore_file = root.join(IGNORE_FILE_NAME);
+        if ignore_file.is_file() {
+            if let Some(e) = builder.add(&ignore_file) {
+                return Err(Error::new(e).context(format!("Failed to read {:?}", ignore_file)));
+            }
+        }
+
+        builder.build().context("Failed to build ignore matcher")
+    }
+
+    /// Build the matcher for priority_patterns, or None when there are none
+    fn priority_matcher(&self, root: &Path) -> Result<Option<Gitignore>> {
+        if self.priority_patterns.is_empty() {
+            return Ok(None);
+        }
+
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in &self.priority_patterns {
+            builder
+                .add_line(None, pattern)
+                .with_context(|| format!("Invalid priority pattern: {}", pattern))?;
+        }
+        Ok(Some(
+            builder
+                .build()
+                .context("Failed to build priority matcher")?,
+        ))
+    }
+
+    /// Whether files of this category should be indexed
+    fn accepts_category(&self, category: FileCategory) -> bool {
+        self.category_filter
+            .as_ref()
+            .is_none_or(|filter| filter.contains(&category))
+    }
+}
+
+/// File size thresholds above which content is not extracted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeLimits {
+    /// Limit for categories without their own entry
+    pub default_max: u64,
+
+    /// Per-category overrides
+    pub per_category: HashMap<FileCategory, u64>,
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        Self {
+            default_max: 100 * 1024 * 1024, // 100MB
+            per_category: HashMap::from([
+                // Large images are usually RAW photos; thumbnails aren't worth decoding them
+                (FileCategory::Media, 50 * 1024 * 1024), // 50MB
+                // Databases are still indexed by schema above this
+                (FileCategory::Database, 5 * 1024 * 1024 * 1024), // 5GB
+            ]),
+        }
+    }
+}
+
+impl SizeLimits {
+    /// Size limit for files of this category
+    pub fn limit_for(&self, category: FileCategory) -> u64 {
+        self.per_category
+            .get(&category)
+            .copied()
+            .unwrap_or(self.default_max)
+    }
+}
+
+/// Files committed to the index together, reported while indexing is still running
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedBatch {
+    pub paths: Vec<PathBuf>,
+
+    /// The batch holds files matching the priority patterns
+    pub priority: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,86 +308,285 @@ pub struct IndexStats {
     pub total_size: u64,
     pub by_category: std::collections::HashMap<String, u64>,
     pub duration_ms: u64,
+    /// Files left out because their category isn't in the category filter
+    pub skipped_by_filter: u64,
+    /// Cached files that couldn't be reached on disk (e.g. an unmounted evidence drive) and
+    /// were left as they were
+    #[serde(default)]
+    pub unavailable_files: Vec<PathBuf>,
+    /// Files that could not be fully extracted
+    pub errors: Vec<IndexError>,
+}
+
+/// A file that failed (or only partially succeeded) during indexing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// What a garbage collection pass removed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcStats {
+    /// Thumbnails of images no longer in the index
+    pub removed_previews: u64,
+
+    /// Unpacked archive directories with no indexed files left
+    pub removed_unpacked_dirs: u64,
+
+    /// Disk space freed (in bytes)
+    pub reclaimed_bytes: u64,
+}
+
+/// MIME type recorded for symlinks indexed as documents of their own
+const SYMLINK_MIME_TYPE: &str = "inode/symlink";
+
+/// Most matches returned by an in-file search
+const MAX_IN_FILE_MATCHES: usize = 1_000;
+
+/// A match found by `search_within_file`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InFileMatch {
+    /// Where the match is, e.g. "line 12", "table messages" or "schema users"
+    pub location: String,
+    /// The matching line (with its neighbours for text files) or row
+    pub context: String,
+}
+
+/// Name of the chain-of-custody manifest written next to exported files
+pub const EXPORT_MANIFEST_NAME: &str = "export_manifest.json";
+
+/// A file copied out of the evidence by `export_files`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedFile {
+    pub doc_id: String,
+    pub source_path: PathBuf,
+    pub exported_path: PathBuf,
+
+    /// SHA256 of the copy
+    pub hash: String,
+    pub size: u64,
+
+    /// Size recorded for the file when it was indexed
+    pub indexed_size: u64,
+
+    /// Whether the copy hashes and sizes the same as the file did when it was indexed
+    pub matches_index: bool,
+}
+
+/// A requested document that could not be exported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedExport {
+    pub doc_id: String,
+
+    /// Source path, if the document is in the index
+    pub source_path: Option<PathBuf>,
+    pub reason: String,
+}
+
+/// Outcome of an export, also written to the output directory as the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileExportReport {
+    pub exported_at: chrono::DateTime<Utc>,
+    pub files: Vec<ExportedFile>,
+    pub skipped: Vec<SkippedExport>,
 }
 
 impl MasterIndexer {
     /// Create a new master indexer
     pub fn create(index_dir: &Path) -> Result<Self> {
-        Self::create_with_settings(index_dir, None, None)
+        Self::create_with_settings(index_dir, None, None, None)
     }
 
-    /// Create with archive and preview settings
+    /// Create with archive, preview and indexing settings
     pub fn create_with_settings(
         index_dir: &Path,
         archive_settings: Option<ArchiveSettings>,
         preview_config: Option<PreviewConfig>,
+        indexing_config: Option<IndexingConfig>,
     ) -> Result<Self> {
-        std::fs::create_dir_all(index_dir)?;
+        // Fail before any settings, caches or thread pools are set up
+        InvertedIndex::ensure_writable(index_dir)?;
 
-        let inverted_index = InvertedIndex::create(&index_dir.join("inverted"))?;
-        let extractor_registry = ExtractorRegistry::new();
+        let indexing_config = indexing_config.unwrap_or_default();
+        let inverted_index = InvertedIndex::create(&index_dir.join("inverted"))?
+            .with_deduplication(indexing_config.deduplicate);
+        let extractor_registry = ExtractorRegistry::with_config(indexing_config.extractors.clone())
+            .with_extraction_timeout(indexing_config.extraction_timeout());
 
         let cache_path = index_dir.join("change_cache.bin");
-        let change_detector = ChangeDetector::load(&cache_path).unwrap_or_default();
-
-        // Set up archive extractor if enabled
-        let archive_extractor = if let Some(settings) = archive_settings {
-            if settings.auto_unpack {
-                Some(Arc::new(ArchiveExtractor::new(settings)))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let change_detector = ChangeDetector::load(&cache_path)
+            .unwrap_or_default()
+            .with_symlinks_recorded(indexing_config.record_symlinks);
 
-        // Set up image preview generator if enabled
-        let image_preview = if let Some(config) = preview_config {
-            if config.enabled {
-                let preview_dir = index_dir.join("previews");
-                Some(Arc::new(ImagePreviewGenerator::new(config, preview_dir)?))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let archive_extractor = Self::build_archive_extractor(archive_settings);
+        let image_preview = Self::build_image_preview(preview_config, index_dir)?;
 
         let auxiliary_db = AuxiliaryProjectDb::init(index_dir.join("aux"))?;
 
+        let thread_pool = indexing_config.build_thread_pool()?;
+
         Ok(Self {
             inverted_index: Arc::new(inverted_index),
             extractor_registry: Arc::new(extractor_registry),
             change_detector: Arc::new(parking_lot::Mutex::new(change_detector)),
-            archive_extractor,
-            image_preview,
+            archive_extractor: parking_lot::RwLock::new(archive_extractor),
+            image_preview: parking_lot::RwLock::new(image_preview),
             index_dir: index_dir.to_path_buf(),
             auxiliary_db: Arc::new(auxiliary_db),
+            indexing_config,
+            thread_pool: Arc::new(thread_pool),
+            index_errors: Arc::new(parking_lot::Mutex::new(Vec::new())),
+            batch_extractions: Arc::new(parking_lot::Mutex::new(HashMap::new())),
         })
     }
 
     /// Open an existing indexer
     pub fn open(index_dir: &Path) -> Result<Self> {
-        Self::open_with_settings(index_dir, None, None)
+        Self::open_with_settings(index_dir, None, None, None)
+    }
+
+    /// Open an existing indexer for searching while another instance indexes into it
+    /// Writes through the returned indexer fail with IndexReadOnly; call `reload` to see
+    /// batches the other instance has committed since. While the other instance holds the
+    /// project's auxiliary db, a detached stand-in takes its place: tags and groups come
+    /// back empty and changing them fails
+    pub fn open_read_only(index_dir: &Path) -> Result<Self> {
+        let indexing_config = IndexingConfig::default();
+        let inverted_index = InvertedIndex::open_readonly(&index_dir.join("inverted"))?;
+        let extractor_registry = ExtractorRegistry::with_config(indexing_config.extractors.clone())
+            .with_extraction_timeout(indexing_config.extraction_timeout());
+
+        let cache_path = index_dir.join("change_cache.bin");
+        let change_detector = ChangeDetector::load(&cache_path)
+            .with_context(|| format!("Failed to load change cache {}", cache_path.display()))?;
+
+        // sled allows a single handle, which the indexing instance usually has
+        let auxiliary_db = AuxiliaryProjectDb::init(index_dir.join("aux"))
+            .or_else(|_| AuxiliaryProjectDb::detached())?;
+
+        let thread_pool = indexing_config.build_thread_pool()?;
+
+        Ok(Self {
+            inverted_index: Arc::new(inverted_index),
+            extractor_registry: Arc::new(extractor_registry),
+            change_detector: Arc::new(parking_lot::Mutex::new(change_detector)),
+            archive_extractor: parking_lot::RwLock::new(None),
+            image_preview: parking_lot::RwLock::new(None),
+            index_dir: index_dir.to_path_buf(),
+            auxiliary_db: Arc::new(auxiliary_db),
+            indexing_config,
+            thread_pool: Arc::new(thread_pool),
+            index_errors: Arc::new(parking_lot::Mutex::new(Vec::new())),
+            batch_extractions: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Pick up everything committed to the index since it was opened or last reloaded
+    pub fn reload(&self) -> Result<()> {
+        self.inverted_index.reload()
+    }
+
+    /// Whether the indexer was opened with `open_read_only`
+    pub fn is_read_only(&self) -> bool {
+        self.inverted_index.is_read_only()
     }
 
     pub fn get_or_init_from_project_path(project_path: &Path) -> Result<MasterIndexer> {
         let db_path = Self::project_path_to_db_path(project_path)?;
         println!("DB path {:?}", db_path);
-        let open = Self::open_with_settings(
-            db_path.as_path(),
-            Some(ArchiveSettings::default()),
-            Some(PreviewConfig::default()),
-        );
+        Self::open_or_create_with_saved_settings(&db_path)
+    }
 
-        if open.as_ref().err().is_some() {
-            Self::create_with_settings(
-                db_path.as_path(),
-                Some(ArchiveSettings::default()),
-                Some(PreviewConfig::default()),
-            )
-        } else {
-            open
+    /// Open a project's index for searching while another instance is indexing it
+    pub fn open_read_only_from_project_path(project_path: &Path) -> Result<MasterIndexer> {
+        Self::open_read_only(&Self::project_path_to_db_path(project_path)?)
+    }
+
+    /// Copy the committed search index to backup_dir
+    pub fn backup_index(&self, backup_dir: &Path) -> Result<()> {
+        self.inverted_index.backup(backup_dir)
+    }
+
+    /// Replace the search index with a backup and reopen the project
+    /// Consumes the indexer, since its hold on the index has to be released first
+    /// Files changed since the backup are picked up again by rebuild()
+    pub fn restore_index(self, backup_dir: &Path) -> Result<Self> {
+        let index_dir = self.index_dir.clone();
+        drop(self);
+
+        InvertedIndex::restore_from_backup(backup_dir, &index_dir.join("inverted"))?;
+        Self::open_or_create_with_saved_settings(&index_dir)
+    }
+
+    /// Open the index at index_dir, creating it on first use
+    /// Archive and preview settings saved in the project take the place of the defaults
+    fn open_or_create_with_saved_settings(index_dir: &Path) -> Result<Self> {
+        let indexer = match Self::open_with_settings(index_dir, None, None, None) {
+            Ok(indexer) => indexer,
+            // An index from an older schema can't be reused; the user has to re-index
+            Err(e) if e.downcast_ref::<IndexSchemaOutdated>().is_some() => return Err(e),
+            Err(_) => Self::create_with_settings(index_dir, None, None, None)?,
+        };
+
+        // sled allows a single handle to the auxiliary db, so the saved settings are read
+        // through the indexer's own handle and applied before it is handed out
+        let archive_settings = indexer
+            .auxiliary_db
+            .load_archive_settings()
+            .unwrap_or_default();
+        let preview_config = indexer
+            .auxiliary_db
+            .load_preview_config()
+            .unwrap_or_default();
+        *indexer.archive_extractor.write() = Self::build_archive_extractor(Some(archive_settings));
+        *indexer.image_preview.write() =
+            Self::build_image_preview(Some(preview_config), index_dir)?;
+
+        Ok(indexer)
+    }
+
+    /// Change how archives are unpacked and remember the settings for this project
+    pub fn update_archive_settings(&self, settings: ArchiveSettings) -> Result<()> {
+        self.auxiliary_db.save_archive_settings(&settings)?;
+        *self.archive_extractor.write() = Self::build_archive_extractor(Some(settings));
+        Ok(())
+    }
+
+    /// Change how image previews are generated and remember the config for this project
+    pub fn update_preview_config(&self, config: PreviewConfig) -> Result<()> {
+        self.auxiliary_db.save_preview_config(&config)?;
+        *self.image_preview.write() = Self::build_image_preview(Some(config), &self.index_dir)?;
+        Ok(())
+    }
+
+    /// Archive extractor for the given settings, if unpacking is enabled
+    fn build_archive_extractor(settings: Option<ArchiveSettings>) -> Option<Arc<ArchiveExtractor>> {
+        settings
+            .filter(|settings| settings.auto_unpack)
+            .map(|settings| Arc::new(ArchiveExtractor::new(settings)))
+    }
+
+    /// Image preview generator for the given config, if previews are enabled
+    fn build_image_preview(
+        config: Option<PreviewConfig>,
+        index_dir: &Path,
+    ) -> Result<Option<Arc<ImagePreviewGenerator>>> {
+        match config.filter(|config| config.enabled) {
+            Some(config) => {
+                let preview_dir = index_dir.join("previews");
+                Ok(Some(Arc::new(ImagePreviewGenerator::new(
+                    config,
+                    preview_dir,
+                )?)))
+            }
+            None => Ok(None),
         }
     }
 
@@ -171,125 +609,270 @@ impl MasterIndexer {
         Ok(data_dir.join(db_name))
     }
 
-    /// Open with archive and preview settings
+    /// Open with archive, preview and indexing settings
     pub fn open_with_settings(
         index_dir: &Path,
         archive_settings: Option<ArchiveSettings>,
         preview_config: Option<PreviewConfig>,
+        indexing_config: Option<IndexingConfig>,
     ) -> Result<Self> {
-        let inverted_index = InvertedIndex::open(&index_dir.join("inverted"))?;
-        let extractor_registry = ExtractorRegistry::new();
+        let indexing_config = indexing_config.unwrap_or_default();
+        // A crash can leave Tantivy's writer lock behind, which would keep the project closed
+        let inverted_index = InvertedIndex::open_force(&index_dir.join("inverted"))?
+            .with_deduplication(indexing_config.deduplicate);
+        let extractor_registry = ExtractorRegistry::with_config(indexing_config.extractors.clone())
+            .with_extraction_timeout(indexing_config.extraction_timeout());
 
         let cache_path = index_dir.join("change_cache.bin");
-        let change_detector = ChangeDetector::load(&cache_path).unwrap_or_default();
-
-        // Set up archive extractor if enabled
-        let archive_extractor = if let Some(settings) = archive_settings {
-            if settings.auto_unpack {
-                Some(Arc::new(ArchiveExtractor::new(settings)))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let change_detector = ChangeDetector::load(&cache_path)
+            .unwrap_or_default()
+            .with_symlinks_recorded(indexing_config.record_symlinks);
 
-        // Set up image preview generator if enabled
-        let image_preview = if let Some(config) = preview_config {
-            if config.enabled {
-                let preview_dir = index_dir.join("previews");
-                Some(Arc::new(ImagePreviewGenerator::new(config, preview_dir)?))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let archive_extractor = Self::build_archive_extractor(archive_settings);
+        let image_preview = Self::build_image_preview(preview_config, index_dir)?;
 
         let auxiliary_db = AuxiliaryProjectDb::init(index_dir.join("aux"))?;
 
+        let thread_pool = indexing_config.build_thread_pool()?;
+
         Ok(Self {
             inverted_index: Arc::new(inverted_index),
             extractor_registry: Arc::new(extractor_registry),
             change_detector: Arc::new(parking_lot::Mutex::new(change_detector)),
-            archive_extractor,
-            image_preview,
+            archive_extractor: parking_lot::RwLock::new(archive_extractor),
+            image_preview: parking_lot::RwLock::new(image_preview),
             index_dir: index_dir.to_path_buf(),
             auxiliary_db: Arc::new(auxiliary_db),
+            indexing_config,
+            thread_pool: Arc::new(thread_pool),
+            index_errors: Arc::new(parking_lot::Mutex::new(Vec::new())),
+            batch_extractions: Arc::new(parking_lot::Mutex::new(HashMap::new())),
         })
     }
 
     /// Index a directory tree
     pub fn index_directory(&self, root: &Path) -> Result<IndexStats> {
+        self.index_directory_streaming(root, &mut |_| Ok(()))
+    }
+
+    /// Index a directory tree, handing each batch to on_batch once it is committed
+    /// Files matching the priority patterns come first, so they are searchable
+    /// before the bulk of the evidence has been processed
+    pub fn index_directory_streaming(
+        &self,
+        root: &Path,
+        on_batch: &mut dyn FnMut(IndexedBatch) -> Result<()>,
+    ) -> Result<IndexStats> {
         let start = std::time::Instant::now();
+        self.index_errors.lock().clear();
 
         // 1. Scan directory to find all files
-        let files = Self::scan_directory(root)?;
+        let ignore = self.indexing_config.ignore_matcher(root)?;
+        let files = Self::scan_directory(root, &ignore, self.indexing_config.record_symlinks)?;
         let total_files = files.len() as u64;
 
         // 2. Detect changes (incremental indexing)
-        let changes = {
+        let (changes, bytes_hashed) = {
             let mut detector = self.change_detector.lock();
-            detector.detect_changes(&files)?
+            let before = detector.bytes_hashed();
+            let changes = detector.detect_changes(&files)?;
+            (changes, detector.bytes_hashed() - before)
         };
 
         // Filter to only new/modified files
-        let files_to_index: Vec<PathBuf> = changes
+        let files_to_index: Vec<FileChange> = changes
             .into_iter()
-            .filter_map(|change| match change {
-                FileChange::Added(p) | FileChange::Modified(p) => Some(p),
-                _ => None,
-            })
+            .filter(|change| matches!(change, FileChange::Added(_) | FileChange::Modified(_)))
             .collect();
 
+        // Move high-value artifacts to the front, keeping scan order within each group
+        let (priority, bulk): (Vec<FileChange>, Vec<FileChange>) =
+            match self.indexing_config.priority_matcher(root)? {
+                Some(matcher) => files_to_index.into_iter().partition(|change| {
+                    matches!(change, FileChange::Added(path) | FileChange::Modified(path)
+                        if matcher.matched_path_or_any_parents(path, false).is_ignore())
+                }),
+                None => (Vec::new(), files_to_index),
+            };
+
+        println!(
+            "Files to index: {} ({} priority) out of {}, {} bytes hashed to detect changes",
+            priority.len() + bulk.len(),
+            priority.len(),
+            total_files,
+            bytes_hashed
+        );
+
+        self.index_files(&priority, &bulk, total_files, start, on_batch)
+    }
+
+    /// Re-extract and re-index every file under root, ignoring the change cache
+    /// Lets extractor upgrades apply to files that were already indexed
+    pub fn rebuild(&self, root: &Path) -> Result<IndexStats> {
+        let start = std::time::Instant::now();
+        self.index_errors.lock().clear();
+
+        let ignore = self.indexing_config.ignore_matcher(root)?;
+        let files = Self::scan_directory(root, &ignore, self.indexing_config.record_symlinks)?;
+        let total_files = files.len() as u64;
+
+        // Refresh cached states so the next incremental run sees these files as unchanged
+        self.change_detector.lock().detect_changes(&files)?;
+
+        println!("Rebuilding index for {} files", total_files);
+
+        // Every file already has a document, so treat them all as modified
+        let files_to_index: Vec<FileChange> = files.into_iter().map(FileChange::Modified).collect();
+
+        self.index_files(&[], &files_to_index, total_files, start, &mut |_| Ok(()))
+    }
+
+    /// Re-extract every file known to the change cache, without scanning the filesystem
+    /// Unlike rebuild this needs no root and covers every directory indexed into the project;
+    /// files that can't be reached on disk are skipped and listed in the stats
+    /// Progress is reported after each committed batch; the last report is always Complete
+    pub fn rebuild_index_with_progress(
+        &self,
+        on_progress: &mut dyn FnMut(IndexProgress),
+    ) -> Result<IndexStats> {
+        let start = std::time::Instant::now();
+        self.index_errors.lock().clear();
+
+        // A missing file may only be on a drive that isn't mounted right now, so its document
+        // and cached state are kept rather than deleted
+        let (files, unavailable): (Vec<PathBuf>, Vec<PathBuf>) = self
+            .change_detector
+            .lock()
+            .cached_states()
+            .into_iter()
+            .map(|state| state.path)
+            .partition(|path| path.symlink_metadata().is_ok());
+        let total_files = files.len() as u64;
+
         println!(
-            "Files to index: {} out of {}",
-            files_to_index.len(),
-            total_files
+            "Rebuilding index for {} cached files ({} not reachable on disk)",
+            total_files,
+            unavailable.len()
         );
 
+        let files_to_index: Vec<FileChange> = files.into_iter().map(FileChange::Modified).collect();
+
+        let mut files_processed = 0u64;
+        let mut bytes_processed = 0u64;
+        let mut current_file = String::new();
+        let mut stats =
+            self.index_files(&[], &files_to_index, total_files, start, &mut |batch| {
+                // Only files whose documents are committed are marked current, so a rebuild that
+                // fails partway leaves the rest to be picked up again
+                let mut detector = self.change_detector.lock();
+                detector.detect_changes(&batch.paths)?;
+
+                files_processed += batch.paths.len() as u64;
+                bytes_processed += batch
+                    .paths
+                    .iter()
+                    .filter_map(|path| detector.get_cached_state(path))
+                    .map(|state| state.size)
+                    .sum::<u64>();
+                if let Some(last) = batch.paths.last() {
+                    current_file = last.to_string_lossy().to_string();
+                }
+                on_progress(IndexProgress {
+                    files_processed,
+                    files_total: total_files,
+                    bytes_processed,
+                    current_file: current_file.clone(),
+                    phase: IndexPhase::Indexing,
+                });
+                Ok(())
+            })?;
+        stats.unavailable_files = unavailable;
+
+        on_progress(IndexProgress {
+            files_processed,
+            files_total: total_files,
+            bytes_processed,
+            current_file,
+            phase: IndexPhase::Complete,
+        });
+
+        Ok(stats)
+    }
+
+    /// Index files in batches, committing after each batch
+    /// Modified files replace their previous document; added files get a new one
+    /// Priority files are batched separately so none of their batches waits on bulk files
+    fn index_files(
+        &self,
+        priority: &[FileChange],
+        bulk: &[FileChange],
+        total_files: u64,
+        start: std::time::Instant,
+        on_batch: &mut dyn FnMut(IndexedBatch) -> Result<()>,
+    ) -> Result<IndexStats> {
         // 3. Index files in batches with memory limits
         let files_processed = Arc::new(AtomicU64::new(0));
+        let skipped_by_filter = Arc::new(AtomicU64::new(0));
         let total_size = Arc::new(AtomicU64::new(0));
         let by_category = Arc::new(parking_lot::Mutex::new(std::collections::HashMap::new()));
 
-        const BATCH_SIZE: usize = 100;
-        const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024; // 100MB per file limit
+        let config = &self.indexing_config;
 
         // Process in batches to avoid memory exhaustion
-        for batch in files_to_index.chunks(BATCH_SIZE) {
-            batch.par_iter().for_each(|path| {
-                // Skip extremely large files to prevent crashes
-                if let Ok(metadata) = std::fs::metadata(path) {
-                    if metadata.len() > MAX_FILE_SIZE {
-                        println!(
-                            "Skipping large file ({}MB): {}",
-                            metadata.len() / (1024 * 1024),
-                            path.display()
-                        );
-                        return;
+        let batch_size = config.batch_size.max(1);
+        let batches = priority
+            .chunks(batch_size)
+            .map(|batch| (batch, true))
+            .chain(bulk.chunks(batch_size).map(|batch| (batch, false)));
+        for (batch, is_priority) in batches {
+            // Run on the bounded pool so indexing leaves CPU for other work
+            self.thread_pool.install(|| {
+                batch.par_iter().for_each(|change| {
+                    let (path, replace_existing) = match change {
+                        FileChange::Added(path) => (path, false),
+                        FileChange::Modified(path) => (path, true),
+                        FileChange::Deleted(_) | FileChange::Unchanged(_) => return,
+                    };
+
+                    match self.index_file(path, replace_existing) {
+                        Ok(Some(file_doc)) => {
+                            // Update statistics
+                            files_processed.fetch_add(1, Ordering::Relaxed);
+                            total_size.fetch_add(file_doc.metadata.size, Ordering::Relaxed);
+
+                            let mut cat_map = by_category.lock();
+                            *cat_map
+                                .entry(format!("{:?}", file_doc.metadata.category))
+                                .or_insert(0) += 1;
+                        }
+                        Ok(None) => {
+                            skipped_by_filter.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(_) => {}
                     }
-                }
-
-                if let Ok(file_doc) = self.index_file(path) {
-                    // Update statistics
-                    files_processed.fetch_add(1, Ordering::Relaxed);
-                    total_size.fetch_add(file_doc.metadata.size, Ordering::Relaxed);
-
-                    let mut cat_map = by_category.lock();
-                    *cat_map
-                        .entry(format!("{:?}", file_doc.metadata.category))
-                        .or_insert(0) += 1;
-                }
+                })
             });
 
             // Commit after each batch to save progress
             if let Err(e) = self.inverted_index.commit() {
                 eprintln!("Failed to commit batch: {}", e);
             }
+            // Committed copies are found through the index from here on
+            self.batch_extractions.lock().clear();
+
+            on_batch(IndexedBatch {
+                paths: batch
+                    .iter()
+                    .filter_map(|change| match change {
+                        FileChange::Added(path) | FileChange::Modified(path) => Some(path.clone()),
+                        FileChange::Deleted(_) | FileChange::Unchanged(_) => None,
+                    })
+                    .collect(),
+                priority: is_priority,
+            })?;
 
             // Give system time to breathe between batches
-            std::thread::sleep(std::time::Duration::from_millis(10));
+            std::thread::sleep(std::time::Duration::from_millis(config.commit_interval_ms));
         }
 
         // 4. Final commit
@@ -310,64 +893,194 @@ impl MasterIndexer {
             total_size: total_size.load(Ordering::Relaxed),
             by_category: by_category_map,
             duration_ms,
+            skipped_by_filter: skipped_by_filter.load(Ordering::Relaxed),
+            unavailable_files: Vec::new(),
+            errors: std::mem::take(&mut *self.index_errors.lock()),
         })
     }
 
-    /// Index a single file
-    fn index_file(&self, path: &Path) -> Result<FileDocument> {
-        // 1. Check if file is an archive and unpack if enabled
-        if let Some(ref archive_extractor) = self.archive_extractor {
-            if archive_extractor.is_archive(path) {
-                // Unpack archive
-                if let Ok(unpacked_info) = archive_extractor.unpack(
-                    path,
-                    &self.index_dir,
-                    0, // Top-level nesting
-                ) {
-                    // Note: The unpacked files will be indexed in subsequent scans
-                    println!(
-                        "Unpacked archive {} to {}: {} files",
-                        path.display(),
-                        unpacked_info.unpacked_to.display(),
-                        unpacked_info.file_count
-                    );
-                }
-            }
+    /// Index a single file, replacing its existing document if replace_existing is set
+    /// Returns None when the file's category is excluded by the category filter
+    fn index_file(&self, path: &Path, replace_existing: bool) -> Result<Option<FileDocument>> {
+        self.index_file_from_archive(path, None, 0, replace_existing)
+    }
+
+    /// Index a single file, recording the archive it was unpacked from (if any)
+    /// nesting_level is the depth of archives this file sits in (0 = loose file)
+    fn index_file_from_archive(
+        &self,
+        path: &Path,
+        archive_source: Option<ArchiveSource>,
+        nesting_level: u32,
+        replace_existing: bool,
+    ) -> Result<Option<FileDocument>> {
+        if self.indexing_config.record_symlinks && path.is_symlink() {
+            return self.index_symlink(path, archive_source, replace_existing);
         }
 
-        // 2. Detect file type via magic bytes
+        // 1. Detect file type via magic bytes; filtered-out archives aren't unpacked either
         let detected = FileTypeDetector::detect(path).context("Failed to detect file type")?;
 
-        // 3. Generate image preview if it's an image
-        let mut image_info = None;
-        if let Some(ref image_preview) = self.image_preview {
-            if image_preview.is_image(path) {
-                if let Ok(info) = image_preview.generate_preview(path) {
-                    image_info = Some(info);
+        if !self.indexing_config.accepts_category(detected.category) {
+            return Ok(None);
+        }
+
+        // 2. Check if file is an archive and unpack if enabled
+        let archive_extractor = self.archive_extractor.read().clone();
+        if let Some(archive_extractor) = archive_extractor {
+            if archive_extractor.is_archive(path) {
+                // Unpack archive (fails once max_nesting_level is reached)
+                match archive_extractor.unpack(path, &self.index_dir, nesting_level) {
+                    Ok(unpacked_info) => {
+                        println!(
+                            "Unpacked archive {} to {}: {} files",
+                            path.display(),
+                            unpacked_info.unpacked_to.display(),
+                            unpacked_info.file_count
+                        );
+
+                        if let Err(e) = self.index_unpacked_archive(&unpacked_info) {
+                            eprintln!("Failed to index contents of {}: {}", path.display(), e);
+                        }
+                    }
+                    Err(e) => {
+                        // Formats we can't open are reported so the archive isn't silently skipped
+                        if let Some(archive_error) = e.downcast_ref::<ArchiveError>() {
+                            self.index_errors.lock().push(IndexError {
+                                path: path.to_path_buf(),
+                                message: archive_error.to_string(),
+                            });
+                        }
+                    }
                 }
             }
         }
 
-        // 4. Get file metadata
+        // 3. Get file metadata; files over their category's limit only get metadata extracted
         let metadata = std::fs::metadata(path)?;
         let size = metadata.len();
+        let (owner_uid, owner_gid) = file_owner(&metadata);
+
+        let mode = if size
+            > self
+                .indexing_config
+                .size_limits
+                .limit_for(detected.category)
+        {
+            println!(
+                "Indexing metadata only for large file ({}MB): {}",
+                size / (1024 * 1024),
+                path.display()
+            );
+            ExtractionMode::MetadataOnly
+        } else {
+            ExtractionMode::Full
+        };
 
         let modified =
             chrono::DateTime::from(metadata.modified().unwrap_or(std::time::SystemTime::now()));
 
         let created = metadata.created().ok().map(chrono::DateTime::from);
 
-        // 5. Calculate hash
-        let hash = Self::calculate_hash(path)?;
+        // 4. Reuse the hashes the change detector computed for this scan; rehash only if stale
+        let (hash, keccak256) = match self
+            .cached_state(path, size, modified)
+            .and_then(|state| Some((state.hash, state.keccak256?)))
+        {
+            Some(hashes) => hashes,
+            None => {
+                let (hash, keccak256, _) = ChangeDetector::content_hashes(path)?;
+                (hash, keccak256)
+            }
+        };
 
-        // 6. Build document ID
+        // 5. Build document ID and metadata
         let doc_id = Self::make_doc_id(path);
+        let document_metadata = DocumentMetadata {
+            path: path.to_path_buf(),
+            size,
+            modified,
+            created,
+            hash,
+            keccak256: Some(keccak256),
+            mime_type: detected.mime_type,
+            category: detected.category,
+            magic_header: detected.magic_header,
+            confidence: detected.confidence,
+            extension: path
+                .extension()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_string()),
+            owner_uid,
+            owner_gid,
+            acl: read_acl(path),
+            empty: size == 0,
+            indexed: true,
+            indexed_at: Some(Utc::now()),
+        };
+
+        // 6. Empty files have nothing to extract, and parsers would only report them as broken
+        if document_metadata.empty {
+            let file_doc = FileDocument {
+                id: doc_id,
+                metadata: document_metadata,
+                structured: None,
+                content: None,
+                preview: Some("Empty file".to_string()),
+                image_metadata: None,
+                archive_source,
+                fields: HashMap::new(),
+            };
+            self.write_document(&file_doc, replace_existing)?;
+            return Ok(Some(file_doc));
+        }
+
+        // 7. Identical content was already extracted, so the copy gets the same output
+        let claim = if self.indexing_config.skip_duplicate_extraction
+            && !self.indexing_config.deduplicate
+        {
+            match self.duplicate_extraction(&document_metadata, &doc_id)? {
+                DuplicateExtraction::Reuse(extraction) => {
+                    let file_doc = FileDocument {
+                        id: doc_id,
+                        metadata: document_metadata,
+                        structured: extraction.structured,
+                        content: extraction.content,
+                        preview: Some(extraction.preview),
+                        image_metadata: None,
+                        archive_source,
+                        fields: extraction.fields,
+                    };
+                    self.write_document(&file_doc, replace_existing)?;
+                    return Ok(Some(file_doc));
+                }
+                DuplicateExtraction::Claimed(claim) => Some(claim),
+            }
+        } else {
+            None
+        };
+
+        // 8. Generate image preview if it's an image
+        let mut image_info = None;
+        let image_preview = self.image_preview.read().clone();
+        if let Some(image_preview) = image_preview {
+            if mode == ExtractionMode::Full && image_preview.is_image(path) {
+                if let Ok(info) = image_preview.generate_preview(path) {
+                    image_info = Some(info);
+                }
+            }
+        }
 
-        // 7. Extract content using appropriate extractor
+        // 9. Extract content using appropriate extractor
         let mut extraction = self
-            .extractor_registry
-            .extract(path, detected.category, &detected.mime_type)
-            .unwrap_or_else(|_| {
+            .extract_with_timeout(path, detected.category, &document_metadata.mime_type, mode)
+            .unwrap_or_else(|e| {
+                eprintln!("Extraction failed for {}: {}", path.display(), e);
+                self.index_errors.lock().push(IndexError {
+                    path: path.to_path_buf(),
+                    message: e.to_string(),
+                });
+
                 // Minimal extraction if extractor fails
                 super::extractors::ExtractorOutput {
                     structured: None,
@@ -377,7 +1090,7 @@ impl MasterIndexer {
                 }
             });
 
-        // 8. Enhance extraction with image metadata if available
+        // 10. Enhance extraction with image metadata if available
         if let Some(ref img_info) = image_info {
             extraction
                 .fields
@@ -389,6 +1102,12 @@ impl MasterIndexer {
                 .fields
                 .insert("image_format".to_string(), img_info.format.clone());
 
+            if img_info.extension_mismatch {
+                extraction
+                    .fields
+                    .insert("image_extension_mismatch".to_string(), "true".to_string());
+            }
+
             if let Some(ref thumb_path) = img_info.thumbnail_path {
                 extraction.fields.insert(
                     "thumbnail".to_string(),
@@ -403,59 +1122,647 @@ impl MasterIndexer {
             );
         }
 
-        // 9. Build image metadata if available
+        if let Some(claim) = &claim {
+            claim.fulfil(&extraction);
+        }
+
+        // 11. Build image metadata if available
         let image_metadata = image_info.map(|info| super::schema::ImageMetadata {
             width: info.width,
             height: info.height,
             format: info.format,
             has_alpha: info.has_alpha,
             thumbnail_path: info.thumbnail_path,
+            extension_mismatch: info.extension_mismatch,
         });
 
-        // 10. Build file document
+        // 12. Build file document
         let file_doc = FileDocument {
             id: doc_id,
-            metadata: DocumentMetadata {
-                path: path.to_path_buf(),
-                size,
-                modified,
-                created,
-                hash,
-                mime_type: detected.mime_type,
-                category: detected.category,
-                magic_header: detected.magic_header,
-                extension: path
-                    .extension()
-                    .and_then(|s| s.to_str())
-                    .map(|s| s.to_string()),
-                indexed: true,
-                indexed_at: Some(Utc::now()),
-            },
+            metadata: document_metadata,
             structured: extraction.structured,
             content: extraction.content,
             preview: Some(extraction.preview),
             image_metadata,
-            archive_source: None, // TODO: Track if file came from archive
+            archive_source,
+            fields: extraction.fields,
         };
 
-        // 10. Add to inverted index
-        self.inverted_index.add_document(&file_doc)?;
+        // 13. Add to inverted index, replacing the previous version of a modified file
+        self.write_document(&file_doc, replace_existing)?;
 
-        Ok(file_doc)
+        Ok(Some(file_doc))
     }
 
-    /// Scan directory recursively to find all files
-    fn scan_directory(root: &Path) -> Result<Vec<PathBuf>> {
-        let mut files = Vec::new();
-        Self::scan_recursive(root, &mut files)?;
-        Ok(files)
+    /// Add a document, or replace the existing one when re-indexing a modified file
+    fn write_document(&self, file_doc: &FileDocument, replace_existing: bool) -> Result<()> {
+        if replace_existing {
+            self.inverted_index.update_document(file_doc)
+        } else {
+            self.inverted_index.add_document(file_doc)
+        }
     }
 
-    pub fn get_auxiliary_db(&self) -> Arc<AuxiliaryProjectDb> {
-        self.auxiliary_db.clone()
+    /// Extraction to reuse for a file whose content may already have been extracted
+    /// The first file seen with a hash in a batch claims it, so copies indexed in parallel
+    /// wait for that extraction rather than repeating it. Committed documents only keep
+    /// their content and preview, so they are reused only for types with nothing else
+    fn duplicate_extraction(
+        &self,
+        metadata: &DocumentMetadata,
+        doc_id: &str,
+    ) -> Result<DuplicateExtraction> {
+        let shared = {
+            let mut batch_extractions = self.batch_extractions.lock();
+            match batch_extractions.get(&metadata.hash) {
+                Some(shared) => shared.clone(),
+                None => {
+                    if Self::extraction_is_stored(metadata.category, &metadata.mime_type) {
+                        if let Some(stored) = self
+                            .inverted_index
+                            .stored_text_for_hash(&metadata.hash, doc_id)?
+                        {
+                            return Ok(DuplicateExtraction::Reuse(ExtractorOutput {
+                                structured: None,
+                                content: stored.content,
+                                preview: stored.preview.unwrap_or_default(),
+                                fields: HashMap::new(),
+                            }));
+                        }
+                    }
+
+                    let shared = SharedExtraction::default();
+                    batch_extractions.insert(metadata.hash.clone(), shared.clone());
+                    return Ok(DuplicateExtraction::Claimed(ExtractionClaim(shared)));
+                }
+            }
+        };
+
+        Ok(match shared.wait() {
+            Some(extraction) => DuplicateExtraction::Reuse(extraction.clone()),
+            None => DuplicateExtraction::Claimed(ExtractionClaim(SharedExtraction::default())),
+        })
+    }
+
+    /// Whether everything extracted for this type can be read back from its stored document
+    /// Structured output (tables, columns, JSON paths, sheets) is indexed but not all stored
+    fn extraction_is_stored(category: FileCategory, mime_type: &str) -> bool {
+        match category {
+            FileCategory::Database | FileCategory::StructuredData => false,
+            FileCategory::Document => {
+                !(mime_type.contains("spreadsheetml") || mime_type == "application/vnd.ms-excel")
+            }
+            _ => true,
+        }
     }
 
-    fn scan_recursive(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    /// Index a symlink as its own document, recording its target without reading through it
+    /// Only used when IndexingConfig::record_symlinks is set
+    fn index_symlink(
+        &self,
+        path: &Path,
+        archive_source: Option<ArchiveSource>,
+        replace_existing: bool,
+    ) -> Result<Option<FileDocument>> {
+        if !self.indexing_config.accepts_category(FileCategory::Unknown) {
+            return Ok(None);
+        }
+
+        let link_metadata = std::fs::symlink_metadata(path)?;
+        let target = std::fs::read_link(path)?;
+        let modified = chrono::DateTime::from(
+            link_metadata
+                .modified()
+                .unwrap_or(std::time::SystemTime::now()),
+        );
+        let hash = self
+            .cached_state(path, link_metadata.len(), modified)
+            .map(|state| state.hash)
+            .unwrap_or_else(|| ChangeDetector::symlink_hash(&target));
+        let (owner_uid, owner_gid) = file_owner(&link_metadata);
+        let target_str = target.to_string_lossy().to_string();
+
+        let file_doc = FileDocument {
+            id: Self::make_doc_id(path),
+            metadata: DocumentMetadata {
+                path: path.to_path_buf(),
+                size: link_metadata.len(),
+                modified,
+                created: None,
+                hash,
+                keccak256: None,
+                mime_type: SYMLINK_MIME_TYPE.to_string(),
+                category: FileCategory::Unknown,
+                magic_header: String::new(),
+                confidence: 1.0,
+                extension: path
+                    .extension()
+                    .and_then(|s| s.to_str())
+                    .map(|s| s.to_string()),
+                owner_uid,
+                owner_gid,
+                acl: Vec::new(),
+                empty: false,
+                indexed: true,
+                indexed_at: Some(Utc::now()),
+            },
+            structured: None,
+            preview: Some(format!("Symlink -> {}", target_str)),
+            content: Some(target_str),
+            image_metadata: None,
+            archive_source,
+            fields: HashMap::new(),
+        };
+
+        self.write_document(&file_doc, replace_existing)?;
+
+        Ok(Some(file_doc))
+    }
+
+    /// Index every file unpacked from an archive, tagging each with its origin
+    /// Nested archives are unpacked in turn, one level deeper
+    fn index_unpacked_archive(&self, unpacked_info: &UnpackedArchiveInfo) -> Result<()> {
+        // Archive contents are indexed in full; exclusions apply to the evidence tree only
+        let files = Self::scan_directory(
+            &unpacked_info.unpacked_to,
+            &Gitignore::empty(),
+            self.indexing_config.record_symlinks,
+        )?;
+
+        // Remember the unpacked files so later scans don't re-index them as loose files
+        // Done first so indexing can reuse the hashes computed here
+        self.change_detector.lock().detect_changes(&files)?;
+
+        for file in &files {
+            let relative_path = file
+                .strip_prefix(&unpacked_info.unpacked_to)
+                .unwrap_or(file)
+                .to_string_lossy()
+                .to_string();
+
+            let archive_source = ArchiveSource {
+                archive_path: unpacked_info.archive_path.clone(),
+                relative_path,
+                archive_format: format!("{:?}", unpacked_info.format),
+            };
+
+            // Replace any document left over from a previous unpack
+            if let Err(e) = self.index_file_from_archive(
+                file,
+                Some(archive_source),
+                unpacked_info.nesting_level + 1,
+                true,
+            ) {
+                eprintln!("Failed to index unpacked file {}: {}", file.display(), e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run the extractor, treating the registry's timeout fallback as an error for this file
+    fn extract_with_timeout(
+        &self,
+        path: &Path,
+        category: FileCategory,
+        mime_type: &str,
+        mode: ExtractionMode,
+    ) -> Result<ExtractorOutput> {
+        let extraction = self
+            .extractor_registry
+            .extract_with_mode(path, category, mime_type, mode)?;
+        if extraction.timed_out() {
+            anyhow::bail!(
+                "Extraction timed out after {}ms",
+                self.indexing_config.extraction_timeout_ms
+            );
+        }
+        Ok(extraction)
+    }
+
+    /// Re-run extraction for a single file, replacing its existing document
+    /// Returns the error recorded for the file, if extraction failed again
+    pub fn retry_file(&self, path: &Path) -> Result<Option<IndexError>> {
+        if !path.is_file() {
+            anyhow::bail!("Not a file: {}", path.display());
+        }
+
+        // Only this file's entries are touched, each time under a single lock, so errors an
+        // index run records for other files in the meantime are kept
+        self.index_errors.lock().retain(|error| error.path != path);
+        self.index_file(path, true)?;
+        self.inverted_index.commit()?;
+
+        let mut errors = self.index_errors.lock();
+        Ok(errors
+            .iter()
+            .rposition(|error| error.path == path)
+            .map(|i| errors.remove(i)))
+    }
+
+    /// Search a single file for a pattern without going through the index (case-insensitive)
+    /// Databases are queried table by table; structured and text files are re-extracted
+    pub fn search_within_file(&self, path: &Path, pattern: &str) -> Result<Vec<InFileMatch>> {
+        if !path.is_file() {
+            anyhow::bail!("Not a file: {}", path.display());
+        }
+        if pattern.is_empty() {
+            anyhow::bail!("Search pattern is empty");
+        }
+
+        let detected = FileTypeDetector::detect(path).context("Failed to detect file type")?;
+        let context_lines = match detected.category {
+            FileCategory::Database => return Self::search_within_database(path, pattern),
+            FileCategory::StructuredData => 0,
+            FileCategory::Text => 1,
+            other => anyhow::bail!("In-file search is not supported for {:?} files", other),
+        };
+
+        let extraction = self.extract_with_timeout(
+            path,
+            detected.category,
+            &detected.mime_type,
+            ExtractionMode::Full,
+        )?;
+        let content = extraction.content.unwrap_or_default();
+        Ok(Self::search_lines(&content, pattern, context_lines))
+    }
+
+    /// Match lines of extracted content, reporting 1-based line numbers
+    fn search_lines(content: &str, pattern: &str, context_lines: usize) -> Vec<InFileMatch> {
+        let needle = pattern.to_lowercase();
+        let lines: Vec<&str> = content.lines().collect();
+
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.to_lowercase().contains(&needle))
+            .take(MAX_IN_FILE_MATCHES)
+            .map(|(i, _)| {
+                let start = i.saturating_sub(context_lines);
+                let end = (i + context_lines + 1).min(lines.len());
+                InFileMatch {
+                    location: format!("line {}", i + 1),
+                    context: lines[start..end].join("\n"),
+                }
+            })
+            .collect()
+    }
+
+    /// Match the schema in sqlite_master, then every table's rows with LIKE
+    fn search_within_database(path: &Path, pattern: &str) -> Result<Vec<InFileMatch>> {
+        use rusqlite::types::ValueRef;
+        use rusqlite::{Connection, OpenFlags};
+
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .context("Failed to open database")?;
+        let needle = pattern.to_lowercase();
+        let mut matches = Vec::new();
+
+        let mut tables = Vec::new();
+        let mut stmt = conn.prepare("SELECT type, name, sql FROM sqlite_master")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let kind: String = row.get(0)?;
+            let name: String = row.get(1)?;
+            let sql: Option<String> = row.get(2)?;
+
+            let schema_text = format!("{} {}", name, sql.as_deref().unwrap_or(""));
+            if schema_text.to_lowercase().contains(&needle) {
+                matches.push(InFileMatch {
+                    location: format!("schema {}", name),
+                    context: sql.unwrap_or_else(|| name.clone()),
+                });
+            }
+            if kind == "table" && !name.starts_with("sqlite_") {
+                tables.push(name);
+            }
+        }
+
+        let like = format!(
+            "%{}%",
+            pattern
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        let quote = |name: &str| format!("\"{}\"", name.replace('"', "\"\""));
+
+        for table in tables {
+            let remaining = MAX_IN_FILE_MATCHES.saturating_sub(matches.len());
+            if remaining == 0 {
+                break;
+            }
+
+            let columns: Vec<String> = conn
+                .prepare(&format!("PRAGMA table_info({})", quote(&table)))?
+                .query_map([], |row| row.get::<_, String>(1))?
+                .collect::<rusqlite::Result<_>>()?;
+            if columns.is_empty() {
+                continue;
+            }
+
+            let condition = columns
+                .iter()
+                .map(|column| format!("CAST({} AS TEXT) LIKE ?1 ESCAPE '\\'", quote(column)))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            let sql = format!(
+                "SELECT * FROM {} WHERE {} LIMIT {}",
+                quote(&table),
+                condition,
+                remaining
+            );
+
+            // Virtual tables whose module isn't available can't be queried
+            let Ok(mut stmt) = conn.prepare(&sql) else {
+                continue;
+            };
+            let mut rows = stmt.query([&like])?;
+            while let Some(row) = rows.next()? {
+                let values = columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        let value = match row.get_ref(i)? {
+                            ValueRef::Null => "NULL".to_string(),
+                            ValueRef::Integer(n) => n.to_string(),
+                            ValueRef::Real(f) => f.to_string(),
+                            ValueRef::Text(text) => String::from_utf8_lossy(text).to_string(),
+                            ValueRef::Blob(bytes) => format!("<{} bytes>", bytes.len()),
+                        };
+                        Ok(format!("{}={}", column, value))
+                    })
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                matches.push(InFileMatch {
+                    location: format!("table {}", table),
+                    context: values.join(", "),
+                });
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Delete thumbnails and unpacked archive directories that no indexed document refers to
+    /// These pile up as files are deleted or re-indexed
+    pub fn gc(&self) -> Result<GcStats> {
+        let paths = self.inverted_index.document_paths()?;
+        let mut stats = GcStats::default();
+
+        // Thumbnail names are derived from the image path, so they can be recomputed
+        let image_preview = self.image_preview.read().clone();
+        if let Some(image_preview) = image_preview {
+            let referenced: HashSet<PathBuf> = paths
+                .iter()
+                .filter_map(|path| image_preview.get_thumbnail_path(path).ok())
+                .collect();
+            let (removed, bytes) =
+                Self::remove_unreferenced(&self.index_dir.join("previews"), &referenced)?;
+            stats.removed_previews = removed;
+            stats.reclaimed_bytes += bytes;
+        }
+
+        // Each archive is unpacked into its own directory; keep those holding an indexed file
+        let unpacked_root = self.index_dir.join("unpacked_archives");
+        let referenced: HashSet<PathBuf> = paths
+            .iter()
+            .filter_map(|path| {
+                let dir = path
+                    .strip_prefix(&unpacked_root)
+                    .ok()?
+                    .components()
+                    .next()?;
+                Some(unpacked_root.join(dir))
+            })
+            .collect();
+        let (removed, bytes) = Self::remove_unreferenced(&unpacked_root, &referenced)?;
+        stats.removed_unpacked_dirs = removed;
+        stats.reclaimed_bytes += bytes;
+
+        Ok(stats)
+    }
+
+    /// Remove the entries of dir that aren't referenced; returns (entries removed, bytes freed)
+    fn remove_unreferenced(dir: &Path, referenced: &HashSet<PathBuf>) -> Result<(u64, u64)> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+            Err(e) => return Err(e).context("Failed to read directory for cleanup"),
+        };
+
+        let mut removed = 0;
+        let mut bytes = 0;
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            if referenced.contains(&path) {
+                continue;
+            }
+
+            let size = Self::disk_usage(&path);
+            if entry.file_type()?.is_dir() {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            }
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+
+            removed += 1;
+            bytes += size;
+        }
+
+        Ok((removed, bytes))
+    }
+
+    /// Total size of a file or directory tree (symlinks are not followed)
+    fn disk_usage(path: &Path) -> u64 {
+        let Ok(metadata) = std::fs::symlink_metadata(path) else {
+            return 0;
+        };
+        if !metadata.is_dir() {
+            return metadata.len();
+        }
+
+        std::fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| Self::disk_usage(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Copy indexed files to out_dir and write a manifest of what was copied
+    /// Files that are missing or unreadable are skipped and listed in the report
+    pub fn export_files(
+        &self,
+        doc_ids: &[String],
+        out_dir: &Path,
+        preserve_structure: bool,
+    ) -> Result<FileExportReport> {
+        std::fs::create_dir_all(out_dir).context("Failed to create export directory")?;
+
+        let mut report = FileExportReport {
+            exported_at: Utc::now(),
+            files: Vec::new(),
+            skipped: Vec::new(),
+        };
+
+        for doc_id in doc_ids {
+            let Some(info) = self.inverted_index.stored_file_info(doc_id)? else {
+                report.skipped.push(SkippedExport {
+                    doc_id: doc_id.clone(),
+                    source_path: None,
+                    reason: "Not in the index".to_string(),
+                });
+                continue;
+            };
+
+            if !info.path.is_file() {
+                report.skipped.push(SkippedExport {
+                    doc_id: doc_id.clone(),
+                    source_path: Some(info.path),
+                    reason: "Source file no longer exists".to_string(),
+                });
+                continue;
+            }
+
+            let target = Self::export_target(out_dir, &info.path, doc_id, preserve_structure);
+            match Self::copy_and_hash(&info.path, &target) {
+                Ok((hash, size)) => report.files.push(ExportedFile {
+                    doc_id: doc_id.clone(),
+                    source_path: info.path,
+                    exported_path: target,
+                    matches_index: hash == info.hash && size == info.size,
+                    hash,
+                    size,
+                    indexed_size: info.size,
+                }),
+                Err(e) => report.skipped.push(SkippedExport {
+                    doc_id: doc_id.clone(),
+                    source_path: Some(info.path),
+                    reason: format!("{:#}", e),
+                }),
+            }
+        }
+
+        let manifest = serde_json::to_vec_pretty(&report)?;
+        std::fs::write(out_dir.join(EXPORT_MANIFEST_NAME), manifest)
+            .context("Failed to write export manifest")?;
+
+        Ok(report)
+    }
+
+    /// Where an exported file goes: its full source path under out_dir, or just its name
+    /// Flat exports prefix the document ID when the name is already taken
+    fn export_target(
+        out_dir: &Path,
+        source: &Path,
+        doc_id: &str,
+        preserve_structure: bool,
+    ) -> PathBuf {
+        use std::path::Component;
+
+        if preserve_structure {
+            // Drive letters become a plain directory; root and `..` components are dropped
+            let relative: PathBuf = source
+                .components()
+                .filter_map(|component| match component {
+                    Component::Prefix(prefix) => Some(
+                        prefix
+                            .as_os_str()
+                            .to_string_lossy()
+                            .replace([':', '\\', '?'], "")
+                            .into(),
+                    ),
+                    Component::Normal(part) => Some(part.to_os_string()),
+                    _ => None,
+                })
+                .collect();
+            return out_dir.join(relative);
+        }
+
+        let name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| doc_id.to_string());
+        let target = out_dir.join(&name);
+        if target.exists() {
+            out_dir.join(format!("{}_{}", doc_id, name))
+        } else {
+            target
+        }
+    }
+
+    /// Copy a file while hashing it, so the manifest describes the bytes actually written
+    /// The copy keeps the source's modified/accessed times, as copy_file_preserving does
+    fn copy_and_hash(source: &Path, target: &Path) -> Result<(String, u64)> {
+        use std::io::{Read, Write};
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Read before opening, since reading the source updates its access time
+        let source_metadata = std::fs::metadata(source)
+            .with_context(|| format!("Failed to read metadata of {}", source.display()))?;
+        let mut input = std::fs::File::open(source)
+            .with_context(|| format!("Failed to open {}", source.display()))?;
+        let mut output = std::fs::File::create(target)
+            .with_context(|| format!("Failed to create {}", target.display()))?;
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; 8192];
+        let mut size = 0u64;
+
+        loop {
+            let bytes_read = input.read(&mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..bytes_read]);
+            output.write_all(&buffer[..bytes_read])?;
+            size += bytes_read as u64;
+        }
+        output.sync_all()?;
+        drop(output);
+        LocalFileSystem::copy_file_times(&source_metadata, target)
+            .with_context(|| format!("Failed to set times on {}", target.display()))?;
+
+        Ok((format!("{:x}", hasher.finalize()), size))
+    }
+
+    /// Replace the extractor registry (e.g. to register custom extractors)
+    /// The registry takes on the configured extraction timeout
+    pub fn with_extractor_registry(mut self, registry: ExtractorRegistry) -> Self {
+        self.extractor_registry =
+            Arc::new(registry.with_extraction_timeout(self.indexing_config.extraction_timeout()));
+        self
+    }
+
+    /// Scan directory recursively to find all files not excluded by ignore
+    /// With record_symlinks, symlinks are listed as files and never followed
+    fn scan_directory(
+        root: &Path,
+        ignore: &Gitignore,
+        record_symlinks: bool,
+    ) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        Self::scan_recursive(root, ignore, record_symlinks, &mut files)?;
+        Ok(files)
+    }
+
+    pub fn get_auxiliary_db(&self) -> Arc<AuxiliaryProjectDb> {
+        self.auxiliary_db.clone()
+    }
+
+    fn scan_recursive(
+        dir: &Path,
+        ignore: &Gitignore,
+        record_symlinks: bool,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<()> {
         if !dir.is_dir() {
             return Ok(());
         }
@@ -464,13 +1771,22 @@ impl MasterIndexer {
             let entry = entry?;
             let path = entry.path();
 
-            if path.is_file() {
+            // The ignore file itself is case tooling, not evidence
+            if path == ignore.path().join(IGNORE_FILE_NAME)
+                || ignore
+                    .matched(&path, path.is_dir() && !path.is_symlink())
+                    .is_ignore()
+            {
+                continue;
+            }
+
+            if (record_symlinks && path.is_symlink()) || path.is_file() {
                 files.push(path);
             } else if path.is_dir() {
                 // Skip hidden directories
                 if let Some(name) = path.file_name() {
                     if !name.to_string_lossy().starts_with('.') {
-                        Self::scan_recursive(&path, files)?;
+                        Self::scan_recursive(&path, ignore, record_symlinks, files)?;
                     }
                 }
             }
@@ -479,23 +1795,18 @@ impl MasterIndexer {
         Ok(())
     }
 
-    /// Calculate SHA256 hash incrementally to avoid loading entire file into memory
-    fn calculate_hash(path: &Path) -> Result<String> {
-        use std::io::Read;
-
-        let mut file = std::fs::File::open(path)?;
-        let mut hasher = Sha256::new();
-        let mut buffer = [0u8; 8192]; // 8KB buffer
-
-        loop {
-            let bytes_read = file.read(&mut buffer)?;
-            if bytes_read == 0 {
-                break;
-            }
-            hasher.update(&buffer[..bytes_read]);
-        }
-
-        Ok(format!("{:x}", hasher.finalize()))
+    /// State recorded by the change detector, if it still describes the file on disk
+    /// A hash carried over on a partial-hash match is skipped, since it was never re-read
+    fn cached_state(
+        &self,
+        path: &Path,
+        size: u64,
+        modified: chrono::DateTime<Utc>,
+    ) -> Option<FileState> {
+        let detector = self.change_detector.lock();
+        let state = detector.get_cached_state(path)?;
+        (state.hash_verified && state.size == size && state.modified == modified)
+            .then(|| state.clone())
     }
 
     /// Create document ID from path
@@ -509,6 +1820,102 @@ impl MasterIndexer {
     /// Create a query planner for searching
     pub fn query_planner(&self) -> QueryPlanner {
         QueryPlanner::new(self.inverted_index.clone(), self.extractor_registry.clone())
+            .with_auxiliary_db(self.auxiliary_db.clone())
+    }
+
+    /// Files modified within start..=end, earliest first
+    pub fn get_files_modified_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<TypedHit>> {
+        self.files_between("modified", start, end, limit)
+    }
+
+    /// Files created within start..=end, earliest first
+    /// Only files whose filesystem recorded a creation time are found
+    pub fn get_files_created_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<TypedHit>> {
+        self.files_between("created", start, end, limit)
+    }
+
+    fn files_between(
+        &self,
+        field: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<TypedHit>> {
+        Ok(self
+            .inverted_index
+            .search_by_date_range(field, Some(start), Some(end), limit)?
+            .into_iter()
+            .map(QueryPlanner::search_hit_to_typed)
+            .collect())
+    }
+
+    /// Catalog of SQLite tables and columns across the indexed evidence
+    pub fn structured_catalog(&self) -> Result<StructuredCatalog> {
+        self.inverted_index.structured_catalog()
+    }
+
+    /// Count the files matching query per containing folder
+    pub fn directory_breakdown(&self, query: &str) -> Result<Vec<DirectoryCount>> {
+        self.inverted_index.directory_breakdown(query)
+    }
+
+    /// Documents with content similar to doc_id, most similar first
+    pub fn find_similar(&self, doc_id: &str, limit: usize) -> Result<Vec<SimilarityResult>> {
+        find_similar_documents(doc_id, &self.inverted_index, limit)
+    }
+
+    /// Thumbnail of an indexed image as a JPEG data URL, for display without the asset protocol
+    /// Thumbnails that were never written (small images, or files indexed before previews
+    /// were enabled) are generated on demand
+    pub fn get_thumbnail(&self, doc_id: &str) -> Result<String> {
+        let info = self
+            .inverted_index
+            .stored_file_info(doc_id)?
+            .with_context(|| format!("Document {} not found", doc_id))?;
+
+        // The thumbnail made while indexing still works after previews are turned off or
+        // the original has gone; one is only generated here when none was stored, or the
+        // stored one is truncated or corrupt
+        let stored = info
+            .thumbnail_path
+            .filter(|path| ImagePreviewGenerator::is_valid_thumbnail(path));
+        let thumbnail_path = match stored {
+            Some(thumbnail_path) => thumbnail_path,
+            None => {
+                let image_preview = self
+                    .image_preview
+                    .read()
+                    .clone()
+                    .context("Image previews are disabled for this project")?;
+                if !image_preview.is_image(&info.path) {
+                    anyhow::bail!("Not a supported image: {}", info.path.display());
+                }
+                image_preview.ensure_thumbnail(&info.path)?
+            }
+        };
+        let bytes = std::fs::read(&thumbnail_path)
+            .with_context(|| format!("Failed to read thumbnail {}", thumbnail_path.display()))?;
+        Ok(format!("data:image/jpeg;base64,{}", BASE64.encode(bytes)))
+    }
+
+    /// Indexed terms of field starting with prefix, most common first
+    pub fn suggest(&self, prefix: &str, field: &str, limit: usize) -> Result<Vec<String>> {
+        self.inverted_index.suggest(prefix, field, limit)
+    }
+
+    /// Export the index to a portable SQLite database for use in other tools
+    pub fn export_to_sqlite(&self, output_path: &Path) -> Result<u64> {
+        self.inverted_index.export_to_sqlite(output_path)
     }
 
     /// Get index statistics
@@ -521,6 +1928,1182 @@ impl MasterIndexer {
             total_size: 0, // Would need to query index for this
             by_category: std::collections::HashMap::new(),
             duration_ms: 0,
+            skipped_by_filter: 0,
+            unavailable_files: Vec::new(),
+            errors: Vec::new(),
         })
     }
 }
+
+/// Owning user and group IDs, indexed so files can be attributed to an account
+#[cfg(unix)]
+fn file_owner(metadata: &std::fs::Metadata) -> (Option<u32>, Option<u32>) {
+    use std::os::unix::fs::MetadataExt;
+    (Some(metadata.uid()), Some(metadata.gid()))
+}
+
+#[cfg(not(unix))]
+fn file_owner(_metadata: &std::fs::Metadata) -> (Option<u32>, Option<u32>) {
+    (None, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::extractors::Extractor;
+    use crate::index::inverted::SearchFilter;
+    use crate::index::query::Query;
+    use tempfile::TempDir;
+
+    /// Extractor that hangs on files named "hang.txt"
+    struct SlowExtractor;
+
+    impl Extractor for SlowExtractor {
+        fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
+            if path.file_name().and_then(|n| n.to_str()) == Some("hang.txt") {
+                std::thread::sleep(Duration::from_secs(5));
+            }
+            Ok(ExtractorOutput {
+                structured: None,
+                content: Some("extracted".to_string()),
+                preview: "extracted".to_string(),
+                fields: HashMap::new(),
+            })
+        }
+
+        fn can_handle(&self, _category: FileCategory, _mime_type: &str) -> bool {
+            true
+        }
+
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+    }
+
+    /// Extractor that returns fixed content for every file
+    struct FixedExtractor(&'static str);
+
+    impl Extractor for FixedExtractor {
+        fn extract(&self, _path: &Path) -> Result<ExtractorOutput> {
+            Ok(ExtractorOutput {
+                structured: None,
+                content: Some(self.0.to_string()),
+                preview: self.0.to_string(),
+                fields: HashMap::new(),
+            })
+        }
+
+        fn can_handle(&self, _category: FileCategory, _mime_type: &str) -> bool {
+            true
+        }
+
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+    }
+
+    /// Extractor that counts how many files it was asked to extract
+    struct CountingExtractor(Arc<AtomicU64>);
+
+    impl Extractor for CountingExtractor {
+        fn extract(&self, path: &Path) -> Result<ExtractorOutput> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let content = std::fs::read_to_string(path)?;
+            Ok(ExtractorOutput {
+                structured: None,
+                preview: content.clone(),
+                content: Some(content),
+                fields: HashMap::new(),
+            })
+        }
+
+        fn can_handle(&self, _category: FileCategory, _mime_type: &str) -> bool {
+            true
+        }
+
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+    }
+
+    fn registry_with(extractor: impl Extractor + 'static) -> ExtractorRegistry {
+        let mut registry = ExtractorRegistry::empty();
+        registry.register(Box::new(extractor));
+        registry
+    }
+
+    #[test]
+    fn test_rebuild_reextracts_unchanged_files() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("notes.txt"), "original bytes").unwrap();
+        let index_dir = TempDir::new().unwrap();
+
+        {
+            let indexer = MasterIndexer::create(index_dir.path())
+                .unwrap()
+                .with_extractor_registry(registry_with(FixedExtractor("legacyextraction")));
+            indexer.index_directory(evidence.path()).unwrap();
+        }
+
+        let indexer = MasterIndexer::open(index_dir.path())
+            .unwrap()
+            .with_extractor_registry(registry_with(FixedExtractor("upgradedextraction")));
+
+        // Incremental indexing skips the unchanged file
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+        assert_eq!(stats.indexed_files, 0);
+
+        let stats = indexer.rebuild(evidence.path()).unwrap();
+        assert_eq!(stats.indexed_files, 1);
+
+        let search = |term: &str| indexer.inverted_index.search(term, 10).unwrap();
+        assert_eq!(search("upgradedextraction").len(), 1);
+        assert!(search("legacyextraction").is_empty());
+        assert_eq!(indexer.inverted_index.document_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_rebuild_index_uses_cached_files_only() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("notes.txt"), "original bytes").unwrap();
+        std::fs::write(evidence.path().join("gone.txt"), "deleted later").unwrap();
+        let index_dir = TempDir::new().unwrap();
+
+        {
+            let indexer = MasterIndexer::create(index_dir.path())
+                .unwrap()
+                .with_extractor_registry(registry_with(FixedExtractor("legacyextraction")));
+            indexer.index_directory(evidence.path()).unwrap();
+        }
+
+        // Neither change is seen, since the filesystem isn't scanned again
+        std::fs::remove_file(evidence.path().join("gone.txt")).unwrap();
+        std::fs::write(evidence.path().join("new.txt"), "added later").unwrap();
+
+        let indexer = MasterIndexer::open(index_dir.path())
+            .unwrap()
+            .with_extractor_registry(registry_with(FixedExtractor("upgradedextraction")));
+
+        let mut progress = Vec::new();
+        let stats = indexer
+            .rebuild_index_with_progress(&mut |p| progress.push(p))
+            .unwrap();
+        assert_eq!(stats.total_files, 1);
+        assert_eq!(stats.indexed_files, 1);
+
+        let last = progress.last().unwrap();
+        assert!(matches!(last.phase, IndexPhase::Complete));
+        assert_eq!(last.files_processed, 1);
+        assert_eq!(last.bytes_processed, "original bytes".len() as u64);
+
+        let search = |term: &str| indexer.inverted_index.search(term, 10).unwrap();
+        assert_eq!(search("upgradedextraction").len(), 1);
+        // The deleted file is skipped, so its document keeps the old extraction
+        assert_eq!(search("legacyextraction").len(), 1);
+    }
+
+    #[test]
+    fn test_rebuild_index_keeps_unreachable_files() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("notes.txt"), "on the drive").unwrap();
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path())
+            .unwrap()
+            .with_extractor_registry(registry_with(FixedExtractor("driveextraction")));
+        indexer.index_directory(evidence.path()).unwrap();
+
+        // Stands in for the evidence drive being unmounted
+        let notes = evidence.path().join("notes.txt");
+        std::fs::remove_file(&notes).unwrap();
+
+        let stats = indexer.rebuild_index_with_progress(&mut |_| {}).unwrap();
+        assert_eq!(stats.indexed_files, 0);
+        assert_eq!(stats.unavailable_files, vec![notes.clone()]);
+
+        assert_eq!(
+            indexer
+                .inverted_index
+                .search("driveextraction", 10)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(indexer
+            .change_detector
+            .lock()
+            .get_cached_state(&notes)
+            .is_some());
+    }
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, data) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_nested_archives_are_indexed_with_source() {
+        let scratch = TempDir::new().unwrap();
+        let inner_zip = scratch.path().join("inner.zip");
+        write_zip(&inner_zip, &[("secret.txt", b"hidden ledger")]);
+        let inner_bytes = std::fs::read(&inner_zip).unwrap();
+
+        let evidence = TempDir::new().unwrap();
+        let outer_zip = evidence.path().join("outer.zip");
+        write_zip(
+            &outer_zip,
+            &[("notes.txt", b"meeting notes"), ("inner.zip", &inner_bytes)],
+        );
+
+        let index_dir = TempDir::new().unwrap();
+        let archive_settings = ArchiveSettings {
+            unpack_to_host: false,
+            ..ArchiveSettings::default()
+        };
+        let indexer = MasterIndexer::create_with_settings(
+            index_dir.path(),
+            Some(archive_settings),
+            None,
+            None,
+        )
+        .unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let planner = indexer.query_planner();
+        let from_outer = planner
+            .execute(&Query::FromArchive {
+                archive_path: outer_zip.to_string_lossy().to_string(),
+            })
+            .unwrap();
+        assert_eq!(from_outer.total, 2);
+
+        let unpacked_inner = from_outer
+            .hits
+            .iter()
+            .find(|hit| hit.path.ends_with("inner.zip"))
+            .expect("inner archive should be indexed")
+            .path
+            .clone();
+        let from_inner = planner
+            .execute(&Query::FromArchive {
+                archive_path: unpacked_inner.to_string_lossy().to_string(),
+            })
+            .unwrap();
+        assert_eq!(from_inner.total, 1);
+        assert!(from_inner.hits[0].path.ends_with("secret.txt"));
+    }
+
+    #[test]
+    fn test_structured_catalog_groups_shared_tables() {
+        let evidence = TempDir::new().unwrap();
+        let whatsapp = evidence.path().join("msgstore.db");
+        let signal = evidence.path().join("signal.db");
+
+        let conn = rusqlite::Connection::open(&whatsapp).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE messages (id INTEGER, body TEXT); CREATE TABLE contacts (jid TEXT);",
+        )
+        .unwrap();
+        drop(conn);
+        let conn = rusqlite::Connection::open(&signal).unwrap();
+        conn.execute_batch("CREATE TABLE messages (id INTEGER, sent_at INTEGER);")
+            .unwrap();
+        drop(conn);
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let catalog = indexer.structured_catalog().unwrap();
+
+        let mut expected = vec![whatsapp.clone(), signal.clone()];
+        expected.sort();
+        assert_eq!(catalog.tables["messages"], expected);
+        assert_eq!(catalog.tables["contacts"], vec![whatsapp.clone()]);
+        assert_eq!(catalog.columns["id"], expected);
+        assert_eq!(catalog.columns["sent_at"], vec![signal]);
+    }
+
+    #[test]
+    fn test_chat_fields_are_searchable() {
+        let evidence = TempDir::new().unwrap();
+        let sms_table = "CREATE TABLE sms (_id INTEGER PRIMARY KEY, thread_id INTEGER,
+                 address TEXT, body TEXT, date_sent INTEGER);
+             INSERT INTO sms (thread_id, address, body, date_sent)
+                 VALUES (1, '+4915112345678', 'meet at the docks', 1700000000000);";
+        for (name, threads_table) in [("signal.db", "thread"), ("mmssms.db", "threads")] {
+            let conn = rusqlite::Connection::open(evidence.path().join(name)).unwrap();
+            conn.execute_batch(sms_table).unwrap();
+            conn.execute_batch(&format!("CREATE TABLE {} (_id INTEGER);", threads_table))
+                .unwrap();
+        }
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let search = |query: &str| indexer.inverted_index.search(query, 10).unwrap();
+        // Android's own SMS store has the same sms table, but isn't Signal
+        let signal = search("fields:\"app signal\"");
+        assert_eq!(signal.len(), 1);
+        assert!(signal[0].path.ends_with("signal.db"));
+        assert_eq!(search("fields:\"message_count 1\"").len(), 1);
+    }
+
+    #[test]
+    fn test_export_to_sqlite_writes_all_documents() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("a.txt"), "alpha").unwrap();
+        // Enough rows to need several multi-row INSERTs
+        for i in 0..150 {
+            std::fs::write(evidence.path().join(format!("note_{}.log", i)), "entry").unwrap();
+        }
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let output = index_dir.path().join("export.sqlite");
+        assert_eq!(indexer.export_to_sqlite(&output).unwrap(), 151);
+
+        let conn = rusqlite::Connection::open(&output).unwrap();
+        let rows: u64 = conn
+            .query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 151);
+        let (size, mime_type, modified, indexed_at): (u64, String, String, String) = conn
+            .query_row(
+                "SELECT size, mime_type, modified, indexed_at FROM files WHERE path LIKE '%a.txt'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(size, 5);
+        assert_eq!(mime_type, "text/plain");
+        assert!(chrono::DateTime::parse_from_rfc3339(&modified).is_ok());
+        assert!(chrono::DateTime::parse_from_rfc3339(&indexed_at).is_ok());
+
+        // Refuses to overwrite an existing file
+        assert!(indexer.export_to_sqlite(&output).is_err());
+    }
+
+    #[test]
+    fn test_extraction_timeout_records_error_and_continues() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("hang.txt"), "never finishes").unwrap();
+        std::fs::write(evidence.path().join("fine.txt"), "quick").unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let config = IndexingConfig {
+            extraction_timeout_ms: 200,
+            ..IndexingConfig::default()
+        };
+        let mut registry = ExtractorRegistry::empty();
+        registry.register(Box::new(SlowExtractor));
+
+        let indexer =
+            MasterIndexer::create_with_settings(index_dir.path(), None, None, Some(config))
+                .unwrap()
+                .with_extractor_registry(registry);
+
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+
+        assert_eq!(stats.indexed_files, 2);
+        assert_eq!(stats.errors.len(), 1);
+        assert!(stats.errors[0].path.ends_with("hang.txt"));
+        assert!(stats.errors[0].message.contains("timed out"));
+    }
+
+    #[test]
+    fn test_registry_timeout_returns_fallback_output() {
+        let evidence = TempDir::new().unwrap();
+        let path = evidence.path().join("hang.txt");
+        std::fs::write(&path, "never finishes").unwrap();
+
+        let mut registry =
+            ExtractorRegistry::empty().with_extraction_timeout(Duration::from_millis(100));
+        registry.register(Box::new(SlowExtractor));
+
+        let output = registry
+            .extract(&path, FileCategory::Text, "text/plain")
+            .unwrap();
+
+        assert!(output.timed_out());
+        assert_eq!(output.preview, "Extraction timed out");
+        assert_eq!(output.fields["extraction_error"], "timeout");
+        assert!(output.content.is_none());
+    }
+
+    #[test]
+    fn test_category_filter_indexes_only_selected_categories() {
+        let evidence = TempDir::new().unwrap();
+        let conn = rusqlite::Connection::open(evidence.path().join("contacts.db")).unwrap();
+        conn.execute_batch("CREATE TABLE contacts (name TEXT);")
+            .unwrap();
+        drop(conn);
+        std::fs::write(evidence.path().join("notes.txt"), "plain notes").unwrap();
+        std::fs::write(evidence.path().join("photo.png"), b"\x89PNG\r\n\x1a\nrest").unwrap();
+        // Archives are filtered before they are unpacked, so the database inside is not reached
+        let db_bytes = std::fs::read(evidence.path().join("contacts.db")).unwrap();
+        write_zip(
+            &evidence.path().join("backup.zip"),
+            &[("old_contacts.db", &db_bytes)],
+        );
+
+        let index_dir = TempDir::new().unwrap();
+        let config = IndexingConfig {
+            category_filter: Some(HashSet::from([FileCategory::Database])),
+            ..IndexingConfig::default()
+        };
+        let archive_settings = ArchiveSettings {
+            unpack_to_host: false,
+            ..ArchiveSettings::default()
+        };
+        let indexer = MasterIndexer::create_with_settings(
+            index_dir.path(),
+            Some(archive_settings),
+            None,
+            Some(config),
+        )
+        .unwrap();
+
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+
+        assert_eq!(stats.indexed_files, 1);
+        assert_eq!(stats.skipped_by_filter, 3);
+        assert_eq!(indexer.stats().unwrap().total_files, 1);
+        assert_eq!(
+            stats.by_category.keys().collect::<Vec<_>>(),
+            vec!["Database"]
+        );
+    }
+
+    #[test]
+    fn test_files_over_category_size_limit_are_indexed_without_content() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("small.txt"), "alpha").unwrap();
+        std::fs::write(evidence.path().join("large.txt"), "bravo ".repeat(100)).unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let config = IndexingConfig {
+            size_limits: SizeLimits {
+                default_max: u64::MAX,
+                per_category: HashMap::from([(FileCategory::Text, 64)]),
+            },
+            ..IndexingConfig::default()
+        };
+        let indexer =
+            MasterIndexer::create_with_settings(index_dir.path(), None, None, Some(config))
+                .unwrap();
+
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+
+        assert_eq!(stats.indexed_files, 2);
+        assert_eq!(indexer.inverted_index.search("alpha", 10).unwrap().len(), 1);
+        assert!(indexer
+            .inverted_index
+            .search("bravo", 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_modified_file_replaces_its_document() {
+        let evidence = TempDir::new().unwrap();
+        let file = evidence.path().join("ledger.txt");
+        std::fs::write(&file, "first draft").unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+        assert_eq!(indexer.inverted_index.document_count().unwrap(), 1);
+
+        // Change size and mtime so the change detector reports the file as modified
+        std::fs::write(&file, "second draft, rewritten").unwrap();
+        filetime::set_file_mtime(&file, filetime::FileTime::from_unix_time(2_000_000_000, 0))
+            .unwrap();
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+
+        assert_eq!(stats.indexed_files, 1);
+        assert_eq!(indexer.inverted_index.document_count().unwrap(), 1);
+        assert_eq!(
+            indexer
+                .inverted_index
+                .search("rewritten", 10)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(indexer
+            .inverted_index
+            .search("first", 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_gc_removes_orphaned_thumbnails_and_unpacked_dirs() {
+        let evidence = TempDir::new().unwrap();
+        let kept = evidence.path().join("kept.png");
+        let deleted = evidence.path().join("deleted.png");
+        for path in [&kept, &deleted] {
+            image::RgbImage::new(8, 8).save(path).unwrap();
+        }
+
+        let index_dir = TempDir::new().unwrap();
+        // Thumbnails are only made for images larger than thumbnail_size
+        let preview_config = PreviewConfig {
+            enabled: true,
+            thumbnail_size: 4,
+            ..PreviewConfig::default()
+        };
+        let indexer =
+            MasterIndexer::create_with_settings(index_dir.path(), None, Some(preview_config), None)
+                .unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let previews = index_dir.path().join("previews");
+        assert_eq!(std::fs::read_dir(&previews).unwrap().count(), 2);
+
+        let orphan_dir = index_dir
+            .path()
+            .join("unpacked_archives/old_0123456789abcdef");
+        std::fs::create_dir_all(&orphan_dir).unwrap();
+        std::fs::write(orphan_dir.join("leftover.txt"), "stale").unwrap();
+
+        indexer
+            .inverted_index
+            .delete_document(&MasterIndexer::make_doc_id(&deleted))
+            .unwrap();
+        indexer.inverted_index.commit().unwrap();
+
+        let stats = indexer.gc().unwrap();
+
+        assert_eq!(stats.removed_previews, 1);
+        assert_eq!(stats.removed_unpacked_dirs, 1);
+        assert!(stats.reclaimed_bytes >= 5);
+        let image_preview = indexer.image_preview.read().clone().unwrap();
+        assert!(image_preview.get_thumbnail_path(&kept).unwrap().exists());
+        assert!(!image_preview.get_thumbnail_path(&deleted).unwrap().exists());
+        assert!(!orphan_dir.exists());
+    }
+
+    #[test]
+    fn test_get_thumbnail_returns_decodable_data_url() {
+        let evidence = TempDir::new().unwrap();
+        let large = evidence.path().join("large.png");
+        let small = evidence.path().join("small.png");
+        image::RgbImage::from_pixel(32, 16, image::Rgb([200, 0, 0]))
+            .save(&large)
+            .unwrap();
+        image::RgbImage::from_pixel(4, 4, image::Rgb([0, 0, 200]))
+            .save(&small)
+            .unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let preview_config = PreviewConfig {
+            enabled: true,
+            thumbnail_size: 8,
+            ..PreviewConfig::default()
+        };
+        let indexer =
+            MasterIndexer::create_with_settings(index_dir.path(), None, Some(preview_config), None)
+                .unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let decode = |path: &Path| {
+            let url = indexer
+                .get_thumbnail(&MasterIndexer::make_doc_id(path))
+                .unwrap();
+            let encoded = url.strip_prefix("data:image/jpeg;base64,").unwrap();
+            image::load_from_memory(&BASE64.decode(encoded).unwrap()).unwrap()
+        };
+
+        let thumbnail = decode(&large);
+        assert_eq!((thumbnail.width(), thumbnail.height()), (8, 4));
+        // Too small to get a thumbnail while indexing, so one is made on request
+        assert_eq!(decode(&small).width(), 4);
+
+        assert!(indexer.get_thumbnail("missing").is_err());
+
+        // A truncated stored thumbnail is regenerated rather than served
+        let stored = indexer
+            .inverted_index
+            .stored_file_info(&MasterIndexer::make_doc_id(&large))
+            .unwrap()
+            .unwrap()
+            .thumbnail_path
+            .unwrap();
+        std::fs::write(&stored, b"\xFF\xD8").unwrap();
+        assert_eq!(decode(&large).width(), 8);
+        assert!(ImagePreviewGenerator::is_valid_thumbnail(&stored));
+
+        // The stored thumbnail is served without the original or the preview generator
+        std::fs::remove_file(&large).unwrap();
+        *indexer.image_preview.write() = None;
+        let thumbnail = decode(&large);
+        assert_eq!((thumbnail.width(), thumbnail.height()), (8, 4));
+    }
+
+    #[test]
+    fn test_renamed_image_flag_is_indexed() {
+        let evidence = TempDir::new().unwrap();
+        let mut jpeg = Vec::new();
+        image::RgbImage::from_pixel(16, 16, image::Rgb([10, 20, 30]))
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+        std::fs::write(evidence.path().join("evidence.dat"), &jpeg).unwrap();
+        std::fs::write(evidence.path().join("photo.jpg"), &jpeg).unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let preview_config = PreviewConfig {
+            enabled: true,
+            thumbnail_size: 8,
+            ..PreviewConfig::default()
+        };
+        let indexer =
+            MasterIndexer::create_with_settings(index_dir.path(), None, Some(preview_config), None)
+                .unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let hits = indexer
+            .inverted_index
+            .search("extension_mismatch:true", 10)
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].path.ends_with("evidence.dat"));
+    }
+
+    #[test]
+    fn test_export_files_copies_and_writes_manifest() {
+        let evidence = TempDir::new().unwrap();
+        let first = evidence.path().join("a/notes.txt");
+        let second = evidence.path().join("b/notes.txt");
+        for (path, content) in [(&first, "first copy"), (&second, "second copy")] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+            filetime::set_file_mtime(path, filetime::FileTime::from_unix_time(1_262_304_000, 0))
+                .unwrap();
+        }
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let doc_ids = vec![
+            MasterIndexer::make_doc_id(&first),
+            MasterIndexer::make_doc_id(&second),
+            "not-indexed".to_string(),
+        ];
+        let out = TempDir::new().unwrap();
+        let report = indexer.export_files(&doc_ids, out.path(), false).unwrap();
+
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].doc_id, "not-indexed");
+        for (file, source) in report.files.iter().zip([&first, &second]) {
+            assert_eq!(file.source_path, *source);
+            assert_eq!(file.hash, ChangeDetector::content_hashes(source).unwrap().0);
+            assert_eq!(
+                ChangeDetector::content_hashes(&file.exported_path)
+                    .unwrap()
+                    .0,
+                file.hash
+            );
+            assert!(file.matches_index);
+            assert_eq!(file.indexed_size, file.size);
+            assert_eq!(
+                std::fs::metadata(&file.exported_path)
+                    .unwrap()
+                    .modified()
+                    .unwrap(),
+                std::fs::metadata(source).unwrap().modified().unwrap()
+            );
+        }
+        // Same file name from two folders: the second is renamed rather than overwritten
+        assert_ne!(report.files[0].exported_path, report.files[1].exported_path);
+
+        let manifest: FileExportReport =
+            serde_json::from_slice(&std::fs::read(out.path().join(EXPORT_MANIFEST_NAME)).unwrap())
+                .unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.files[1].hash, report.files[1].hash);
+
+        let mirrored = TempDir::new().unwrap();
+        let report = indexer
+            .export_files(&doc_ids[..1], mirrored.path(), true)
+            .unwrap();
+        assert!(report.files[0].exported_path.ends_with("a/notes.txt"));
+        assert!(report.files[0].exported_path.starts_with(mirrored.path()));
+    }
+
+    #[test]
+    fn test_indexed_hash_matches_file_sha256() {
+        let evidence = TempDir::new().unwrap();
+        let file = evidence.path().join("statement.txt");
+        std::fs::write(&file, "opening balance 100").unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let indexed_hash = |export_name: &str| -> String {
+            let output = index_dir.path().join(export_name);
+            indexer.export_to_sqlite(&output).unwrap();
+            rusqlite::Connection::open(&output)
+                .unwrap()
+                .query_row("SELECT hash FROM files", [], |row| row.get(0))
+                .unwrap()
+        };
+        let sha256 = |content: &str| format!("{:x}", Sha256::digest(content.as_bytes()));
+
+        assert_eq!(indexed_hash("first.sqlite"), sha256("opening balance 100"));
+
+        // Changed behind the change detector's back: the cached hash is stale and not reused
+        std::fs::write(&file, "opening balance 9000").unwrap();
+        let other = IndexError {
+            path: evidence.path().join("other.bin"),
+            message: "recorded by another run".to_string(),
+        };
+        indexer.index_errors.lock().push(other);
+        assert!(indexer.retry_file(&file).unwrap().is_none());
+        // Errors recorded for other files are left alone
+        assert_eq!(indexer.index_errors.lock().len(), 1);
+
+        assert_eq!(
+            indexed_hash("second.sqlite"),
+            sha256("opening balance 9000")
+        );
+    }
+
+    #[test]
+    fn test_search_within_file_finds_rows_and_lines() {
+        let evidence = TempDir::new().unwrap();
+        let db_path = evidence.path().join("chat.db");
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE messages (id INTEGER, sender TEXT, body TEXT);
+             INSERT INTO messages VALUES (1, 'alice', 'Meet at the harbor');
+             INSERT INTO messages VALUES (2, 'bob', 'see you at noon');
+             INSERT INTO messages VALUES (3, 'carol', '100% sure');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let notes = evidence.path().join("notes.txt");
+        std::fs::write(&notes, "first line\nboat left the HARBOR\nlast line\n").unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+
+        let db_matches = indexer.search_within_file(&db_path, "harbor").unwrap();
+        assert_eq!(db_matches.len(), 1);
+        assert_eq!(db_matches[0].location, "table messages");
+        assert_eq!(
+            db_matches[0].context,
+            "id=1, sender=alice, body=Meet at the harbor"
+        );
+
+        // Schema matches come from sqlite_master; LIKE wildcards in the pattern are literal
+        let schema_matches = indexer.search_within_file(&db_path, "sender").unwrap();
+        assert_eq!(schema_matches[0].location, "schema messages");
+        assert_eq!(indexer.search_within_file(&db_path, "%").unwrap().len(), 1);
+
+        let text_matches = indexer.search_within_file(&notes, "harbor").unwrap();
+        assert_eq!(text_matches.len(), 1);
+        assert_eq!(text_matches[0].location, "line 2");
+        assert_eq!(
+            text_matches[0].context,
+            "first line\nboat left the HARBOR\nlast line"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_indexed_as_own_document() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("real.txt"), "quarterly figures").unwrap();
+        std::os::unix::fs::symlink("/etc/shadow", evidence.path().join("shortcut")).unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let config = IndexingConfig {
+            record_symlinks: true,
+            ..IndexingConfig::default()
+        };
+        let indexer =
+            MasterIndexer::create_with_settings(index_dir.path(), None, None, Some(config))
+                .unwrap();
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+        assert_eq!(stats.indexed_files, 2);
+
+        let output = index_dir.path().join("export.sqlite");
+        indexer.export_to_sqlite(&output).unwrap();
+        let (mime_type, content): (String, String) = rusqlite::Connection::open(&output)
+            .unwrap()
+            .query_row(
+                "SELECT mime_type, content FROM files WHERE path LIKE '%shortcut'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(mime_type, SYMLINK_MIME_TYPE);
+        assert_eq!(content, "/etc/shadow");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_followed_by_default() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("real.txt"), "quarterly figures").unwrap();
+        std::os::unix::fs::symlink(
+            evidence.path().join("real.txt"),
+            evidence.path().join("shortcut"),
+        )
+        .unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+        assert_eq!(stats.indexed_files, 2);
+
+        let hits = indexer.inverted_index.search("quarterly", 10).unwrap();
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[test]
+    fn test_lookup_by_keccak256() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("contract.bin"), "abc").unwrap();
+        std::fs::write(evidence.path().join("other.txt"), "unrelated").unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        // Keccak-256("abc"), written the way block explorers show it
+        let hit = indexer
+            .query_planner()
+            .lookup_keccak256("0x4E03657AEA45A94FC7D47BA826C8D667C0D1E6E33A64A036EC44F58FA12D6C45")
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.path, evidence.path().join("contract.bin"));
+
+        let missing = indexer
+            .query_planner()
+            .lookup_keccak256(&"0".repeat(64))
+            .unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn test_forensicsignore_excludes_and_reincludes_files() {
+        let evidence = TempDir::new().unwrap();
+        let root = evidence.path();
+        std::fs::write(
+            root.join(IGNORE_FILE_NAME),
+            "*.log\n!important.log\n/cache/\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("notes.txt"), "notes").unwrap();
+        std::fs::write(root.join("debug.log"), "noise").unwrap();
+        std::fs::write(root.join("important.log"), "keep me").unwrap();
+        std::fs::create_dir_all(root.join("cache")).unwrap();
+        std::fs::write(root.join("cache").join("blob.txt"), "cached").unwrap();
+        std::fs::create_dir_all(root.join("logs").join("cache")).unwrap();
+        std::fs::write(root.join("logs").join("trace.log"), "noise").unwrap();
+        std::fs::write(root.join("logs").join("important.log"), "keep").unwrap();
+        std::fs::write(root.join("logs").join("cache").join("kept.txt"), "nested").unwrap();
+        std::fs::write(root.join("scratch.tmp"), "scratch").unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let config = IndexingConfig {
+            exclude_patterns: vec!["*.tmp".to_string()],
+            ..IndexingConfig::default()
+        };
+        let indexer =
+            MasterIndexer::create_with_settings(index_dir.path(), None, None, Some(config))
+                .unwrap();
+        indexer.index_directory(root).unwrap();
+
+        let mut indexed: Vec<PathBuf> = indexer
+            .inverted_index
+            .document_paths()
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        indexed.sort();
+
+        // The anchored /cache/ pattern only matches at the root
+        let expected: Vec<PathBuf> = [
+            "important.log",
+            "logs/cache/kept.txt",
+            "logs/important.log",
+            "notes.txt",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        assert_eq!(indexed, expected);
+    }
+
+    #[test]
+    fn test_priority_files_are_committed_first() {
+        let evidence = TempDir::new().unwrap();
+        let root = evidence.path();
+        std::fs::create_dir_all(root.join("home/Recent")).unwrap();
+        for i in 0..5 {
+            std::fs::write(root.join(format!("bulk_{i}.txt")), "bulk").unwrap();
+        }
+        std::fs::write(root.join("home/.bash_history"), "ssh evil.example").unwrap();
+        std::fs::write(root.join("home/Recent/invoice.txt"), "opened yesterday").unwrap();
+
+        let index_dir = TempDir::new().unwrap();
+        let config = IndexingConfig {
+            batch_size: 2,
+            commit_interval_ms: 0,
+            priority_patterns: vec![".bash_history".to_string(), "Recent/".to_string()],
+            ..IndexingConfig::default()
+        };
+        let indexer =
+            MasterIndexer::create_with_settings(index_dir.path(), None, None, Some(config))
+                .unwrap();
+
+        let mut batches = Vec::new();
+        indexer
+            .index_directory_streaming(root, &mut |batch| {
+                // Each batch is already searchable when it is reported
+                let indexed = indexer.inverted_index.document_count()?;
+                batches.push((batch, indexed));
+                Ok(())
+            })
+            .unwrap();
+
+        let (first, indexed) = &batches[0];
+        assert!(first.priority);
+        assert_eq!(*indexed, 2);
+        let mut names: Vec<_> = first
+            .paths
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, [".bash_history", "invoice.txt"]);
+
+        assert!(batches[1..].iter().all(|(batch, _)| !batch.priority));
+        assert_eq!(batches.last().unwrap().1, 7);
+    }
+
+    #[test]
+    fn test_archive_and_preview_settings_survive_reopen() {
+        let index_dir = TempDir::new().unwrap();
+
+        {
+            let indexer =
+                MasterIndexer::open_or_create_with_saved_settings(index_dir.path()).unwrap();
+            // Defaults apply until something is saved
+            assert!(indexer.archive_extractor.read().is_some());
+            assert!(indexer.image_preview.read().is_none());
+
+            indexer
+                .update_archive_settings(ArchiveSettings {
+                    auto_unpack: false,
+                    ..ArchiveSettings::default()
+                })
+                .unwrap();
+            indexer
+                .update_preview_config(PreviewConfig {
+                    enabled: true,
+                    thumbnail_size: 64,
+                    ..PreviewConfig::default()
+                })
+                .unwrap();
+            assert!(indexer.archive_extractor.read().is_none());
+        }
+
+        let indexer = MasterIndexer::open_or_create_with_saved_settings(index_dir.path()).unwrap();
+        assert!(indexer.archive_extractor.read().is_none());
+        assert!(indexer.image_preview.read().is_some());
+        let auxiliary_db = indexer.get_auxiliary_db();
+        assert!(!auxiliary_db.load_archive_settings().unwrap().auto_unpack);
+        assert_eq!(
+            auxiliary_db.load_preview_config().unwrap().thumbnail_size,
+            64
+        );
+    }
+
+    #[test]
+    fn test_identical_files_are_extracted_once() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("original.txt"), "quarterly ledger").unwrap();
+        std::fs::create_dir(evidence.path().join("backup")).unwrap();
+        std::fs::write(evidence.path().join("backup/copy.txt"), "quarterly ledger").unwrap();
+        std::fs::write(evidence.path().join("other.txt"), "unrelated notes").unwrap();
+
+        let extractions = Arc::new(AtomicU64::new(0));
+        let index_dir = TempDir::new().unwrap();
+        let config = IndexingConfig {
+            skip_duplicate_extraction: true,
+            ..IndexingConfig::default()
+        };
+        let indexer =
+            MasterIndexer::create_with_settings(index_dir.path(), None, None, Some(config))
+                .unwrap()
+                .with_extractor_registry(registry_with(CountingExtractor(extractions.clone())));
+
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+
+        assert_eq!(stats.indexed_files, 3);
+        assert_eq!(extractions.load(Ordering::SeqCst), 2);
+        assert_eq!(indexer.inverted_index.document_count().unwrap(), 3);
+
+        // Every copy carries the content, so each is found by a full-text search
+        let hits = indexer.inverted_index.search("ledger", 10).unwrap();
+        assert_eq!(hits.len(), 2);
+
+        // A copy added later reuses the committed content rather than re-extracting
+        std::fs::write(evidence.path().join("late_copy.txt"), "quarterly ledger").unwrap();
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+        assert_eq!(stats.indexed_files, 1);
+        assert_eq!(extractions.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            indexer.inverted_index.search("ledger", 10).unwrap().len(),
+            3
+        );
+
+        // Exact copies have the same terms, so they are found as fully similar
+        let original = evidence.path().join("original.txt");
+        let similar = indexer
+            .find_similar(&MasterIndexer::make_doc_id(&original), 10)
+            .unwrap();
+        assert_eq!(similar.len(), 2);
+        assert!(similar.iter().all(|r| (r.similarity - 1.0).abs() < 1e-4));
+
+        // The copies keep their content when the first one is deleted
+        indexer
+            .inverted_index
+            .delete_document(&MasterIndexer::make_doc_id(&original))
+            .unwrap();
+        indexer.inverted_index.commit().unwrap();
+        assert_eq!(
+            indexer.inverted_index.search("ledger", 10).unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_identical_files_are_extracted_separately_by_default() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("original.txt"), "quarterly ledger").unwrap();
+        std::fs::write(evidence.path().join("copy.txt"), "quarterly ledger").unwrap();
+
+        let extractions = Arc::new(AtomicU64::new(0));
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path())
+            .unwrap()
+            .with_extractor_registry(registry_with(CountingExtractor(extractions.clone())));
+
+        indexer.index_directory(evidence.path()).unwrap();
+        assert_eq!(extractions.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_empty_files_are_indexed_without_extraction() {
+        let evidence = TempDir::new().unwrap();
+        std::fs::write(evidence.path().join("config.json"), "").unwrap();
+        std::fs::write(evidence.path().join("export.csv"), "").unwrap();
+        std::fs::write(evidence.path().join("notes.txt"), "meeting moved").unwrap();
+
+        let extractions = Arc::new(AtomicU64::new(0));
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path())
+            .unwrap()
+            .with_extractor_registry(registry_with(CountingExtractor(extractions.clone())));
+
+        let stats = indexer.index_directory(evidence.path()).unwrap();
+
+        assert_eq!(stats.indexed_files, 3);
+        assert!(stats.errors.is_empty());
+        assert_eq!(extractions.load(Ordering::SeqCst), 1);
+
+        let doc = indexer
+            .index_file(&evidence.path().join("config.json"), true)
+            .unwrap()
+            .unwrap();
+        assert!(doc.metadata.empty);
+        assert_eq!(doc.metadata.size, 0);
+        assert!(doc.content.is_none());
+        assert_eq!(extractions.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_files_modified_between_are_listed_earliest_first() {
+        let evidence = TempDir::new().unwrap();
+        let set_mtime = |name: &str, unix_time: i64| {
+            let path = evidence.path().join(name);
+            std::fs::write(&path, name).unwrap();
+            filetime::set_file_mtime(&path, filetime::FileTime::from_unix_time(unix_time, 0))
+                .unwrap();
+        };
+        set_mtime("march.txt", 1_709_251_200); // 2024-03-01
+        set_mtime("january.txt", 1_704_067_200); // 2024-01-01
+        set_mtime("february.txt", 1_706_745_600); // 2024-02-01
+        set_mtime("old.txt", 1_262_304_000); // 2010-01-01
+
+        let index_dir = TempDir::new().unwrap();
+        let indexer = MasterIndexer::create(index_dir.path()).unwrap();
+        indexer.index_directory(evidence.path()).unwrap();
+
+        let date = |unix_time: i64| DateTime::from_timestamp(unix_time, 0).unwrap();
+        let names = |hits: &[TypedHit]| -> Vec<String> {
+            hits.iter()
+                .map(|hit| hit.path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let hits = indexer
+            .get_files_modified_between(date(1_704_067_200), date(1_709_251_200), 10)
+            .unwrap();
+        assert_eq!(
+            names(&hits),
+            vec!["january.txt", "february.txt", "march.txt"]
+        );
+
+        let hits = indexer
+            .get_files_modified_between(date(1_704_067_200), date(1_709_251_200), 1)
+            .unwrap();
+        assert_eq!(names(&hits), vec!["january.txt"]);
+
+        // The range is part of the search, so the limit only counts files within it
+        let result = indexer
+            .query_planner()
+            .execute_filtered(
+                &Query::FullText {
+                    query: "txt".to_string(),
+                    limit: Some(2),
+                    boost_config: None,
+                    fields: None,
+                },
+                &SearchFilter {
+                    modified_before: Some(date(1_704_067_200)),
+                    ..SearchFilter::default()
+                },
+            )
+            .unwrap();
+        let mut remaining = names(&result.hits);
+        remaining.sort();
+        assert_eq!(remaining, vec!["january.txt", "old.txt"]);
+        assert_eq!(result.total, 2);
+    }
+}
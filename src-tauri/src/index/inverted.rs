@@ -1,20 +1,309 @@
-use super::schema::{DocumentMetadata, FileCategory, FileDocument, TypedHit};
+use super::schema::{
+    DocumentMetadata, FileCategory, FileDocument, HitMetadata, StructuredCatalog, TypedHit, XmlPath,
+};
+use crate::io::atomic_write_sync;
+use crate::io::fs::atomic_temp_path;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value as SqlValue;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::ops::Bound;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tantivy::collector::TopDocs;
+use tantivy::collector::{DocSetCollector, TopDocs};
 use tantivy::directory::MmapDirectory;
-use tantivy::query::QueryParser;
+use tantivy::query::{
+    AllQuery, BooleanQuery, MoreLikeThisQuery, Occur, Query, QueryParser, RangeQuery, RegexQuery,
+    TermQuery, TermSetQuery,
+};
 use tantivy::schema::*;
-use tantivy::{doc, Index, IndexWriter, Searcher, TantivyDocument};
+use tantivy::tokenizer::TokenStream;
+use tantivy::{
+    doc, DocAddress, Index, IndexReader, IndexWriter, Order, ReloadPolicy, Searcher,
+    TantivyDocument, TantivyError,
+};
+use thiserror::Error;
+
+/// Advisory lock file held in the index directory while an index is open
+const LOCK_FILE_NAME: &str = "detective.lock";
+
+/// Tantivy's own writer lock, which can outlive a crashed or force-quit app
+const WRITER_LOCK_FILE_NAME: &str = ".tantivy-writer.lock";
+
+/// Tantivy's record of the committed segments; written last so a partial copy never opens
+const META_FILE_NAME: &str = "meta.json";
+
+/// Tantivy's list of the files it manages and may garbage-collect
+const MANAGED_FILE_NAME: &str = ".managed.json";
+
+/// Scratch file written when an index is created, to find an unusable volume up front
+const WRITE_PROBE_FILE_NAME: &str = ".write-probe";
+
+/// One filesystem block, so a full volume fails the probe rather than buffering it
+const WRITE_PROBE_LEN: usize = 4096;
+
+/// Background merges can delete a segment mid-copy, so a backup is retried a few times
+const BACKUP_ATTEMPTS: usize = 3;
+
+/// Version of the layout built by `build_schema`; bump it whenever a field is added or changed.
+/// Indexes written before the version was recorded count as version 1
+pub const SCHEMA_VERSION: u32 = 8;
+
+/// Commit payload prefix that carries the schema version in tantivy's meta.json
+const SCHEMA_VERSION_PAYLOAD: &str = "detective-schema-v";
+
+/// Fields describing a path rather than its content, taken from the alias that replaces
+/// a deleted de-duplicated document
+const PATH_FIELDS: &[&str] = &[
+    "id",
+    "path",
+    "parent_dir",
+    "modified",
+    "created",
+    "indexed_at",
+    "extension",
+    "uid",
+    "gid",
+    "acl",
+    "archive_path",
+];
+
+/// Stored documents loaded per batch by `export_to_sqlite`
+const EXPORT_BATCH_ROWS: usize = 1000;
+
+/// Columns of the exported `files` table, in order
+const EXPORT_COLUMNS: &[&str] = &[
+    "id",
+    "path",
+    "size",
+    "modified",
+    "created",
+    "indexed_at",
+    "hash",
+    "mime_type",
+    "category",
+    "extension",
+    "magic_header",
+    "archive_path",
+    "preview",
+    "content",
+];
+
+/// The index directory is already open in another instance of the app
+#[derive(Debug, Error)]
+#[error("Index at {} is already open in another instance", path.display())]
+pub struct IndexLocked {
+    pub path: PathBuf,
+}
+
+/// A write was attempted through a handle opened with `InvertedIndex::open_readonly`
+#[derive(Debug, Error)]
+#[error("Index at {} was opened read-only", path.display())]
+pub struct IndexReadOnly {
+    pub path: PathBuf,
+}
+
+/// The index on disk was built with a different schema and has to be rebuilt before use
+#[derive(Debug, Error)]
+#[error(
+    "Index at {} uses schema version {found}, this version of the app needs {expected}; re-index required",
+    path.display()
+)]
+pub struct IndexSchemaOutdated {
+    pub path: PathBuf,
+    pub found: u32,
+    pub expected: u32,
+}
+
+/// Why the index directory can't be written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageUnavailableReason {
+    /// The volume is mounted read-only, or the user may not write there
+    ReadOnly,
+    /// The volume (or the user's quota on it) has no space left
+    DiskFull,
+}
+
+impl std::fmt::Display for StorageUnavailableReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ReadOnly => write!(f, "the location is read-only or not writable by this user"),
+            Self::DiskFull => write!(f, "the volume is out of space"),
+        }
+    }
+}
+
+/// The index directory can't be created or written to; choosing another location is the fix
+#[derive(Debug, Error)]
+#[error("Cannot store the index at {}: {reason}", path.display())]
+pub struct IndexStorageUnavailable {
+    pub path: PathBuf,
+    pub reason: StorageUnavailableReason,
+}
+
+impl IndexStorageUnavailable {
+    /// Classify an IO error hit while writing at path; None if it isn't a storage problem
+    pub fn from_io(path: &Path, error: &std::io::Error) -> Option<Self> {
+        let reason = match error.kind() {
+            std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem => {
+                StorageUnavailableReason::ReadOnly
+            }
+            std::io::ErrorKind::StorageFull | std::io::ErrorKind::QuotaExceeded => {
+                StorageUnavailableReason::DiskFull
+            }
+            _ => return None,
+        };
+        Some(Self {
+            path: path.to_path_buf(),
+            reason,
+        })
+    }
+}
+
+/// Path with `\` separators replaced by `/`, so Windows and Unix paths compare alike
+pub fn normalize_path_separators(path: &str) -> String {
+    path.replace('\\', "/")
+}
 
 /// Inverted index using Tantivy
 /// Provides lightning-fast full-text search and filtering
 pub struct InvertedIndex {
     index: Index,
     schema: Schema,
-    writer: Arc<parking_lot::Mutex<IndexWriter>>,
+    /// None for read-only handles
+    writer: Option<Arc<parking_lot::Mutex<IndexWriter>>>,
+    /// Reloaded on commit, or by `reload` to pick up commits made by another handle
+    reader: IndexReader,
+    /// Store files with identical content once, recording the other paths as aliases
+    deduplicate: bool,
+    /// Copies of each hash touched since the last commit (not yet visible to searchers)
+    pending_copies: parking_lot::Mutex<HashMap<String, ContentCopies>>,
+    /// Directory holding the index files
+    index_dir: PathBuf,
+    /// Single-writer guard; the lock is released when the index is dropped
+    /// Read-only handles don't take it
+    _lock: Option<File>,
+}
+
+/// Per-field score multipliers applied to full-text queries
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryBoost {
+    pub path: f32,
+    pub preview: f32,
+    pub content: f32,
+    pub tables: f32,
+    pub columns: f32,
+}
+
+impl QueryBoost {
+    /// Favour file names and schema names over raw content matches
+    pub fn forensic_defaults() -> Self {
+        Self {
+            path: 3.0,
+            preview: 1.5,
+            content: 1.0,
+            tables: 2.0,
+            columns: 2.0,
+        }
+    }
+}
+
+impl Default for QueryBoost {
+    fn default() -> Self {
+        Self::forensic_defaults()
+    }
+}
+
+/// Restrictions added to a search's query, so they hold before its limit is applied
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    /// Only documents with these IDs
+    pub doc_ids: Option<HashSet<String>>,
+    /// Only documents whose path starts with this, whichever separator either side uses
+    pub path_prefix: Option<String>,
+    /// Only documents modified at or after this
+    pub modified_after: Option<DateTime<Utc>>,
+    /// Only documents modified at or before this
+    pub modified_before: Option<DateTime<Utc>>,
+}
+
+impl SearchFilter {
+    /// Also require one of ids, on top of any IDs already required
+    pub fn restrict_to_ids(mut self, ids: HashSet<String>) -> Self {
+        self.doc_ids = Some(match self.doc_ids {
+            Some(existing) => existing.intersection(&ids).cloned().collect(),
+            None => ids,
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doc_ids.is_none()
+            && self.path_prefix.is_none()
+            && self.modified_after.is_none()
+            && self.modified_before.is_none()
+    }
+}
+
+/// Order of size-range results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeSort {
+    Ascending,
+    /// Largest first, usually what an investigator wants to see
+    #[default]
+    Descending,
+}
+
+/// Documents sharing one content hash when de-duplicating: the one holding the content
+/// and an alias document per other path, which has the path's metadata but no content
+#[derive(Debug, Clone, Default)]
+struct ContentCopies {
+    canonical: Option<TantivyDocument>,
+    aliases: Vec<TantivyDocument>,
+}
+
+/// Where an indexed file lives and what it looked like when indexed
+#[derive(Debug, Clone)]
+pub struct StoredFileInfo {
+    pub path: PathBuf,
+    pub hash: String,
+    pub size: u64,
+    pub thumbnail_path: Option<PathBuf>,
+}
+
+/// The committed documents sharing one content hash
+#[derive(Debug, Clone)]
+pub struct ContentCopyIds {
+    /// (id, path) of the document holding the content
+    pub canonical: (String, PathBuf),
+    /// (id, path) of the de-duplicated aliases, which hold no content of their own
+    pub aliases: Vec<(String, PathBuf)>,
+}
+
+/// Full-text fields of an indexed document, as stored
+#[derive(Debug, Clone)]
+pub struct StoredText {
+    pub content: Option<String>,
+    pub preview: Option<String>,
+}
+
+/// Fields of a document needed to count it per folder
+struct FastPathEntry {
+    path: String,
+    folder: String,
+    hash: String,
+    alias: bool,
+}
+
+/// Number of matching files in one folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryCount {
+    pub path: PathBuf,
+    pub count: u64,
 }
 
 /// Search hit result
@@ -25,18 +314,28 @@ pub struct SearchHit {
     pub category: FileCategory,
     pub snippet: String,
     pub score: f32,
+    /// Other paths with identical content, when de-duplication is enabled
+    #[serde(default)]
+    pub aliases: Vec<PathBuf>,
+    /// Indexed size and modification time
+    #[serde(default)]
+    pub metadata: Option<HitMetadata>,
 }
 
 impl InvertedIndex {
     /// Create a new inverted index at the specified path
     pub fn create(index_dir: &Path) -> Result<Self> {
-        std::fs::create_dir_all(index_dir)?;
+        Self::ensure_writable(index_dir)?;
+        let lock = Self::acquire_lock(index_dir)?;
 
         // Build schema
         let schema = Self::build_schema();
 
-        // Create index
+        // Create index, refusing to reuse one left behind by an older schema
         let dir = MmapDirectory::open(index_dir).context("Failed to open index directory")?;
+        if Index::exists(&dir)? {
+            Self::check_schema(&Index::open(dir.clone())?, index_dir)?;
+        }
         let index = Index::open_or_create(dir, schema.clone()).context("Failed to create index")?;
 
         // Create writer with 128MB heap
@@ -45,9 +344,14 @@ impl InvertedIndex {
             .context("Failed to create index writer")?;
 
         Ok(Self {
+            reader: Self::build_reader(&index)?,
             index,
             schema,
-            writer: Arc::new(parking_lot::Mutex::new(writer)),
+            writer: Some(Arc::new(parking_lot::Mutex::new(writer))),
+            deduplicate: false,
+            pending_copies: parking_lot::Mutex::new(HashMap::new()),
+            index_dir: index_dir.to_path_buf(),
+            _lock: Some(lock),
         })
     }
 
@@ -55,199 +359,1782 @@ impl InvertedIndex {
     pub fn open(index_dir: &Path) -> Result<Self> {
         let schema = Self::build_schema();
         let dir = MmapDirectory::open(index_dir).context("Failed to open index directory")?;
+        let lock = Self::acquire_lock(index_dir)?;
         let index = Index::open(dir).context("Failed to open index")?;
+        Self::check_schema(&index, index_dir)?;
 
         let writer = index
             .writer(128_000_000)
             .context("Failed to create index writer")?;
 
         Ok(Self {
+            reader: Self::build_reader(&index)?,
+            index,
+            schema,
+            writer: Some(Arc::new(parking_lot::Mutex::new(writer))),
+            deduplicate: false,
+            pending_copies: parking_lot::Mutex::new(HashMap::new()),
+            index_dir: index_dir.to_path_buf(),
+            _lock: Some(lock),
+        })
+    }
+
+    /// Open an existing index for searching only
+    /// No writer is created and no lock is taken, so this works while another handle is
+    /// indexing into the same directory; call `reload` to see what it has committed since
+    pub fn open_readonly(index_dir: &Path) -> Result<Self> {
+        let schema = Self::build_schema();
+        let dir = MmapDirectory::open(index_dir).context("Failed to open index directory")?;
+        let index = Index::open(dir).context("Failed to open index")?;
+        Self::check_schema(&index, index_dir)?;
+
+        Ok(Self {
+            reader: Self::build_reader(&index)?,
             index,
             schema,
-            writer: Arc::new(parking_lot::Mutex::new(writer)),
+            writer: None,
+            deduplicate: false,
+            pending_copies: parking_lot::Mutex::new(HashMap::new()),
+            index_dir: index_dir.to_path_buf(),
+            _lock: None,
+        })
+    }
+
+    /// Fail with IndexSchemaOutdated unless the index was built with the current schema.
+    /// A fresh index has no commit payload yet, so the stored schema itself decides
+    fn check_schema(index: &Index, index_dir: &Path) -> Result<()> {
+        let found = index
+            .load_metas()
+            .context("Failed to read index metadata")?
+            .payload
+            .as_deref()
+            .and_then(|payload| payload.strip_prefix(SCHEMA_VERSION_PAYLOAD))
+            .and_then(|version| version.parse::<u32>().ok());
+
+        if found.is_some_and(|version| version != SCHEMA_VERSION)
+            || index.schema() != Self::build_schema()
+        {
+            return Err(IndexSchemaOutdated {
+                path: index_dir.to_path_buf(),
+                found: found.unwrap_or(1),
+                expected: SCHEMA_VERSION,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Reader that only moves to newer commits when told to
+    fn build_reader(index: &Index) -> Result<IndexReader> {
+        index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .context("Failed to create index reader")
+    }
+
+    /// Make everything committed so far, by this or any other handle, visible to searches
+    pub fn reload(&self) -> Result<()> {
+        self.reader
+            .reload()
+            .context("Failed to reload index reader")
+    }
+
+    /// Whether the index was opened with `open_readonly`
+    pub fn is_read_only(&self) -> bool {
+        self.writer.is_none()
+    }
+
+    /// The index writer, or IndexReadOnly for read-only handles
+    fn writer(&self) -> Result<parking_lot::MutexGuard<'_, IndexWriter>> {
+        match &self.writer {
+            Some(writer) => Ok(writer.lock()),
+            None => Err(IndexReadOnly {
+                path: self.index_dir.clone(),
+            }
+            .into()),
+        }
+    }
+
+    /// Open an existing index, clearing a writer lock left behind by a crash
+    /// The lock file is only removed once no other process is found holding it
+    pub fn open_force(index_dir: &Path) -> Result<Self> {
+        match Self::open(index_dir) {
+            Err(e) if Self::is_writer_lock_error(&e) => {
+                Self::remove_stale_writer_lock(index_dir)?;
+                Self::open(index_dir)
+            }
+            result => result,
+        }
+    }
+
+    fn is_writer_lock_error(error: &anyhow::Error) -> bool {
+        error.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<TantivyError>(),
+                Some(TantivyError::LockFailure(..))
+            )
         })
     }
 
+    /// Delete the Tantivy writer lock if nothing holds it
+    fn remove_stale_writer_lock(index_dir: &Path) -> Result<()> {
+        // Another instance of the app would hold the directory lock as well
+        let _lock = Self::acquire_lock(index_dir)?;
+
+        let lock_path = index_dir.join(WRITER_LOCK_FILE_NAME);
+        let file = OpenOptions::new()
+            .write(true)
+            .open(&lock_path)
+            .context("Failed to open index writer lock")?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                anyhow::bail!(
+                    "Index writer lock {} is held by another process",
+                    lock_path.display()
+                )
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).context("Failed to check index writer lock");
+            }
+        }
+        drop(file);
+
+        std::fs::remove_file(&lock_path).context("Failed to remove stale index writer lock")?;
+        log::warn!("Removed stale index writer lock {}", lock_path.display());
+        Ok(())
+    }
+
+    /// Copy the committed index to backup_dir, replacing any earlier backup there
+    /// Uncommitted documents are not included. Files are copied under temporary names and
+    /// renamed into place, meta.json last, so an interrupted backup is never mistaken for one
+    pub fn backup(&self, backup_dir: &Path) -> Result<()> {
+        // Holding the writer keeps commits out while the files are copied
+        let _writer = self.writer.as_ref().map(|writer| writer.lock());
+
+        let mut attempt = 1;
+        loop {
+            match self.copy_committed_files(backup_dir) {
+                Err(e) if attempt < BACKUP_ATTEMPTS && Self::is_not_found(&e) => attempt += 1,
+                result => return result.context("Failed to back up index"),
+            }
+        }
+    }
+
+    /// Copy the segment files of the last commit, then the metadata that refers to them
+    fn copy_committed_files(&self, backup_dir: &Path) -> Result<()> {
+        let metas = self.index.load_metas()?;
+        let files: BTreeSet<PathBuf> = metas
+            .segments
+            .iter()
+            .flat_map(|segment| {
+                // The delete file is only listed meaningfully once a segment has deletes
+                let has_deletes = segment.has_deletes();
+                segment.list_files().into_iter().filter(move |file| {
+                    has_deletes || file.extension().is_none_or(|ext| ext != "del")
+                })
+            })
+            .collect();
+
+        std::fs::create_dir_all(backup_dir)?;
+        for file in &files {
+            Self::copy_atomically(&self.index_dir.join(file), &backup_dir.join(file))?;
+        }
+        atomic_write_sync(
+            &backup_dir.join(MANAGED_FILE_NAME),
+            &serde_json::to_vec(&files)?,
+        )?;
+        atomic_write_sync(
+            &backup_dir.join(META_FILE_NAME),
+            serde_json::to_string_pretty(&metas)?.as_bytes(),
+        )?;
+
+        // Segments left over from an earlier backup are no longer referenced
+        for entry in std::fs::read_dir(backup_dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name != META_FILE_NAME
+                && name != MANAGED_FILE_NAME
+                && !files.contains(Path::new(&name))
+                && entry.file_type()?.is_file()
+            {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replace the index in index_dir with a backup made by backup() and open it
+    /// The index must not be open anywhere; everything else in index_dir is discarded
+    /// The backup is copied and checked in a sibling directory first, so a failed restore
+    /// leaves the live index as it was
+    pub fn restore_from_backup(backup_dir: &Path, index_dir: &Path) -> Result<Self> {
+        if !backup_dir.join(META_FILE_NAME).is_file() {
+            anyhow::bail!("No index backup found in {}", backup_dir.display());
+        }
+
+        std::fs::create_dir_all(index_dir)?;
+        // Only checks that nothing has the index open; Windows can't rename a directory
+        // while a handle inside it is open, so the lock is released before the swap
+        drop(Self::acquire_lock(index_dir)?);
+
+        let staging_dir = atomic_temp_path(index_dir);
+        if let Err(e) = Self::stage_backup(backup_dir, &staging_dir) {
+            let _ = std::fs::remove_dir_all(&staging_dir);
+            return Err(e);
+        }
+
+        let retired_dir = atomic_temp_path(index_dir);
+        if let Err(e) = std::fs::rename(index_dir, &retired_dir) {
+            let _ = std::fs::remove_dir_all(&staging_dir);
+            return Err(e).context("Failed to move the current index aside");
+        }
+        if let Err(e) = std::fs::rename(&staging_dir, index_dir) {
+            let _ = std::fs::rename(&retired_dir, index_dir);
+            let _ = std::fs::remove_dir_all(&staging_dir);
+            return Err(e).context("Failed to move the restored index into place");
+        }
+        let _ = std::fs::remove_dir_all(&retired_dir);
+
+        Self::open(index_dir).context("Failed to open restored index")
+    }
+
+    /// Copy a backup into staging_dir and check that it opens with the current schema
+    fn stage_backup(backup_dir: &Path, staging_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(staging_dir)?;
+
+        // Leftovers of an interrupted backup end in .tmp and are skipped
+        for entry in std::fs::read_dir(backup_dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if entry.file_type()?.is_file() && !name.to_string_lossy().ends_with(".tmp") {
+                std::fs::copy(entry.path(), staging_dir.join(&name))?;
+            }
+        }
+
+        let index = Index::open(MmapDirectory::open(staging_dir)?)
+            .context("Index backup could not be opened")?;
+        Self::check_schema(&index, staging_dir)
+    }
+
+    /// Copy under a temporary name next to the destination, then rename into place
+    fn copy_atomically(from: &Path, to: &Path) -> Result<()> {
+        let temp_path = atomic_temp_path(to);
+        if let Err(e) = std::fs::copy(from, &temp_path) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
+        std::fs::rename(&temp_path, to)?;
+        Ok(())
+    }
+
+    fn is_not_found(error: &anyhow::Error) -> bool {
+        error
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+    }
+
+    /// Create index_dir if needed and check that a file can be written and synced there
+    /// Fails with IndexStorageUnavailable on a read-only or full volume, which Tantivy
+    /// would otherwise only report as an opaque error halfway through creating the index
+    pub fn ensure_writable(index_dir: &Path) -> Result<()> {
+        let storage_error =
+            |e: std::io::Error| match IndexStorageUnavailable::from_io(index_dir, &e) {
+                Some(unavailable) => anyhow::Error::new(unavailable),
+                None => anyhow::Error::new(e).context(format!(
+                    "Failed to prepare index directory {}",
+                    index_dir.display()
+                )),
+            };
+
+        std::fs::create_dir_all(index_dir).map_err(storage_error)?;
+
+        let probe_path = atomic_temp_path(&index_dir.join(WRITE_PROBE_FILE_NAME));
+        let probe = (|| {
+            let mut file = File::create(&probe_path)?;
+            std::io::Write::write_all(&mut file, &[0u8; WRITE_PROBE_LEN])?;
+            file.sync_all()
+        })();
+        let _ = std::fs::remove_file(&probe_path);
+        probe.map_err(storage_error)
+    }
+
+    /// Take the advisory lock on the index directory, failing with IndexLocked if held
+    fn acquire_lock(index_dir: &Path) -> Result<File> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(index_dir.join(LOCK_FILE_NAME))
+            .context("Failed to open index lock file")?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(file),
+            Err(TryLockError::WouldBlock) => Err(IndexLocked {
+                path: index_dir.to_path_buf(),
+            }
+            .into()),
+            Err(TryLockError::Error(e)) => Err(e).context("Failed to lock index directory"),
+        }
+    }
+
     /// Build the Tantivy schema
     fn build_schema() -> Schema {
         let mut schema_builder = Schema::builder();
 
         // Core metadata fields (always indexed)
         schema_builder.add_text_field("id", STRING | STORED);
-        schema_builder.add_text_field("path", STRING | STORED);
-        schema_builder.add_u64_field("size", INDEXED | STORED);
-        schema_builder.add_date_field("modified", INDEXED | STORED);
-        schema_builder.add_text_field("hash", STRING | STORED);
+        // Fast, like parent_dir, hash and alias, so folder counts don't load stored documents
+        schema_builder.add_text_field("path", STRING | STORED | FAST);
+        // Tokenized copy of the path so file and folder names match full-text queries
+        schema_builder.add_text_field("path_terms", TEXT);
+        // Separator-normalized copy of the path for prefix matching; `path` keeps the original
+        schema_builder.add_text_field("path_normalized", STRING);
+        // Containing folder, so results can be grouped or filtered by directory
+        schema_builder.add_text_field("parent_dir", STRING | STORED | FAST);
+        // Fast so size-range results can be sorted by size
+        schema_builder.add_u64_field("size", INDEXED | FAST | STORED);
+        // Fast so date-range results can be listed in time order
+        schema_builder.add_date_field("modified", INDEXED | FAST | STORED);
+        schema_builder.add_date_field("created", INDEXED | FAST | STORED);
+        // When this path was last indexed, for the SQLite export
+        schema_builder.add_date_field("indexed_at", STORED);
+        schema_builder.add_text_field("hash", STRING | STORED | FAST);
+        schema_builder.add_text_field("keccak256", STRING | STORED);
         schema_builder.add_text_field("mime_type", STRING | STORED);
         schema_builder.add_text_field("category", STRING | STORED);
         schema_builder.add_text_field("extension", STRING | STORED);
+        schema_builder.add_text_field("magic_header", STRING | STORED);
+        // Owning uid/gid (Unix only)
+        schema_builder.add_u64_field("uid", INDEXED | STORED);
+        schema_builder.add_u64_field("gid", INDEXED | STORED);
+        // ACL entries, so `acl:alice` finds files someone other than the owner was granted
+        schema_builder.add_text_field("acl", TEXT | STORED);
+        // Type detection confidence, fast so low-confidence files can be range-filtered
+        schema_builder.add_f64_field("confidence", INDEXED | FAST | STORED);
+        // Path of the archive a file was unpacked from (absent for loose files)
+        schema_builder.add_text_field("archive_path", STRING | STORED);
+        // Thumbnail made while indexing an image, so it can be served without the original
+        schema_builder.add_text_field("thumbnail_path", STORED);
+        // Set on a path whose content is stored on another document (de-duplication only)
+        schema_builder.add_bool_field("alias", INDEXED | STORED | FAST);
+        // Set on zero-byte files, which are indexed without extraction
+        schema_builder.add_bool_field("empty", INDEXED | STORED);
+        // Set on images whose extension hides what they are (e.g. a JPEG saved as .dat)
+        schema_builder.add_bool_field("extension_mismatch", INDEXED | STORED);
 
         // Full-text searchable fields
         schema_builder.add_text_field("preview", TEXT | STORED);
-        schema_builder.add_text_field("content", TEXT);
+        // Content is stored so similarity queries can work from the reference document
+        schema_builder.add_text_field("content", TEXT | STORED);
 
         // Structured data fields (for filtering)
-        schema_builder.add_text_field("tables", TEXT); // SQLite table names
-        schema_builder.add_text_field("columns", TEXT); // Column names
-        schema_builder.add_text_field("paths", TEXT); // JSON paths
-        schema_builder.add_text_field("sheets", TEXT); // Excel sheet names
+        // Table and column names are stored so the schema catalog can be rebuilt from the index
+        schema_builder.add_text_field("tables", TEXT | STORED); // SQLite table names
+        schema_builder.add_text_field("columns", TEXT | STORED); // Column names
+
+        // Stored so a de-duplicated document can be rebuilt for another path
+        schema_builder.add_text_field("paths", TEXT | STORED); // JSON paths
+        schema_builder.add_text_field("sheets", TEXT | STORED); // Excel sheet names
 
         // Generic fields extracted by type-specific extractors
-        schema_builder.add_text_field("fields", TEXT);
+        schema_builder.add_text_field("fields", TEXT | STORED);
 
         schema_builder.build()
     }
 
     /// Add a document to the index
     pub fn add_document(&self, file_doc: &FileDocument) -> Result<()> {
-        let mut doc = TantivyDocument::new();
+        if self.deduplicate && !file_doc.metadata.hash.is_empty() {
+            // Held throughout so two copies of the same content can't both become the first
+            let writer = self.writer()?;
+            let mut pending = self.pending_copies.lock();
+            return self.add_copy(&writer, &mut pending, file_doc);
+        }
 
-        // Add core metadata
-        let id = self.schema.get_field("id").unwrap();
-        let path = self.schema.get_field("path").unwrap();
-        let size = self.schema.get_field("size").unwrap();
-        let modified = self.schema.get_field("modified").unwrap();
-        let hash = self.schema.get_field("hash").unwrap();
-        let mime_type = self.schema.get_field("mime_type").unwrap();
-        let category = self.schema.get_field("category").unwrap();
-        let extension = self.schema.get_field("extension").unwrap();
-        let preview = self.schema.get_field("preview").unwrap();
-        let content = self.schema.get_field("content").unwrap();
+        let doc = self.build_document(file_doc)?;
 
-        doc.add_text(id, &file_doc.id);
-        doc.add_text(path, &file_doc.metadata.path.to_string_lossy());
-        doc.add_u64(size, file_doc.metadata.size);
-        doc.add_date(
-            modified,
-            tantivy::DateTime::from_timestamp_secs(file_doc.metadata.modified.timestamp()),
-        );
-        doc.add_text(hash, &file_doc.metadata.hash);
-        doc.add_text(mime_type, &file_doc.metadata.mime_type);
-        doc.add_text(
-            category,
-            &format!("{:?}", file_doc.metadata.category).to_lowercase(),
-        );
+        let writer = self.writer()?;
+        writer.add_document(doc)?;
 
-        if let Some(ext) = &file_doc.metadata.extension {
-            doc.add_text(extension, ext);
-        }
+        Ok(())
+    }
 
-        if let Some(prev) = &file_doc.preview {
-            doc.add_text(preview, prev);
-        }
+    /// Add a document unless its content is already indexed under another path, in which
+    /// case only an alias document with the path's own metadata is added
+    fn add_copy(
+        &self,
+        writer: &IndexWriter,
+        pending: &mut HashMap<String, ContentCopies>,
+        file_doc: &FileDocument,
+    ) -> Result<()> {
+        let id_field = self.schema.get_field("id").unwrap();
+        let copies = self.copies_for(pending, &file_doc.metadata.hash)?;
 
-        if let Some(cont) = &file_doc.content {
-            doc.add_text(content, cont);
+        // Adding a path again replaces its previous document
+        if self.copies_contain(copies, &file_doc.id) {
+            writer.delete_term(Term::from_field_text(id_field, &file_doc.id));
+            copies
+                .aliases
+                .retain(|alias| self.doc_id(alias) != Some(file_doc.id.as_str()));
         }
 
-        // Add structured data fields
-        if let Some(structured) = &file_doc.structured {
-            self.add_structured_fields(&mut doc, structured)?;
+        match &copies.canonical {
+            Some(canonical) if self.doc_id(canonical) != Some(file_doc.id.as_str()) => {
+                let mut alias = self.build_document(&FileDocument {
+                    content: None,
+                    preview: None,
+                    structured: None,
+                    fields: HashMap::new(),
+                    ..file_doc.clone()
+                })?;
+                alias.add_bool(self.schema.get_field("alias").unwrap(), true);
+                writer.add_document(alias.clone())?;
+                copies.aliases.push(alias);
+            }
+            _ => {
+                let doc = self.build_document(file_doc)?;
+                writer.add_document(doc.clone())?;
+                copies.canonical = Some(doc);
+            }
         }
 
-        // Write document
-        let mut writer = self.writer.lock();
-        writer.add_document(doc)?;
-
         Ok(())
     }
 
-    /// Add structured data fields to document
-    fn add_structured_fields(
+    /// Delete one path's document; when it held the content, the first alias takes over
+    /// the content so the remaining copies stay searchable
+    fn remove_copy(
         &self,
-        doc: &mut TantivyDocument,
-        structured: &super::schema::StructuredData,
+        writer: &IndexWriter,
+        pending: &mut HashMap<String, ContentCopies>,
+        doc_id: &str,
     ) -> Result<()> {
-        use super::schema::StructuredData;
-
-        match structured {
-            StructuredData::Sqlite { tables, .. } => {
-                let tables_field = self.schema.get_field("tables").unwrap();
-                let columns_field = self.schema.get_field("columns").unwrap();
-
-                let table_names: Vec<String> = tables.iter().map(|t| t.name.clone()).collect();
-                doc.add_text(tables_field, &table_names.join(" "));
+        let id_field = self.schema.get_field("id").unwrap();
+        writer.delete_term(Term::from_field_text(id_field, doc_id));
 
-                let mut all_columns = Vec::new();
-                for table in tables {
-                    for col in &table.columns {
-                        all_columns.push(format!("{}.{}", table.name, col.name));
-                    }
+        // Copies added in this batch aren't searchable yet, so the batch is checked first
+        let hash = match pending
+            .iter()
+            .find(|(_, copies)| self.copies_contain(copies, doc_id))
+        {
+            Some((hash, _)) => hash.clone(),
+            None => {
+                let searcher = self.reader.searcher();
+                let Some(address) = self.find_doc_address(&searcher, doc_id)? else {
+                    return Ok(());
+                };
+                let doc: TantivyDocument = searcher.doc(address)?;
+                match self.stored_str(&doc, "hash") {
+                    Some(hash) if !hash.is_empty() => hash.to_string(),
+                    _ => return Ok(()),
                 }
-                doc.add_text(columns_field, &all_columns.join(" "));
-            }
-            StructuredData::Json { paths, .. } => {
-                let paths_field = self.schema.get_field("paths").unwrap();
-                let path_strings: Vec<String> = paths.iter().map(|p| p.path.clone()).collect();
-                doc.add_text(paths_field, &path_strings.join(" "));
             }
-            StructuredData::Excel { sheets, .. } => {
-                let sheets_field = self.schema.get_field("sheets").unwrap();
-                let columns_field = self.schema.get_field("columns").unwrap();
+        };
 
-                let sheet_names: Vec<String> = sheets.iter().map(|s| s.name.clone()).collect();
-                doc.add_text(sheets_field, &sheet_names.join(" "));
+        let copies = self.copies_for(pending, &hash)?;
+        copies
+            .aliases
+            .retain(|alias| self.doc_id(alias) != Some(doc_id));
+        let removed_canonical = copies
+            .canonical
+            .take_if(|canonical| self.doc_id(canonical) == Some(doc_id));
 
-                let mut all_headers = Vec::new();
-                for sheet in sheets {
-                    for header in &sheet.headers {
-                        all_headers.push(format!("{}.{}", sheet.name, header));
-                    }
+        if let Some(canonical) = removed_canonical {
+            if !copies.aliases.is_empty() {
+                let alias = copies.aliases.remove(0);
+                if let Some(alias_id) = self.doc_id(&alias) {
+                    writer.delete_term(Term::from_field_text(id_field, alias_id));
                 }
-                doc.add_text(columns_field, &all_headers.join(" "));
+                let promoted = self.move_to_path(&canonical, &alias);
+                writer.add_document(promoted.clone())?;
+                copies.canonical = Some(promoted);
             }
-            StructuredData::Csv { headers, .. } => {
-                let columns_field = self.schema.get_field("columns").unwrap();
-                doc.add_text(columns_field, &headers.join(" "));
-            }
-            _ => {}
         }
 
         Ok(())
     }
 
-    /// Commit changes to the index
-    pub fn commit(&self) -> Result<()> {
-        let mut writer = self.writer.lock();
-        writer.commit()?;
-        Ok(())
+    /// The document holding the content, rebuilt with the path fields of an alias
+    fn move_to_path(
+        &self,
+        canonical: &TantivyDocument,
+        alias: &TantivyDocument,
+    ) -> TantivyDocument {
+        let path_fields: Vec<Field> = PATH_FIELDS
+            .iter()
+            .map(|name| self.schema.get_field(name).unwrap())
+            .collect();
+        let path_terms = self.schema.get_field("path_terms").unwrap();
+        let path_normalized = self.schema.get_field("path_normalized").unwrap();
+
+        let mut doc = TantivyDocument::new();
+        for value in canonical.field_values() {
+            let field = value.field();
+            if !path_fields.contains(&field) && field != path_terms && field != path_normalized {
+                doc.add_field_value(field, value.value().clone());
+            }
+        }
+        for value in alias.field_values() {
+            if path_fields.contains(&value.field()) {
+                doc.add_field_value(value.field(), value.value().clone());
+            }
+        }
+        // Unstored copies of the path, so they are derived again
+        if let Some(path) = self.stored_str(alias, "path") {
+            doc.add_text(path_terms, path);
+            doc.add_text(path_normalized, normalize_path_separators(path));
+        }
+        doc
+    }
+
+    /// Copies of hash as of this batch, loaded from the committed index on first use
+    fn copies_for<'a>(
+        &self,
+        pending: &'a mut HashMap<String, ContentCopies>,
+        hash: &str,
+    ) -> Result<&'a mut ContentCopies> {
+        match pending.entry(hash.to_string()) {
+            std::collections::hash_map::Entry::Occupied(entry) => Ok(entry.into_mut()),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                Ok(entry.insert(self.committed_copies(hash)?))
+            }
+        }
+    }
+
+    /// Committed documents with the given content hash, in the order they were added
+    fn committed_copies(&self, hash: &str) -> Result<ContentCopies> {
+        let searcher = self.reader.searcher();
+        let hash_field = self.schema.get_field("hash").unwrap();
+
+        let query = TermQuery::new(
+            Term::from_field_text(hash_field, hash),
+            IndexRecordOption::Basic,
+        );
+        let mut addresses: Vec<DocAddress> = searcher
+            .search(&query, &DocSetCollector)?
+            .into_iter()
+            .collect();
+        addresses.sort();
+
+        let mut copies = ContentCopies::default();
+        for address in addresses {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            if self.is_alias(&doc) {
+                copies.aliases.push(doc);
+            } else if copies.canonical.is_none() {
+                copies.canonical = Some(doc);
+            }
+        }
+        Ok(copies)
+    }
+
+    /// The committed document holding the content with this hash, and its aliases
+    /// None if no committed document holds it
+    pub fn canonical_doc_for_hash(&self, hash: &str) -> Result<Option<ContentCopyIds>> {
+        let copies = self.committed_copies(hash)?;
+        let id_and_path = |doc: &TantivyDocument| {
+            Some((
+                self.doc_id(doc)?.to_string(),
+                PathBuf::from(self.stored_str(doc, "path")?),
+            ))
+        };
+        Ok(copies
+            .canonical
+            .as_ref()
+            .and_then(id_and_path)
+            .map(|canonical| ContentCopyIds {
+                canonical,
+                aliases: copies.aliases.iter().filter_map(id_and_path).collect(),
+            }))
+    }
+
+    /// Whether a document with doc_id is among copies
+    fn copies_contain(&self, copies: &ContentCopies, doc_id: &str) -> bool {
+        copies
+            .canonical
+            .iter()
+            .chain(&copies.aliases)
+            .any(|doc| self.doc_id(doc) == Some(doc_id))
+    }
+
+    /// Whether a stored document is the alias of a de-duplicated path
+    fn is_alias(&self, doc: &TantivyDocument) -> bool {
+        doc.get_first(self.schema.get_field("alias").unwrap())
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// ID of a stored document
+    fn doc_id<'a>(&self, doc: &'a TantivyDocument) -> Option<&'a str> {
+        self.stored_str(doc, "id")
+    }
+
+    /// First value of a text field on a document
+    fn stored_str<'a>(&self, doc: &'a TantivyDocument, name: &str) -> Option<&'a str> {
+        doc.get_first(self.schema.get_field(name).unwrap())
+            .and_then(|v| v.as_str())
+    }
+
+    /// Stored content and preview of a committed document with the given content hash,
+    /// other than exclude_id
+    pub fn stored_text_for_hash(&self, hash: &str, exclude_id: &str) -> Result<Option<StoredText>> {
+        let searcher = self.reader.searcher();
+        let hash_field = self.schema.get_field("hash").unwrap();
+        let id_field = self.schema.get_field("id").unwrap();
+
+        let query = BooleanQuery::new(vec![
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(hash_field, hash),
+                    IndexRecordOption::Basic,
+                )) as Box<dyn Query>,
+            ),
+            (
+                Occur::MustNot,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(id_field, exclude_id),
+                    IndexRecordOption::Basic,
+                )),
+            ),
+        ]);
+        let Some((_, doc_address)) = searcher.search(&query, &TopDocs::with_limit(1))?.pop() else {
+            return Ok(None);
+        };
+        let doc: TantivyDocument = searcher.doc(doc_address)?;
+
+        let text = |name: &str| {
+            doc.get_first(self.schema.get_field(name).unwrap())
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        Ok(Some(StoredText {
+            content: text("content"),
+            preview: text("preview"),
+        }))
+    }
+
+    /// Paths of the alias documents sharing a document's content
+    fn alias_paths(&self, doc: &TantivyDocument) -> Result<Vec<PathBuf>> {
+        let hash = match self.stored_str(doc, "hash") {
+            Some(hash) if !hash.is_empty() && !self.is_alias(doc) => hash,
+            _ => return Ok(Vec::new()),
+        };
+        let searcher = self.reader.searcher();
+        let query = BooleanQuery::new(vec![
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(self.schema.get_field("hash").unwrap(), hash),
+                    IndexRecordOption::Basic,
+                )) as Box<dyn Query>,
+            ),
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_bool(self.schema.get_field("alias").unwrap(), true),
+                    IndexRecordOption::Basic,
+                )),
+            ),
+        ]);
+
+        let mut addresses: Vec<DocAddress> = searcher
+            .search(&query, &DocSetCollector)?
+            .into_iter()
+            .collect();
+        addresses.sort();
+        let mut paths = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let alias: TantivyDocument = searcher.doc(address)?;
+            if let Some(path) = self.stored_str(&alias, "path") {
+                paths.push(PathBuf::from(path));
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Replace the document with the same ID, or add it if there is none
+    /// Delete and add happen under one writer lock, so a commit never lands between them
+    pub fn update_document(&self, file_doc: &FileDocument) -> Result<()> {
+        if self.deduplicate {
+            // The old version may hold content other paths share, or be an alias itself
+            let writer = self.writer()?;
+            let mut pending = self.pending_copies.lock();
+            self.remove_copy(&writer, &mut pending, &file_doc.id)?;
+            if !file_doc.metadata.hash.is_empty() {
+                return self.add_copy(&writer, &mut pending, file_doc);
+            }
+            writer.add_document(self.build_document(file_doc)?)?;
+            return Ok(());
+        }
+
+        let doc = self.build_document(file_doc)?;
+        let id_field = self.schema.get_field("id").unwrap();
+
+        let writer = self.writer()?;
+        writer.delete_term(Term::from_field_text(id_field, &file_doc.id));
+        writer.add_document(doc)?;
+
+        Ok(())
+    }
+
+    /// Convert a file document into a Tantivy document
+    fn build_document(&self, file_doc: &FileDocument) -> Result<TantivyDocument> {
+        let mut doc = TantivyDocument::new();
+
+        // Add core metadata
+        let id = self.schema.get_field("id").unwrap();
+        let path = self.schema.get_field("path").unwrap();
+        let size = self.schema.get_field("size").unwrap();
+        let modified = self.schema.get_field("modified").unwrap();
+        let hash = self.schema.get_field("hash").unwrap();
+        let mime_type = self.schema.get_field("mime_type").unwrap();
+        let category = self.schema.get_field("category").unwrap();
+        let extension = self.schema.get_field("extension").unwrap();
+        let preview = self.schema.get_field("preview").unwrap();
+        let content = self.schema.get_field("content").unwrap();
+
+        doc.add_text(id, &file_doc.id);
+        doc.add_text(path, &file_doc.metadata.path.to_string_lossy());
+        doc.add_text(
+            self.schema.get_field("path_terms").unwrap(),
+            &file_doc.metadata.path.to_string_lossy(),
+        );
+        doc.add_text(
+            self.schema.get_field("path_normalized").unwrap(),
+            normalize_path_separators(&file_doc.metadata.path.to_string_lossy()),
+        );
+        if let Some(parent) = file_doc.metadata.path.parent() {
+            doc.add_text(
+                self.schema.get_field("parent_dir").unwrap(),
+                parent.to_string_lossy(),
+            );
+        }
+        doc.add_u64(size, file_doc.metadata.size);
+        doc.add_date(
+            modified,
+            tantivy::DateTime::from_timestamp_secs(file_doc.metadata.modified.timestamp()),
+        );
+        if let Some(created) = file_doc.metadata.created {
+            doc.add_date(
+                self.schema.get_field("created").unwrap(),
+                tantivy::DateTime::from_timestamp_secs(created.timestamp()),
+            );
+        }
+        if let Some(indexed_at) = file_doc.metadata.indexed_at {
+            doc.add_date(
+                self.schema.get_field("indexed_at").unwrap(),
+                tantivy::DateTime::from_timestamp_secs(indexed_at.timestamp()),
+            );
+        }
+        doc.add_text(hash, &file_doc.metadata.hash);
+        if let Some(keccak256) = &file_doc.metadata.keccak256 {
+            doc.add_text(self.schema.get_field("keccak256").unwrap(), keccak256);
+        }
+        doc.add_text(mime_type, &file_doc.metadata.mime_type);
+        doc.add_text(
+            category,
+            &format!("{:?}", file_doc.metadata.category).to_lowercase(),
+        );
+
+        if let Some(ext) = &file_doc.metadata.extension {
+            doc.add_text(extension, ext);
+        }
+
+        let magic_header = self.schema.get_field("magic_header").unwrap();
+        doc.add_text(magic_header, &file_doc.metadata.magic_header);
+
+        let confidence = self.schema.get_field("confidence").unwrap();
+        doc.add_f64(confidence, file_doc.metadata.confidence as f64);
+
+        if let Some(uid) = file_doc.metadata.owner_uid {
+            doc.add_u64(self.schema.get_field("uid").unwrap(), uid as u64);
+        }
+        if let Some(gid) = file_doc.metadata.owner_gid {
+            doc.add_u64(self.schema.get_field("gid").unwrap(), gid as u64);
+        }
+        let acl = self.schema.get_field("acl").unwrap();
+        for entry in &file_doc.metadata.acl {
+            doc.add_text(acl, entry);
+        }
+
+        if file_doc.metadata.empty {
+            doc.add_bool(self.schema.get_field("empty").unwrap(), true);
+        }
+
+        if let Some(source) = &file_doc.archive_source {
+            let archive_path = self.schema.get_field("archive_path").unwrap();
+            doc.add_text(archive_path, &source.archive_path.to_string_lossy());
+        }
+
+        if file_doc
+            .image_metadata
+            .as_ref()
+            .is_some_and(|image| image.extension_mismatch)
+        {
+            doc.add_bool(self.schema.get_field("extension_mismatch").unwrap(), true);
+        }
+
+        if let Some(thumbnail_path) = file_doc
+            .image_metadata
+            .as_ref()
+            .and_then(|image| image.thumbnail_path.as_ref())
+        {
+            let field = self.schema.get_field("thumbnail_path").unwrap();
+            doc.add_text(field, &thumbnail_path.to_string_lossy());
+        }
+
+        if let Some(prev) = &file_doc.preview {
+            doc.add_text(preview, prev);
+        }
+
+        if let Some(cont) = &file_doc.content {
+            doc.add_text(content, cont);
+        }
+
+        // Add structured data fields
+        if let Some(structured) = &file_doc.structured {
+            self.add_structured_fields(&mut doc, structured)?;
+        }
+
+        // One `key: value` entry per extractor field, so `fields:"app whatsapp"` matches the pair
+        let fields = self.schema.get_field("fields").unwrap();
+        let mut extracted: Vec<_> = file_doc.fields.iter().collect();
+        extracted.sort();
+        for (key, value) in extracted {
+            doc.add_text(fields, format!("{}: {}", key, value));
+        }
+
+        Ok(doc)
+    }
+
+    /// Add structured data fields to document
+    fn add_structured_fields(
+        &self,
+        doc: &mut TantivyDocument,
+        structured: &super::schema::StructuredData,
+    ) -> Result<()> {
+        use super::schema::StructuredData;
+
+        match structured {
+            StructuredData::Sqlite { tables, .. } => {
+                let tables_field = self.schema.get_field("tables").unwrap();
+                let columns_field = self.schema.get_field("columns").unwrap();
+
+                // One value per name so they can be read back individually
+                for table in tables {
+                    doc.add_text(tables_field, &table.name);
+                    for col in &table.columns {
+                        doc.add_text(columns_field, &format!("{}.{}", table.name, col.name));
+                    }
+                }
+            }
+            StructuredData::Json { paths, .. } => {
+                let paths_field = self.schema.get_field("paths").unwrap();
+                let path_strings: Vec<String> = paths.iter().map(|p| p.path.clone()).collect();
+                doc.add_text(paths_field, &path_strings.join(" "));
+            }
+            StructuredData::Xml { paths, .. } => {
+                // Index "path=value" pairs so attribute and element values are searchable
+                let paths_field = self.schema.get_field("paths").unwrap();
+                let path_strings: Vec<String> =
+                    paths.iter().map(XmlPath::to_search_string).collect();
+                doc.add_text(paths_field, &path_strings.join(" "));
+            }
+            StructuredData::Excel { sheets, .. } => {
+                let sheets_field = self.schema.get_field("sheets").unwrap();
+                let columns_field = self.schema.get_field("columns").unwrap();
+
+                let sheet_names: Vec<String> = sheets.iter().map(|s| s.name.clone()).collect();
+                doc.add_text(sheets_field, &sheet_names.join(" "));
+
+                let mut all_headers = Vec::new();
+                for sheet in sheets {
+                    for header in &sheet.headers {
+                        all_headers.push(format!("{}.{}", sheet.name, header));
+                    }
+                }
+                doc.add_text(columns_field, &all_headers.join(" "));
+            }
+            StructuredData::Csv { headers, .. } => {
+                let columns_field = self.schema.get_field("columns").unwrap();
+                doc.add_text(columns_field, &headers.join(" "));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Delete a document by its ID (takes effect on the next commit)
+    pub fn delete_document(&self, doc_id: &str) -> Result<()> {
+        if self.deduplicate {
+            let writer = self.writer()?;
+            let mut pending = self.pending_copies.lock();
+            return self.remove_copy(&writer, &mut pending, doc_id);
+        }
+
+        let id_field = self.schema.get_field("id").unwrap();
+        let writer = self.writer()?;
+        writer.delete_term(Term::from_field_text(id_field, doc_id));
+        Ok(())
+    }
+
+    /// Commit changes to the index
+    pub fn commit(&self) -> Result<()> {
+        let mut writer = self.writer()?;
+        let mut prepared = writer.prepare_commit()?;
+        prepared.set_payload(&format!("{SCHEMA_VERSION_PAYLOAD}{SCHEMA_VERSION}"));
+        prepared.commit()?;
+        // Committed documents are found through the hash field from now on
+        self.pending_copies.lock().clear();
+        self.reload()?;
+        Ok(())
+    }
+
+    /// Store identical files once, recording later copies as aliases of the first
+    pub fn with_deduplication(mut self, enabled: bool) -> Self {
+        self.deduplicate = enabled;
+        self
     }
 
     /// Search the index
     pub fn search(&self, query_str: &str, limit: usize) -> Result<Vec<SearchHit>> {
-        let reader = self.index.reader()?;
-        let searcher = reader.searcher();
+        self.search_filtered(query_str, limit, None, None, &SearchFilter::default())
+    }
+
+    /// Search the index, keeping only documents that satisfy filter
+    /// boost overrides the forensic default field boost, and fields restricts the
+    /// search to the named schema fields (None = the default fields)
+    pub fn search_filtered(
+        &self,
+        query_str: &str,
+        limit: usize,
+        boost: Option<&QueryBoost>,
+        fields: Option<&[String]>,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchHit>> {
+        let query = self.apply_filter(self.parse_query(query_str, boost, fields)?, filter)?;
+        let mut hits = Vec::new();
+        self.collect_batched(query.as_ref(), limit, limit.max(1), &mut |batch| {
+            hits.extend(batch);
+            Ok(())
+        })?;
+        Ok(hits)
+    }
+
+    /// Search the index, loading and handing over hits a batch at a time
+    /// Returns the total number of hits
+    pub fn search_batched(
+        &self,
+        query_str: &str,
+        limit: usize,
+        boost: Option<&QueryBoost>,
+        fields: Option<&[String]>,
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<SearchHit>) -> Result<()>,
+    ) -> Result<usize> {
+        let query = self.parse_query(query_str, boost, fields)?;
+        self.collect_batched(query.as_ref(), limit, batch_size, on_batch)
+    }
+
+    /// Run query, loading and handing over its top hits a batch at a time
+    fn collect_batched(
+        &self,
+        query: &dyn Query,
+        limit: usize,
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<SearchHit>) -> Result<()>,
+    ) -> Result<usize> {
+        let searcher = self.reader.searcher();
+
+        // Execute search
+        let top_docs = searcher.search(query, &TopDocs::with_limit(limit))?;
+
+        // Loading stored documents is the slow part, so convert one batch at a time
+        for batch in top_docs.chunks(batch_size.max(1)) {
+            let mut hits = Vec::with_capacity(batch.len());
+            for (score, doc_address) in batch {
+                let doc = searcher.doc(*doc_address)?;
+                hits.push(self.doc_to_hit(&doc, *score)?);
+            }
+            on_batch(hits)?;
+        }
+
+        Ok(top_docs.len())
+    }
+
+    /// Count the files matching query per containing folder, largest folder first
+    /// Every match is counted, not just the top hits, and de-duplicated copies count
+    /// towards their own folders
+    pub fn directory_breakdown(&self, query_str: &str) -> Result<Vec<DirectoryCount>> {
+        let searcher = self.reader.searcher();
+        let query = self.parse_query(query_str, None, None)?;
+
+        // Path -> folder, so a path matched both directly and as an alias counts once
+        let mut folders: HashMap<String, String> = HashMap::new();
+        let mut content_hashes: HashSet<String> = HashSet::new();
+        let matches = searcher.search(&query, &DocSetCollector)?;
+        for entry in Self::fast_path_entries(&searcher, matches)? {
+            if !entry.alias && !entry.hash.is_empty() {
+                content_hashes.insert(entry.hash);
+            }
+            folders.entry(entry.path).or_insert(entry.folder);
+        }
+
+        // An alias can match on its own metadata as well as through its content document
+        if !content_hashes.is_empty() {
+            let hash_field = self.schema.get_field("hash").unwrap();
+            let aliases = BooleanQuery::new(vec![
+                (
+                    Occur::Must,
+                    Box::new(TermSetQuery::new(
+                        content_hashes
+                            .iter()
+                            .map(|hash| Term::from_field_text(hash_field, hash)),
+                    )) as Box<dyn Query>,
+                ),
+                (
+                    Occur::Must,
+                    Box::new(TermQuery::new(
+                        Term::from_field_bool(self.schema.get_field("alias").unwrap(), true),
+                        IndexRecordOption::Basic,
+                    )),
+                ),
+            ]);
+            let matches = searcher.search(&aliases, &DocSetCollector)?;
+            for entry in Self::fast_path_entries(&searcher, matches)? {
+                folders.entry(entry.path).or_insert(entry.folder);
+            }
+        }
+
+        let mut counts: HashMap<PathBuf, u64> = HashMap::new();
+        for folder in folders.into_values().filter(|folder| !folder.is_empty()) {
+            *counts.entry(PathBuf::from(folder)).or_default() += 1;
+        }
+
+        let mut breakdown: Vec<DirectoryCount> = counts
+            .into_iter()
+            .map(|(path, count)| DirectoryCount { path, count })
+            .collect();
+        breakdown.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.path.cmp(&b.path)));
+        Ok(breakdown)
+    }
+
+    /// Path, folder, hash and alias flag of each document, read from fast fields
+    fn fast_path_entries(
+        searcher: &Searcher,
+        addresses: HashSet<DocAddress>,
+    ) -> Result<Vec<FastPathEntry>> {
+        let mut by_segment: HashMap<u32, Vec<u32>> = HashMap::new();
+        for address in addresses {
+            by_segment
+                .entry(address.segment_ord)
+                .or_default()
+                .push(address.doc_id);
+        }
+
+        let mut entries = Vec::new();
+        for (segment_ord, doc_ids) in by_segment {
+            let fast_fields = searcher.segment_reader(segment_ord).fast_fields();
+            let column = |name: &str| -> Result<_> {
+                fast_fields
+                    .str(name)?
+                    .with_context(|| format!("Field {} is not a fast field", name))
+            };
+            let (paths, parents, hashes) =
+                (column("path")?, column("parent_dir")?, column("hash")?);
+            let aliases = fast_fields.bool("alias")?;
+
+            let first = |column: &tantivy::columnar::StrColumn, doc_id: u32| -> Result<String> {
+                let mut value = String::new();
+                if let Some(ord) = column.term_ords(doc_id).next() {
+                    column.ord_to_str(ord, &mut value)?;
+                }
+                Ok(value)
+            };
+            for doc_id in doc_ids {
+                let path = first(&paths, doc_id)?;
+                let mut folder = first(&parents, doc_id)?;
+                if folder.is_empty() {
+                    folder = Path::new(&path)
+                        .parent()
+                        .map(|parent| parent.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                }
+                entries.push(FastPathEntry {
+                    hash: first(&hashes, doc_id)?,
+                    alias: aliases.first(doc_id).unwrap_or(false),
+                    path,
+                    folder,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Parse a user query against the search fields with their boosts
+    /// Without fields, terms match in any of the default fields
+    fn parse_query(
+        &self,
+        query_str: &str,
+        boost: Option<&QueryBoost>,
+        fields: Option<&[String]>,
+    ) -> Result<Box<dyn Query>> {
+        let path = self.schema.get_field("path").unwrap();
+        let path_terms = self.schema.get_field("path_terms").unwrap();
+        let preview = self.schema.get_field("preview").unwrap();
+        let content = self.schema.get_field("content").unwrap();
+        let tables = self.schema.get_field("tables").unwrap();
+        let columns = self.schema.get_field("columns").unwrap();
+
+        let search_fields = match fields.filter(|fields| !fields.is_empty()) {
+            Some(names) => self.search_fields(names)?,
+            None => vec![
+                path,
+                path_terms,
+                preview,
+                content,
+                tables,
+                columns,
+                self.schema.get_field("paths").unwrap(),
+            ],
+        };
+
+        // Parse query
+        let mut query_parser = QueryParser::for_index(&self.index, search_fields);
+
+        let boost = boost.copied().unwrap_or_default();
+        query_parser.set_field_boost(path, boost.path);
+        query_parser.set_field_boost(path_terms, boost.path);
+        query_parser.set_field_boost(preview, boost.preview);
+        query_parser.set_field_boost(content, boost.content);
+        query_parser.set_field_boost(tables, boost.tables);
+        query_parser.set_field_boost(columns, boost.columns);
+
+        Ok(query_parser.parse_query(query_str)?)
+    }
+
+    /// query narrowed by every restriction in filter
+    fn apply_filter(&self, query: Box<dyn Query>, filter: &SearchFilter) -> Result<Box<dyn Query>> {
+        if filter.is_empty() {
+            return Ok(query);
+        }
+
+        let mut clauses = vec![(Occur::Must, query)];
+        if let Some(doc_ids) = &filter.doc_ids {
+            let id_field = self.schema.get_field("id").unwrap();
+            let terms = doc_ids.iter().map(|id| Term::from_field_text(id_field, id));
+            clauses.push((Occur::Must, Box::new(TermSetQuery::new(terms))));
+        }
+        if let Some(prefix) = &filter.path_prefix {
+            // Matched on the separator-normalized path
+            let path_field = self.schema.get_field("path_normalized").unwrap();
+            let pattern = format!("{}.*", regex::escape(&normalize_path_separators(prefix)));
+            let query = RegexQuery::from_pattern(&pattern, path_field)
+                .context("Failed to build path prefix query")?;
+            clauses.push((Occur::Must, Box::new(query)));
+        }
+        if filter.modified_after.is_some() || filter.modified_before.is_some() {
+            clauses.push((
+                Occur::Must,
+                Box::new(Self::date_range_query(
+                    "modified",
+                    filter.modified_after,
+                    filter.modified_before,
+                )),
+            ));
+        }
+        Ok(Box::new(BooleanQuery::new(clauses)))
+    }
+
+    /// The subset of doc_ids whose documents satisfy filter
+    /// For narrowing results of queries that don't go through search_filtered
+    pub fn filter_ids(&self, doc_ids: &[String], filter: &SearchFilter) -> Result<HashSet<String>> {
+        if filter.is_empty() || doc_ids.is_empty() {
+            return Ok(doc_ids.iter().cloned().collect());
+        }
+
+        let searcher = self.reader.searcher();
+        let id_field = self.schema.get_field("id").unwrap();
+        let terms = doc_ids.iter().map(|id| Term::from_field_text(id_field, id));
+        let query = self.apply_filter(Box::new(TermSetQuery::new(terms)), filter)?;
+
+        let mut ids = HashSet::new();
+        for doc_address in searcher.search(&query, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            if let Some(id) = self.stored_str(&doc, "id") {
+                ids.insert(id.to_string());
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Resolve user-chosen field names to indexed text fields
+    /// `path` also searches the tokenized path, since the path itself is matched whole
+    fn search_fields(&self, names: &[String]) -> Result<Vec<Field>> {
+        let mut fields = Vec::new();
+        for name in names {
+            let field = self
+                .schema
+                .get_field(name)
+                .map_err(|_| anyhow::anyhow!("Unknown search field: {}", name))?;
+            let entry = self.schema.get_field_entry(field);
+            if !entry.is_indexed() || !matches!(entry.field_type(), FieldType::Str(_)) {
+                anyhow::bail!("Field {} can't be searched as text", name);
+            }
+
+            fields.push(field);
+            if name == "path" {
+                fields.push(self.schema.get_field("path_terms").unwrap());
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Find documents that were unpacked from the given archive
+    pub fn search_by_archive(&self, archive_path: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let searcher = self.reader.searcher();
+
+        let archive_field = self.schema.get_field("archive_path").unwrap();
+        let query = TermQuery::new(
+            Term::from_field_text(archive_field, archive_path),
+            IndexRecordOption::Basic,
+        );
+
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+
+        let mut hits = Vec::new();
+        for (score, doc_address) in top_docs {
+            let doc = searcher.doc(doc_address)?;
+            hits.push(self.doc_to_hit(&doc, score)?);
+        }
+
+        Ok(hits)
+    }
 
-        // Parse query
-        let query_parser = QueryParser::for_index(
-            &self.index,
-            vec![
-                self.schema.get_field("path").unwrap(),
-                self.schema.get_field("preview").unwrap(),
-                self.schema.get_field("content").unwrap(),
-                self.schema.get_field("tables").unwrap(),
-                self.schema.get_field("columns").unwrap(),
-                self.schema.get_field("paths").unwrap(),
-            ],
+    /// Find the document whose content has the given Keccak-256 hash (hex, any case)
+    pub fn search_by_keccak256(&self, keccak256: &str) -> Result<Option<SearchHit>> {
+        let searcher = self.reader.searcher();
+
+        let keccak_field = self.schema.get_field("keccak256").unwrap();
+        let hash = keccak256.trim().trim_start_matches("0x").to_lowercase();
+        let query = TermQuery::new(
+            Term::from_field_text(keccak_field, &hash),
+            IndexRecordOption::Basic,
         );
 
-        let query = query_parser.parse_query(query_str)?;
+        let Some((score, doc_address)) = searcher.search(&query, &TopDocs::with_limit(1))?.pop()
+        else {
+            return Ok(None);
+        };
+        let doc = searcher.doc(doc_address)?;
+        Ok(Some(self.doc_to_hit(&doc, score)?))
+    }
+
+    /// Find documents whose type detection confidence is below the threshold
+    pub fn search_low_confidence(&self, threshold: f32, limit: usize) -> Result<Vec<SearchHit>> {
+        let searcher = self.reader.searcher();
+
+        let query = RangeQuery::new_f64_bounds(
+            "confidence".to_string(),
+            Bound::Unbounded,
+            Bound::Excluded(threshold as f64),
+        );
 
-        // Execute search
         let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
 
-        // Convert results
         let mut hits = Vec::new();
         for (score, doc_address) in top_docs {
             let doc = searcher.doc(doc_address)?;
-            hits.push(self.doc_to_hit(&doc, score));
+            hits.push(self.doc_to_hit(&doc, score)?);
+        }
+
+        Ok(hits)
+    }
+
+    /// Fetch the documents with the given IDs; IDs not in the index are skipped
+    pub fn search_by_ids(&self, doc_ids: &[String]) -> Result<Vec<SearchHit>> {
+        if doc_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let searcher = self.reader.searcher();
+
+        let id_field = self.schema.get_field("id").unwrap();
+        let clauses: Vec<(Occur, Box<dyn Query>)> = doc_ids
+            .iter()
+            .map(|id| {
+                let query = TermQuery::new(
+                    Term::from_field_text(id_field, id),
+                    IndexRecordOption::Basic,
+                );
+                (Occur::Should, Box::new(query) as Box<dyn Query>)
+            })
+            .collect();
+
+        let top_docs = searcher.search(
+            &BooleanQuery::new(clauses),
+            &TopDocs::with_limit(doc_ids.len()),
+        )?;
+
+        let mut hits = Vec::new();
+        for (score, doc_address) in top_docs {
+            let doc = searcher.doc(doc_address)?;
+            hits.push(self.doc_to_hit(&doc, score)?);
+        }
+
+        Ok(hits)
+    }
+
+    /// Find documents owned by the given user and/or group
+    pub fn search_by_owner(
+        &self,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let searcher = self.reader.searcher();
+
+        let clauses: Vec<(Occur, Box<dyn Query>)> = [("uid", uid), ("gid", gid)]
+            .into_iter()
+            .filter_map(|(name, id)| {
+                let field = self.schema.get_field(name).unwrap();
+                let query = TermQuery::new(
+                    Term::from_field_u64(field, id? as u64),
+                    IndexRecordOption::Basic,
+                );
+                Some((Occur::Must, Box::new(query) as Box<dyn Query>))
+            })
+            .collect();
+        if clauses.is_empty() {
+            anyhow::bail!("Owner query needs a uid or gid");
+        }
+
+        let top_docs = searcher.search(&BooleanQuery::new(clauses), &TopDocs::with_limit(limit))?;
+
+        let mut hits = Vec::new();
+        for (score, doc_address) in top_docs {
+            let doc = searcher.doc(doc_address)?;
+            hits.push(self.doc_to_hit(&doc, score)?);
+        }
+
+        Ok(hits)
+    }
+
+    /// Find documents whose size is within [min, max], ordered by size
+    pub fn search_by_size_range(
+        &self,
+        min: Option<u64>,
+        max: Option<u64>,
+        limit: usize,
+        sort: SizeSort,
+    ) -> Result<Vec<SearchHit>> {
+        let searcher = self.reader.searcher();
+
+        let query = RangeQuery::new_u64_bounds(
+            "size".to_string(),
+            min.map_or(Bound::Unbounded, Bound::Included),
+            max.map_or(Bound::Unbounded, Bound::Included),
+        );
+        let order = match sort {
+            SizeSort::Ascending => Order::Asc,
+            SizeSort::Descending => Order::Desc,
+        };
+
+        let top_docs = searcher.search(
+            &query,
+            &TopDocs::with_limit(limit).order_by_u64_field("size", order),
+        )?;
+
+        let mut hits = Vec::new();
+        for (_size, doc_address) in top_docs {
+            let doc = searcher.doc(doc_address)?;
+            hits.push(self.doc_to_hit(&doc, 1.0)?);
+        }
+
+        Ok(hits)
+    }
+
+    /// Files whose date field ("modified" or "created") is within start..=end, earliest first
+    /// Files without a created time never match a created range
+    pub fn search_by_date_range(
+        &self,
+        field: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let searcher = self.reader.searcher();
+
+        let query = Self::date_range_query(field, start, end);
+        let top_docs = searcher.search(
+            &query,
+            &TopDocs::with_limit(limit).order_by_fast_field::<tantivy::DateTime>(field, Order::Asc),
+        )?;
+
+        let mut hits = Vec::new();
+        for (_timestamp, doc_address) in top_docs {
+            let doc = searcher.doc(doc_address)?;
+            hits.push(self.doc_to_hit(&doc, 1.0)?);
+        }
+
+        Ok(hits)
+    }
+
+    /// Inclusive range over a date field; timestamps are indexed to the second
+    fn date_range_query(
+        field: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> RangeQuery {
+        let bound = |date: Option<DateTime<Utc>>| {
+            date.map_or(Bound::Unbounded, |date| {
+                Bound::Included(tantivy::DateTime::from_timestamp_secs(date.timestamp()))
+            })
+        };
+        RangeQuery::new_date_bounds(field.to_string(), bound(start), bound(end))
+    }
+
+    /// Find documents with content similar to the given document
+    /// Uses MoreLikeThis over the stored preview and content of the reference document
+    pub fn search_similar(&self, doc_id: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let searcher = self.reader.searcher();
+
+        let doc_address = self
+            .find_doc_address(&searcher, doc_id)?
+            .ok_or_else(|| anyhow::anyhow!("Document not found: {}", doc_id))?;
+        let reference: TantivyDocument = searcher.doc(doc_address)?;
+
+        // Only compare text fields - metadata like size or dates would add noise
+        let mut doc_fields = Vec::new();
+        for name in ["preview", "content"] {
+            let field = self.schema.get_field(name).unwrap();
+            let values: Vec<OwnedValue> = reference
+                .get_all(field)
+                .filter_map(|v| v.as_str())
+                .map(|s| OwnedValue::Str(s.to_string()))
+                .collect();
+            if !values.is_empty() {
+                doc_fields.push((field, values));
+            }
+        }
+
+        if doc_fields.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = MoreLikeThisQuery::builder()
+            .with_min_doc_frequency(1)
+            .with_min_term_frequency(1)
+            .with_min_word_length(3)
+            .with_max_query_terms(50)
+            .with_document_fields(doc_fields);
+
+        // Fetch one extra hit since the reference document matches itself
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit + 1))?;
+
+        let mut hits = Vec::new();
+        for (score, address) in top_docs {
+            if address == doc_address {
+                continue;
+            }
+            let doc = searcher.doc(address)?;
+            hits.push(self.doc_to_hit(&doc, score)?);
         }
+        hits.truncate(limit);
 
         Ok(hits)
     }
 
+    /// TF-IDF weight of each term in a document's content, None if the document isn't indexed
+    /// Terms come from the content field's own tokenizer, so they match what the index holds
+    pub fn content_term_weights(&self, doc_id: &str) -> Result<Option<HashMap<String, f32>>> {
+        let searcher = self.reader.searcher();
+        let Some(address) = self.find_doc_address(&searcher, doc_id)? else {
+            return Ok(None);
+        };
+        let doc: TantivyDocument = searcher.doc(address)?;
+
+        let content = self.schema.get_field("content").unwrap();
+        let mut tokenizer = self.index.tokenizer_for_field(content)?;
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for text in doc.get_all(content).filter_map(|v| v.as_str()) {
+            tokenizer.token_stream(text).process(&mut |token| {
+                *counts.entry(token.text.clone()).or_default() += 1;
+            });
+        }
+
+        // Smoothed IDF, so a term found in every document still counts a little
+        let num_docs = searcher.num_docs() as f32;
+        counts
+            .into_iter()
+            .map(|(term, count)| {
+                let doc_freq = searcher.doc_freq(&Term::from_field_text(content, &term))? as f32;
+                let idf = ((1.0 + num_docs) / (1.0 + doc_freq)).ln() + 1.0;
+                Ok((term, count as f32 * idf))
+            })
+            .collect::<Result<HashMap<_, _>>>()
+            .map(Some)
+    }
+
+    /// Look up the stored path, hash and size of a document by ID
+    pub fn stored_file_info(&self, doc_id: &str) -> Result<Option<StoredFileInfo>> {
+        let searcher = self.reader.searcher();
+
+        let Some(doc_address) = self.find_doc_address(&searcher, doc_id)? else {
+            return Ok(None);
+        };
+        let doc: TantivyDocument = searcher.doc(doc_address)?;
+
+        let text = |name: &str| {
+            doc.get_first(self.schema.get_field(name).unwrap())
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let size = doc
+            .get_first(self.schema.get_field("size").unwrap())
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+
+        let thumbnail_path = Some(text("thumbnail_path"))
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+
+        Ok(Some(StoredFileInfo {
+            path: PathBuf::from(text("path")),
+            hash: text("hash"),
+            size,
+            thumbnail_path,
+        }))
+    }
+
+    /// Paths of every indexed document, including de-duplicated aliases
+    pub fn document_paths(&self) -> Result<Vec<PathBuf>> {
+        let searcher = self.reader.searcher();
+        let path_field = self.schema.get_field("path").unwrap();
+
+        let mut paths = Vec::new();
+        for doc_address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            if let Some(path) = doc.get_first(path_field).and_then(|v| v.as_str()) {
+                paths.push(PathBuf::from(path));
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Group SQLite table and column names across all indexed databases
+    pub fn structured_catalog(&self) -> Result<StructuredCatalog> {
+        let searcher = self.reader.searcher();
+
+        let path_field = self.schema.get_field("path").unwrap();
+        let tables_field = self.schema.get_field("tables").unwrap();
+        let columns_field = self.schema.get_field("columns").unwrap();
+
+        let mut catalog = StructuredCatalog::default();
+        for doc_address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+
+            // Only SQLite documents populate the tables field
+            let tables: Vec<&str> = doc
+                .get_all(tables_field)
+                .filter_map(|v| v.as_str())
+                .collect();
+            if tables.is_empty() {
+                continue;
+            }
+
+            let path = PathBuf::from(
+                doc.get_first(path_field)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(""),
+            );
+
+            for table in &tables {
+                catalog
+                    .tables
+                    .entry(table.to_string())
+                    .or_default()
+                    .push(path.clone());
+            }
+
+            // Columns are stored as "table.column"; strip the known table prefix
+            for qualified in doc.get_all(columns_field).filter_map(|v| v.as_str()) {
+                let column = tables
+                    .iter()
+                    .find_map(|table| {
+                        qualified
+                            .strip_prefix(table)
+                            .and_then(|rest| rest.strip_prefix('.'))
+                    })
+                    .unwrap_or(qualified);
+
+                let paths = catalog.columns.entry(column.to_string()).or_default();
+                if !paths.contains(&path) {
+                    paths.push(path.clone());
+                }
+            }
+        }
+
+        for paths in catalog.tables.values_mut() {
+            paths.sort();
+        }
+        for paths in catalog.columns.values_mut() {
+            paths.sort();
+        }
+
+        Ok(catalog)
+    }
+
+    /// Indexed terms of field starting with prefix, for autocompleting queries
+    /// Terms found in more documents come first. Tokenized fields store lowercased
+    /// terms, so the prefix is lowercased to match them
+    pub fn suggest(&self, prefix: &str, field: &str, limit: usize) -> Result<Vec<String>> {
+        let field_handle = self
+            .schema
+            .get_field(field)
+            .map_err(|_| anyhow::anyhow!("Unknown field: {}", field))?;
+        let tokenizer = match self.schema.get_field_entry(field_handle).field_type() {
+            FieldType::Str(options) => options
+                .get_indexing_options()
+                .map(|indexing| indexing.tokenizer().to_string()),
+            _ => None,
+        };
+        let Some(tokenizer) = tokenizer else {
+            anyhow::bail!("Field {} has no searchable text terms", field);
+        };
+        let prefix = if tokenizer == "raw" {
+            prefix.to_string()
+        } else {
+            prefix.to_lowercase()
+        };
+
+        let searcher = self.reader.searcher();
+
+        // The same term shows up in every segment holding a document with it
+        let mut doc_freqs: HashMap<String, u64> = HashMap::new();
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(field_handle)?;
+            let mut terms = inverted_index
+                .terms()
+                .range()
+                .ge(prefix.as_bytes())
+                .into_stream()?;
+            while terms.advance() {
+                if !terms.key().starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                let term = String::from_utf8_lossy(terms.key()).into_owned();
+                *doc_freqs.entry(term).or_default() += terms.value().doc_freq as u64;
+            }
+        }
+
+        let mut suggestions: Vec<(String, u64)> = doc_freqs.into_iter().collect();
+        suggestions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(suggestions
+            .into_iter()
+            .take(limit)
+            .map(|(term, _)| term)
+            .collect())
+    }
+
+    /// Dump every stored document into a `files` table of a new SQLite database
+    /// Returns the number of rows written
+    pub fn export_to_sqlite(&self, output_path: &Path) -> Result<u64> {
+        if output_path.exists() {
+            anyhow::bail!("Output file already exists: {}", output_path.display());
+        }
+
+        let searcher = self.reader.searcher();
+        let mut addresses: Vec<DocAddress> = searcher
+            .search(&AllQuery, &DocSetCollector)?
+            .into_iter()
+            .collect();
+        // In store order, so each batch reads neighbouring compressed blocks
+        addresses.sort();
+
+        let mut conn =
+            rusqlite::Connection::open(output_path).context("Failed to create export database")?;
+        conn.execute_batch(
+            "CREATE TABLE files (
+                id TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                size INTEGER,
+                modified TEXT,
+                created TEXT,
+                indexed_at TEXT,
+                hash TEXT,
+                mime_type TEXT,
+                category TEXT,
+                extension TEXT,
+                magic_header TEXT,
+                archive_path TEXT,
+                preview TEXT,
+                content TEXT
+            );",
+        )?;
+
+        // One transaction for the whole export, so a failure leaves no partial table
+        let tx = conn.transaction()?;
+        for batch in addresses.chunks(EXPORT_BATCH_ROWS) {
+            let mut rows = Vec::with_capacity(batch.len() * EXPORT_COLUMNS.len());
+            for address in batch {
+                let doc: TantivyDocument = searcher.doc(*address)?;
+                for &column in EXPORT_COLUMNS {
+                    let value = doc.get_first(self.schema.get_field(column).unwrap());
+                    rows.push(match column {
+                        "size" => value
+                            .and_then(|v| v.as_u64())
+                            .map_or(SqlValue::Null, |size| SqlValue::Integer(size as i64)),
+                        "modified" | "created" | "indexed_at" => value
+                            .and_then(|v| v.as_datetime())
+                            .and_then(|dt| {
+                                chrono::DateTime::from_timestamp(dt.into_timestamp_secs(), 0)
+                            })
+                            .map_or(SqlValue::Null, |dt| SqlValue::Text(dt.to_rfc3339())),
+                        _ => value
+                            .and_then(|v| v.as_str())
+                            .map_or(SqlValue::Null, |s| SqlValue::Text(s.to_string())),
+                    });
+                }
+            }
+
+            // Several rows per INSERT, kept under SQLite's default limit of 999 parameters
+            let rows_per_insert = 999 / EXPORT_COLUMNS.len();
+            for chunk in rows.chunks(rows_per_insert * EXPORT_COLUMNS.len()) {
+                let row = format!("({})", vec!["?"; EXPORT_COLUMNS.len()].join(", "));
+                let sql = format!(
+                    "INSERT INTO files ({}) VALUES {}",
+                    EXPORT_COLUMNS.join(", "),
+                    vec![row; chunk.len() / EXPORT_COLUMNS.len()].join(", ")
+                );
+                tx.prepare_cached(&sql)?
+                    .execute(rusqlite::params_from_iter(chunk))?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(addresses.len() as u64)
+    }
+
+    /// Look up the address of a document by its ID
+    fn find_doc_address(&self, searcher: &Searcher, doc_id: &str) -> Result<Option<DocAddress>> {
+        let id_field = self.schema.get_field("id").unwrap();
+        let term = Term::from_field_text(id_field, doc_id);
+        let query = TermQuery::new(term, IndexRecordOption::Basic);
+
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+        Ok(top_docs.into_iter().next().map(|(_, address)| address))
+    }
+
     /// Convert Tantivy document to SearchHit
-    fn doc_to_hit(&self, doc: &TantivyDocument, score: f32) -> SearchHit {
+    fn doc_to_hit(&self, doc: &TantivyDocument, score: f32) -> Result<SearchHit> {
         let id_field = self.schema.get_field("id").unwrap();
         let path_field = self.schema.get_field("path").unwrap();
         let category_field = self.schema.get_field("category").unwrap();
@@ -274,6 +2161,7 @@ impl InvertedIndex {
             "database" => FileCategory::Database,
             "structureddata" => FileCategory::StructuredData,
             "document" => FileCategory::Document,
+            "email" => FileCategory::Email,
             "text" => FileCategory::Text,
             "media" => FileCategory::Media,
             "archive" => FileCategory::Archive,
@@ -287,19 +2175,558 @@ impl InvertedIndex {
             .unwrap_or("")
             .to_string();
 
-        SearchHit {
+        let size = doc
+            .get_first(self.schema.get_field("size").unwrap())
+            .and_then(|v| v.as_u64());
+        let modified = doc
+            .get_first(self.schema.get_field("modified").unwrap())
+            .and_then(|v| v.as_datetime())
+            .and_then(|dt| chrono::DateTime::from_timestamp(dt.into_timestamp_secs(), 0));
+        let indexed_at = doc
+            .get_first(self.schema.get_field("indexed_at").unwrap())
+            .and_then(|v| v.as_datetime())
+            .and_then(|dt| chrono::DateTime::from_timestamp(dt.into_timestamp_secs(), 0));
+        let metadata = size.zip(modified).map(|(size, modified)| HitMetadata {
+            size,
+            modified,
+            indexed_at,
+        });
+
+        Ok(SearchHit {
             id,
             path: PathBuf::from(path_str),
             category,
             snippet,
             score,
-        }
+            aliases: self.alias_paths(doc)?,
+            metadata,
+        })
     }
 
     /// Get total document count
     pub fn document_count(&self) -> Result<u64> {
-        let reader = self.index.reader()?;
-        let searcher = reader.searcher();
+        let searcher = self.reader.searcher();
         Ok(searcher.num_docs())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_storage_errors_are_classified() {
+        let path = Path::new("/evidence/index");
+        let full = std::io::Error::from(std::io::ErrorKind::StorageFull);
+        let read_only = std::io::Error::from(std::io::ErrorKind::ReadOnlyFilesystem);
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+
+        assert_eq!(
+            IndexStorageUnavailable::from_io(path, &full)
+                .unwrap()
+                .reason,
+            StorageUnavailableReason::DiskFull
+        );
+        assert_eq!(
+            IndexStorageUnavailable::from_io(path, &read_only)
+                .unwrap()
+                .reason,
+            StorageUnavailableReason::ReadOnly
+        );
+        assert!(IndexStorageUnavailable::from_io(path, &missing).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_in_read_only_dir_reports_storage_unavailable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let locked_dir = temp_dir.path().join("readonly");
+        std::fs::create_dir(&locked_dir).unwrap();
+        std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Permission bits don't apply to root, so there is nothing to observe there
+        let writable = File::create(locked_dir.join("probe")).is_ok();
+        if !writable {
+            let err = InvertedIndex::create(&locked_dir.join("index"))
+                .err()
+                .unwrap();
+            let unavailable = err.downcast_ref::<IndexStorageUnavailable>().unwrap();
+            assert_eq!(unavailable.reason, StorageUnavailableReason::ReadOnly);
+            assert_eq!(unavailable.path, locked_dir.join("index"));
+        }
+
+        std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_second_open_of_same_dir_is_locked() {
+        let temp_dir = TempDir::new().unwrap();
+        let index_dir = temp_dir.path().join("index");
+
+        let first = InvertedIndex::create(&index_dir).unwrap();
+        let err = InvertedIndex::open(&index_dir).err().unwrap();
+        assert!(err.downcast_ref::<IndexLocked>().is_some());
+
+        drop(first);
+        assert!(InvertedIndex::open(&index_dir).is_ok());
+    }
+
+    #[test]
+    fn test_index_with_older_schema_requires_reindex() {
+        let temp_dir = TempDir::new().unwrap();
+        let index_dir = temp_dir.path().join("index");
+        std::fs::create_dir_all(&index_dir).unwrap();
+
+        let mut old_schema = Schema::builder();
+        old_schema.add_text_field("path", STRING | STORED);
+        old_schema.add_text_field("content", TEXT);
+        Index::create_in_dir(&index_dir, old_schema.build()).unwrap();
+
+        let err = InvertedIndex::open(&index_dir).err().unwrap();
+        let outdated = err.downcast_ref::<IndexSchemaOutdated>().unwrap();
+        assert_eq!(outdated.found, 1);
+        assert_eq!(outdated.expected, SCHEMA_VERSION);
+        assert!(InvertedIndex::open_readonly(&index_dir)
+            .err()
+            .unwrap()
+            .downcast_ref::<IndexSchemaOutdated>()
+            .is_some());
+        assert!(InvertedIndex::create(&index_dir)
+            .err()
+            .unwrap()
+            .downcast_ref::<IndexSchemaOutdated>()
+            .is_some());
+    }
+
+    #[test]
+    fn test_commit_records_schema_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let index_dir = temp_dir.path().join("index");
+
+        let index = InvertedIndex::create(&index_dir).unwrap();
+        index
+            .add_document(&text_doc("/evidence/a.txt", "h1", "ledger entry"))
+            .unwrap();
+        index.commit().unwrap();
+        drop(index);
+
+        let reopened = InvertedIndex::open(&index_dir).unwrap();
+        assert_eq!(
+            reopened.index.load_metas().unwrap().payload.as_deref(),
+            Some(format!("{SCHEMA_VERSION_PAYLOAD}{SCHEMA_VERSION}").as_str())
+        );
+    }
+
+    #[test]
+    fn test_read_only_handle_sees_commits_after_reload() {
+        let temp_dir = TempDir::new().unwrap();
+        let index_dir = temp_dir.path().join("index");
+
+        let writer = InvertedIndex::create(&index_dir).unwrap();
+        writer
+            .add_document(&text_doc("/evidence/a.txt", "h1", "ledger entry"))
+            .unwrap();
+        writer.commit().unwrap();
+
+        let reader = InvertedIndex::open_readonly(&index_dir).unwrap();
+        assert!(reader.is_read_only());
+        assert_eq!(reader.search("ledger", 10).unwrap().len(), 1);
+
+        writer
+            .add_document(&text_doc("/evidence/b.txt", "h2", "second ledger"))
+            .unwrap();
+        writer.commit().unwrap();
+        assert_eq!(reader.search("ledger", 10).unwrap().len(), 1);
+
+        reader.reload().unwrap();
+        assert_eq!(reader.search("ledger", 10).unwrap().len(), 2);
+
+        let err = reader
+            .add_document(&text_doc("/evidence/c.txt", "h3", "ledger"))
+            .err()
+            .unwrap();
+        assert!(err.downcast_ref::<IndexReadOnly>().is_some());
+    }
+
+    #[test]
+    fn test_search_restricted_to_content_field() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path()).unwrap();
+        index
+            .add_document(&text_doc("/evidence/passwords.txt", "h1", "grocery list"))
+            .unwrap();
+        index
+            .add_document(&text_doc(
+                "/evidence/notes.txt",
+                "h2",
+                "passwords are on the desk",
+            ))
+            .unwrap();
+        index.commit().unwrap();
+
+        assert_eq!(index.search("passwords", 10).unwrap().len(), 2);
+
+        let content_only = ["content".to_string()];
+        let hits = index
+            .search_filtered(
+                "passwords",
+                10,
+                None,
+                Some(&content_only),
+                &SearchFilter::default(),
+            )
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, PathBuf::from("/evidence/notes.txt"));
+
+        let path_only = ["path".to_string()];
+        let hits = index
+            .search_filtered(
+                "passwords",
+                10,
+                None,
+                Some(&path_only),
+                &SearchFilter::default(),
+            )
+            .unwrap();
+        assert_eq!(hits[0].path, PathBuf::from("/evidence/passwords.txt"));
+
+        assert!(index
+            .search_filtered(
+                "passwords",
+                10,
+                None,
+                Some(&["nope".to_string()]),
+                &SearchFilter::default(),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_acl_entries_are_searchable() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path()).unwrap();
+        let mut shared = text_doc("/evidence/shared.txt", "h1", "ledger");
+        shared.metadata.acl = vec!["user:alice:rw-".to_string(), "mask::rw-".to_string()];
+        index.add_document(&shared).unwrap();
+        index
+            .add_document(&text_doc("/evidence/private.txt", "h2", "ledger"))
+            .unwrap();
+        index.commit().unwrap();
+
+        let hits = index.search("acl:alice", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, PathBuf::from("/evidence/shared.txt"));
+    }
+
+    #[test]
+    fn test_search_low_confidence_excludes_threshold_and_above() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path()).unwrap();
+        for (path, confidence) in [
+            ("/evidence/magic.db", 1.0),
+            ("/evidence/at_threshold.json", 0.6),
+            ("/evidence/guess.csv", 0.5),
+            ("/evidence/unknown.bin", 0.1),
+        ] {
+            let mut doc = text_doc(path, path, "contents");
+            doc.metadata.confidence = confidence;
+            index.add_document(&doc).unwrap();
+        }
+        index.commit().unwrap();
+
+        let mut paths: Vec<_> = index
+            .search_low_confidence(0.6, 10)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.path)
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/evidence/guess.csv"),
+                PathBuf::from("/evidence/unknown.bin"),
+            ]
+        );
+        assert_eq!(index.search_low_confidence(0.6, 1).unwrap().len(), 1);
+        assert!(index.search_low_confidence(0.1, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_writer_lock_removed_only_when_not_held() {
+        let temp_dir = TempDir::new().unwrap();
+        let index_dir = temp_dir.path().join("index");
+        drop(InvertedIndex::create(&index_dir).unwrap());
+
+        // Another process still writing keeps its lock
+        let lock_path = index_dir.join(WRITER_LOCK_FILE_NAME);
+        let holder = File::create(&lock_path).unwrap();
+        holder.lock().unwrap();
+        assert!(InvertedIndex::open_force(&index_dir).is_err());
+        assert!(lock_path.exists());
+
+        drop(holder);
+        InvertedIndex::remove_stale_writer_lock(&index_dir).unwrap();
+        assert!(!lock_path.exists());
+        assert!(InvertedIndex::open_force(&index_dir).is_ok());
+    }
+
+    fn text_doc(path: &str, hash: &str, content: &str) -> FileDocument {
+        let mut doc = FileDocument::text_fixture(path, path, content);
+        doc.metadata.hash = hash.to_string();
+        doc
+    }
+
+    #[test]
+    fn test_restore_from_backup_after_corruption() {
+        let index_dir = TempDir::new().unwrap();
+        let backup_dir = TempDir::new().unwrap();
+
+        {
+            let index = InvertedIndex::create(index_dir.path()).unwrap();
+            index
+                .add_document(&text_doc("/evidence/a.txt", "h1", "wire transfer"))
+                .unwrap();
+            index.commit().unwrap();
+            index.backup(backup_dir.path()).unwrap();
+
+            // Changes made after the backup are not part of it
+            index
+                .add_document(&text_doc("/evidence/b.txt", "h2", "later notes"))
+                .unwrap();
+            index.commit().unwrap();
+        }
+        assert!(!backup_dir.path().join(LOCK_FILE_NAME).exists());
+
+        std::fs::write(index_dir.path().join(META_FILE_NAME), b"{ truncated").unwrap();
+        assert!(InvertedIndex::open(index_dir.path()).is_err());
+
+        let index =
+            InvertedIndex::restore_from_backup(backup_dir.path(), index_dir.path()).unwrap();
+        assert_eq!(index.search("wire", 10).unwrap().len(), 1);
+        assert!(index.search("later", 10).unwrap().is_empty());
+        assert_eq!(index.document_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_failed_restore_keeps_live_index() {
+        let parent = TempDir::new().unwrap();
+        let index_dir = parent.path().join("inverted");
+        let backup_dir = TempDir::new().unwrap();
+
+        {
+            let index = InvertedIndex::create(&index_dir).unwrap();
+            index
+                .add_document(&text_doc("/evidence/a.txt", "h1", "wire transfer"))
+                .unwrap();
+            index.commit().unwrap();
+        }
+        std::fs::write(backup_dir.path().join(META_FILE_NAME), b"{ truncated").unwrap();
+
+        assert!(InvertedIndex::restore_from_backup(backup_dir.path(), &index_dir).is_err());
+        // Neither the staging copy nor the live index moved aside is left behind
+        assert_eq!(std::fs::read_dir(parent.path()).unwrap().count(), 1);
+
+        let index = InvertedIndex::open(&index_dir).unwrap();
+        assert_eq!(index.search("wire", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_content_is_stored_once_with_aliases() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path())
+            .unwrap()
+            .with_deduplication(true);
+
+        index
+            .add_document(&text_doc("/evidence/plans.txt", "abc", "launch plans"))
+            .unwrap();
+        index.commit().unwrap();
+        // One copy found through the committed index, one still pending in the same batch
+        index
+            .add_document(&text_doc("/backup/plans.txt", "abc", "launch plans"))
+            .unwrap();
+        index
+            .add_document(&text_doc("/usb/plans.txt", "abc", "launch plans"))
+            .unwrap();
+        index
+            .add_document(&text_doc("/evidence/notes.txt", "def", "other notes"))
+            .unwrap();
+        index.commit().unwrap();
+
+        assert_eq!(index.document_paths().unwrap().len(), 4);
+        let hits = index.search("launch", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, PathBuf::from("/evidence/plans.txt"));
+        assert_eq!(
+            hits[0].aliases,
+            vec![
+                PathBuf::from("/backup/plans.txt"),
+                PathBuf::from("/usb/plans.txt")
+            ]
+        );
+
+        // Every copy counts towards its own folder, once, whether it matches through its
+        // content document or on its own path
+        let folders = |query: &str| -> Vec<(String, u64)> {
+            index
+                .directory_breakdown(query)
+                .unwrap()
+                .into_iter()
+                .map(|dir| (dir.path.to_string_lossy().into_owned(), dir.count))
+                .collect()
+        };
+        let expected = vec![
+            ("/backup".to_string(), 1),
+            ("/evidence".to_string(), 1),
+            ("/usb".to_string(), 1),
+        ];
+        assert_eq!(folders("launch"), expected);
+        assert_eq!(folders("plans"), expected);
+    }
+
+    #[test]
+    fn test_deleting_one_duplicate_path_keeps_the_others() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path())
+            .unwrap()
+            .with_deduplication(true);
+
+        for path in ["/evidence/plans.txt", "/backup/plans.txt", "/usb/plans.txt"] {
+            index
+                .add_document(&text_doc(path, "abc", "launch plans"))
+                .unwrap();
+        }
+        index.commit().unwrap();
+
+        // Deleting an alias leaves the content and the other alias
+        index.delete_document("/usb/plans.txt").unwrap();
+        index.commit().unwrap();
+        let hits = index.search("launch", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, PathBuf::from("/evidence/plans.txt"));
+        assert_eq!(hits[0].aliases, vec![PathBuf::from("/backup/plans.txt")]);
+
+        // Deleting the copy holding the content hands it to the remaining alias
+        index.delete_document("/evidence/plans.txt").unwrap();
+        index.commit().unwrap();
+        let hits = index.search("launch", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "/backup/plans.txt");
+        assert_eq!(hits[0].path, PathBuf::from("/backup/plans.txt"));
+        assert!(hits[0].aliases.is_empty());
+        assert_eq!(
+            index.document_paths().unwrap(),
+            vec![PathBuf::from("/backup/plans.txt")]
+        );
+    }
+
+    #[test]
+    fn test_updating_a_duplicate_goes_through_deduplication() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path())
+            .unwrap()
+            .with_deduplication(true);
+
+        index
+            .add_document(&text_doc("/evidence/plans.txt", "abc", "launch plans"))
+            .unwrap();
+        index
+            .add_document(&text_doc("/backup/plans.txt", "abc", "launch plans"))
+            .unwrap();
+        index.commit().unwrap();
+
+        // The first copy was edited, so the backup takes over the old content
+        index
+            .update_document(&text_doc("/evidence/plans.txt", "def", "revised plans"))
+            .unwrap();
+        // A new copy of the edited file becomes its alias
+        index
+            .update_document(&text_doc("/usb/plans.txt", "def", "revised plans"))
+            .unwrap();
+        index.commit().unwrap();
+
+        let launch = index.search("launch", 10).unwrap();
+        assert_eq!(launch.len(), 1);
+        assert_eq!(launch[0].path, PathBuf::from("/backup/plans.txt"));
+        assert!(launch[0].aliases.is_empty());
+
+        let revised = index.search("revised", 10).unwrap();
+        assert_eq!(revised.len(), 1);
+        assert_eq!(revised[0].path, PathBuf::from("/evidence/plans.txt"));
+        assert_eq!(revised[0].aliases, vec![PathBuf::from("/usb/plans.txt")]);
+        assert_eq!(index.document_paths().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_suggest_returns_indexed_columns_by_prefix() {
+        use crate::index::schema::StructuredData;
+
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path()).unwrap();
+
+        let csv_doc = |path: &str, headers: &[&str]| FileDocument {
+            structured: Some(StructuredData::Csv {
+                headers: headers.iter().map(|h| h.to_string()).collect(),
+                row_count: 1,
+                delimiter: ',',
+                schema: Vec::new(),
+            }),
+            ..text_doc(path, path, "")
+        };
+        index
+            .add_document(&csv_doc(
+                "/evidence/orders.csv",
+                &["customer", "country", "amount"],
+            ))
+            .unwrap();
+        index
+            .add_document(&csv_doc("/evidence/refunds.csv", &["customer", "currency"]))
+            .unwrap();
+        index.commit().unwrap();
+
+        assert_eq!(
+            index.suggest("CU", "columns", 10).unwrap(),
+            vec!["customer".to_string(), "currency".to_string()]
+        );
+        assert_eq!(index.suggest("cu", "columns", 1).unwrap(), vec!["customer"]);
+        assert_eq!(index.suggest("co", "columns", 10).unwrap(), vec!["country"]);
+        assert!(index.suggest("zz", "columns", 10).unwrap().is_empty());
+        assert!(index.suggest("cu", "no_such_field", 10).is_err());
+    }
+
+    #[test]
+    fn test_directory_breakdown_counts_matches_per_folder() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path()).unwrap();
+
+        for (path, content) in [
+            ("/evidence/mail/1.txt", "invoice attached"),
+            ("/evidence/mail/2.txt", "second invoice"),
+            ("/evidence/mail/3.txt", "lunch plans"),
+            ("/evidence/docs/ledger.txt", "invoice ledger"),
+        ] {
+            index.add_document(&text_doc(path, path, content)).unwrap();
+        }
+        index.commit().unwrap();
+
+        assert_eq!(
+            index.directory_breakdown("invoice").unwrap(),
+            vec![
+                DirectoryCount {
+                    path: PathBuf::from("/evidence/mail"),
+                    count: 2
+                },
+                DirectoryCount {
+                    path: PathBuf::from("/evidence/docs"),
+                    count: 1
+                },
+            ]
+        );
+        assert!(index.directory_breakdown("nothing").unwrap().is_empty());
+    }
+}
@@ -10,6 +10,7 @@
 
 pub mod archive_extractor;
 pub mod archive_settings;
+pub mod carving;
 pub mod detector;
 pub mod extractors;
 pub mod image_preview;
@@ -17,18 +18,31 @@ pub mod indexer;
 pub mod inverted;
 pub mod query;
 pub mod schema;
+pub mod similarity;
+pub mod sqlite_recovery;
 pub mod watcher;
 
-pub use archive_extractor::ArchiveExtractor;
-pub use archive_settings::{ArchiveFormat, ArchiveSettings, UnpackedArchiveInfo};
+pub use archive_extractor::{ArchiveError, ArchiveExtractor};
+pub use archive_settings::{ArchiveEntry, ArchiveFormat, ArchiveSettings, UnpackedArchiveInfo};
+pub use carving::{CarvingLimits, CarvingReport, CarvingTarget, FileCarver};
 pub use detector::{DetectedFileType, FileTypeDetector};
-pub use extractors::{Extractor, ExtractorRegistry};
+pub use extractors::{
+    ExtractionMode, Extractor, ExtractorInfo, ExtractorRegistry, SupportedFormat,
+};
 pub use image_preview::{ImageInfo, ImagePreviewGenerator, PreviewConfig};
-pub use indexer::{IndexPhase, IndexProgress, IndexStats, MasterIndexer};
-pub use inverted::{InvertedIndex, SearchHit};
+pub use indexer::{
+    ExportedFile, FileExportReport, GcStats, InFileMatch, IndexError, IndexPhase, IndexProgress,
+    IndexStats, IndexedBatch, IndexingConfig, MasterIndexer, SizeLimits, SkippedExport,
+};
+pub use inverted::{
+    DirectoryCount, IndexLocked, IndexStorageUnavailable, InvertedIndex, QueryBoost, SearchFilter,
+    SearchHit, SizeSort, StorageUnavailableReason, StoredFileInfo,
+};
 pub use query::{Query, QueryPlanner, QueryResult};
 pub use schema::{
-    DocumentMetadata, FileCategory, FileDocument, IndexStats as SchemaIndexStats, StructuredData,
-    TypedHit,
+    DocumentMetadata, FileCategory, FileDocument, HitMetadata, IndexStats as SchemaIndexStats,
+    StructuredCatalog, StructuredData, TypedHit,
 };
+pub use similarity::SimilarityResult;
+pub use sqlite_recovery::{RecoveredRow, SqliteRecovery};
 pub use watcher::{ChangeDetector, FileChange};
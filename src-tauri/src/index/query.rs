@@ -1,6 +1,7 @@
-use super::extractors::ExtractorRegistry;
-use super::inverted::{InvertedIndex, SearchHit};
+use super::extractors::{binary_strings, ExtractorRegistry};
+use super::inverted::{InvertedIndex, QueryBoost, SearchFilter, SearchHit, SizeSort};
 use super::schema::{FileCategory, TypedHit};
+use crate::db::AuxiliaryProjectDb;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -11,6 +12,8 @@ use std::sync::Arc;
 pub struct QueryPlanner {
     inverted_index: Arc<InvertedIndex>,
     extractor_registry: Arc<ExtractorRegistry>,
+    /// Project database holding tags; tag queries fail without one
+    auxiliary_db: Option<Arc<AuxiliaryProjectDb>>,
 }
 
 /// Query types
@@ -18,7 +21,16 @@ pub struct QueryPlanner {
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum Query {
     /// Full-text search across all indexed content
-    FullText { query: String, limit: Option<usize> },
+    FullText {
+        query: String,
+        limit: Option<usize>,
+        /// Per-query field boost (defaults to QueryBoost::forensic_defaults)
+        #[serde(default)]
+        boost_config: Option<QueryBoost>,
+        /// Schema fields to search, e.g. ["content"] (defaults to all full-text fields)
+        #[serde(default)]
+        fields: Option<Vec<String>>,
+    },
 
     /// Filter by metadata
     Metadata {
@@ -44,6 +56,52 @@ pub enum Query {
         query: String,
     },
 
+    /// Find documents with content similar to an indexed document
+    SimilarTo {
+        /// ID of the reference document
+        doc_id: String,
+        limit: Option<usize>,
+    },
+
+    /// Files unpacked from a specific archive
+    FromArchive {
+        /// Path of the source archive
+        archive_path: String,
+    },
+
+    /// Files whose type was detected with confidence below the threshold
+    /// Useful for finding misidentified or deliberately disguised files
+    LowConfidence {
+        /// Upper bound (exclusive), 0.0-1.0
+        threshold: f32,
+    },
+
+    /// Files whose size is within a range, sorted by size
+    SizeRange {
+        /// Minimum size in bytes (inclusive)
+        min: Option<u64>,
+        /// Maximum size in bytes (inclusive)
+        max: Option<u64>,
+        limit: Option<usize>,
+        #[serde(default)]
+        sort: SizeSort,
+    },
+
+    /// Files owned by a user and/or group (Unix evidence only)
+    Owner {
+        uid: Option<u32>,
+        gid: Option<u32>,
+        limit: Option<usize>,
+    },
+
+    /// Documents carrying a tag, optionally narrowed to the results of another query
+    Tagged {
+        tag: String,
+        /// Results of this query are filtered to tagged documents (None = all tagged documents)
+        #[serde(default)]
+        query: Option<Box<Query>>,
+    },
+
     /// Combined query (metadata filters + full-text)
     Combined {
         metadata: Box<Query>,
@@ -58,6 +116,8 @@ pub enum StructuredQueryType {
     SqlTable,
     /// Search JSON paths
     JsonPath,
+    /// Search XML element/attribute paths and values
+    XmlPath,
     /// Search CSV/Excel column names
     ColumnName,
 }
@@ -78,17 +138,39 @@ impl QueryPlanner {
         Self {
             inverted_index,
             extractor_registry,
+            auxiliary_db: None,
         }
     }
 
+    pub fn with_auxiliary_db(mut self, auxiliary_db: Arc<AuxiliaryProjectDb>) -> Self {
+        self.auxiliary_db = Some(auxiliary_db);
+        self
+    }
+
     /// Execute a query
     pub fn execute(&self, query: &Query) -> Result<QueryResult> {
+        self.execute_filtered(query, &SearchFilter::default())
+    }
+
+    /// Execute a query, keeping only documents that satisfy filter
+    /// Full-text, metadata and structured queries apply the filter inside the search, so it
+    /// holds before their limit is taken; other queries are narrowed afterwards
+    pub fn execute_filtered(&self, query: &Query, filter: &SearchFilter) -> Result<QueryResult> {
         let start = std::time::Instant::now();
 
         let hits = match query {
-            Query::FullText { query, limit } => {
-                self.execute_fulltext(query, limit.unwrap_or(100))?
-            }
+            Query::FullText {
+                query,
+                limit,
+                boost_config,
+                fields,
+            } => self.execute_fulltext(
+                query,
+                limit.unwrap_or(100),
+                boost_config.as_ref(),
+                fields.as_deref(),
+                filter,
+            )?,
             Query::Metadata {
                 category,
                 mime_type,
@@ -102,16 +184,43 @@ impl QueryPlanner {
                 *min_size,
                 *max_size,
                 extension.as_deref(),
-                path_prefix.as_deref(),
+                // The path prefix is a clause of the search, so it holds before the limit is taken
+                &SearchFilter {
+                    path_prefix: path_prefix.clone(),
+                    ..filter.clone()
+                },
             )?,
             Query::Structured {
                 structured_type,
                 query,
-            } => self.execute_structured(structured_type, query)?,
+            } => self.execute_structured(structured_type, query, filter)?,
+            Query::SimilarTo { doc_id, limit } => {
+                self.retain_filtered(self.execute_similar(doc_id, limit.unwrap_or(20))?, filter)?
+            }
+            Query::FromArchive { archive_path } => {
+                self.retain_filtered(self.execute_from_archive(archive_path)?, filter)?
+            }
+            Query::LowConfidence { threshold } => {
+                self.retain_filtered(self.execute_low_confidence(*threshold)?, filter)?
+            }
+            Query::SizeRange {
+                min,
+                max,
+                limit,
+                sort,
+            } => self.retain_filtered(
+                self.execute_size_range(*min, *max, limit.unwrap_or(10000), *sort)?,
+                filter,
+            )?,
+            Query::Owner { uid, gid, limit } => self.retain_filtered(
+                self.execute_owner(*uid, *gid, limit.unwrap_or(10000))?,
+                filter,
+            )?,
+            Query::Tagged { tag, query } => self.execute_tagged(tag, query.as_deref(), filter)?,
             Query::Combined { metadata, fulltext } => {
                 // Execute both queries and intersect results
-                let metadata_results = self.execute(metadata)?;
-                let fulltext_results = self.execute(fulltext)?;
+                let metadata_results = self.execute_filtered(metadata, filter)?;
+                let fulltext_results = self.execute_filtered(fulltext, filter)?;
                 self.intersect_results(metadata_results.hits, fulltext_results.hits)
             }
         };
@@ -125,24 +234,111 @@ impl QueryPlanner {
         })
     }
 
+    /// Execute a query, handing hits over in batches as they are loaded
+    /// Full-text hits are streamed from the index; other queries are chunked after execution
+    /// Returns the total number of hits
+    pub fn execute_streaming(
+        &self,
+        query: &Query,
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<TypedHit>) -> Result<()>,
+    ) -> Result<usize> {
+        match query {
+            Query::FullText {
+                query,
+                limit,
+                boost_config,
+                fields,
+            } => self.inverted_index.search_batched(
+                query,
+                limit.unwrap_or(100),
+                boost_config.as_ref(),
+                fields.as_deref(),
+                batch_size,
+                &mut |hits| {
+                    on_batch(
+                        hits.into_iter()
+                            .map(|hit| {
+                                Self::with_binary_offset(Self::search_hit_to_typed(hit), query)
+                            })
+                            .collect(),
+                    )
+                },
+            ),
+            _ => {
+                let result = self.execute(query)?;
+                for chunk in result.hits.chunks(batch_size.max(1)) {
+                    on_batch(chunk.to_vec())?;
+                }
+                Ok(result.total)
+            }
+        }
+    }
+
     /// Execute full-text search
-    fn execute_fulltext(&self, query: &str, limit: usize) -> Result<Vec<TypedHit>> {
-        let search_hits = self.inverted_index.search(query, limit)?;
+    fn execute_fulltext(
+        &self,
+        query: &str,
+        limit: usize,
+        boost: Option<&QueryBoost>,
+        fields: Option<&[String]>,
+        filter: &SearchFilter,
+    ) -> Result<Vec<TypedHit>> {
+        Ok(self
+            .search_typed(query, limit, boost, fields, filter)?
+            .into_iter()
+            .map(|hit| Self::with_binary_offset(hit, query))
+            .collect())
+    }
+
+    /// Run a query string against the index without locating matches in binary files
+    /// Metadata and structured queries go through here, since their terms aren't file content
+    fn search_typed(
+        &self,
+        query: &str,
+        limit: usize,
+        boost: Option<&QueryBoost>,
+        fields: Option<&[String]>,
+        filter: &SearchFilter,
+    ) -> Result<Vec<TypedHit>> {
+        let search_hits = self
+            .inverted_index
+            .search_filtered(query, limit, boost, fields, filter)?;
         Ok(search_hits
             .into_iter()
             .map(Self::search_hit_to_typed)
             .collect())
     }
 
+    /// For binary files, find where the first query term matches so the hex viewer can jump to it
+    /// Each binary hit means a scan of its file, so this is only done for full-text queries
+    fn with_binary_offset(mut hit: TypedHit, query: &str) -> TypedHit {
+        if hit.category == FileCategory::Binary {
+            hit.offset = Self::query_terms(query)
+                .find_map(|term| binary_strings::locate_in_file(&hit.path, term));
+        }
+        hit
+    }
+
+    /// Plain search terms from a query string, without field prefixes, operators or quotes
+    fn query_terms(query: &str) -> impl Iterator<Item = &str> {
+        query
+            .split_whitespace()
+            .filter(|word| !matches!(*word, "AND" | "OR" | "NOT"))
+            .map(|word| word.rsplit(':').next().unwrap_or(word))
+            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+            .filter(|word| !word.is_empty())
+    }
+
     /// Execute metadata filter
     fn execute_metadata_filter(
         &self,
         category: Option<&FileCategory>,
         mime_type: Option<&str>,
-        _min_size: Option<u64>,
-        _max_size: Option<u64>,
+        min_size: Option<u64>,
+        max_size: Option<u64>,
         extension: Option<&str>,
-        path_prefix: Option<&str>,
+        filter: &SearchFilter,
     ) -> Result<Vec<TypedHit>> {
         // Build Tantivy query for metadata filtering
         let mut query_parts = Vec::new();
@@ -159,35 +355,19 @@ impl QueryPlanner {
             query_parts.push(format!("extension:{}", ext));
         }
 
-        if let Some(prefix) = path_prefix {
-            query_parts.push(format!("path:{}", prefix));
+        // Inclusive range on the indexed size, open-ended where a bound is missing
+        if min_size.is_some() || max_size.is_some() {
+            let bound = |size: Option<u64>| size.map_or("*".to_string(), |s| s.to_string());
+            query_parts.push(format!("size:[{} TO {}]", bound(min_size), bound(max_size)));
         }
 
-        // For size filtering, we'll need to post-filter since Tantivy range queries
-        // are more complex. For now, just do the text filters.
         let query_str = if query_parts.is_empty() {
             "*".to_string()
         } else {
             query_parts.join(" AND ")
         };
 
-        let hits = self.execute_fulltext(&query_str, 10000)?;
-
-        // Post-filter by path prefix if specified (more precise matching)
-        let hits = if let Some(prefix) = path_prefix {
-            hits.into_iter()
-                .filter(|hit| {
-                    hit.metadata
-                        .path
-                        .to_string_lossy()
-                        .starts_with(prefix)
-                })
-                .collect()
-        } else {
-            hits
-        };
-
-        Ok(hits)
+        self.search_typed(&query_str, 10000, None, None, filter)
     }
 
     /// Execute structured data query
@@ -195,16 +375,124 @@ impl QueryPlanner {
         &self,
         structured_type: &StructuredQueryType,
         query: &str,
+        filter: &SearchFilter,
     ) -> Result<Vec<TypedHit>> {
         let field = match structured_type {
             StructuredQueryType::SqlTable => "tables",
-            StructuredQueryType::JsonPath => "paths",
+            StructuredQueryType::JsonPath | StructuredQueryType::XmlPath => "paths",
             StructuredQueryType::ColumnName => "columns",
         };
 
         // Search in the specific structured field
         let query_str = format!("{}:{}", field, query);
-        self.execute_fulltext(&query_str, 100)
+        self.search_typed(&query_str, 100, None, None, filter)
+    }
+
+    /// Execute similarity search against a reference document
+    fn execute_similar(&self, doc_id: &str, limit: usize) -> Result<Vec<TypedHit>> {
+        let search_hits = self.inverted_index.search_similar(doc_id, limit)?;
+        Ok(search_hits
+            .into_iter()
+            .map(Self::search_hit_to_typed)
+            .collect())
+    }
+
+    /// Execute archive origin filter
+    fn execute_from_archive(&self, archive_path: &str) -> Result<Vec<TypedHit>> {
+        let search_hits = self.inverted_index.search_by_archive(archive_path, 10000)?;
+        Ok(search_hits
+            .into_iter()
+            .map(Self::search_hit_to_typed)
+            .collect())
+    }
+
+    /// Execute detection confidence filter
+    fn execute_low_confidence(&self, threshold: f32) -> Result<Vec<TypedHit>> {
+        let search_hits = self
+            .inverted_index
+            .search_low_confidence(threshold, 10000)?;
+        Ok(search_hits
+            .into_iter()
+            .map(Self::search_hit_to_typed)
+            .collect())
+    }
+
+    /// Execute size range filter
+    fn execute_size_range(
+        &self,
+        min: Option<u64>,
+        max: Option<u64>,
+        limit: usize,
+        sort: SizeSort,
+    ) -> Result<Vec<TypedHit>> {
+        let search_hits = self
+            .inverted_index
+            .search_by_size_range(min, max, limit, sort)?;
+        Ok(search_hits
+            .into_iter()
+            .map(Self::search_hit_to_typed)
+            .collect())
+    }
+
+    /// Execute owner filter
+    fn execute_owner(
+        &self,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        limit: usize,
+    ) -> Result<Vec<TypedHit>> {
+        let search_hits = self.inverted_index.search_by_owner(uid, gid, limit)?;
+        Ok(search_hits
+            .into_iter()
+            .map(Self::search_hit_to_typed)
+            .collect())
+    }
+
+    /// Execute tag filter, joining the tag store with the index
+    /// The tagged IDs become part of the filter, so an inner query's limit counts only
+    /// tagged documents
+    fn execute_tagged(
+        &self,
+        tag: &str,
+        query: Option<&Query>,
+        filter: &SearchFilter,
+    ) -> Result<Vec<TypedHit>> {
+        let auxiliary_db = self
+            .auxiliary_db
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Tag queries need an open project database"))?;
+        let filter = filter
+            .clone()
+            .restrict_to_ids(auxiliary_db.docs_with_tag(tag)?);
+
+        match query {
+            Some(query) => Ok(self.execute_filtered(query, &filter)?.hits),
+            None => {
+                let doc_ids: Vec<String> = filter.doc_ids.iter().flatten().cloned().collect();
+                let hits = self
+                    .inverted_index
+                    .search_by_ids(&doc_ids)?
+                    .into_iter()
+                    .map(Self::search_hit_to_typed)
+                    .collect();
+                self.retain_filtered(hits, &filter)
+            }
+        }
+    }
+
+    /// Drop hits whose documents don't satisfy filter
+    fn retain_filtered(
+        &self,
+        mut hits: Vec<TypedHit>,
+        filter: &SearchFilter,
+    ) -> Result<Vec<TypedHit>> {
+        if filter.is_empty() {
+            return Ok(hits);
+        }
+        let ids: Vec<String> = hits.iter().map(|hit| hit.id.clone()).collect();
+        let matching = self.inverted_index.filter_ids(&ids, filter)?;
+        hits.retain(|hit| matching.contains(&hit.id));
+        Ok(hits)
     }
 
     /// Intersect two result sets
@@ -215,7 +503,7 @@ impl QueryPlanner {
     }
 
     /// Convert SearchHit to TypedHit
-    fn search_hit_to_typed(hit: SearchHit) -> TypedHit {
+    pub(super) fn search_hit_to_typed(hit: SearchHit) -> TypedHit {
         TypedHit {
             id: hit.id,
             path: hit.path,
@@ -224,9 +512,19 @@ impl QueryPlanner {
             snippet: hit.snippet,
             score: hit.score,
             schema: None,
+            offset: None,
+            metadata: hit.metadata,
         }
     }
 
+    /// Look up an indexed file by the Keccak-256 hash of its content
+    pub fn lookup_keccak256(&self, keccak256: &str) -> Result<Option<TypedHit>> {
+        Ok(self
+            .inverted_index
+            .search_by_keccak256(keccak256)?
+            .map(Self::search_hit_to_typed))
+    }
+
     /// Lazy deep extraction on demand
     /// When a user wants detailed data from a specific file, extract it
     pub fn extract_deep(
@@ -256,12 +554,16 @@ impl QueryPlanner {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::index::schema::FileDocument;
+    use tempfile::TempDir;
 
     #[test]
     fn test_query_serialization() {
         let query = Query::FullText {
             query: "test".to_string(),
             limit: Some(10),
+            boost_config: None,
+            fields: None,
         };
 
         let json = serde_json::to_string(&query).unwrap();
@@ -269,4 +571,390 @@ mod tests {
 
         matches!(deserialized, Query::FullText { .. });
     }
+
+    fn make_doc(id: &str, content: &str) -> FileDocument {
+        let mut doc = FileDocument::text_fixture(id, format!("/evidence/{}.txt", id), content);
+        doc.metadata.hash = id.to_string();
+        doc
+    }
+
+    #[test]
+    fn test_similar_query_ranks_near_duplicate_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = Arc::new(InvertedIndex::create(temp_dir.path()).unwrap());
+
+        index
+            .add_document(&make_doc(
+                "reference",
+                "wire transfer to offshore account approved by director meeting harbor",
+            ))
+            .unwrap();
+        index
+            .add_document(&make_doc(
+                "near_duplicate",
+                "wire transfer to offshore account approved by director after meeting",
+            ))
+            .unwrap();
+        index
+            .add_document(&make_doc(
+                "unrelated",
+                "grocery list with apples bananas and approved coupons",
+            ))
+            .unwrap();
+        index.commit().unwrap();
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()));
+        let result = planner
+            .execute(&Query::SimilarTo {
+                doc_id: "reference".to_string(),
+                limit: Some(10),
+            })
+            .unwrap();
+
+        assert!(!result.hits.is_empty());
+        assert_eq!(result.hits[0].id, "near_duplicate");
+        assert!(result.hits.iter().all(|hit| hit.id != "reference"));
+    }
+
+    #[test]
+    fn test_low_confidence_query_filters_by_threshold() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = Arc::new(InvertedIndex::create(temp_dir.path()).unwrap());
+
+        let mut magic = make_doc("magic", "sqlite pages");
+        magic.metadata.confidence = 1.0;
+        let mut heuristic = make_doc("heuristic", "looks like json");
+        heuristic.metadata.confidence = 0.7;
+        let mut csv = make_doc("csv", "a,b,c");
+        csv.metadata.confidence = 0.5;
+        for doc in [&magic, &heuristic, &csv] {
+            index.add_document(doc).unwrap();
+        }
+        index.commit().unwrap();
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()));
+        let result = planner
+            .execute(&Query::LowConfidence { threshold: 0.7 })
+            .unwrap();
+
+        let ids: Vec<_> = result.hits.iter().map(|hit| hit.id.as_str()).collect();
+        assert_eq!(ids, vec!["csv"]);
+    }
+
+    #[test]
+    fn test_binary_offset_only_located_for_fulltext() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = Arc::new(InvertedIndex::create(&temp_dir.path().join("index")).unwrap());
+
+        let dump = temp_dir.path().join("memory.dmp");
+        let mut bytes = vec![0u8; 64];
+        bytes.extend_from_slice(b"PASSWORD=hunter2");
+        std::fs::write(&dump, &bytes).unwrap();
+        let mut doc = make_doc("dump", "password hunter2");
+        doc.metadata.path = dump;
+        doc.metadata.category = FileCategory::Binary;
+        index.add_document(&doc).unwrap();
+        index.commit().unwrap();
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()));
+        let fulltext = planner
+            .execute(&Query::FullText {
+                query: "password".to_string(),
+                limit: Some(10),
+                boost_config: None,
+                fields: None,
+            })
+            .unwrap();
+        assert_eq!(fulltext.hits[0].offset, Some(64));
+
+        let metadata = planner
+            .execute(&Query::Metadata {
+                category: Some(FileCategory::Binary),
+                mime_type: None,
+                min_size: None,
+                max_size: None,
+                extension: None,
+                path_prefix: None,
+            })
+            .unwrap();
+        assert_eq!(metadata.total, 1);
+        assert_eq!(metadata.hits[0].offset, None);
+    }
+
+    #[test]
+    fn test_metadata_query_applies_size_bounds() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = Arc::new(InvertedIndex::create(temp_dir.path()).unwrap());
+        for (id, size) in [("small", 10), ("medium", 500), ("large", 9000)] {
+            let mut doc = make_doc(id, "log");
+            doc.metadata.size = size;
+            index.add_document(&doc).unwrap();
+        }
+        index.commit().unwrap();
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()));
+        let ids = |min_size, max_size| {
+            let result = planner
+                .execute(&Query::Metadata {
+                    category: None,
+                    mime_type: None,
+                    min_size,
+                    max_size,
+                    extension: Some("txt".to_string()),
+                    path_prefix: None,
+                })
+                .unwrap();
+            let mut ids: Vec<_> = result.hits.into_iter().map(|hit| hit.id).collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(ids(Some(500), None), ["large", "medium"]);
+        assert_eq!(ids(None, Some(500)), ["medium", "small"]);
+        assert_eq!(ids(Some(11), Some(8999)), ["medium"]);
+        assert_eq!(ids(None, None).len(), 3);
+    }
+
+    #[test]
+    fn test_path_boost_ranks_file_name_match_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = Arc::new(InvertedIndex::create(temp_dir.path()).unwrap());
+
+        let mut browser_db = make_doc("browser_db", "binary page data");
+        browser_db.metadata.path = PathBuf::from("/evidence/Chrome/History");
+        index.add_document(&browser_db).unwrap();
+        index
+            .add_document(&make_doc("notes", "notes about the browsing history"))
+            .unwrap();
+        index.commit().unwrap();
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()));
+        let search = |boost_config| {
+            planner
+                .execute(&Query::FullText {
+                    query: "history".to_string(),
+                    limit: Some(10),
+                    boost_config,
+                    fields: None,
+                })
+                .unwrap()
+        };
+
+        let result = search(None);
+        assert_eq!(result.total, 2);
+        assert_eq!(result.hits[0].id, "browser_db");
+
+        let content_first = QueryBoost {
+            path: 0.1,
+            content: 5.0,
+            ..QueryBoost::forensic_defaults()
+        };
+        let result = search(Some(content_first));
+        assert_eq!(result.hits[0].id, "notes");
+    }
+
+    #[test]
+    fn test_streaming_batches_match_full_result() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = Arc::new(InvertedIndex::create(temp_dir.path()).unwrap());
+        for i in 0..25 {
+            index
+                .add_document(&make_doc(&format!("doc{}", i), "quarterly ledger entry"))
+                .unwrap();
+        }
+        index.commit().unwrap();
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()));
+        let query = Query::FullText {
+            query: "ledger".to_string(),
+            limit: Some(100),
+            boost_config: None,
+            fields: None,
+        };
+
+        let mut batches = Vec::new();
+        let total = planner
+            .execute_streaming(&query, 10, &mut |hits| {
+                batches.push(hits);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(total, 25);
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![10, 10, 5]
+        );
+
+        let streamed: Vec<String> = batches.into_iter().flatten().map(|hit| hit.id).collect();
+        let full: Vec<String> = planner
+            .execute(&query)
+            .unwrap()
+            .hits
+            .into_iter()
+            .map(|hit| hit.id)
+            .collect();
+        assert_eq!(streamed, full);
+    }
+
+    #[test]
+    fn test_forward_slash_prefix_matches_windows_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = Arc::new(InvertedIndex::create(temp_dir.path()).unwrap());
+
+        let mut report = make_doc("report", "quarterly numbers");
+        report.metadata.path = PathBuf::from(r"C:\Users\alice\Documents\report.txt");
+        let mut other = make_doc("other", "someone else");
+        other.metadata.path = PathBuf::from(r"C:\Users\bob\notes.txt");
+        index.add_document(&report).unwrap();
+        index.add_document(&other).unwrap();
+        index.commit().unwrap();
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()));
+        let search = |prefix: &str| {
+            planner
+                .execute(&Query::Metadata {
+                    category: None,
+                    mime_type: None,
+                    min_size: None,
+                    max_size: None,
+                    extension: None,
+                    path_prefix: Some(prefix.to_string()),
+                })
+                .unwrap()
+        };
+
+        let result = search("C:/Users/alice");
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].id, "report");
+        // The original path is kept for display
+        assert_eq!(
+            result.hits[0].path,
+            PathBuf::from(r"C:\Users\alice\Documents\report.txt")
+        );
+        assert_eq!(search(r"C:\Users").hits.len(), 2);
+    }
+
+    #[test]
+    fn test_size_range_filters_and_sorts_largest_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = Arc::new(InvertedIndex::create(temp_dir.path()).unwrap());
+
+        for (id, size) in [
+            ("tiny", 512),
+            ("medium", 15_000_000),
+            ("large", 80_000_000),
+            ("huge", 2_000_000_000),
+            ("small", 10_000_000),
+        ] {
+            let mut doc = make_doc(id, "evidence");
+            doc.metadata.size = size;
+            index.add_document(&doc).unwrap();
+        }
+        index.commit().unwrap();
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()));
+        let search = |sort| {
+            planner
+                .execute(&Query::SizeRange {
+                    min: Some(10_000_000),
+                    max: Some(100_000_000),
+                    limit: None,
+                    sort,
+                })
+                .unwrap()
+                .hits
+                .into_iter()
+                .map(|hit| hit.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            search(SizeSort::Descending),
+            vec!["large", "medium", "small"]
+        );
+        assert_eq!(
+            search(SizeSort::Ascending),
+            vec!["small", "medium", "large"]
+        );
+    }
+
+    #[test]
+    fn test_tagged_query_returns_exactly_tagged_documents() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = Arc::new(InvertedIndex::create(&temp_dir.path().join("index")).unwrap());
+        for (id, content) in [
+            ("invoice", "wire transfer invoice"),
+            ("ledger", "wire transfer transfer ledger"),
+            ("memo", "lunch memo"),
+        ] {
+            index.add_document(&make_doc(id, content)).unwrap();
+        }
+        index.commit().unwrap();
+
+        let auxiliary_db = Arc::new(AuxiliaryProjectDb::init(temp_dir.path().join("aux")).unwrap());
+        let tagged = auxiliary_db
+            .add_tags_bulk(
+                vec!["invoice".to_string(), "memo".to_string()],
+                vec!["suspect".to_string(), "reviewed".to_string()],
+            )
+            .unwrap();
+        assert_eq!(tagged, 4);
+        // Re-tagging adds nothing
+        let retagged = auxiliary_db
+            .add_tags_bulk(vec!["memo".to_string()], vec!["suspect".to_string()])
+            .unwrap();
+        assert_eq!(retagged, 0);
+        assert_eq!(
+            auxiliary_db.get_all_tags().unwrap(),
+            vec!["reviewed", "suspect"]
+        );
+
+        let planner = QueryPlanner::new(index, Arc::new(ExtractorRegistry::new()))
+            .with_auxiliary_db(auxiliary_db);
+        let ids = |query: Query| {
+            let mut ids: Vec<_> = planner
+                .execute(&query)
+                .unwrap()
+                .hits
+                .into_iter()
+                .map(|hit| hit.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(
+            ids(Query::Tagged {
+                tag: "suspect".to_string(),
+                query: None,
+            }),
+            vec!["invoice", "memo"]
+        );
+        assert_eq!(
+            ids(Query::Tagged {
+                tag: "suspect".to_string(),
+                query: Some(Box::new(Query::FullText {
+                    query: "transfer".to_string(),
+                    limit: None,
+                    boost_config: None,
+                    fields: None,
+                })),
+            }),
+            vec!["invoice"]
+        );
+        // ledger outranks invoice, so filtering after the limit would leave nothing
+        assert_eq!(
+            ids(Query::Tagged {
+                tag: "suspect".to_string(),
+                query: Some(Box::new(Query::FullText {
+                    query: "transfer".to_string(),
+                    limit: Some(1),
+                    boost_config: None,
+                    fields: None,
+                })),
+            }),
+            vec!["invoice"]
+        );
+    }
 }
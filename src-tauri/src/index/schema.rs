@@ -1,6 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -28,6 +28,10 @@ pub struct FileDocument {
 
     /// Archive metadata (if file was unpacked from archive)
     pub archive_source: Option<ArchiveSource>,
+
+    /// Searchable key-value fields from the type-specific extractor (e.g. the chat app)
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
 }
 
 /// Image metadata stored in document
@@ -38,6 +42,9 @@ pub struct ImageMetadata {
     pub format: String,
     pub has_alpha: bool,
     pub thumbnail_path: Option<PathBuf>,
+    /// Recognised as an image by its magic bytes despite a non-image extension
+    #[serde(default)]
+    pub extension_mismatch: bool,
 }
 
 /// Archive source information
@@ -67,6 +74,10 @@ pub struct DocumentMetadata {
     /// SHA256 hash for change detection
     pub hash: String,
 
+    /// Keccak-256 hash, for matching blockchain and IPFS identifiers
+    #[serde(default)]
+    pub keccak256: Option<String>,
+
     /// MIME type detected via magic bytes
     pub mime_type: String,
 
@@ -76,9 +87,26 @@ pub struct DocumentMetadata {
     /// Magic header bytes (first 16 bytes in hex)
     pub magic_header: String,
 
+    /// Confidence of the type detection (0.0-1.0)
+    pub confidence: f32,
+
     /// File extension (if any)
     pub extension: Option<String>,
 
+    /// Owning user and group IDs (Unix only)
+    #[serde(default)]
+    pub owner_uid: Option<u32>,
+    #[serde(default)]
+    pub owner_gid: Option<u32>,
+
+    /// POSIX ACL entries beyond the mode bits (Linux only), e.g. "user:alice:rw-"
+    #[serde(default)]
+    pub acl: Vec<String>,
+
+    /// Zero-byte file, indexed from metadata alone since there is nothing to extract
+    #[serde(default)]
+    pub empty: bool,
+
     /// Indexing status
     pub indexed: bool,
 
@@ -86,6 +114,41 @@ pub struct DocumentMetadata {
     pub indexed_at: Option<DateTime<Utc>>,
 }
 
+#[cfg(test)]
+impl FileDocument {
+    /// Indexed plain-text file for tests; override fields with struct update syntax
+    pub(crate) fn text_fixture(id: &str, path: impl Into<PathBuf>, content: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            metadata: DocumentMetadata {
+                path: path.into(),
+                size: content.len() as u64,
+                modified: Utc::now(),
+                created: None,
+                hash: String::new(),
+                keccak256: None,
+                mime_type: "text/plain".to_string(),
+                category: FileCategory::Text,
+                magic_header: String::new(),
+                confidence: 1.0,
+                extension: Some("txt".to_string()),
+                owner_uid: None,
+                owner_gid: None,
+                acl: Vec::new(),
+                empty: false,
+                indexed: true,
+                indexed_at: None,
+            },
+            structured: None,
+            content: Some(content.to_string()),
+            preview: None,
+            image_metadata: None,
+            archive_source: None,
+            fields: HashMap::new(),
+        }
+    }
+}
+
 /// High-level file categories for efficient filtering
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -99,6 +162,9 @@ pub enum FileCategory {
     /// Office documents (XLSX, DOCX, PDF, etc.)
     Document,
 
+    /// Email messages and mailboxes (EML, MBOX, Outlook MSG)
+    Email,
+
     /// Plain text files
     Text,
 
@@ -156,6 +222,8 @@ pub enum StructuredData {
         root_element: String,
         namespaces: Vec<String>,
         element_count: usize,
+        /// Element and attribute paths with their values (capped)
+        paths: Vec<XmlPath>,
     },
 
     /// Parquet structure
@@ -170,6 +238,26 @@ pub enum StructuredData {
         key_count: u64,
         approximate_size: u64,
     },
+
+    /// Email store such as an Outlook PST/OST mailbox
+    EmailDatabase {
+        format: String,
+        folder_count: u64,
+        message_count: u64,
+        unique_senders: Vec<String>,
+        /// Earliest and latest message time, if any message had one
+        date_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+        folders: Vec<EmailFolder>,
+    },
+}
+
+/// Table and column names across all indexed SQLite databases
+/// Each name maps to the database files that contain it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructuredCatalog {
+    pub tables: BTreeMap<String, Vec<PathBuf>>,
+    pub columns: BTreeMap<String, Vec<PathBuf>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -186,6 +274,9 @@ pub struct ColumnInfo {
     pub data_type: String,
     pub nullable: bool,
     pub primary_key: bool,
+    /// A few distinct values from the column, when sampling is enabled ("<blob>" for binary)
+    #[serde(default)]
+    pub sample_values: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -200,6 +291,25 @@ pub struct JsonPath {
     pub sample: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XmlPath {
+    /// XPath-like expression (e.g., "/users/user/@id", "/users/user/name")
+    pub path: String,
+
+    /// Element text or attribute value (truncated)
+    pub value: Option<String>,
+}
+
+impl XmlPath {
+    /// Searchable form: "path=value" for values, bare path for structure
+    pub fn to_search_string(&self) -> String {
+        match &self.value {
+            Some(value) => format!("{}={}", self.path, value),
+            None => self.path.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum JsonValueType {
@@ -223,6 +333,17 @@ pub struct SheetInfo {
     pub name: String,
     pub headers: Vec<String>,
     pub row_count: u64,
+
+    /// Only the first rows were scanned; row_count comes from the sheet's dimension record
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailFolder {
+    /// Folder names from the top of the mailbox, joined with `/`
+    pub path: String,
+    pub message_count: u64,
 }
 
 /// Search hit with type information
@@ -248,6 +369,24 @@ pub struct TypedHit {
 
     /// Schema information (if structured)
     pub schema: Option<String>,
+
+    /// Byte offset of the match inside a binary file, for the hex viewer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+
+    /// Size and modification time recorded when the file was indexed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HitMetadata>,
+}
+
+/// File metadata stored in the index, as it was when the file was indexed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HitMetadata {
+    pub size: u64,
+    pub modified: DateTime<Utc>,
+    /// When the file was indexed (absent in indexes built before it was stored)
+    #[serde(default)]
+    pub indexed_at: Option<DateTime<Utc>>,
 }
 
 /// Index statistics
@@ -277,6 +416,8 @@ impl FileCategory {
             m if m.contains("vnd.openxmlformats") => Self::Document,
             m if m.contains("msword") => Self::Document,
             m if m.contains("vnd.ms-excel") => Self::Document,
+            m if m.contains("vnd.ms-powerpoint") => Self::Document,
+            "application/x-ole-storage" => Self::Document,
 
             // Text
             m if m.starts_with("text/") => Self::Text,
@@ -0,0 +1,222 @@
+use super::inverted::InvertedIndex;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// MoreLikeThis ranks by BM25, so a few times more candidates are fetched than returned
+/// to leave room for the cosine ranking to reorder them
+const CANDIDATE_MULTIPLIER: usize = 4;
+
+/// A document whose content resembles the reference document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarityResult {
+    pub doc_id: String,
+    pub path: PathBuf,
+    /// TF-IDF cosine similarity, from 0 (no terms in common) to 1 (same term distribution)
+    pub similarity: f32,
+}
+
+/// Documents most similar in content to doc_id, most similar first
+/// Candidates come from a MoreLikeThis query built from the reference document's top
+/// TF-IDF terms, and are then ranked by the cosine of their TF-IDF vectors. De-duplicated
+/// aliases hold no content, so an alias is compared through its canonical document, and
+/// aliases are reported alongside the document whose content they share
+pub fn find_similar_documents(
+    doc_id: &str,
+    inverted_index: &InvertedIndex,
+    limit: usize,
+) -> Result<Vec<SimilarityResult>> {
+    let info = inverted_index
+        .stored_file_info(doc_id)?
+        .ok_or_else(|| anyhow::anyhow!("Document not found: {}", doc_id))?;
+    let copies = content_copies(inverted_index, &info.hash)?;
+    let reference_id = copies.first().map(|(id, _)| id.as_str()).unwrap_or(doc_id);
+
+    let reference = inverted_index
+        .content_term_weights(reference_id)?
+        .ok_or_else(|| anyhow::anyhow!("Document not found: {}", reference_id))?;
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Exact copies of the reference, whatever their content
+    let mut seen: HashSet<String> = HashSet::from([doc_id.to_string()]);
+    let mut results = Vec::new();
+    for (id, path) in &copies {
+        if seen.insert(id.clone()) {
+            results.push(SimilarityResult {
+                doc_id: id.clone(),
+                path: path.clone(),
+                similarity: 1.0,
+            });
+        }
+    }
+
+    if !reference.is_empty() {
+        let candidates = inverted_index
+            .search_similar(reference_id, limit.saturating_mul(CANDIDATE_MULTIPLIER))?;
+        for candidate in candidates {
+            if seen.contains(&candidate.id) {
+                continue;
+            }
+            let Some(weights) = inverted_index.content_term_weights(&candidate.id)? else {
+                continue;
+            };
+            let similarity = cosine_similarity(&reference, &weights);
+            if similarity <= 0.0 {
+                continue;
+            }
+
+            // Candidates hold content, so any copies of theirs are aliases sharing it
+            let hash = inverted_index
+                .stored_file_info(&candidate.id)?
+                .map(|info| info.hash)
+                .unwrap_or_default();
+            let mut candidate_copies = content_copies(inverted_index, &hash)?;
+            if candidate_copies.first().map(|(id, _)| id) != Some(&candidate.id) {
+                candidate_copies = vec![(candidate.id, candidate.path)];
+            }
+            for (id, path) in candidate_copies {
+                if seen.insert(id.clone()) {
+                    results.push(SimilarityResult {
+                        doc_id: id,
+                        path,
+                        similarity,
+                    });
+                }
+            }
+        }
+    }
+
+    results.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// (id, path) of every committed document with this content hash, the one holding the
+/// content first; empty for documents without a hash
+fn content_copies(inverted_index: &InvertedIndex, hash: &str) -> Result<Vec<(String, PathBuf)>> {
+    if hash.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(inverted_index
+        .canonical_doc_for_hash(hash)?
+        .map(|copies| {
+            std::iter::once(copies.canonical)
+                .chain(copies.aliases)
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Cosine of the angle between two sparse term-weight vectors
+fn cosine_similarity(a: &HashMap<String, f32>, b: &HashMap<String, f32>) -> f32 {
+    let (smaller, larger) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let dot: f32 = smaller
+        .iter()
+        .filter_map(|(term, weight)| larger.get(term).map(|other| weight * other))
+        .sum();
+    let norm = |v: &HashMap<String, f32>| v.values().map(|w| w * w).sum::<f32>().sqrt();
+
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        (dot / denominator).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::schema::FileDocument;
+    use tempfile::TempDir;
+
+    fn make_doc(id: &str, content: &str) -> FileDocument {
+        FileDocument::text_fixture(id, format!("/evidence/{}.txt", id), content)
+    }
+
+    #[test]
+    fn test_finds_copies_and_variations_ranked_by_similarity() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path()).unwrap();
+        for (id, content) in [
+            (
+                "report",
+                "quarterly audit report flags invoices paid to shell company",
+            ),
+            (
+                "copy",
+                "quarterly audit report flags invoices paid to shell company",
+            ),
+            (
+                "variation",
+                "draft audit report flags invoices paid to vendor",
+            ),
+            (
+                "unrelated",
+                "holiday party planning with invoices for catering",
+            ),
+            ("disjoint", "weather forecast sunny tomorrow"),
+        ] {
+            index.add_document(&make_doc(id, content)).unwrap();
+        }
+        index.commit().unwrap();
+
+        let results = find_similar_documents("report", &index, 10).unwrap();
+
+        let ids: Vec<&str> = results.iter().map(|r| r.doc_id.as_str()).collect();
+        assert_eq!(ids, ["copy", "variation", "unrelated"]);
+        assert!((results[0].similarity - 1.0).abs() < 1e-4);
+        assert!(results[1].similarity > results[2].similarity);
+        assert_eq!(results[0].path, PathBuf::from("/evidence/copy.txt"));
+
+        assert_eq!(
+            find_similar_documents("report", &index, 1).unwrap().len(),
+            1
+        );
+        assert!(find_similar_documents("missing", &index, 10).is_err());
+    }
+
+    #[test]
+    fn test_deduplicated_copies_are_fully_similar() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = InvertedIndex::create(temp_dir.path())
+            .unwrap()
+            .with_deduplication(true);
+        let report = "quarterly audit report flags invoices paid to shell company";
+        let variation = "draft audit report flags invoices paid to vendor";
+        for (id, hash, content) in [
+            ("report", "h1", report),
+            ("copy", "h1", report),
+            ("variation", "h2", variation),
+            ("variation_copy", "h2", variation),
+        ] {
+            let mut doc = make_doc(id, content);
+            doc.metadata.hash = hash.to_string();
+            index.add_document(&doc).unwrap();
+        }
+        index.commit().unwrap();
+
+        let similar = |doc_id: &str| -> Vec<(String, f32)> {
+            find_similar_documents(doc_id, &index, 10)
+                .unwrap()
+                .into_iter()
+                .map(|r| (r.doc_id, r.similarity))
+                .collect()
+        };
+
+        // The alias is compared through the document holding its content
+        for (doc_id, copy_id) in [("copy", "report"), ("report", "copy")] {
+            let results = similar(doc_id);
+            assert_eq!(results.len(), 3);
+            assert_eq!(results[0], (copy_id.to_string(), 1.0));
+            assert_eq!(results[1].0, "variation");
+            assert_eq!(results[2].0, "variation_copy");
+            assert_eq!(results[1].1, results[2].1);
+            assert!(results[1].1 < 1.0);
+        }
+    }
+}
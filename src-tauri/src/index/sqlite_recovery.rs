@@ -0,0 +1,465 @@
+use anyhow::{bail, Context, Result};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+const HEADER_MAGIC: &[u8] = b"SQLite format 3\0";
+const HEADER_SIZE: usize = 100;
+
+/// B-tree page types (first byte of the page header)
+const TABLE_INTERIOR_PAGE: u8 = 0x05;
+const TABLE_LEAF_PAGE: u8 = 0x0D;
+
+/// A record left behind on a page SQLite has released to its freelist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveredRow {
+    /// Table whose column count matches the record, when exactly one does
+    pub table_guess: Option<String>,
+
+    /// The record as stored on the page (header and body, without any overflow)
+    pub raw_bytes: Vec<u8>,
+
+    /// One entry per column; NULL as "NULL", blobs hex encoded
+    pub decoded_fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// Reads deleted rows straight from the pages of a SQLite file
+/// Pages freed by DELETE or DROP TABLE keep their old cells until SQLite reuses them,
+/// unless secure_delete was on. Only cells still listed in a freed page's cell
+/// pointer array are decoded, so rows removed one at a time from a page that stayed
+/// in use are not recovered
+pub struct SqliteRecovery;
+
+impl SqliteRecovery {
+    pub fn recover_deleted_rows(path: &Path) -> Result<Vec<RecoveredRow>> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        // Safety: the mapping is read-only; evidence files aren't expected to change while parsed
+        let data = unsafe { Mmap::map(&file) }
+            .with_context(|| format!("Failed to map {}", path.display()))?;
+
+        let db = DatabaseFile::parse(&data)?;
+        let tables = db.table_column_counts();
+
+        let mut rows = Vec::new();
+        for page_number in db.freelist_leaf_pages() {
+            let Some(page) = db.page(page_number) else {
+                continue;
+            };
+            if page[0] != TABLE_LEAF_PAGE {
+                continue;
+            }
+
+            for payload in db.leaf_payloads(page, 0) {
+                let Some(fields) = db.decode_record(payload) else {
+                    continue;
+                };
+
+                let mut candidates = tables
+                    .iter()
+                    .filter(|(_, columns)| *columns == fields.len())
+                    .map(|(name, _)| name);
+                let table_guess = match (candidates.next(), candidates.next()) {
+                    (Some(name), None) => Some(name.clone()),
+                    _ => None,
+                };
+
+                rows.push(RecoveredRow {
+                    table_guess,
+                    raw_bytes: payload.to_vec(),
+                    decoded_fields: fields,
+                });
+            }
+        }
+
+        Ok(rows)
+    }
+}
+
+struct DatabaseFile<'a> {
+    data: &'a [u8],
+    page_size: usize,
+    /// Page size minus the bytes reserved at the end of each page
+    usable_size: usize,
+    encoding: TextEncoding,
+}
+
+impl<'a> DatabaseFile<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE || !data.starts_with(HEADER_MAGIC) {
+            bail!("Not a SQLite database");
+        }
+
+        let page_size = match u16::from_be_bytes([data[16], data[17]]) {
+            1 => 65536,
+            size => size as usize,
+        };
+        if page_size < 512 || !page_size.is_power_of_two() {
+            bail!("Invalid page size {}", page_size);
+        }
+
+        let reserved = data[20] as usize;
+        let encoding = match read_u32(data, 56) {
+            2 => TextEncoding::Utf16Le,
+            3 => TextEncoding::Utf16Be,
+            _ => TextEncoding::Utf8,
+        };
+
+        Ok(Self {
+            data,
+            page_size,
+            usable_size: page_size.saturating_sub(reserved),
+            encoding,
+        })
+    }
+
+    /// Pages are numbered from 1; page 1 includes the file header
+    fn page(&self, number: u32) -> Option<&'a [u8]> {
+        let start = (number as usize).checked_sub(1)? * self.page_size;
+        self.data.get(start..start + self.page_size)
+    }
+
+    /// Leaf pages listed by the freelist trunk chain
+    /// Trunk pages themselves are skipped: their header is overwritten by the leaf list
+    fn freelist_leaf_pages(&self) -> Vec<u32> {
+        let mut leaves = Vec::new();
+        let mut visited = HashSet::new();
+        let mut trunk = read_u32(self.data, 32);
+
+        while trunk != 0 && visited.insert(trunk) {
+            let Some(page) = self.page(trunk) else {
+                break;
+            };
+            let count = read_u32(page, 4) as usize;
+            // A corrupt count can't point past the end of the trunk page
+            let count = count.min((self.usable_size - 8) / 4);
+            leaves.extend((0..count).map(|i| read_u32(page, 8 + i * 4)));
+            trunk = read_u32(page, 0);
+        }
+
+        leaves
+    }
+
+    /// Name and column count of every user table, read from sqlite_schema on page 1
+    fn table_column_counts(&self) -> Vec<(String, usize)> {
+        let mut tables = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![(1u32, HEADER_SIZE)];
+
+        while let Some((number, header)) = pending.pop() {
+            if !visited.insert(number) {
+                continue;
+            }
+            let Some(page) = self.page(number) else {
+                continue;
+            };
+
+            match page.get(header) {
+                Some(&TABLE_INTERIOR_PAGE) => {
+                    pending.push((read_u32(page, header + 8), 0));
+                    for pointer in cell_pointers(page, header, 12) {
+                        pending.push((read_u32(page, pointer), 0));
+                    }
+                }
+                Some(&TABLE_LEAF_PAGE) => {
+                    for payload in self.leaf_payloads(page, header) {
+                        // type, name, tbl_name, rootpage, sql
+                        let Some(fields) = self.decode_record(payload) else {
+                            continue;
+                        };
+                        if fields.len() < 5
+                            || fields[0] != "table"
+                            || fields[1].starts_with("sqlite_")
+                        {
+                            continue;
+                        }
+                        if let Some(columns) = column_count(&fields[4]) {
+                            tables.push((fields[1].clone(), columns));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        tables
+    }
+
+    /// The locally stored payload of each cell on a table leaf page
+    /// Payloads that spilled to overflow pages are cut at the page boundary
+    fn leaf_payloads(&self, page: &'a [u8], header: usize) -> Vec<&'a [u8]> {
+        let mut payloads = Vec::new();
+
+        for pointer in cell_pointers(page, header, 8) {
+            let Some((payload_size, used)) = read_varint(page.get(pointer..).unwrap_or_default())
+            else {
+                continue;
+            };
+            let Some((_rowid, rowid_len)) =
+                read_varint(page.get(pointer + used..).unwrap_or_default())
+            else {
+                continue;
+            };
+
+            let start = pointer + used + rowid_len;
+            let local = self.local_payload_size(payload_size as usize);
+            if let Some(payload) = page.get(start..start + local) {
+                payloads.push(payload);
+            }
+        }
+
+        payloads
+    }
+
+    /// How much of a table leaf payload is stored on the page itself
+    /// (see "B-tree Pages" in the file format spec)
+    fn local_payload_size(&self, payload_size: usize) -> usize {
+        let max_local = self.usable_size - 35;
+        if payload_size <= max_local {
+            return payload_size;
+        }
+        let min_local = (self.usable_size - 12) * 32 / 255 - 23;
+        let local = min_local + (payload_size - min_local) % (self.usable_size - 4);
+        if local <= max_local {
+            local
+        } else {
+            min_local
+        }
+    }
+
+    /// Decode a record into one string per column
+    /// A record cut short (e.g. by overflow) yields the columns that fit
+    fn decode_record(&self, payload: &[u8]) -> Option<Vec<String>> {
+        let (header_size, mut position) = read_varint(payload)?;
+        let header_size = header_size as usize;
+        if header_size <= position || header_size > payload.len() {
+            return None;
+        }
+
+        let mut serial_types = Vec::new();
+        while position < header_size {
+            let (serial_type, used) = read_varint(&payload[position..header_size])?;
+            // 10 and 11 are reserved; seeing them means this isn't a record
+            if serial_type == 10 || serial_type == 11 {
+                return None;
+            }
+            serial_types.push(serial_type);
+            position += used;
+        }
+        if serial_types.is_empty() {
+            return None;
+        }
+
+        let mut fields = Vec::with_capacity(serial_types.len());
+        let mut body = &payload[header_size..];
+        for serial_type in serial_types {
+            let size = serial_type_size(serial_type);
+            if size > body.len() {
+                break;
+            }
+            let (value, rest) = body.split_at(size);
+            fields.push(self.format_value(serial_type, value));
+            body = rest;
+        }
+
+        Some(fields)
+    }
+
+    fn format_value(&self, serial_type: u64, value: &[u8]) -> String {
+        match serial_type {
+            0 => "NULL".to_string(),
+            1..=6 => {
+                // Sign-extend the big-endian integer to 64 bits
+                let mut bytes = if value[0] & 0x80 != 0 {
+                    [0xFF; 8]
+                } else {
+                    [0; 8]
+                };
+                bytes[8 - value.len()..].copy_from_slice(value);
+                i64::from_be_bytes(bytes).to_string()
+            }
+            7 => f64::from_be_bytes(value.try_into().unwrap_or_default()).to_string(),
+            8 => "0".to_string(),
+            9 => "1".to_string(),
+            n if n % 2 == 0 => hex::encode(value),
+            _ => match self.encoding {
+                TextEncoding::Utf8 => String::from_utf8_lossy(value).into_owned(),
+                TextEncoding::Utf16Le => encoding_rs::UTF_16LE.decode(value).0.into_owned(),
+                TextEncoding::Utf16Be => encoding_rs::UTF_16BE.decode(value).0.into_owned(),
+            },
+        }
+    }
+}
+
+/// Offsets of the cells listed in a b-tree page's cell pointer array
+fn cell_pointers(page: &[u8], header: usize, header_len: usize) -> Vec<usize> {
+    let cell_count = match page.get(header + 3..header + 5) {
+        Some(bytes) => u16::from_be_bytes([bytes[0], bytes[1]]) as usize,
+        None => return Vec::new(),
+    };
+
+    let array = header + header_len;
+    (0..cell_count)
+        .filter_map(|i| page.get(array + i * 2..array + i * 2 + 2))
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+        .filter(|&pointer| pointer >= array && pointer < page.len())
+        .collect()
+}
+
+/// Size in bytes of a value with the given serial type
+fn serial_type_size(serial_type: u64) -> usize {
+    match serial_type {
+        0 | 8 | 9 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 3,
+        4 => 4,
+        5 => 6,
+        6 | 7 => 8,
+        n => ((n - 12) / 2) as usize,
+    }
+}
+
+/// SQLite varint: up to nine bytes, seven bits each, all eight bits of the ninth
+fn read_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(9) {
+        if i == 8 {
+            return Some(((value << 8) | byte as u64, 9));
+        }
+        value = (value << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .unwrap_or(0)
+}
+
+/// Number of columns declared by a CREATE TABLE statement
+/// Table constraints (PRIMARY KEY (...), UNIQUE, CHECK, ...) are not columns
+fn column_count(sql: &str) -> Option<usize> {
+    let start = sql.find('(')?;
+    let end = sql.rfind(')')?;
+    if end <= start {
+        return None;
+    }
+
+    let mut definitions = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut current = String::new();
+    for c in sql[start + 1..end].chars() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                definitions.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    definitions.push(current);
+
+    let columns = definitions
+        .iter()
+        .filter(|definition| {
+            let keyword = definition
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_ascii_uppercase();
+            !keyword.is_empty()
+                && !["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"]
+                    .contains(&keyword.as_str())
+        })
+        .count();
+    Some(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_recovers_rows_from_freed_pages() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("chat.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "PRAGMA page_size = 1024;
+                 PRAGMA secure_delete = OFF;
+                 CREATE TABLE contacts (id INTEGER PRIMARY KEY, name TEXT);
+                 CREATE TABLE messages (
+                     id INTEGER PRIMARY KEY,
+                     sender TEXT NOT NULL,
+                     body TEXT,
+                     sent REAL,
+                     CHECK (length(sender) > 0)
+                 );
+                 INSERT INTO contacts (name) VALUES ('alice');",
+            )
+            .unwrap();
+            for i in 0..200 {
+                conn.execute(
+                    "INSERT INTO messages (sender, body, sent) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![
+                        "alice",
+                        format!("meet at the dock, message {i}"),
+                        i as f64 + 0.5
+                    ],
+                )
+                .unwrap();
+            }
+            conn.execute("DELETE FROM messages", []).unwrap();
+        }
+
+        let rows = SqliteRecovery::recover_deleted_rows(&path).unwrap();
+
+        assert!(!rows.is_empty());
+        assert!(rows
+            .iter()
+            .all(|row| row.table_guess.as_deref() == Some("messages")));
+        // The rowid alias is stored as NULL in the record
+        let row = rows
+            .iter()
+            .find(|row| row.decoded_fields[2] == "meet at the dock, message 42")
+            .expect("row 42 should sit on a freed page");
+        assert_eq!(
+            row.decoded_fields,
+            ["NULL", "alice", "meet at the dock, message 42", "42.5"]
+        );
+        assert!(!row.raw_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_column_count_skips_table_constraints() {
+        assert_eq!(
+            column_count("CREATE TABLE t (a INTEGER, b TEXT DEFAULT 'x,y', c NUMERIC(10, 2), PRIMARY KEY (a, b))"),
+            Some(3)
+        );
+    }
+}
@@ -1,17 +1,36 @@
+use crate::io::atomic_write_sync;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use sha3::Keccak256;
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+/// Bytes read per step when hashing a whole file
+const HASH_BUFFER_LEN: usize = 64 * 1024;
+
+/// Bytes hashed from each end of a file for the partial hash
+const PARTIAL_HASH_EDGE_LEN: u64 = 64 * 1024;
+
+/// Prefix of cache files in the current format; older caches are a bare bincode map
+const CACHE_FORMAT_MAGIC: &[u8; 4] = b"CDv3";
+
+/// Prefix of cache files written before hashes were flagged as verified
+const CACHE_FORMAT_MAGIC_V2: &[u8; 4] = b"CDv2";
+
 /// Change detector for incremental indexing
 /// Uses SHA256 hashing and mtime to detect file changes
 pub struct ChangeDetector {
     /// Cached file states: path -> FileState
     cache: HashMap<PathBuf, FileState>,
+    /// File bytes read for hashing since the detector was created
+    bytes_hashed: u64,
+    /// Track symlinks themselves, hashing their target path, instead of what they point to
+    record_symlinks: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +39,33 @@ pub struct FileState {
     pub size: u64,
     pub modified: DateTime<Utc>,
     pub hash: String,
+    /// Keccak-256 of the contents, computed in the same pass as hash; None for symlinks
+    /// and entries from older caches
+    pub keccak256: Option<String>,
+    /// SHA256 of the size and the first and last 64KB; empty for entries from older caches
+    pub partial_hash: String,
+    /// False when only the partial hash was checked after a size or mtime change, so
+    /// hash was carried over from the previous state without reading the whole file
+    pub hash_verified: bool,
+}
+
+/// FileState as saved before hashes were flagged as verified
+#[derive(Deserialize)]
+struct FileStateV2 {
+    path: PathBuf,
+    size: u64,
+    modified: DateTime<Utc>,
+    hash: String,
+    partial_hash: String,
+}
+
+/// FileState as saved before partial hashes were recorded
+#[derive(Deserialize)]
+struct LegacyFileState {
+    path: PathBuf,
+    size: u64,
+    modified: DateTime<Utc>,
+    hash: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -38,36 +84,87 @@ impl ChangeDetector {
     pub fn new() -> Self {
         Self {
             cache: HashMap::new(),
+            bytes_hashed: 0,
+            record_symlinks: false,
         }
     }
 
+    /// Track symlinks as links rather than following them, matching an indexer that
+    /// records them as documents of their own
+    pub fn with_symlinks_recorded(mut self, record_symlinks: bool) -> Self {
+        self.record_symlinks = record_symlinks;
+        self
+    }
+
     /// Load cache from disk
+    /// Caches written before partial hashes existed still load; their entries get a full
+    /// hash the next time their size or mtime changes
     pub fn load(cache_path: &Path) -> Result<Self> {
-        if cache_path.exists() {
-            let data = fs::read(cache_path).context("Failed to read cache file")?;
-            let cache: HashMap<PathBuf, FileState> =
-                bincode::deserialize(&data).context("Failed to deserialize cache")?;
-            Ok(Self { cache })
-        } else {
-            Ok(Self::new())
+        if !cache_path.exists() {
+            return Ok(Self::new());
         }
+
+        let data = fs::read(cache_path).context("Failed to read cache file")?;
+        let cache = if let Some(data) = data.strip_prefix(CACHE_FORMAT_MAGIC) {
+            bincode::deserialize(data).context("Failed to deserialize cache")?
+        } else if let Some(data) = data.strip_prefix(CACHE_FORMAT_MAGIC_V2) {
+            let v2: HashMap<PathBuf, FileStateV2> =
+                bincode::deserialize(data).context("Failed to deserialize cache")?;
+            v2.into_iter()
+                .map(|(key, state)| {
+                    let state = FileState {
+                        path: state.path,
+                        size: state.size,
+                        modified: state.modified,
+                        hash: state.hash,
+                        keccak256: None,
+                        partial_hash: state.partial_hash,
+                        hash_verified: true,
+                    };
+                    (key, state)
+                })
+                .collect()
+        } else {
+            let legacy: HashMap<PathBuf, LegacyFileState> =
+                bincode::deserialize(&data).context("Failed to deserialize cache")?;
+            legacy
+                .into_iter()
+                .map(|(key, state)| {
+                    let state = FileState {
+                        path: state.path,
+                        size: state.size,
+                        modified: state.modified,
+                        hash: state.hash,
+                        keccak256: None,
+                        partial_hash: String::new(),
+                        hash_verified: true,
+                    };
+                    (key, state)
+                })
+                .collect()
+        };
+        Ok(Self {
+            cache,
+            bytes_hashed: 0,
+            record_symlinks: false,
+        })
     }
 
     /// Save cache to disk
     pub fn save(&self, cache_path: &Path) -> Result<()> {
-        let data = bincode::serialize(&self.cache).context("Failed to serialize cache")?;
-
-        if let Some(parent) = cache_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
+        let mut data = CACHE_FORMAT_MAGIC.to_vec();
+        bincode::serialize_into(&mut data, &self.cache).context("Failed to serialize cache")?;
 
-        fs::write(cache_path, data).context("Failed to write cache file")?;
+        // A crash mid-save must not leave a truncated cache that fails to load
+        atomic_write_sync(cache_path, &data).context("Failed to write cache file")?;
         Ok(())
     }
 
     /// Detect changes for a file
     pub fn detect_change(&mut self, path: &Path) -> Result<FileChange> {
-        if !path.exists() {
+        // A dangling symlink is still there, so check the link rather than its target
+        let recorded_symlink = self.is_recorded_symlink(path);
+        if !path.exists() && !recorded_symlink {
             // File was deleted
             if self.cache.contains_key(path) {
                 self.cache.remove(path);
@@ -76,9 +173,14 @@ impl ChangeDetector {
             return Ok(FileChange::Unchanged(path.to_path_buf()));
         }
 
-        let metadata = fs::metadata(path).context("Failed to read file metadata")?;
+        let metadata = if recorded_symlink {
+            fs::symlink_metadata(path)
+        } else {
+            fs::metadata(path)
+        }
+        .context("Failed to read file metadata")?;
 
-        if !metadata.is_file() {
+        if !metadata.is_file() && !recorded_symlink {
             return Ok(FileChange::Unchanged(path.to_path_buf()));
         }
 
@@ -93,10 +195,33 @@ impl ChangeDetector {
                 return Ok(FileChange::Unchanged(path.to_path_buf()));
             }
 
-            // Size or mtime changed - verify with hash
-            let hash = Self::calculate_hash(path)?;
+            // Size or mtime changed - the partial hash settles a merely touched file without
+            // reading all of it; an edit confined to the middle of the file isn't seen here,
+            // so the carried-over hash is flagged as unverified
+            let cached_hash = cached_state.hash.clone();
+            let cached_keccak256 = cached_state.keccak256.clone();
+            let cached_partial_hash = cached_state.partial_hash.clone();
+            let partial_hash = self.calculate_partial_hash(path, size)?;
+            if !cached_partial_hash.is_empty() && partial_hash == cached_partial_hash {
+                self.cache.insert(
+                    path.to_path_buf(),
+                    FileState {
+                        path: path.to_path_buf(),
+                        size,
+                        modified,
+                        hash: cached_hash,
+                        keccak256: cached_keccak256,
+                        partial_hash,
+                        hash_verified: false,
+                    },
+                );
+                return Ok(FileChange::Unchanged(path.to_path_buf()));
+            }
+
+            // Verify with the full hash
+            let (hash, keccak256) = self.calculate_hashes(path)?;
 
-            if hash == cached_state.hash {
+            if hash == cached_hash {
                 // False positive - file unchanged but mtime updated
                 // Update cache with new mtime
                 self.cache.insert(
@@ -106,6 +231,9 @@ impl ChangeDetector {
                         size,
                         modified,
                         hash,
+                        keccak256,
+                        partial_hash,
+                        hash_verified: true,
                     },
                 );
                 return Ok(FileChange::Unchanged(path.to_path_buf()));
@@ -119,13 +247,17 @@ impl ChangeDetector {
                     size,
                     modified,
                     hash: hash.clone(),
+                    keccak256,
+                    partial_hash,
+                    hash_verified: true,
                 },
             );
             return Ok(FileChange::Modified(path.to_path_buf()));
         }
 
         // New file
-        let hash = Self::calculate_hash(path)?;
+        let partial_hash = self.calculate_partial_hash(path, size)?;
+        let (hash, keccak256) = self.calculate_hashes(path)?;
         self.cache.insert(
             path.to_path_buf(),
             FileState {
@@ -133,6 +265,9 @@ impl ChangeDetector {
                 size,
                 modified,
                 hash,
+                keccak256,
+                partial_hash,
+                hash_verified: true,
             },
         );
 
@@ -144,14 +279,96 @@ impl ChangeDetector {
         paths.iter().map(|p| self.detect_change(p)).collect()
     }
 
-    /// Calculate SHA256 hash of a file
-    fn calculate_hash(path: &Path) -> Result<String> {
-        let data = fs::read(path).context("Failed to read file for hashing")?;
+    /// SHA256 and Keccak-256 of a file, or the SHA256 of a recorded symlink's target path
+    fn calculate_hashes(&mut self, path: &Path) -> Result<(String, Option<String>)> {
+        if self.is_recorded_symlink(path) {
+            let target = fs::read_link(path).context("Failed to read symlink target")?;
+            return Ok((Self::symlink_hash(&target), None));
+        }
+
+        let (hash, keccak256, len) = Self::content_hashes(path)?;
+        self.bytes_hashed += len;
+        Ok((hash, Some(keccak256)))
+    }
+
+    /// SHA256 and Keccak-256 of a file's contents in one buffered pass, plus the bytes read
+    pub fn content_hashes(path: &Path) -> Result<(String, String, u64)> {
+        let mut file = File::open(path).context("Failed to open file for hashing")?;
+        let mut sha256 = Sha256::new();
+        let mut keccak256 = Keccak256::new();
+        let mut buffer = vec![0u8; HASH_BUFFER_LEN];
+        let mut len = 0u64;
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .context("Failed to read file for hashing")?;
+            if read == 0 {
+                break;
+            }
+            sha256.update(&buffer[..read]);
+            keccak256.update(&buffer[..read]);
+            len += read as u64;
+        }
+        Ok((
+            format!("{:x}", sha256.finalize()),
+            format!("{:x}", keccak256.finalize()),
+            len,
+        ))
+    }
+
+    /// SHA256 of the size plus the first and last 64KB, which reads at most 128KB
+    /// Files up to that size are covered completely; recorded symlinks use their target path
+    fn calculate_partial_hash(&mut self, path: &Path, size: u64) -> Result<String> {
+        if self.is_recorded_symlink(path) {
+            let target = fs::read_link(path).context("Failed to read symlink target")?;
+            return Ok(Self::symlink_hash(&target));
+        }
+
+        let mut file = File::open(path).context("Failed to open file for hashing")?;
         let mut hasher = Sha256::new();
-        hasher.update(&data);
+        hasher.update(size.to_le_bytes());
+
+        let mut head = Vec::new();
+        (&mut file)
+            .take(PARTIAL_HASH_EDGE_LEN)
+            .read_to_end(&mut head)
+            .context("Failed to read file for hashing")?;
+        hasher.update(&head);
+        self.bytes_hashed += head.len() as u64;
+
+        // The tail starts after the head, so a small file isn't read twice
+        let tail_start = size
+            .saturating_sub(PARTIAL_HASH_EDGE_LEN)
+            .max(head.len() as u64);
+        if tail_start < size {
+            let mut tail = Vec::new();
+            file.seek(SeekFrom::Start(tail_start))?;
+            file.take(size - tail_start)
+                .read_to_end(&mut tail)
+                .context("Failed to read file for hashing")?;
+            hasher.update(&tail);
+            self.bytes_hashed += tail.len() as u64;
+        }
+
         Ok(format!("{:x}", hasher.finalize()))
     }
 
+    /// File bytes read for hashing since the detector was created
+    pub fn bytes_hashed(&self) -> u64 {
+        self.bytes_hashed
+    }
+
+    fn is_recorded_symlink(&self, path: &Path) -> bool {
+        self.record_symlinks && path.is_symlink()
+    }
+
+    /// A symlink's content is its target, so that is what gets hashed
+    pub fn symlink_hash(target: &Path) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(target.to_string_lossy().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
     /// Convert SystemTime to DateTime<Utc>
     fn system_time_to_datetime(st: SystemTime) -> DateTime<Utc> {
         DateTime::from(st)
@@ -162,6 +379,13 @@ impl ChangeDetector {
         self.cache.get(path)
     }
 
+    /// Cached state of every known file, ordered by path
+    pub fn cached_states(&self) -> Vec<FileState> {
+        let mut states: Vec<FileState> = self.cache.values().cloned().collect();
+        states.sort_by(|a, b| a.path.cmp(&b.path));
+        states
+    }
+
     /// Remove a file from cache
     pub fn remove(&mut self, path: &Path) {
         self.cache.remove(path);
@@ -199,6 +423,15 @@ mod tests {
 
         let change = detector.detect_change(file.path()).unwrap();
         assert!(matches!(change, FileChange::Added(_)));
+
+        // Both digests come from the one read of the file
+        let state = detector.get_cached_state(file.path()).unwrap();
+        assert_eq!(state.hash, format!("{:x}", Sha256::digest(b"test content")));
+        assert_eq!(
+            state.keccak256.as_deref(),
+            Some(format!("{:x}", Keccak256::digest(b"test content")).as_str())
+        );
+        assert_eq!(detector.bytes_hashed(), 2 * b"test content".len() as u64);
     }
 
     #[test]
@@ -235,4 +468,86 @@ mod tests {
         let change = detector.detect_change(file.path()).unwrap();
         assert!(matches!(change, FileChange::Modified(_)));
     }
+
+    #[test]
+    fn test_touched_large_file_is_unchanged_without_full_read() {
+        let mut detector = ChangeDetector::new();
+        let mut file = NamedTempFile::new().unwrap();
+        let content: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+        file.write_all(&content).unwrap();
+        file.flush().unwrap();
+
+        assert!(matches!(
+            detector.detect_change(file.path()).unwrap(),
+            FileChange::Added(_)
+        ));
+        let hash = detector.get_cached_state(file.path()).unwrap().hash.clone();
+
+        // Only the mtime changes
+        let touched = SystemTime::now() + std::time::Duration::from_secs(3600);
+        file.as_file().set_modified(touched).unwrap();
+
+        let before = detector.bytes_hashed();
+        assert!(matches!(
+            detector.detect_change(file.path()).unwrap(),
+            FileChange::Unchanged(_)
+        ));
+        let read = detector.bytes_hashed() - before;
+        assert_eq!(read, 2 * PARTIAL_HASH_EDGE_LEN);
+
+        let state = detector.get_cached_state(file.path()).unwrap();
+        assert_eq!(state.hash, hash);
+        assert!(!state.hash_verified);
+        assert_eq!(state.modified, DateTime::<Utc>::from(touched));
+    }
+
+    #[test]
+    fn test_cache_without_partial_hashes_still_loads() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache_path = dir.path().join("change_cache.bin");
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"test content").unwrap();
+        file.flush().unwrap();
+
+        let mut detector = ChangeDetector::new();
+        detector.detect_change(file.path()).unwrap();
+        let state = detector.get_cached_state(file.path()).unwrap().clone();
+
+        // Write the cache the way it was saved before partial hashes existed
+        #[derive(Serialize)]
+        struct OldFileState {
+            path: PathBuf,
+            size: u64,
+            modified: DateTime<Utc>,
+            hash: String,
+        }
+        let old: HashMap<PathBuf, OldFileState> = HashMap::from([(
+            state.path.clone(),
+            OldFileState {
+                path: state.path.clone(),
+                size: state.size,
+                modified: state.modified,
+                hash: state.hash.clone(),
+            },
+        )]);
+        fs::write(&cache_path, bincode::serialize(&old).unwrap()).unwrap();
+
+        let mut loaded = ChangeDetector::load(&cache_path).unwrap();
+        assert!(loaded
+            .get_cached_state(file.path())
+            .unwrap()
+            .partial_hash
+            .is_empty());
+        assert!(matches!(
+            loaded.detect_change(file.path()).unwrap(),
+            FileChange::Unchanged(_)
+        ));
+
+        loaded.save(&cache_path).unwrap();
+        let reloaded = ChangeDetector::load(&cache_path).unwrap();
+        assert_eq!(
+            reloaded.get_cached_state(file.path()).unwrap().hash,
+            state.hash
+        );
+    }
 }
@@ -0,0 +1,111 @@
+use std::path::Path;
+
+/// POSIX ACL entries granted beyond a file's mode bits, in getfacl's notation
+/// (e.g. "user:alice:rw-", "default:group:staff:r-x"); empty when the file has none
+/// Only Linux exposes ACLs this way; other platforms always report none
+#[cfg(target_os = "linux")]
+pub fn read_acl(path: &Path) -> Vec<String> {
+    let mut entries = Vec::new();
+    for (attribute, prefix) in [
+        ("system.posix_acl_access", ""),
+        ("system.posix_acl_default", "default:"),
+    ] {
+        // The kernel only stores the attribute when the ACL says more than the mode does
+        if let Ok(Some(value)) = xattr::get_deref(path, attribute) {
+            entries.extend(
+                parse_posix_acl(&value)
+                    .into_iter()
+                    .map(|entry| format!("{}{}", prefix, entry)),
+            );
+        }
+    }
+    entries
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_acl(_path: &Path) -> Vec<String> {
+    Vec::new()
+}
+
+/// Decode the kernel's xattr form of an ACL: a u32 version, then one
+/// (u16 tag, u16 permissions, u32 id) record per entry, all little-endian
+#[cfg(target_os = "linux")]
+fn parse_posix_acl(value: &[u8]) -> Vec<String> {
+    use super::local::owner_names;
+
+    const ACL_VERSION: u32 = 2;
+    let Some(version) = value.get(..4) else {
+        return Vec::new();
+    };
+    if u32::from_le_bytes(version.try_into().unwrap()) != ACL_VERSION {
+        return Vec::new();
+    }
+
+    value[4..]
+        .chunks_exact(8)
+        .filter_map(|record| {
+            let tag = u16::from_le_bytes([record[0], record[1]]);
+            let perm = u16::from_le_bytes([record[2], record[3]]);
+            let id = u32::from_le_bytes([record[4], record[5], record[6], record[7]]);
+
+            // Named entries fall back to the numeric id when the account isn't known here
+            let (kind, qualifier) = match tag {
+                0x01 => ("user", String::new()),
+                0x02 => (
+                    "user",
+                    owner_names::user_name(id).unwrap_or_else(|| id.to_string()),
+                ),
+                0x04 => ("group", String::new()),
+                0x08 => (
+                    "group",
+                    owner_names::group_name(id).unwrap_or_else(|| id.to_string()),
+                ),
+                0x10 => ("mask", String::new()),
+                0x20 => ("other", String::new()),
+                _ => return None,
+            };
+            let rwx: String = [(4, 'r'), (2, 'w'), (1, 'x')]
+                .iter()
+                .map(|&(bit, c)| if perm & bit != 0 { c } else { '-' })
+                .collect();
+            Some(format!("{}:{}:{}", kind, qualifier, rwx))
+        })
+        .collect()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    fn record(tag: u16, perm: u16, id: u32) -> Vec<u8> {
+        let mut bytes = tag.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&perm.to_le_bytes());
+        bytes.extend_from_slice(&id.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_parse_posix_acl() {
+        // An account id that won't resolve to a name on the test machine
+        let unknown = 4_000_000_000;
+        let mut value = 2u32.to_le_bytes().to_vec();
+        value.extend(record(0x01, 6, u32::MAX));
+        value.extend(record(0x02, 4, unknown));
+        value.extend(record(0x04, 5, u32::MAX));
+        value.extend(record(0x10, 7, u32::MAX));
+        value.extend(record(0x20, 0, u32::MAX));
+
+        assert_eq!(
+            parse_posix_acl(&value),
+            vec![
+                "user::rw-",
+                "user:4000000000:r--",
+                "group::r-x",
+                "mask::rwx",
+                "other::---",
+            ]
+        );
+        assert!(parse_posix_acl(&[1, 0, 0, 0]).is_empty());
+        assert!(parse_posix_acl(&[]).is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Stream names that record where a file was downloaded from
+const ZONE_MARKERS: &[&str] = &["Zone.Identifier", "com.apple.quarantine"];
+
+/// An alternate data stream attached to a file
+/// On NTFS these are `file.txt:stream` streams; elsewhere they are extended attributes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdsEntry {
+    pub file_path: PathBuf,
+    pub stream_name: String,
+    pub stream_size: u64,
+    /// Download origin marker (Zone.Identifier, or macOS quarantine)
+    pub is_zone_identifier: bool,
+}
+
+/// Recursively enumerate alternate data streams under base_path
+/// Unreadable directories and files are skipped
+pub fn scan_for_ads(base_path: &Path) -> Vec<AdsEntry> {
+    let mut entries = Vec::new();
+    scan_recursive(base_path, &mut entries);
+    entries
+}
+
+fn scan_recursive(path: &Path, entries: &mut Vec<AdsEntry>) {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return;
+    };
+
+    if metadata.is_dir() {
+        if let Ok(dir) = std::fs::read_dir(path) {
+            for entry in dir.flatten() {
+                scan_recursive(&entry.path(), entries);
+            }
+        }
+    } else if metadata.is_file() {
+        for (stream_name, stream_size) in list_streams(path) {
+            entries.push(AdsEntry {
+                file_path: path.to_path_buf(),
+                is_zone_identifier: ZONE_MARKERS.contains(&stream_name.as_str()),
+                stream_name,
+                stream_size,
+            });
+        }
+    }
+}
+
+/// List named streams of a file via FindFirstStreamW / FindNextStreamW
+#[cfg(windows)]
+fn list_streams(path: &Path) -> Vec<(String, u64)> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FindClose, FindFirstStreamW, FindNextStreamW, FindStreamInfoStandard,
+        WIN32_FIND_STREAM_DATA,
+    };
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut streams = Vec::new();
+    // SAFETY: wide is NUL-terminated and data outlives every call that writes to it
+    unsafe {
+        let mut data: WIN32_FIND_STREAM_DATA = std::mem::zeroed();
+        let handle = FindFirstStreamW(
+            wide.as_ptr(),
+            FindStreamInfoStandard,
+            &mut data as *mut _ as *mut _,
+            0,
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return streams;
+        }
+
+        loop {
+            let len = data
+                .cStreamName
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(data.cStreamName.len());
+            let raw = String::from_utf16_lossy(&data.cStreamName[..len]);
+
+            // Names look like ":name:$DATA"; the unnamed "::$DATA" is the file's main content
+            if raw != "::$DATA" {
+                let name = raw
+                    .trim_start_matches(':')
+                    .trim_end_matches(":$DATA")
+                    .to_string();
+                streams.push((name, data.StreamSize.max(0) as u64));
+            }
+
+            if FindNextStreamW(handle, &mut data as *mut _ as *mut _) == 0 {
+                break;
+            }
+        }
+
+        FindClose(handle);
+    }
+
+    streams
+}
+
+/// Extended attributes are the closest equivalent outside NTFS
+/// NTFS volumes mounted through ntfs-3g expose streams as `user.<name>` attributes
+#[cfg(unix)]
+fn list_streams(path: &Path) -> Vec<(String, u64)> {
+    let Ok(names) = xattr::list(path) else {
+        return Vec::new();
+    };
+
+    names
+        .map(|name| {
+            let size = xattr::get(path, &name)
+                .ok()
+                .flatten()
+                .map(|value| value.len() as u64)
+                .unwrap_or(0);
+            let name = name.to_string_lossy();
+            let name = name.strip_prefix("user.").unwrap_or(&name).to_string();
+            (name, size)
+        })
+        .collect()
+}
+
+#[cfg(not(any(windows, unix)))]
+fn list_streams(_path: &Path) -> Vec<(String, u64)> {
+    Vec::new()
+}
@@ -1,25 +1,36 @@
+use parking_lot::RwLock;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tauri::ipc::Channel;
 use tauri::State;
 
-use super::error::Result;
+use super::ads::{self, AdsEntry};
+use super::error::{FileSystemError, Result};
 use super::fs::{FileSystem, FileSystemBuilder};
 use super::types::*;
 
 /// Global file system state
 pub struct FileSystemState {
-    fs: Arc<Box<dyn FileSystem>>,
+    fs: RwLock<Arc<dyn FileSystem>>,
 }
 
 impl FileSystemState {
     pub fn new() -> Self {
         Self {
-            fs: Arc::new(FileSystemBuilder::local().build()),
+            fs: RwLock::new(Arc::from(FileSystemBuilder::local().build())),
         }
     }
 
-    pub fn fs(&self) -> &dyn FileSystem {
-        self.fs.as_ref().as_ref()
+    pub fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.read().clone()
+    }
+
+    /// Switch how later commands describe symlinks; commands already running are unaffected
+    pub fn set_record_symlinks(&self, record_symlinks: bool) {
+        let fs = FileSystemBuilder::local()
+            .record_symlinks(record_symlinks)
+            .build();
+        *self.fs.write() = Arc::from(fs);
     }
 }
 
@@ -40,7 +51,17 @@ pub async fn read_file_as_string(
     state.fs().read_to_string(&path).await
 }
 
-/// Write file contents
+/// Read file contents as string, tolerating invalid UTF-8
+#[tauri::command]
+pub async fn read_file_as_string_lossy(
+    path: String,
+    state: State<'_, FileSystemState>,
+) -> Result<String> {
+    let path = PathBuf::from(path);
+    state.fs().read_to_string_lossy(&path).await
+}
+
+/// Write file contents, replacing the file atomically so an interrupted write never truncates it
 #[tauri::command]
 pub async fn write_file(
     path: String,
@@ -48,7 +69,7 @@ pub async fn write_file(
     state: State<'_, FileSystemState>,
 ) -> Result<()> {
     let path = PathBuf::from(path);
-    state.fs().write_file(&path, &data).await
+    state.fs().atomic_write(&path, &data).await
 }
 
 /// Check if path exists
@@ -79,14 +100,26 @@ pub async fn get_metadata(path: String, state: State<'_, FileSystemState>) -> Re
     state.fs().metadata(&path).await
 }
 
-/// List directory contents (non-recursive)
+/// Describe symlinks themselves in get_metadata (true) or the files they point to (false,
+/// the default); scans choose per call with DirectoryScanOptions::follow_symlinks
+#[tauri::command]
+pub async fn set_record_symlinks(
+    record_symlinks: bool,
+    state: State<'_, FileSystemState>,
+) -> Result<()> {
+    state.set_record_symlinks(record_symlinks);
+    Ok(())
+}
+
+/// List directory contents (non-recursive), sorted by name unless `sort` says otherwise
 #[tauri::command]
 pub async fn list_directory(
     path: String,
+    sort: Option<SortOrder>,
     state: State<'_, FileSystemState>,
 ) -> Result<Vec<FileInfo>> {
     let path = PathBuf::from(path);
-    state.fs().list_dir(&path).await
+    state.fs().list_dir(&path, sort.unwrap_or_default()).await
 }
 
 /// Scan directory recursively with options
@@ -100,6 +133,28 @@ pub async fn scan_directory(
     state.fs().scan_directory(&path, options).await
 }
 
+/// List every entry in a scanned tree that could not be read
+#[tauri::command]
+pub async fn get_scan_errors(tree: FileInfo) -> Result<Vec<ScanError>> {
+    Ok(tree.scan_errors())
+}
+
+/// Enumerate alternate data streams (NTFS ADS, or extended attributes elsewhere)
+#[tauri::command]
+pub async fn scan_for_ads(
+    base_path: String,
+    state: State<'_, FileSystemState>,
+) -> Result<Vec<AdsEntry>> {
+    let path = PathBuf::from(base_path);
+    if !state.fs().exists(&path).await? {
+        return Err(FileSystemError::FileNotFound { path });
+    }
+
+    tokio::task::spawn_blocking(move || ads::scan_for_ads(&path))
+        .await
+        .map_err(|e| FileSystemError::Unknown(e.to_string()))
+}
+
 /// Delete a file
 #[tauri::command]
 pub async fn delete_file(path: String, state: State<'_, FileSystemState>) -> Result<()> {
@@ -129,6 +184,38 @@ pub async fn copy_file(from: String, to: String, state: State<'_, FileSystemStat
     state.fs().copy_file(&from_path, &to_path).await
 }
 
+/// Copy a file keeping its original timestamps (for chain-of-custody exports)
+#[tauri::command]
+pub async fn copy_file_preserving(
+    from: String,
+    to: String,
+    preserve_permissions: Option<bool>,
+    state: State<'_, FileSystemState>,
+) -> Result<()> {
+    let from_path = PathBuf::from(from);
+    let to_path = PathBuf::from(to);
+    state
+        .fs()
+        .copy_file_preserving(&from_path, &to_path, preserve_permissions.unwrap_or(false))
+        .await
+}
+
+/// Recursively copy a directory (e.g. exporting an evidence subtree to another volume)
+#[tauri::command]
+pub async fn copy_directory(
+    from: String,
+    to: String,
+    preserve_timestamps: Option<bool>,
+    state: State<'_, FileSystemState>,
+) -> Result<()> {
+    let from_path = PathBuf::from(from);
+    let to_path = PathBuf::from(to);
+    state
+        .fs()
+        .copy_dir(&from_path, &to_path, preserve_timestamps.unwrap_or(true))
+        .await
+}
+
 /// Move/rename a file or directory
 #[tauri::command]
 pub async fn move_path(from: String, to: String, state: State<'_, FileSystemState>) -> Result<()> {
@@ -137,6 +224,31 @@ pub async fn move_path(from: String, to: String, state: State<'_, FileSystemStat
     state.fs().move_path(&from_path, &to_path).await
 }
 
+/// Create a symbolic link at link pointing to target
+#[tauri::command]
+pub async fn create_symlink(
+    target: String,
+    link: String,
+    state: State<'_, FileSystemState>,
+) -> Result<()> {
+    let target = PathBuf::from(target);
+    let link = PathBuf::from(link);
+    state.fs().create_symlink(&target, &link).await
+}
+
+/// Create a hard link at link to original, e.g. for case working copies that
+/// shouldn't duplicate evidence data
+#[tauri::command]
+pub async fn create_hardlink(
+    original: String,
+    link: String,
+    state: State<'_, FileSystemState>,
+) -> Result<()> {
+    let original = PathBuf::from(original);
+    let link = PathBuf::from(link);
+    state.fs().create_hardlink(&original, &link).await
+}
+
 /// Calculate file hashes (MD5, SHA256)
 #[tauri::command]
 pub async fn calculate_hash(path: String, state: State<'_, FileSystemState>) -> Result<FileHash> {
@@ -144,6 +256,41 @@ pub async fn calculate_hash(path: String, state: State<'_, FileSystemState>) ->
     state.fs().calculate_hash(&path).await
 }
 
+/// Calculate file hashes, sending progress with an ETA over the channel every 10 MB
+#[tauri::command]
+pub async fn calculate_hash_with_progress(
+    path: String,
+    on_progress: Channel<HashProgress>,
+    state: State<'_, FileSystemState>,
+) -> Result<FileHash> {
+    let path = PathBuf::from(path);
+    state
+        .fs()
+        .calculate_hash_with_progress(&path, &|progress| {
+            // A closed channel only means the UI stopped listening; keep hashing
+            let _ = on_progress.send(progress);
+        })
+        .await
+}
+
+/// Binary diff of two files, giving up after max_diff_bytes differing bytes
+#[tauri::command]
+pub async fn compare_files(
+    path_a: String,
+    path_b: String,
+    max_diff_bytes: Option<u64>,
+    state: State<'_, FileSystemState>,
+) -> Result<FileDiff> {
+    state
+        .fs()
+        .compare_files(
+            &PathBuf::from(path_a),
+            &PathBuf::from(path_b),
+            max_diff_bytes,
+        )
+        .await
+}
+
 /// Search for files matching a pattern
 #[tauri::command]
 pub async fn search_files(
@@ -159,10 +306,13 @@ pub async fn search_files(
 #[tauri::command]
 pub async fn search_content(
     base_path: String,
-    options: SearchOptions,
+    mut options: SearchOptions,
     state: State<'_, FileSystemState>,
 ) -> Result<Vec<SearchResult>> {
     let path = PathBuf::from(base_path);
+    options
+        .parallel_threshold
+        .get_or_insert(DEFAULT_PARALLEL_SEARCH_THRESHOLD);
     state.fs().search_content(&path, options).await
 }
 
@@ -184,5 +334,44 @@ pub async fn get_file_size(path: String, state: State<'_, FileSystemState>) -> R
     state.fs().file_size(&path).await
 }
 
+/// Total size in bytes of the files under a directory
+#[tauri::command]
+pub async fn get_recursive_size(path: String, state: State<'_, FileSystemState>) -> Result<u64> {
+    let path = PathBuf::from(path);
+    state.fs().recursive_size(&path).await
+}
+
+/// Number of files under a directory
+#[tauri::command]
+pub async fn get_recursive_file_count(
+    path: String,
+    state: State<'_, FileSystemState>,
+) -> Result<u64> {
+    let path = PathBuf::from(path);
+    state.fs().recursive_file_count(&path).await
+}
+
+/// Preview the first n lines of a text file
+#[tauri::command]
+pub async fn head_lines(
+    path: String,
+    n: usize,
+    state: State<'_, FileSystemState>,
+) -> Result<Vec<String>> {
+    let path = PathBuf::from(path);
+    state.fs().head_lines(&path, n).await
+}
+
+/// Preview the last n lines of a text file
+#[tauri::command]
+pub async fn tail_lines(
+    path: String,
+    n: usize,
+    state: State<'_, FileSystemState>,
+) -> Result<Vec<String>> {
+    let path = PathBuf::from(path);
+    state.fs().tail_lines(&path, n).await
+}
+
 // Export all command handlers for use in main app
 // Note: Commands are registered directly in lib.rs using tauri::generate_handler!
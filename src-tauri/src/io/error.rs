@@ -27,9 +27,18 @@ pub enum FileSystemError {
     #[error("Path is not a file: {path}")]
     NotAFile { path: PathBuf },
 
+    #[error("Symlink loop: {path} points back to {target}")]
+    SymlinkLoop { path: PathBuf, target: PathBuf },
+
     #[error("File too large: {path} ({size} bytes)")]
     FileTooLarge { path: PathBuf, size: u64 },
 
+    #[cfg(windows)]
+    #[error(
+        "Cannot create symlink {path}: Windows requires administrator rights or Developer Mode"
+    )]
+    SymlinkNotSupported { path: PathBuf },
+
     #[error("Unsupported operation: {0}")]
     UnsupportedOperation(String),
 
@@ -16,9 +16,25 @@ pub trait FileSystem: Send + Sync {
     /// Read file as UTF-8 string
     async fn read_to_string(&self, path: &Path) -> Result<String>;
 
+    /// Read file as string, replacing invalid UTF-8 sequences with U+FFFD
+    async fn read_to_string_lossy(&self, path: &Path) -> Result<String>;
+
     /// Write data to a file (creates or overwrites)
     async fn write_file(&self, path: &Path, data: &[u8]) -> Result<()>;
 
+    /// Write data so the file holds either its old or its new contents, never a partial write
+    /// Data goes to a temporary file next to the target, which is then renamed over it
+    async fn atomic_write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        let temp_path = atomic_temp_path(path);
+        self.write_file(&temp_path, data).await?;
+
+        if let Err(e) = self.move_path(&temp_path, path).await {
+            let _ = self.delete_file(&temp_path).await;
+            return Err(e);
+        }
+        Ok(())
+    }
+
     /// Check if a path exists
     async fn exists(&self, path: &Path) -> Result<bool>;
 
@@ -31,8 +47,8 @@ pub trait FileSystem: Send + Sync {
     /// Get file metadata
     async fn metadata(&self, path: &Path) -> Result<FileMetadata>;
 
-    /// List directory contents (non-recursive)
-    async fn list_dir(&self, path: &Path) -> Result<Vec<FileInfo>>;
+    /// List directory contents (non-recursive), ordered by `sort`
+    async fn list_dir(&self, path: &Path, sort: SortOrder) -> Result<Vec<FileInfo>>;
 
     /// Recursively scan directory with options
     async fn scan_directory(&self, path: &Path, options: DirectoryScanOptions) -> Result<FileInfo>;
@@ -49,12 +65,49 @@ pub trait FileSystem: Send + Sync {
     /// Copy a file
     async fn copy_file(&self, from: &Path, to: &Path) -> Result<()>;
 
+    /// Copy a file, restoring the source's modified/accessed times on the copy
+    /// Permissions are copied too when preserve_permissions is set
+    async fn copy_file_preserving(
+        &self,
+        from: &Path,
+        to: &Path,
+        preserve_permissions: bool,
+    ) -> Result<()>;
+
+    /// Recursively copy a directory tree, optionally keeping original timestamps
+    async fn copy_dir(&self, from: &Path, to: &Path, preserve_timestamps: bool) -> Result<()>;
+
     /// Move/rename a file or directory
+    /// Falls back to copy-then-delete when the destination is on another volume
     async fn move_path(&self, from: &Path, to: &Path) -> Result<()>;
 
+    /// Create a symbolic link at link pointing to target
+    /// A relative target is resolved from the link's directory, as the OS does
+    async fn create_symlink(&self, target: &Path, link: &Path) -> Result<()>;
+
+    /// Create a hard link at link sharing original's data; both must be on the same volume
+    async fn create_hardlink(&self, original: &Path, link: &Path) -> Result<()>;
+
     /// Calculate file hashes (MD5, SHA256)
     async fn calculate_hash(&self, path: &Path) -> Result<FileHash>;
 
+    /// Calculate file hashes, reporting progress periodically for large files
+    /// The last report is always sent at 100%
+    async fn calculate_hash_with_progress(
+        &self,
+        path: &Path,
+        on_progress: &(dyn Fn(HashProgress) + Send + Sync),
+    ) -> Result<FileHash>;
+
+    /// Compare two files byte by byte, stopping at the first difference past max_diff_bytes
+    /// Some(0) allows no differences; identical files are always compared to the end
+    async fn compare_files(
+        &self,
+        path_a: &Path,
+        path_b: &Path,
+        max_diff_bytes: Option<u64>,
+    ) -> Result<FileDiff>;
+
     /// Search for files matching a pattern
     async fn search_files(&self, base_path: &Path, options: SearchOptions) -> Result<Vec<PathBuf>>;
 
@@ -70,11 +123,36 @@ pub trait FileSystem: Send + Sync {
 
     /// Get file size without reading entire file
     async fn file_size(&self, path: &Path) -> Result<u64>;
+
+    /// Total size of the files under a directory, without building a tree like scan_directory
+    /// Symlinks are not followed and unreadable subdirectories are skipped
+    async fn recursive_size(&self, path: &Path) -> Result<u64>;
+
+    /// Number of files under a directory, counted the same way as recursive_size
+    async fn recursive_file_count(&self, path: &Path) -> Result<u64>;
+
+    /// Read the first n lines of a text file without loading the rest of it
+    /// Each line keeps at most its first 64 KiB
+    async fn head_lines(&self, path: &Path, n: usize) -> Result<Vec<String>>;
+
+    /// Read the last n lines of a text file by scanning backward from the end
+    /// A trailing newline does not count as an extra empty line
+    /// Long lines are capped the same way as in head_lines
+    async fn tail_lines(&self, path: &Path, n: usize) -> Result<Vec<String>>;
+}
+
+/// Temporary sibling of path used by atomic writes: `{path}.{random_hex}.tmp`
+/// It sits in the same directory so the final rename never crosses filesystems
+pub fn atomic_temp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+    PathBuf::from(name)
 }
 
 /// Builder for creating file system instances
 pub struct FileSystemBuilder {
     backend_type: BackendType,
+    record_symlinks: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -85,16 +163,27 @@ pub enum BackendType {
 
 impl FileSystemBuilder {
     pub fn new(backend_type: BackendType) -> Self {
-        Self { backend_type }
+        Self {
+            backend_type,
+            record_symlinks: false,
+        }
     }
 
     pub fn local() -> Self {
         Self::new(BackendType::Local)
     }
 
+    /// Describe symlinks themselves in metadata rather than the files they point to
+    pub fn record_symlinks(mut self, record_symlinks: bool) -> Self {
+        self.record_symlinks = record_symlinks;
+        self
+    }
+
     pub fn build(self) -> Box<dyn FileSystem> {
         match self.backend_type {
-            BackendType::Local => Box::new(super::local::LocalFileSystem::new()),
+            BackendType::Local => Box::new(
+                super::local::LocalFileSystem::new().with_symlinks_recorded(self.record_symlinks),
+            ),
         }
     }
 }
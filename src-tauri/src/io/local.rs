@@ -1,28 +1,175 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use log::info;
 use md5::Md5;
 use rayon::prelude::*;
 use sha2::Digest;
 use sha2::Sha256;
+use sha3::Keccak256;
+use std::collections::{HashSet, VecDeque};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::fs;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt};
 
+use super::acl;
 use super::error::{FileSystemError, Result};
-use super::fs::FileSystem;
+use super::fs::{atomic_temp_path, FileSystem};
 use super::types::*;
+use crate::index::{FileCategory, FileTypeDetector};
+
+/// Read size used when streaming files through hashers
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How often hash progress is reported (in bytes)
+const HASH_PROGRESS_INTERVAL: u64 = 10 * 1024 * 1024;
+
+/// Links followed when resolving the file an atomic write replaces, as the kernel allows
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Blocking atomic write: write to a temporary sibling, flush it, then rename over path
+/// A symlinked path has its target replaced, and an existing file keeps its permissions
+/// Used directly by code outside the async file system layer (e.g. index caches)
+pub fn atomic_write_sync(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let path = resolve_symlinks(path)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let permissions = std::fs::metadata(&path).ok().map(|m| m.permissions());
+
+    let temp_path = atomic_temp_path(&path);
+    let written = std::fs::File::create(&temp_path).and_then(|mut file| {
+        file.write_all(data)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.sync_all()
+    });
+
+    if let Err(e) = written.and_then(|()| replace_file(&temp_path, &path)) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// The file a path ends up at after following symlinks, which need not exist yet
+fn resolve_symlinks(path: &Path) -> std::io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_SYMLINK_HOPS {
+        match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.is_symlink() => {
+                let target = std::fs::read_link(&path)?;
+                // Relative targets are relative to the link's directory
+                path = match path.parent() {
+                    Some(parent) => parent.join(target),
+                    None => target,
+                };
+            }
+            _ => return Ok(path),
+        }
+    }
+    Err(std::io::Error::other(format!(
+        "Too many levels of symbolic links: {}",
+        path.display()
+    )))
+}
+
+/// Rename from over to; atomic on POSIX filesystems
+#[cfg(not(windows))]
+fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::rename(from, to)
+}
+
+/// Rename from over to with MoveFileExW, replacing an existing target
+#[cfg(windows)]
+fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::{
+        MoveFileExW, MOVEFILE_REPLACE_EXISTING, MOVEFILE_WRITE_THROUGH,
+    };
+
+    let wide = |path: &Path| -> Vec<u16> {
+        path.as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    };
+    let (from, to) = (wide(from), wide(to));
+
+    // SAFETY: both paths are NUL-terminated and live until the call returns
+    let moved = unsafe {
+        MoveFileExW(
+            from.as_ptr(),
+            to.as_ptr(),
+            MOVEFILE_REPLACE_EXISTING | MOVEFILE_WRITE_THROUGH,
+        )
+    };
+    if moved == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Cached uid/gid to name lookups; a scan hits the same few owners for every file
+#[cfg(unix)]
+pub(crate) mod owner_names {
+    use nix::unistd::{Gid, Group, Uid, User};
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
+    use std::sync::LazyLock;
+
+    static USERS: LazyLock<Mutex<HashMap<u32, Option<String>>>> = LazyLock::new(Default::default);
+    static GROUPS: LazyLock<Mutex<HashMap<u32, Option<String>>>> = LazyLock::new(Default::default);
+
+    pub fn user_name(uid: u32) -> Option<String> {
+        USERS
+            .lock()
+            .entry(uid)
+            .or_insert_with(|| {
+                User::from_uid(Uid::from_raw(uid))
+                    .ok()
+                    .flatten()
+                    .map(|user| user.name)
+            })
+            .clone()
+    }
+
+    pub fn group_name(gid: u32) -> Option<String> {
+        GROUPS
+            .lock()
+            .entry(gid)
+            .or_insert_with(|| {
+                Group::from_gid(Gid::from_raw(gid))
+                    .ok()
+                    .flatten()
+                    .map(|group| group.name)
+            })
+            .clone()
+    }
+}
 
 /// Local file system implementation using tokio::fs
 #[derive(Debug, Clone)]
 pub struct LocalFileSystem {
     // Could add configuration here like root path, permissions, etc.
+    record_symlinks: bool,
 }
 
 impl LocalFileSystem {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            record_symlinks: false,
+        }
+    }
+
+    /// Describe symlinks themselves in metadata() rather than the file they point to
+    pub fn with_symlinks_recorded(mut self, record_symlinks: bool) -> Self {
+        self.record_symlinks = record_symlinks;
+        self
     }
 
     /// Helper to convert std::time::SystemTime to chrono::DateTime<Utc>
@@ -36,13 +183,18 @@ impl LocalFileSystem {
     /// Helper to extract file permissions
     #[cfg(unix)]
     fn extract_permissions(metadata: &std::fs::Metadata) -> FilePermissions {
-        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
         let mode = metadata.permissions().mode();
         FilePermissions {
             readonly: metadata.permissions().readonly(),
             can_read: (mode & 0o400) != 0,
             can_write: (mode & 0o200) != 0,
             can_execute: (mode & 0o100) != 0,
+            uid: Some(metadata.uid()),
+            gid: Some(metadata.gid()),
+            owner: owner_names::user_name(metadata.uid()),
+            group: owner_names::group_name(metadata.gid()),
+            acl: Vec::new(),
         }
     }
 
@@ -53,13 +205,57 @@ impl LocalFileSystem {
             can_read: true,
             can_write: !metadata.permissions().readonly(),
             can_execute: false,
+            uid: None,
+            gid: None,
+            owner: None,
+            group: None,
+            acl: Vec::new(),
+        }
+    }
+
+    /// MIME type from a well-known extension, falling back to the magic bytes for files
+    fn mime_type_for(path: &Path, is_file: bool) -> Option<String> {
+        let mime_type = path.extension().and_then(|e| e.to_str()).and_then(|ext| {
+            match ext {
+                "txt" | "log" => Some("text/plain"),
+                "json" => Some("application/json"),
+                "xml" => Some("application/xml"),
+                "html" | "htm" => Some("text/html"),
+                "css" => Some("text/css"),
+                "js" => Some("application/javascript"),
+                "png" => Some("image/png"),
+                "jpg" | "jpeg" => Some("image/jpeg"),
+                "gif" => Some("image/gif"),
+                "svg" => Some("image/svg+xml"),
+                "pdf" => Some("application/pdf"),
+                "zip" => Some("application/zip"),
+                _ => None,
+            }
+            .map(|s| s.to_string())
+        });
+
+        // No or unknown extension (e.g. Chrome's `History` SQLite database): check the magic
+        match mime_type {
+            None if is_file => FileTypeDetector::sniff_mime(path).map(|mime| mime.to_string()),
+            known => known,
         }
     }
 
     /// Convert tokio metadata to our FileMetadata type
-    async fn to_file_metadata(path: &Path) -> Result<FileMetadata> {
-        let metadata = fs::metadata(path).await?;
-        let std_metadata = std::fs::metadata(path)?;
+    /// Symlinks are followed unless `record_symlinks` is set, in which case the link itself is
+    /// described; either way its target is reported in `symlink_target`
+    async fn to_file_metadata(path: &Path, record_symlinks: bool) -> Result<FileMetadata> {
+        let link_metadata = fs::symlink_metadata(path).await?;
+        let (metadata, symlink_target) = if link_metadata.is_symlink() {
+            let target = fs::read_link(path).await.ok();
+            match fs::metadata(path).await {
+                Ok(metadata) if !record_symlinks => (metadata, target),
+                // A dangling link has nothing to follow, so it is described as a link
+                _ => (link_metadata, target),
+            }
+        } else {
+            (link_metadata, None)
+        };
 
         let modified = metadata
             .modified()
@@ -81,24 +277,12 @@ impl LocalFileSystem {
             .and_then(|e| e.to_str())
             .map(|e| e.to_string());
 
-        let mime_type = extension.as_ref().and_then(|ext| {
-            match ext.as_str() {
-                "txt" | "log" => Some("text/plain"),
-                "json" => Some("application/json"),
-                "xml" => Some("application/xml"),
-                "html" | "htm" => Some("text/html"),
-                "css" => Some("text/css"),
-                "js" => Some("application/javascript"),
-                "png" => Some("image/png"),
-                "jpg" | "jpeg" => Some("image/jpeg"),
-                "gif" => Some("image/gif"),
-                "svg" => Some("image/svg+xml"),
-                "pdf" => Some("application/pdf"),
-                "zip" => Some("application/zip"),
-                _ => None,
-            }
-            .map(|s| s.to_string())
-        });
+        let mime_type = Self::mime_type_for(path, metadata.is_file());
+
+        let mut permissions = Self::extract_permissions(&metadata);
+        if !metadata.is_symlink() {
+            permissions.acl = acl::read_acl(path);
+        }
 
         Ok(FileMetadata {
             path: path.to_path_buf(),
@@ -109,25 +293,26 @@ impl LocalFileSystem {
             is_file: metadata.is_file(),
             is_dir: metadata.is_dir(),
             is_symlink: metadata.is_symlink(),
-            permissions: Self::extract_permissions(&std_metadata),
+            symlink_target,
+            permissions,
             mime_type,
             extension,
         })
     }
 
     /// Convert metadata to FileInfo
-    async fn to_file_info(path: &Path) -> Result<FileInfo> {
-        let metadata = Self::to_file_metadata(path).await?;
+    async fn to_file_info(path: &Path, record_symlinks: bool) -> Result<FileInfo> {
+        let metadata = Self::to_file_metadata(path, record_symlinks).await?;
         let name = path
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("")
             .to_string();
 
-        let file_type = if metadata.is_dir {
-            FileType::Directory
-        } else if metadata.is_symlink {
+        let file_type = if metadata.is_symlink {
             FileType::Symlink
+        } else if metadata.is_dir {
+            FileType::Directory
         } else if metadata.is_file {
             FileType::File
         } else {
@@ -145,19 +330,76 @@ impl LocalFileSystem {
             path: path.to_path_buf(),
             file_type,
             size: Some(metadata.size),
+            size_human: Some(format_size_si(metadata.size)),
             modified: Some(metadata.modified),
             created: metadata.created,
             accessed: metadata.accessed,
             permissions: Some(metadata.permissions),
+            symlink_target: metadata.symlink_target,
             children: None,
+            depth: 0,
+            error: None,
+            errors: Vec::new(),
+        })
+    }
+
+    /// Read the immediate entries of a directory
+    /// Unreadable entries are kept with an error note unless options.abort_on_error is set
+    async fn read_dir_entries(
+        path: &Path,
+        options: &DirectoryScanOptions,
+    ) -> Result<Vec<FileInfo>> {
+        let mut entries = fs::read_dir(path)
+            .await
+            .map_err(|e| Self::map_io_error(path, e))?;
+        let mut files = Vec::new();
+
+        while let Some(entry) = entries.next_entry().await? {
+            let entry_path = entry.path();
+            match Self::to_file_info(&entry_path, !options.follow_symlinks).await {
+                Ok(info) => files.push(FileInfo { depth: 1, ..info }),
+                Err(e) => files.push(Self::unreadable_entry(
+                    &entry_path,
+                    FileType::Unknown,
+                    1,
+                    e,
+                    options,
+                )?),
+            }
+        }
+
+        options.sort.sort(&mut files);
+        Ok(files)
+    }
+
+    /// Compile exclusion patterns once so they aren't rebuilt per directory
+    fn build_exclude_set(patterns: &[String]) -> Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = Glob::new(pattern).map_err(|_| FileSystemError::InvalidPath {
+                path: PathBuf::from(pattern),
+            })?;
+            builder.add(glob);
+        }
+        builder.build().map_err(|e| FileSystemError::InvalidPath {
+            path: PathBuf::from(e.glob().unwrap_or_default()),
         })
     }
 
+    /// Check whether a directory entry should be skipped by name
+    fn is_excluded(name: &str, options: &DirectoryScanOptions, excludes: &GlobSet) -> bool {
+        (!options.include_hidden && name.starts_with('.')) || excludes.is_match(name)
+    }
+
     /// Parallel directory scan implementation
+    /// `ancestors` holds the canonical paths of the directories above this one, so a symlink
+    /// pointing back up the tree is reported instead of recursed into forever
     fn scan_directory_parallel(
         path: &Path,
         options: &DirectoryScanOptions,
+        excludes: &GlobSet,
         current_depth: usize,
+        ancestors: &HashSet<PathBuf>,
     ) -> Result<FileInfo> {
         let mut info = std::fs::metadata(path)
             .map_err(|_| FileSystemError::DirectoryNotFound {
@@ -188,6 +430,7 @@ impl LocalFileSystem {
                     path: path.to_path_buf(),
                     file_type: FileType::Directory,
                     size: None,
+                    size_human: None,
                     modified: Some(modified),
                     created: metadata
                         .created()
@@ -198,7 +441,11 @@ impl LocalFileSystem {
                         .ok()
                         .and_then(Self::system_time_to_datetime),
                     permissions: Some(Self::extract_permissions(&metadata)),
+                    symlink_target: None,
                     children: Some(Vec::new()),
+                    depth: current_depth,
+                    error: None,
+                    errors: Vec::new(),
                 })
             })?;
 
@@ -209,32 +456,78 @@ impl LocalFileSystem {
             }
         }
 
+        let mut visited = ancestors.clone();
+        if let Ok(canonical) = std::fs::canonicalize(path) {
+            visited.insert(canonical);
+        }
+
         // Read directory entries
-        let entries: Vec<_> = std::fs::read_dir(path)
-            .map_err(|e| FileSystemError::IoError(e))?
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                if !options.include_hidden {
-                    if let Some(name) = entry.file_name().to_str() {
-                        if name.starts_with('.') {
-                            return false;
-                        }
-                    }
-                }
-                true
-            })
-            .collect();
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(path).map_err(|e| Self::map_io_error(path, e))? {
+            match entry {
+                Ok(entry) => entries.push(entry),
+                Err(e) if options.abort_on_error => return Err(Self::map_io_error(path, e)),
+                Err(_) => continue, // No path to report for an entry that can't be read
+            }
+        }
+        entries.retain(|entry| match entry.file_name().to_str() {
+            Some(name) => !Self::is_excluded(name, options, excludes),
+            None => true,
+        });
 
         // Process entries in parallel
-        let children: Vec<FileInfo> = entries
+        let loops = parking_lot::Mutex::new(Vec::new());
+        let mut children: Vec<FileInfo> = entries
             .par_iter()
-            .filter_map(|entry| {
+            .map(|entry| {
                 let path = entry.path();
-                let metadata = std::fs::metadata(&path).ok()?;
+                let is_symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
+                if is_symlink && !options.follow_symlinks {
+                    return Ok(Self::symlink_entry(&path, current_depth + 1));
+                }
 
-                if metadata.is_dir() {
+                let metadata = match std::fs::metadata(&path) {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        return Self::unreadable_entry(
+                            &path,
+                            FileType::Unknown,
+                            current_depth + 1,
+                            Self::map_io_error(&path, e),
+                            options,
+                        )
+                    }
+                };
+
+                let target = metadata
+                    .is_dir()
+                    .then(|| std::fs::canonicalize(&path).ok())
+                    .flatten();
+                if let Some(target) = target.filter(|target| visited.contains(target)) {
+                    let error = FileSystemError::SymlinkLoop {
+                        path: path.clone(),
+                        target,
+                    };
+                    loops.lock().push((path.clone(), error.to_string()));
+                    Ok(Self::symlink_entry(&path, current_depth + 1))
+                } else if metadata.is_dir() {
                     // Recursively scan subdirectory
-                    Self::scan_directory_parallel(&path, options, current_depth + 1).ok()
+                    Self::scan_directory_parallel(
+                        &path,
+                        options,
+                        excludes,
+                        current_depth + 1,
+                        &visited,
+                    )
+                    .or_else(|e| {
+                        Self::unreadable_entry(
+                            &path,
+                            FileType::Directory,
+                            current_depth + 1,
+                            e,
+                            options,
+                        )
+                    })
                 } else {
                     // Create FileInfo for file
                     let name = path
@@ -259,12 +552,13 @@ impl LocalFileSystem {
                         FileType::File
                     };
 
-                    Some(FileInfo {
+                    Ok(FileInfo {
                         id,
                         name,
                         path: path.clone(),
                         file_type,
                         size: Some(metadata.len()),
+                        size_human: Some(format_size_si(metadata.len())),
                         modified: Some(modified),
                         created: metadata
                             .created()
@@ -275,15 +569,339 @@ impl LocalFileSystem {
                             .ok()
                             .and_then(Self::system_time_to_datetime),
                         permissions: Some(Self::extract_permissions(&metadata)),
+                        symlink_target: None,
                         children: None,
+                        depth: current_depth + 1,
+                        error: None,
+                        errors: Vec::new(),
                     })
                 }
             })
-            .collect();
+            .collect::<Result<_>>()?;
+
+        // Detection reads each file's header, so the filter runs in parallel as well
+        if let Some(categories) = &options.filter_categories {
+            children = children
+                .into_par_iter()
+                .filter(|child| Self::passes_category_filter(child, categories))
+                .collect();
+        }
 
+        let mut loops = loops.into_inner();
+        loops.sort();
+        info.errors = loops;
+        options.sort.sort(&mut children);
         info.children = Some(children);
         Ok(info)
     }
+
+    /// Blocking recursive copy: recreate directories first, then copy files in parallel
+    /// Symlinks are never followed out of the tree; with `recreate_links` each one is
+    /// recreated at the destination pointing at the same target, otherwise it is skipped
+    fn copy_dir_blocking(
+        from: &Path,
+        to: &Path,
+        preserve_timestamps: bool,
+        recreate_links: bool,
+    ) -> Result<()> {
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        let mut links = Vec::new();
+        Self::collect_copy_plan(from, to, &mut dirs, &mut files, &mut links)?;
+
+        files.par_iter().try_for_each(|(src, dst)| -> Result<()> {
+            // Read before copying, since reading the source updates its access time
+            let source_metadata = std::fs::metadata(src).map_err(|e| Self::map_io_error(src, e))?;
+            std::fs::copy(src, dst).map_err(|e| Self::map_io_error(src, e))?;
+            if preserve_timestamps {
+                Self::copy_file_times(&source_metadata, dst)?;
+            }
+            Ok(())
+        })?;
+
+        if recreate_links {
+            for (src, dst) in &links {
+                Self::recreate_symlink(src, dst)?;
+            }
+        }
+
+        // Directory times change as files are written, so restore them last, deepest first
+        if preserve_timestamps {
+            for (source_metadata, dst) in dirs.iter().rev() {
+                Self::copy_file_times(source_metadata, dst)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create the destination directories and list the (source, destination) file and
+    /// symlink pairs; directories are listed with their metadata from before they were read
+    fn collect_copy_plan(
+        from: &Path,
+        to: &Path,
+        dirs: &mut Vec<(std::fs::Metadata, PathBuf)>,
+        files: &mut Vec<(PathBuf, PathBuf)>,
+        links: &mut Vec<(PathBuf, PathBuf)>,
+    ) -> Result<()> {
+        let metadata = std::fs::metadata(from).map_err(|e| Self::map_io_error(from, e))?;
+        std::fs::create_dir_all(to).map_err(|e| Self::map_io_error(to, e))?;
+        dirs.push((metadata, to.to_path_buf()));
+
+        for entry in std::fs::read_dir(from).map_err(|e| Self::map_io_error(from, e))? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let src = entry.path();
+            let dst = to.join(entry.file_name());
+
+            if file_type.is_dir() {
+                Self::collect_copy_plan(&src, &dst, dirs, files, links)?;
+            } else if file_type.is_file() {
+                files.push((src, dst));
+            } else if file_type.is_symlink() {
+                links.push((src, dst));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create a symlink at `to` with the same (unresolved) target as the link at `from`
+    fn recreate_symlink(from: &Path, to: &Path) -> Result<()> {
+        let target = std::fs::read_link(from).map_err(|e| Self::map_io_error(from, e))?;
+
+        #[cfg(unix)]
+        let created = std::os::unix::fs::symlink(&target, to);
+
+        #[cfg(windows)]
+        let created = if std::fs::metadata(from).is_ok_and(|m| m.is_dir()) {
+            std::os::windows::fs::symlink_dir(&target, to)
+        } else {
+            std::os::windows::fs::symlink_file(&target, to)
+        };
+
+        created.map_err(|e| Self::map_io_error(to, e))
+    }
+
+    /// Set the destination's accessed/modified times to the source's
+    /// This is what copy_file_preserving adds to a plain copy. The source metadata must be
+    /// read before the source is, or the access time is that of the copy itself
+    pub(crate) fn copy_file_times(source: &std::fs::Metadata, to: &Path) -> Result<()> {
+        let mtime = filetime::FileTime::from_last_modification_time(source);
+        let atime = filetime::FileTime::from_last_access_time(source);
+        filetime::set_file_times(to, atime, mtime)?;
+        Ok(())
+    }
+
+    /// Complete a move given the outcome of the rename attempt
+    /// A cross-device rename is retried as a timestamp-preserving copy followed by a delete
+    /// Symlinks inside a moved directory are recreated, so the delete never loses them
+    async fn finish_move(
+        &self,
+        from: &Path,
+        to: &Path,
+        renamed: std::io::Result<()>,
+    ) -> Result<()> {
+        match renamed {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                if self.is_dir(from).await? {
+                    let (src, dst) = (from.to_path_buf(), to.to_path_buf());
+                    tokio::task::spawn_blocking(move || {
+                        Self::copy_dir_blocking(&src, &dst, true, true)
+                    })
+                    .await
+                    .map_err(|e| FileSystemError::Unknown(e.to_string()))??;
+                    fs::remove_dir_all(from).await?;
+                } else {
+                    self.copy_file_preserving(from, to, true).await?;
+                    fs::remove_file(from).await?;
+                }
+                Ok(())
+            }
+            Err(e) => Err(FileSystemError::IoError(e)),
+        }
+    }
+
+    /// Fill buf as far as the file allows; a short count means EOF was reached
+    async fn read_chunk(file: &mut fs::File, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let n = file.read(&mut buf[filled..]).await?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        Ok(filled)
+    }
+
+    /// (file count, total bytes) of the tree at path, or of the single file it names
+    async fn tree_totals(path: &Path) -> Result<(u64, u64)> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let metadata = std::fs::metadata(&path).map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    FileSystemError::FileNotFound { path: path.clone() }
+                } else {
+                    Self::map_io_error(&path, e)
+                }
+            })?;
+            if metadata.is_dir() {
+                Ok(Self::dir_totals(&path))
+            } else {
+                Ok((1, metadata.len()))
+            }
+        })
+        .await
+        .map_err(|e| FileSystemError::Unknown(e.to_string()))?
+    }
+
+    /// Blocking walk summing files, with subdirectories handled in parallel
+    fn dir_totals(path: &Path) -> (u64, u64) {
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return (0, 0);
+        };
+
+        let mut files = 0;
+        let mut bytes = 0;
+        let mut subdirs = Vec::new();
+        for entry in entries.flatten() {
+            // file_type doesn't follow symlinks, so links can't loop or count twice
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => subdirs.push(entry.path()),
+                Ok(file_type) if file_type.is_file() => {
+                    files += 1;
+                    bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+                }
+                _ => {}
+            }
+        }
+
+        let (sub_files, sub_bytes) = subdirs
+            .par_iter()
+            .map(|dir| Self::dir_totals(dir))
+            .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+        (files + sub_files, bytes + sub_bytes)
+    }
+
+    /// Whether a scanned entry survives the category filter
+    /// Only files are pruned; one whose type can't be detected doesn't match any category
+    fn passes_category_filter(entry: &FileInfo, categories: &[FileCategory]) -> bool {
+        entry.file_type != FileType::File
+            || FileTypeDetector::detect(&entry.path)
+                .is_ok_and(|detected| categories.contains(&detected.category))
+    }
+
+    /// Map an IO error to a FileSystemError, keeping permission problems distinct
+    fn map_io_error(path: &Path, e: std::io::Error) -> FileSystemError {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            FileSystemError::PermissionDenied {
+                path: path.to_path_buf(),
+            }
+        } else {
+            FileSystemError::IoError(e)
+        }
+    }
+
+    /// Entry for a symlink that is listed, with its target, but not followed
+    fn symlink_entry(path: &Path, depth: usize) -> FileInfo {
+        let metadata = std::fs::symlink_metadata(path).ok();
+
+        let mut hasher = Md5::new();
+        hasher.update(path.to_string_lossy().as_bytes());
+        let id = format!("{:x}", hasher.finalize());
+
+        FileInfo {
+            id,
+            name: path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string(),
+            path: path.to_path_buf(),
+            file_type: FileType::Symlink,
+            size: None,
+            size_human: None,
+            modified: metadata
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(Self::system_time_to_datetime),
+            created: None,
+            accessed: None,
+            permissions: metadata.as_ref().map(Self::extract_permissions),
+            symlink_target: std::fs::read_link(path).ok(),
+            children: None,
+            depth,
+            error: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Placeholder for an entry that couldn't be read, or the error itself when aborting
+    fn unreadable_entry(
+        path: &Path,
+        file_type: FileType,
+        depth: usize,
+        error: FileSystemError,
+        options: &DirectoryScanOptions,
+    ) -> Result<FileInfo> {
+        if options.abort_on_error {
+            return Err(error);
+        }
+
+        let mut hasher = Md5::new();
+        hasher.update(path.to_string_lossy().as_bytes());
+        let id = format!("{:x}", hasher.finalize());
+
+        Ok(FileInfo {
+            id,
+            name: path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string(),
+            path: path.to_path_buf(),
+            file_type,
+            size: None,
+            size_human: None,
+            modified: None,
+            created: None,
+            accessed: None,
+            permissions: None,
+            symlink_target: None,
+            children: None,
+            depth,
+            error: Some(error.to_string()),
+            errors: Vec::new(),
+        })
+    }
+
+    /// Progress report with a percentage and an ETA extrapolated from the throughput so far
+    fn hash_progress(
+        path: &Path,
+        bytes_processed: u64,
+        total_bytes: u64,
+        elapsed: Duration,
+    ) -> HashProgress {
+        let percent = if total_bytes == 0 {
+            100.0
+        } else {
+            (bytes_processed as f64 / total_bytes as f64 * 100.0).min(100.0)
+        };
+        let remaining = total_bytes.saturating_sub(bytes_processed);
+        let eta_ms = (bytes_processed > 0).then(|| {
+            (remaining as f64 * elapsed.as_millis() as f64 / bytes_processed as f64) as u64
+        });
+
+        HashProgress {
+            path: path.to_path_buf(),
+            bytes_processed,
+            total_bytes,
+            percent,
+            eta_ms,
+        }
+    }
 }
 
 #[async_trait]
@@ -316,6 +934,11 @@ impl FileSystem for LocalFileSystem {
         })
     }
 
+    async fn read_to_string_lossy(&self, path: &Path) -> Result<String> {
+        let data = self.read_file(path).await?;
+        Ok(String::from_utf8_lossy(&data).into_owned())
+    }
+
     async fn write_file(&self, path: &Path, data: &[u8]) -> Result<()> {
         // Create parent directories if they don't exist
         if let Some(parent) = path.parent() {
@@ -326,6 +949,15 @@ impl FileSystem for LocalFileSystem {
             .map_err(FileSystemError::IoError)
     }
 
+    async fn atomic_write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        let path = path.to_path_buf();
+        let data = data.to_vec();
+        tokio::task::spawn_blocking(move || atomic_write_sync(&path, &data))
+            .await
+            .map_err(|e| FileSystemError::Unknown(e.to_string()))?
+            .map_err(FileSystemError::IoError)
+    }
+
     async fn exists(&self, path: &Path) -> Result<bool> {
         Ok(fs::try_exists(path).await.unwrap_or(false))
     }
@@ -345,27 +977,21 @@ impl FileSystem for LocalFileSystem {
     }
 
     async fn metadata(&self, path: &Path) -> Result<FileMetadata> {
-        Self::to_file_metadata(path).await
+        Self::to_file_metadata(path, self.record_symlinks).await
     }
 
-    async fn list_dir(&self, path: &Path) -> Result<Vec<FileInfo>> {
+    async fn list_dir(&self, path: &Path, sort: SortOrder) -> Result<Vec<FileInfo>> {
         if !self.is_dir(path).await? {
             return Err(FileSystemError::NotADirectory {
                 path: path.to_path_buf(),
             });
         }
 
-        let mut entries = fs::read_dir(path).await?;
-        let mut files = Vec::new();
-
-        while let Some(entry) = entries.next_entry().await? {
-            match Self::to_file_info(&entry.path()).await {
-                Ok(info) => files.push(info),
-                Err(_) => continue, // Skip files we can't read
-            }
-        }
-
-        Ok(files)
+        let options = DirectoryScanOptions {
+            sort,
+            ..DirectoryScanOptions::default()
+        };
+        Self::read_dir_entries(path, &options).await
     }
 
     async fn scan_directory(&self, path: &Path, options: DirectoryScanOptions) -> Result<FileInfo> {
@@ -379,16 +1005,20 @@ impl FileSystem for LocalFileSystem {
             path, options.parallel
         );
 
+        let excludes = Self::build_exclude_set(&options.exclude_patterns)?;
+
         if options.parallel {
             // Use rayon for parallel scanning
             let path = path.to_path_buf();
             let opts = options.clone();
-            tokio::task::spawn_blocking(move || Self::scan_directory_parallel(&path, &opts, 0))
-                .await
-                .map_err(|e| FileSystemError::Unknown(e.to_string()))?
+            tokio::task::spawn_blocking(move || {
+                Self::scan_directory_parallel(&path, &opts, &excludes, 0, &HashSet::new())
+            })
+            .await
+            .map_err(|e| FileSystemError::Unknown(e.to_string()))?
         } else {
             // Sequential scan using async
-            let mut info = Self::to_file_info(path).await?;
+            let mut info = Self::to_file_info(path, !options.follow_symlinks).await?;
 
             if let Some(max_depth) = options.max_depth {
                 if max_depth == 0 {
@@ -396,7 +1026,15 @@ impl FileSystem for LocalFileSystem {
                 }
             }
 
-            let entries = self.list_dir(path).await?;
+            let entries = Self::read_dir_entries(path, &options)
+                .await?
+                .into_iter()
+                .filter(|entry| !Self::is_excluded(&entry.name, &options, &excludes))
+                .filter(|entry| match &options.filter_categories {
+                    Some(categories) => Self::passes_category_filter(entry, categories),
+                    None => true,
+                })
+                .collect();
             info.children = Some(entries);
             Ok(info)
         }
@@ -440,27 +1078,245 @@ impl FileSystem for LocalFileSystem {
         Ok(())
     }
 
-    async fn move_path(&self, from: &Path, to: &Path) -> Result<()> {
-        fs::rename(from, to).await.map_err(FileSystemError::IoError)
-    }
+    async fn copy_file_preserving(
+        &self,
+        from: &Path,
+        to: &Path,
+        preserve_permissions: bool,
+    ) -> Result<()> {
+        let metadata = fs::metadata(from)
+            .await
+            .map_err(|e| Self::map_io_error(from, e))?;
+        self.copy_file(from, to).await?;
 
-    async fn calculate_hash(&self, path: &Path) -> Result<FileHash> {
-        let data = self.read_file(path).await?;
+        if preserve_permissions {
+            fs::set_permissions(to, metadata.permissions()).await?;
+        }
+        Self::copy_file_times(&metadata, to)
+    }
 
-        // Calculate MD5
-        let mut md5_hasher = Md5::new();
-        md5_hasher.update(&data);
-        let md5 = format!("{:x}", md5_hasher.finalize());
+    async fn copy_dir(&self, from: &Path, to: &Path, preserve_timestamps: bool) -> Result<()> {
+        if !self.is_dir(from).await? {
+            return Err(FileSystemError::NotADirectory {
+                path: from.to_path_buf(),
+            });
+        }
+
+        let from = from.to_path_buf();
+        let to = to.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            Self::copy_dir_blocking(&from, &to, preserve_timestamps, false)
+        })
+        .await
+        .map_err(|e| FileSystemError::Unknown(e.to_string()))?
+    }
+
+    async fn move_path(&self, from: &Path, to: &Path) -> Result<()> {
+        let renamed = fs::rename(from, to).await;
+        self.finish_move(from, to, renamed).await
+    }
+
+    async fn create_symlink(&self, target: &Path, link: &Path) -> Result<()> {
+        #[cfg(windows)]
+        {
+            // Without elevation Windows only allows file symlinks, and only in Developer Mode
+            const ERROR_PRIVILEGE_NOT_HELD: i32 = 1314;
+
+            let resolved = link.parent().unwrap_or(Path::new("")).join(target);
+            if self.is_dir(&resolved).await? {
+                return Err(FileSystemError::SymlinkNotSupported {
+                    path: link.to_path_buf(),
+                });
+            }
+            fs::symlink_file(target, link).await.map_err(|e| {
+                if e.raw_os_error() == Some(ERROR_PRIVILEGE_NOT_HELD) {
+                    FileSystemError::SymlinkNotSupported {
+                        path: link.to_path_buf(),
+                    }
+                } else {
+                    Self::map_io_error(link, e)
+                }
+            })
+        }
+
+        #[cfg(unix)]
+        {
+            fs::symlink(target, link)
+                .await
+                .map_err(|e| Self::map_io_error(link, e))
+        }
+    }
+
+    async fn create_hardlink(&self, original: &Path, link: &Path) -> Result<()> {
+        if !self.is_file(original).await? {
+            return Err(FileSystemError::NotAFile {
+                path: original.to_path_buf(),
+            });
+        }
+        fs::hard_link(original, link)
+            .await
+            .map_err(|e| Self::map_io_error(link, e))
+    }
+
+    async fn calculate_hash(&self, path: &Path) -> Result<FileHash> {
+        self.calculate_hash_with_progress(path, &|_| {}).await
+    }
+
+    async fn calculate_hash_with_progress(
+        &self,
+        path: &Path,
+        on_progress: &(dyn Fn(HashProgress) + Send + Sync),
+    ) -> Result<FileHash> {
+        let mut file = fs::File::open(path).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                FileSystemError::FileNotFound {
+                    path: path.to_path_buf(),
+                }
+            } else if e.kind() == std::io::ErrorKind::PermissionDenied {
+                FileSystemError::PermissionDenied {
+                    path: path.to_path_buf(),
+                }
+            } else {
+                FileSystemError::IoError(e)
+            }
+        })?;
+        let total_bytes = file.metadata().await?.len();
 
-        // Calculate SHA256
+        // Stream the file through every hasher instead of loading it into memory
+        let mut md5_hasher = Md5::new();
         let mut sha256_hasher = Sha256::new();
-        sha256_hasher.update(&data);
-        let sha256 = format!("{:x}", sha256_hasher.finalize());
+        let mut keccak256_hasher = Keccak256::new();
+        let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+        let mut bytes_processed = 0u64;
+        let mut next_report = HASH_PROGRESS_INTERVAL;
+        let mut last_reported = None;
+        let started = Instant::now();
+
+        loop {
+            let n = file.read(&mut buffer).await?;
+            if n == 0 {
+                break;
+            }
+            md5_hasher.update(&buffer[..n]);
+            sha256_hasher.update(&buffer[..n]);
+            keccak256_hasher.update(&buffer[..n]);
+            bytes_processed += n as u64;
+
+            // Reports are bounded to one per interval so large files don't flood IPC
+            if bytes_processed >= next_report {
+                on_progress(Self::hash_progress(
+                    path,
+                    bytes_processed,
+                    total_bytes,
+                    started.elapsed(),
+                ));
+                last_reported = Some(bytes_processed);
+                next_report += HASH_PROGRESS_INTERVAL;
+            }
+        }
+
+        // Always finish on 100%, even if the last interval wasn't reached
+        if last_reported != Some(bytes_processed) {
+            on_progress(Self::hash_progress(
+                path,
+                bytes_processed,
+                bytes_processed,
+                started.elapsed(),
+            ));
+        }
 
         Ok(FileHash {
             path: path.to_path_buf(),
-            md5,
-            sha256,
+            md5: format!("{:x}", md5_hasher.finalize()),
+            sha256: format!("{:x}", sha256_hasher.finalize()),
+            keccak256: format!("{:x}", keccak256_hasher.finalize()),
+        })
+    }
+
+    async fn compare_files(
+        &self,
+        path_a: &Path,
+        path_b: &Path,
+        max_diff_bytes: Option<u64>,
+    ) -> Result<FileDiff> {
+        let (file_a, file_b) =
+            tokio::join!(Self::open_for_read(path_a), Self::open_for_read(path_b));
+        let (mut file_a, mut file_b) = (file_a?, file_b?);
+        let size_a = file_a.metadata().await?.len();
+        let size_b = file_b.metadata().await?.len();
+
+        let mut hasher_a = Sha256::new();
+        let mut hasher_b = Sha256::new();
+        let mut buf_a = vec![0u8; HASH_CHUNK_SIZE];
+        let mut buf_b = vec![0u8; HASH_CHUNK_SIZE];
+        let mut offset = 0u64;
+        let mut first_difference_offset = None;
+        let mut different_byte_count = 0u64;
+        let mut truncated = false;
+        let limit = max_diff_bytes.unwrap_or(u64::MAX);
+
+        loop {
+            let (n_a, n_b) = tokio::join!(
+                Self::read_chunk(&mut file_a, &mut buf_a),
+                Self::read_chunk(&mut file_b, &mut buf_b)
+            );
+            let (n_a, n_b) = (n_a?, n_b?);
+            if n_a == 0 && n_b == 0 {
+                break;
+            }
+            hasher_a.update(&buf_a[..n_a]);
+            hasher_b.update(&buf_b[..n_b]);
+
+            // Chunks are filled completely until EOF, so positions line up across files
+            // The limit is checked per byte: counting stops at the first difference past it
+            let common = n_a.min(n_b);
+            let tail = (n_a.max(n_b) - common) as u64;
+            for (i, (a, b)) in buf_a[..common].iter().zip(&buf_b[..common]).enumerate() {
+                if a != b {
+                    first_difference_offset.get_or_insert(offset + i as u64);
+                    if different_byte_count == limit {
+                        truncated = true;
+                        break;
+                    }
+                    different_byte_count += 1;
+                }
+            }
+            if !truncated && tail > 0 {
+                first_difference_offset.get_or_insert(offset + common as u64);
+                let counted = tail.min(limit - different_byte_count);
+                different_byte_count += counted;
+                truncated = counted < tail;
+            }
+            if truncated {
+                break;
+            }
+            offset += n_a.max(n_b) as u64;
+        }
+
+        let largest = size_a.max(size_b);
+        let (hash_a, hash_b) = if truncated {
+            (String::new(), String::new())
+        } else {
+            (
+                format!("{:x}", hasher_a.finalize()),
+                format!("{:x}", hasher_b.finalize()),
+            )
+        };
+
+        Ok(FileDiff {
+            are_identical: !truncated && different_byte_count == 0,
+            size_a,
+            size_b,
+            hash_a,
+            hash_b,
+            first_difference_offset,
+            different_byte_count,
+            diff_ratio: if largest == 0 {
+                0.0
+            } else {
+                (different_byte_count as f64 / largest as f64) as f32
+            },
+            truncated,
         })
     }
 
@@ -492,7 +1348,26 @@ impl FileSystem for LocalFileSystem {
 
         tokio::task::spawn_blocking(move || {
             let mut results = Vec::new();
-            Self::search_content_recursive(&base_path, &opts, &mut results, 0)?;
+
+            let parallel_files = match opts.parallel_threshold {
+                Some(threshold) => {
+                    // An empty name pattern lists every file the content search would visit
+                    let listing = SearchOptions {
+                        pattern: String::new(),
+                        max_results: None,
+                        ..opts.clone()
+                    };
+                    let mut files = Vec::new();
+                    Self::search_files_recursive(&base_path, &listing, &mut files, 0)?;
+                    Some(files).filter(|files| files.len() > threshold)
+                }
+                None => None,
+            };
+
+            match parallel_files {
+                Some(files) => results = Self::search_content_parallel(&files, &opts),
+                None => Self::search_content_recursive(&base_path, &opts, &mut results, 0)?,
+            }
 
             if let Some(max) = opts.max_results {
                 results.truncate(max);
@@ -541,9 +1416,153 @@ impl FileSystem for LocalFileSystem {
         })?;
         Ok(metadata.len())
     }
+
+    async fn recursive_size(&self, path: &Path) -> Result<u64> {
+        Ok(Self::tree_totals(path).await?.1)
+    }
+
+    async fn recursive_file_count(&self, path: &Path) -> Result<u64> {
+        Ok(Self::tree_totals(path).await?.0)
+    }
+
+    async fn head_lines(&self, path: &Path, n: usize) -> Result<Vec<String>> {
+        let file = Self::open_for_read(path).await?;
+        let mut reader = tokio::io::BufReader::new(file);
+        let mut lines = Vec::with_capacity(n.min(LINE_PREVIEW_MAX_PREALLOC));
+        let mut line = Vec::new();
+        let mut in_line = false;
+
+        while lines.len() < n {
+            let buffer = reader.fill_buf().await?;
+            if buffer.is_empty() {
+                if in_line {
+                    lines.push(line_to_string(&line));
+                }
+                break;
+            }
+
+            let (segment, consumed, ends_line) = match memchr::memchr(b'\n', buffer) {
+                Some(newline) => (&buffer[..newline], newline + 1, true),
+                None => (buffer, buffer.len(), false),
+            };
+            // Bytes past the cap are read through but not kept
+            let room = LINE_PREVIEW_MAX_LINE_LEN.saturating_sub(line.len());
+            line.extend_from_slice(&segment[..segment.len().min(room)]);
+            reader.consume(consumed);
+            in_line = true;
+
+            if ends_line {
+                lines.push(line_to_string(&line));
+                line.clear();
+                in_line = false;
+            }
+        }
+
+        Ok(lines)
+    }
+
+    async fn tail_lines(&self, path: &Path, n: usize) -> Result<Vec<String>> {
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut file = Self::open_for_read(path).await?;
+        let len = file.metadata().await?.len();
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        // A trailing newline ends the last line rather than starting an empty one
+        let mut pos = len;
+        let mut last = [0u8; 1];
+        file.seek(std::io::SeekFrom::Start(len - 1)).await?;
+        file.read_exact(&mut last).await?;
+        if last[0] == b'\n' {
+            pos -= 1;
+        }
+
+        // Lines are found last to first, so each one goes on the front; at most n are kept
+        let mut lines: VecDeque<String> = VecDeque::with_capacity(n.min(LINE_PREVIEW_MAX_PREALLOC));
+        let mut line: Vec<u8> = Vec::new();
+        let mut buffer = vec![0u8; LINE_PREVIEW_CHUNK_SIZE as usize];
+
+        // Read fixed-size chunks backward until n complete lines have been seen
+        while pos > 0 {
+            let read_len = LINE_PREVIEW_CHUNK_SIZE.min(pos);
+            pos -= read_len;
+            file.seek(std::io::SeekFrom::Start(pos)).await?;
+            let chunk = &mut buffer[..read_len as usize];
+            file.read_exact(chunk).await?;
+
+            let mut end = chunk.len();
+            while let Some(newline) = memchr::memrchr(b'\n', &chunk[..end]) {
+                prepend_capped(&mut line, &chunk[newline + 1..end]);
+                lines.push_front(line_to_string(&line));
+                if lines.len() == n {
+                    return Ok(lines.into());
+                }
+                line.clear();
+                end = newline;
+            }
+            prepend_capped(&mut line, &chunk[..end]);
+        }
+
+        // Reached the start of the file, so what is left is its first line
+        lines.push_front(line_to_string(&line));
+        Ok(lines.into())
+    }
+}
+
+/// Chunk size used when scanning a file backward for tail_lines
+const LINE_PREVIEW_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Upper bound on the line buffer reserved up front for head_lines and tail_lines
+const LINE_PREVIEW_MAX_PREALLOC: usize = 1024;
+
+/// Bytes kept from a single line in head_lines and tail_lines; the rest of the line is dropped
+const LINE_PREVIEW_MAX_LINE_LEN: usize = 64 * 1024;
+
+/// Put segment in front of a partial line read backward, keeping only its first
+/// LINE_PREVIEW_MAX_LINE_LEN bytes
+fn prepend_capped(line: &mut Vec<u8>, segment: &[u8]) {
+    let segment = &segment[..segment.len().min(LINE_PREVIEW_MAX_LINE_LEN)];
+    line.truncate(LINE_PREVIEW_MAX_LINE_LEN - segment.len());
+    line.splice(0..0, segment.iter().copied());
+}
+
+/// Decode one line for preview, tolerating invalid UTF-8 and CRLF endings
+fn line_to_string(line: &[u8]) -> String {
+    String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line)).into_owned()
 }
 
 impl LocalFileSystem {
+    async fn open_for_read(path: &Path) -> Result<fs::File> {
+        fs::File::open(path).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                FileSystemError::FileNotFound {
+                    path: path.to_path_buf(),
+                }
+            } else {
+                FileSystemError::IoError(e)
+            }
+        })
+    }
+
+    /// Whether a file passes the extension filter, ignoring case and a leading dot
+    /// Shared by the name and content searches so every search path selects the same files
+    fn matches_extensions(path: &Path, options: &SearchOptions) -> bool {
+        let Some(extensions) = &options.file_extensions else {
+            return true;
+        };
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| {
+                extensions
+                    .iter()
+                    .any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(ext))
+            })
+    }
+
     fn search_files_recursive(
         path: &Path,
         options: &SearchOptions,
@@ -579,15 +1598,8 @@ impl LocalFileSystem {
             if entry_path.is_dir() {
                 Self::search_files_recursive(&entry_path, options, results, depth + 1)?;
             } else if entry_path.is_file() {
-                // Check file extension filter
-                if let Some(exts) = &options.file_extensions {
-                    if let Some(ext) = entry_path.extension().and_then(|e| e.to_str()) {
-                        if !exts.contains(&ext.to_string()) {
-                            continue;
-                        }
-                    } else {
-                        continue;
-                    }
+                if !Self::matches_extensions(&entry_path, options) {
+                    continue;
                 }
 
                 // Match against pattern
@@ -645,37 +1657,12 @@ impl LocalFileSystem {
 
             if entry_path.is_dir() {
                 Self::search_content_recursive(&entry_path, options, results, depth + 1)?;
-            } else if entry_path.is_file() {
-                // Try to read file as text
-                if let Ok(content) = std::fs::read_to_string(&entry_path) {
-                    for (line_num, line) in content.lines().enumerate() {
-                        let matches = if options.regex {
-                            // TODO: Use regex crate
-                            line.contains(&options.pattern)
-                        } else if options.case_sensitive {
-                            line.contains(&options.pattern)
-                        } else {
-                            line.to_lowercase()
-                                .contains(&options.pattern.to_lowercase())
-                        };
-
-                        if matches {
-                            if let Some(col) = line.find(&options.pattern) {
-                                results.push(SearchResult {
-                                    path: entry_path.clone(),
-                                    line: line_num + 1,
-                                    column: col,
-                                    content: line.to_string(),
-                                    r#match: options.pattern.clone(),
-                                });
-
-                                if let Some(max) = options.max_results {
-                                    if results.len() >= max {
-                                        return Ok(());
-                                    }
-                                }
-                            }
-                        }
+            } else if entry_path.is_file() && Self::matches_extensions(&entry_path, options) {
+                results.extend(Self::search_file_content(&entry_path, options));
+
+                if let Some(max) = options.max_results {
+                    if results.len() >= max {
+                        return Ok(());
                     }
                 }
             }
@@ -683,6 +1670,93 @@ impl LocalFileSystem {
 
         Ok(())
     }
+
+    /// Search a pre-collected file list in parallel, keeping the walk order of results
+    fn search_content_parallel(files: &[PathBuf], options: &SearchOptions) -> Vec<SearchResult> {
+        files
+            .par_iter()
+            .flat_map_iter(|path| Self::search_file_content(path, options))
+            .collect()
+    }
+
+    /// Snippet fields describing the file itself, with the line fields left empty
+    fn file_snippet(path: &Path) -> ContextSnippet {
+        let metadata = std::fs::metadata(path).ok();
+        let modified = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(Self::system_time_to_datetime);
+
+        ContextSnippet {
+            file_name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            file_size_human: format_size_si(metadata.as_ref().map_or(0, |m| m.len())),
+            mime_type: Self::mime_type_for(path, true),
+            modified_human: modified
+                .map(|modified| format_relative_time(modified, Utc::now()))
+                .unwrap_or_default(),
+            match_line: String::new(),
+            before_lines: Vec::new(),
+            after_lines: Vec::new(),
+        }
+    }
+
+    /// Find matching lines in a single file (non-text files yield nothing)
+    fn search_file_content(path: &Path, options: &SearchOptions) -> Vec<SearchResult> {
+        let mut results = Vec::new();
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return results;
+        };
+        let lines: Vec<&str> = content.lines().collect();
+
+        // File details are the same for every match, so they're looked up on the first one
+        let mut file_snippet: Option<ContextSnippet> = None;
+
+        for (line_num, line) in lines.iter().copied().enumerate() {
+            let matches = if options.regex {
+                // TODO: Use regex crate
+                line.contains(&options.pattern)
+            } else if options.case_sensitive {
+                line.contains(&options.pattern)
+            } else {
+                line.to_lowercase()
+                    .contains(&options.pattern.to_lowercase())
+            };
+
+            if matches {
+                if let Some(col) = line.find(&options.pattern) {
+                    let file_snippet = file_snippet.get_or_insert_with(|| Self::file_snippet(path));
+                    let before_start = line_num.saturating_sub(options.context_lines);
+                    let after_end = (line_num + 1 + options.context_lines).min(lines.len());
+                    let to_strings = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect();
+
+                    results.push(SearchResult {
+                        path: path.to_path_buf(),
+                        line: line_num + 1,
+                        column: col,
+                        content: line.to_string(),
+                        r#match: options.pattern.clone(),
+                        context_snippet: ContextSnippet {
+                            match_line: line.to_string(),
+                            before_lines: to_strings(&lines[before_start..line_num]),
+                            after_lines: to_strings(&lines[line_num + 1..after_end]),
+                            ..file_snippet.clone()
+                        },
+                    });
+
+                    if let Some(max) = options.max_results {
+                        if results.len() >= max {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        results
+    }
 }
 
 #[cfg(test)]
@@ -702,4 +1776,804 @@ mod tests {
 
         fs.delete_file(test_path).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_atomic_write_replaces_file_without_leftovers() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("cache.bin");
+
+        fs.write_file(&path, b"old contents").await.unwrap();
+        fs.atomic_write(&path, b"new").await.unwrap();
+
+        assert_eq!(fs.read_file(&path).await.unwrap(), b"new");
+        let names: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec![std::ffi::OsString::from("cache.bin")]);
+
+        // A failed write leaves the existing file untouched
+        let missing_dir = temp_dir.path().join("cache.bin").join("nested");
+        assert!(atomic_write_sync(&missing_dir, b"oops").is_err());
+        assert_eq!(fs.read_file(&path).await.unwrap(), b"new");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_atomic_write_keeps_permissions_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let script = temp_dir.path().join("collect.sh");
+        fs.write_file(&script, b"#!/bin/sh\n").await.unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o750)).unwrap();
+        let link = temp_dir.path().join("current.sh");
+        std::os::unix::fs::symlink("collect.sh", &link).unwrap();
+
+        fs.atomic_write(&link, b"#!/bin/sh\necho done\n")
+            .await
+            .unwrap();
+
+        assert!(std::fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(
+            fs.read_file(&script).await.unwrap(),
+            b"#!/bin/sh\necho done\n"
+        );
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+    }
+
+    #[tokio::test]
+    async fn test_hash_progress_increases_to_completion() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("disk.img");
+        let size = 2 * HASH_PROGRESS_INTERVAL + 12_345;
+        std::fs::write(&path, vec![0xA5u8; size as usize]).unwrap();
+
+        let reports = std::sync::Mutex::new(Vec::new());
+        let hash = fs
+            .calculate_hash_with_progress(&path, &|progress| reports.lock().unwrap().push(progress))
+            .await
+            .unwrap();
+        let reports = reports.into_inner().unwrap();
+
+        assert_eq!(hash.sha256, fs.calculate_hash(&path).await.unwrap().sha256);
+        // One report per interval plus the final one
+        assert_eq!(reports.len(), 3);
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].bytes_processed < pair[1].bytes_processed
+                && pair[0].percent < pair[1].percent));
+        let last = reports.last().unwrap();
+        assert_eq!(last.bytes_processed, size);
+        assert_eq!(last.percent, 100.0);
+        assert_eq!(last.eta_ms, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_calculate_hash_includes_keccak256() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("abc.txt");
+        std::fs::write(&path, "abc").unwrap();
+
+        let hash = fs.calculate_hash(&path).await.unwrap();
+        assert_eq!(
+            hash.sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hash.keccak256,
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compare_files_reports_differences() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let a = temp_dir.path().join("a.bin");
+        let b = temp_dir.path().join("b.bin");
+        let mut data = vec![7u8; HASH_CHUNK_SIZE + 100];
+        std::fs::write(&a, &data).unwrap();
+        data[10] = 0;
+        data[HASH_CHUNK_SIZE + 10] = 0;
+        data.extend_from_slice(b"tail");
+        std::fs::write(&b, &data).unwrap();
+
+        let same = fs.compare_files(&a, &a, None).await.unwrap();
+        assert!(same.are_identical);
+        assert_eq!(same.first_difference_offset, None);
+        assert_eq!(same.hash_a, same.hash_b);
+
+        let diff = fs.compare_files(&a, &b, None).await.unwrap();
+        assert!(!diff.are_identical);
+        assert_eq!(diff.size_b, diff.size_a + 4);
+        assert_eq!(diff.first_difference_offset, Some(10));
+        assert_eq!(diff.different_byte_count, 6);
+        assert!(!diff.truncated);
+        assert_ne!(diff.hash_a, diff.hash_b);
+
+        let bounded = fs.compare_files(&a, &b, Some(1)).await.unwrap();
+        assert!(bounded.truncated);
+        assert_eq!(bounded.different_byte_count, 1);
+        assert!(bounded.hash_a.is_empty());
+
+        // Stops partway into the 4-byte tail rather than at the end of the chunk
+        let bounded = fs.compare_files(&a, &b, Some(4)).await.unwrap();
+        assert!(bounded.truncated);
+        assert_eq!(bounded.different_byte_count, 4);
+
+        // A limit that is met exactly leaves nothing uncounted
+        let exact = fs.compare_files(&a, &b, Some(6)).await.unwrap();
+        assert!(!exact.truncated);
+        assert_eq!(exact.different_byte_count, 6);
+        assert_eq!(exact.hash_a, diff.hash_a);
+
+        // No differences allowed: equal files are still compared to the end
+        let same = fs.compare_files(&a, &a, Some(0)).await.unwrap();
+        assert!(same.are_identical);
+        assert!(!same.truncated);
+        assert!(!same.hash_a.is_empty());
+
+        let strict = fs.compare_files(&a, &b, Some(0)).await.unwrap();
+        assert!(strict.truncated);
+        assert!(!strict.are_identical);
+        assert_eq!(strict.different_byte_count, 0);
+        assert_eq!(strict.first_difference_offset, Some(10));
+    }
+
+    #[tokio::test]
+    async fn test_extensionless_sqlite_reports_sqlite_mime() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let history = temp_dir.path().join("History");
+        rusqlite::Connection::open(&history)
+            .unwrap()
+            .execute_batch("CREATE TABLE urls (url TEXT);")
+            .unwrap();
+        let notes = temp_dir.path().join("README");
+        fs.write_file(&notes, b"plain notes").await.unwrap();
+
+        let metadata = fs.metadata(&history).await.unwrap();
+        assert_eq!(
+            metadata.mime_type.as_deref(),
+            Some("application/vnd.sqlite3")
+        );
+        // Content heuristics aren't trusted for the browser, only magic signatures
+        assert_eq!(fs.metadata(&notes).await.unwrap().mime_type, None);
+    }
+
+    #[tokio::test]
+    async fn test_copy_file_preserving_keeps_modified_time() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let source = temp_dir.path().join("evidence.bin");
+        let dest = temp_dir.path().join("export.bin");
+
+        fs.write_file(&source, b"original bytes").await.unwrap();
+        let backdated = filetime::FileTime::from_unix_time(1_262_304_000, 0); // 2010-01-01
+        let last_read = filetime::FileTime::from_unix_time(1_293_840_000, 0); // 2011-01-01
+        filetime::set_file_times(&source, last_read, backdated).unwrap();
+
+        fs.copy_file_preserving(&source, &dest, true).await.unwrap();
+
+        let copied = std::fs::metadata(&dest).unwrap();
+        let copied_mtime = filetime::FileTime::from_last_modification_time(&copied);
+        assert!((copied_mtime.unix_seconds() - backdated.unix_seconds()).abs() <= 1);
+        // The access time from before the copy read the source, not the copy's own
+        let copied_atime = filetime::FileTime::from_last_access_time(&copied);
+        assert_eq!(copied_atime.unix_seconds(), last_read.unix_seconds());
+        assert_eq!(fs.read_file(&dest).await.unwrap(), b"original bytes");
+    }
+
+    #[tokio::test]
+    async fn test_copy_dir_recreates_nested_tree() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let source = temp_dir.path().join("case");
+        let dest = temp_dir.path().join("export");
+
+        fs.write_file(&source.join("notes.txt"), b"top")
+            .await
+            .unwrap();
+        fs.write_file(&source.join("mail/inbox/1.eml"), b"message")
+            .await
+            .unwrap();
+        fs.create_dir(&source.join("empty")).await.unwrap();
+        let backdated = filetime::FileTime::from_unix_time(1_262_304_000, 0);
+        filetime::set_file_mtime(source.join("mail/inbox/1.eml"), backdated).unwrap();
+
+        fs.copy_dir(&source, &dest, true).await.unwrap();
+
+        assert_eq!(fs.read_file(&dest.join("notes.txt")).await.unwrap(), b"top");
+        assert_eq!(
+            fs.read_file(&dest.join("mail/inbox/1.eml")).await.unwrap(),
+            b"message"
+        );
+        assert!(dest.join("empty").is_dir());
+        let copied = std::fs::metadata(dest.join("mail/inbox/1.eml")).unwrap();
+        assert_eq!(
+            filetime::FileTime::from_last_modification_time(&copied).unix_seconds(),
+            backdated.unix_seconds()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_falls_back_to_copy_across_devices() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let source = temp_dir.path().join("evidence");
+        let dest = temp_dir.path().join("other_volume/evidence");
+
+        fs.write_file(&source.join("disk/image.dd"), b"sectors")
+            .await
+            .unwrap();
+
+        // Pretend rename failed the way it does between volumes
+        let cross_device = std::io::Error::from(std::io::ErrorKind::CrossesDevices);
+        fs.finish_move(&source, &dest, Err(cross_device))
+            .await
+            .unwrap();
+
+        assert!(!source.exists());
+        assert_eq!(
+            fs.read_file(&dest.join("disk/image.dd")).await.unwrap(),
+            b"sectors"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_move_across_devices_recreates_symlinks() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let source = temp_dir.path().join("evidence");
+        let dest = temp_dir.path().join("other_volume/evidence");
+
+        fs.write_file(&source.join("disk/image.dd"), b"sectors")
+            .await
+            .unwrap();
+        std::os::unix::fs::symlink("disk/image.dd", source.join("latest")).unwrap();
+        std::os::unix::fs::symlink("/nonexistent/mount", source.join("dangling")).unwrap();
+
+        let cross_device = std::io::Error::from(std::io::ErrorKind::CrossesDevices);
+        fs.finish_move(&source, &dest, Err(cross_device))
+            .await
+            .unwrap();
+
+        assert!(!source.exists());
+        assert_eq!(
+            std::fs::read_link(dest.join("latest")).unwrap(),
+            Path::new("disk/image.dd")
+        );
+        assert_eq!(std::fs::read(dest.join("latest")).unwrap(), b"sectors");
+        assert_eq!(
+            std::fs::read_link(dest.join("dangling")).unwrap(),
+            Path::new("/nonexistent/mount")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parallel_content_search_matches_sequential() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for i in 0..150 {
+            let body = if i % 7 == 0 {
+                format!("line one\nuser=suspect{}\n", i)
+            } else {
+                "nothing here\n".to_string()
+            };
+            fs.write_file(
+                &temp_dir.path().join(format!("logs/{}/{}.log", i % 5, i)),
+                body.as_bytes(),
+            )
+            .await
+            .unwrap();
+        }
+
+        fs.write_file(&temp_dir.path().join("notes.txt"), b"suspect\n")
+            .await
+            .unwrap();
+
+        let options = SearchOptions {
+            pattern: "suspect".to_string(),
+            case_sensitive: true,
+            regex: false,
+            include_hidden: false,
+            file_extensions: None,
+            max_depth: None,
+            max_results: None,
+            parallel_threshold: None,
+            context_lines: 0,
+        };
+        // The extension filter applies the same way, whatever its case, on both paths
+        for (file_extensions, expected) in [(None, 23), (Some(vec![".LOG".to_string()]), 22)] {
+            let options = SearchOptions {
+                file_extensions,
+                ..options.clone()
+            };
+            let sequential = fs
+                .search_content(temp_dir.path(), options.clone())
+                .await
+                .unwrap();
+            let parallel = fs
+                .search_content(
+                    temp_dir.path(),
+                    SearchOptions {
+                        parallel_threshold: Some(10),
+                        ..options
+                    },
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(sequential.len(), expected);
+            let key = |r: &SearchResult| (r.path.clone(), r.line);
+            let mut sequential: Vec<_> = sequential.iter().map(key).collect();
+            let mut parallel: Vec<_> = parallel.iter().map(key).collect();
+            sequential.sort();
+            parallel.sort();
+            assert_eq!(sequential, parallel);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_content_includes_context_snippet() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("chat.log"),
+            "line one\nline two\nmeet the suspect at noon\nline four\n",
+        )
+        .unwrap();
+
+        let options = SearchOptions {
+            pattern: "suspect".to_string(),
+            case_sensitive: true,
+            regex: false,
+            include_hidden: false,
+            file_extensions: None,
+            max_depth: None,
+            max_results: None,
+            parallel_threshold: None,
+            context_lines: 2,
+        };
+        let results = fs.search_content(temp_dir.path(), options).await.unwrap();
+
+        let snippet = &results[0].context_snippet;
+        assert_eq!(snippet.file_name, "chat.log");
+        assert_eq!(snippet.file_size_human, "53 B");
+        assert_eq!(snippet.mime_type.as_deref(), Some("text/plain"));
+        assert_eq!(snippet.modified_human, "just now");
+        assert_eq!(snippet.match_line, "meet the suspect at noon");
+        assert_eq!(snippet.before_lines, ["line one", "line two"]);
+        assert_eq!(snippet.after_lines, ["line four"]);
+    }
+
+    #[test]
+    fn test_format_relative_time() {
+        let now = Utc::now();
+        let ago = |seconds| format_relative_time(now - chrono::Duration::seconds(seconds), now);
+        assert_eq!(ago(5), "just now");
+        assert_eq!(ago(60), "1 minute ago");
+        assert_eq!(ago(3 * 86_400), "3 days ago");
+        assert_eq!(ago(400 * 86_400), "1 year ago");
+    }
+
+    #[tokio::test]
+    async fn test_read_to_string_lossy_invalid_utf8() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let test_path = temp_dir.path().join("mixed.log");
+
+        fs.write_file(&test_path, b"login ok\n\xff\xfe user=admin\n")
+            .await
+            .unwrap();
+
+        assert!(fs.read_to_string(&test_path).await.is_err());
+
+        let content = fs.read_to_string_lossy(&test_path).await.unwrap();
+        assert!(content.contains("login ok"));
+        assert!(content.contains("user=admin"));
+        assert!(content.contains('\u{FFFD}'));
+    }
+
+    #[tokio::test]
+    async fn test_scan_directory_exclude_patterns() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("node_modules")).unwrap();
+        std::fs::write(temp_dir.path().join("node_modules/lib.js"), "x").unwrap();
+        std::fs::write(temp_dir.path().join("evidence.txt"), "x").unwrap();
+        std::fs::write(temp_dir.path().join("cache.tmp"), "x").unwrap();
+
+        for parallel in [true, false] {
+            let options = DirectoryScanOptions {
+                parallel,
+                exclude_patterns: vec!["node_modules".to_string(), "*.tmp".to_string()],
+                ..DirectoryScanOptions::default()
+            };
+            let tree = fs.scan_directory(temp_dir.path(), options).await.unwrap();
+            let names: Vec<_> = tree.children.unwrap().into_iter().map(|c| c.name).collect();
+            assert_eq!(names, vec!["evidence.txt".to_string()]);
+        }
+
+        let options = DirectoryScanOptions {
+            exclude_patterns: vec!["[unclosed".to_string()],
+            ..DirectoryScanOptions::default()
+        };
+        assert!(matches!(
+            fs.scan_directory(temp_dir.path(), options).await,
+            Err(FileSystemError::InvalidPath { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_scan_directory_sort_order() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("logs")).unwrap();
+        std::fs::write(temp_dir.path().join("logs/b.txt"), "x").unwrap();
+        std::fs::write(temp_dir.path().join("logs/A.txt"), "x").unwrap();
+        std::fs::write(temp_dir.path().join("big.bin"), vec![0u8; 300]).unwrap();
+        std::fs::write(temp_dir.path().join("Mid.log"), vec![0u8; 200]).unwrap();
+
+        let names = |entries: &[FileInfo]| -> Vec<String> {
+            entries.iter().map(|e| e.name.clone()).collect()
+        };
+
+        let listed = fs
+            .list_dir(temp_dir.path(), SortOrder::default())
+            .await
+            .unwrap();
+        assert_eq!(names(&listed), vec!["big.bin", "logs", "Mid.log"]);
+
+        let by_size = SortOrder::new(SortField::Size, SortDirection::Descending);
+        let mut listed = fs.list_dir(temp_dir.path(), by_size).await.unwrap();
+        // Directory sizes depend on the filesystem, so only the files' order is checked
+        listed.retain(|e| e.file_type == FileType::File);
+        assert_eq!(names(&listed), vec!["big.bin", "Mid.log"]);
+
+        let by_extension = SortOrder::new(SortField::Extension, SortDirection::Ascending);
+        let listed = fs.list_dir(temp_dir.path(), by_extension).await.unwrap();
+        assert_eq!(names(&listed), vec!["logs", "big.bin", "Mid.log"]);
+
+        for parallel in [true, false] {
+            let options = DirectoryScanOptions {
+                parallel,
+                sort: SortOrder::new(SortField::Type, SortDirection::Ascending),
+                ..DirectoryScanOptions::default()
+            };
+            let tree = fs.scan_directory(temp_dir.path(), options).await.unwrap();
+            let children = tree.children.unwrap();
+            assert_eq!(names(&children), vec!["logs", "big.bin", "Mid.log"]);
+            if parallel {
+                let nested = children[0].children.as_ref().unwrap();
+                assert_eq!(names(nested), vec!["A.txt", "b.txt"]);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_directory_sets_depth_and_human_size() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        std::fs::write(temp_dir.path().join("nested/report.bin"), vec![0u8; 1_500]).unwrap();
+
+        let tree = fs
+            .scan_directory(temp_dir.path(), DirectoryScanOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(tree.depth, 0);
+        assert_eq!(tree.size_human, None);
+
+        let nested = &tree.children.unwrap()[0];
+        assert_eq!(nested.depth, 1);
+        let file = &nested.children.as_ref().unwrap()[0];
+        assert_eq!(file.depth, 2);
+        assert_eq!(file.size_human.as_deref(), Some("1.5 KB"));
+
+        assert_eq!(format_size_si(999), "999 B");
+        assert_eq!(format_size_si(2_340_000), "2.3 MB");
+        assert_eq!(format_size_si(999_949), "999.9 KB");
+        assert_eq!(format_size_si(999_950), "1.0 MB");
+        assert_eq!(format_size_si(999_950_000), "1.00 GB");
+        assert_eq!(format_size_si(4_700_000_000), "4.70 GB");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_create_symlink_and_hardlink() {
+        use std::os::unix::fs::MetadataExt;
+
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let original = temp_dir.path().join("evidence.bin");
+        std::fs::write(&original, b"disk image").unwrap();
+
+        let symlink = temp_dir.path().join("latest.bin");
+        fs.create_symlink(Path::new("evidence.bin"), &symlink)
+            .await
+            .unwrap();
+        assert!(std::fs::symlink_metadata(&symlink)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(std::fs::read(&symlink).unwrap(), b"disk image");
+
+        let hardlink = temp_dir.path().join("working_copy.bin");
+        fs.create_hardlink(&original, &hardlink).await.unwrap();
+        assert_eq!(
+            std::fs::metadata(&hardlink).unwrap().ino(),
+            std::fs::metadata(&original).unwrap().ino()
+        );
+        assert_eq!(std::fs::metadata(&original).unwrap().nlink(), 2);
+
+        assert!(matches!(
+            fs.create_hardlink(temp_dir.path(), &temp_dir.path().join("dir_link"))
+                .await,
+            Err(FileSystemError::NotAFile { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_metadata_reports_owner_of_current_process() {
+        use nix::unistd::{getegid, geteuid, User};
+
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("owned.txt");
+        std::fs::write(&path, b"evidence").unwrap();
+
+        let permissions = fs.metadata(&path).await.unwrap().permissions;
+        assert_eq!(permissions.uid, Some(geteuid().as_raw()));
+        assert_eq!(permissions.gid, Some(getegid().as_raw()));
+        let expected_owner = User::from_uid(geteuid()).unwrap().map(|user| user.name);
+        assert_eq!(permissions.owner, expected_owner);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_scan_directory_reports_unreadable_subdir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let locked = temp_dir.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::write(locked.join("secret.txt"), "x").unwrap();
+        std::fs::write(temp_dir.path().join("open.txt"), "x").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Permission bits don't apply when running with elevated privileges
+        if std::fs::read_dir(&locked).is_ok() {
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+            return;
+        }
+
+        let tree = fs
+            .scan_directory(temp_dir.path(), DirectoryScanOptions::default())
+            .await
+            .unwrap();
+        let errors = tree.scan_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, locked);
+
+        let options = DirectoryScanOptions {
+            abort_on_error: true,
+            ..DirectoryScanOptions::default()
+        };
+        assert!(matches!(
+            fs.scan_directory(temp_dir.path(), options).await,
+            Err(FileSystemError::PermissionDenied { .. })
+        ));
+
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scan_directory_filters_files_by_category() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let nested = temp_dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "plain text notes").unwrap();
+        std::fs::write(temp_dir.path().join("history.db"), b"SQLite format 3\0").unwrap();
+        std::fs::write(nested.join("more.txt"), "more plain text").unwrap();
+
+        for parallel in [true, false] {
+            let options = DirectoryScanOptions {
+                parallel,
+                filter_categories: Some(vec![FileCategory::Database]),
+                ..DirectoryScanOptions::default()
+            };
+            let tree = fs.scan_directory(temp_dir.path(), options).await.unwrap();
+
+            let children = tree.children.unwrap();
+            let mut names: Vec<_> = children.iter().map(|child| child.name.as_str()).collect();
+            names.sort();
+            assert_eq!(names, ["history.db", "nested"]);
+            if parallel {
+                let nested_info = children.iter().find(|c| c.name == "nested").unwrap();
+                assert!(nested_info.children.as_ref().unwrap().is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recursive_size_and_file_count() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let deep = temp_dir.path().join("a").join("b");
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::create_dir(temp_dir.path().join("empty")).unwrap();
+        std::fs::write(temp_dir.path().join("top.bin"), vec![0u8; 100]).unwrap();
+        std::fs::write(temp_dir.path().join("a").join("mid.bin"), vec![0u8; 20]).unwrap();
+        std::fs::write(deep.join("leaf.bin"), vec![0u8; 3]).unwrap();
+
+        assert_eq!(fs.recursive_size(temp_dir.path()).await.unwrap(), 123);
+        assert_eq!(fs.recursive_file_count(temp_dir.path()).await.unwrap(), 3);
+        assert_eq!(fs.recursive_size(&deep.join("leaf.bin")).await.unwrap(), 3);
+        assert!(matches!(
+            fs.recursive_size(&temp_dir.path().join("missing")).await,
+            Err(FileSystemError::FileNotFound { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_scan_directory_stops_at_symlink_loop() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let nested = temp_dir.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        std::fs::write(nested.join("file.txt"), "x").unwrap();
+        let link = nested.join("back");
+        std::os::unix::fs::symlink(temp_dir.path(), &link).unwrap();
+
+        let tree = fs
+            .scan_directory(temp_dir.path(), DirectoryScanOptions::default())
+            .await
+            .unwrap();
+
+        let nested_info = &tree.children.as_ref().unwrap()[0];
+        let back = nested_info
+            .children
+            .iter()
+            .flatten()
+            .find(|child| child.path == link)
+            .unwrap();
+        assert!(matches!(back.file_type, FileType::Symlink));
+        assert!(back.children.is_none());
+
+        assert_eq!(nested_info.errors.len(), 1);
+        assert_eq!(nested_info.errors[0].0, link);
+        assert!(nested_info.errors[0].1.starts_with("Symlink loop"));
+        assert_eq!(tree.scan_errors().len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_recorded_with_target_and_not_followed() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let target_dir = temp_dir.path().join("target_dir");
+        std::fs::create_dir(&target_dir).unwrap();
+        std::fs::write(target_dir.join("inside.txt"), "x").unwrap();
+        let link = temp_dir.path().join("link");
+        std::os::unix::fs::symlink(&target_dir, &link).unwrap();
+
+        let metadata = fs.metadata(&link).await.unwrap();
+        assert!(!metadata.is_symlink);
+        assert!(metadata.is_dir);
+        assert_eq!(metadata.symlink_target, Some(target_dir.clone()));
+
+        // Turned on the way the app does it, through the shared file system state
+        let state = crate::io::commands::FileSystemState::new();
+        state.set_record_symlinks(true);
+        let metadata = state.fs().metadata(&link).await.unwrap();
+        assert!(metadata.is_symlink);
+        assert!(!metadata.is_dir);
+        assert_eq!(metadata.symlink_target, Some(target_dir.clone()));
+
+        let options = DirectoryScanOptions {
+            follow_symlinks: false,
+            ..DirectoryScanOptions::default()
+        };
+        let tree = fs.scan_directory(temp_dir.path(), options).await.unwrap();
+        let entry = tree
+            .children
+            .iter()
+            .flatten()
+            .find(|child| child.path == link)
+            .unwrap();
+        assert!(matches!(entry.file_type, FileType::Symlink));
+        assert_eq!(entry.symlink_target, Some(target_dir));
+        assert!(entry.children.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_head_lines_reads_first_lines() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.log");
+        std::fs::write(&path, "one\r\ntwo\nthree\nfour\n").unwrap();
+
+        assert_eq!(fs.head_lines(&path, 2).await.unwrap(), vec!["one", "two"]);
+        assert!(fs.head_lines(&path, 0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tail_lines_spans_multiple_chunks() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("big.log");
+        let content: String = (0..20_000).map(|i| format!("line {}\n", i)).collect();
+        assert!(content.len() as u64 > LINE_PREVIEW_CHUNK_SIZE);
+        std::fs::write(&path, content).unwrap();
+
+        let expected: Vec<String> = (19_997..20_000).map(|i| format!("line {}", i)).collect();
+        assert_eq!(fs.tail_lines(&path, 3).await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_head_and_tail_with_fewer_lines_than_requested() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("short.log");
+        std::fs::write(&path, "a\nb\n").unwrap();
+
+        assert_eq!(fs.head_lines(&path, 10).await.unwrap(), vec!["a", "b"]);
+        assert_eq!(fs.tail_lines(&path, 10).await.unwrap(), vec!["a", "b"]);
+
+        let empty = temp_dir.path().join("empty.log");
+        std::fs::write(&empty, "").unwrap();
+        assert!(fs.head_lines(&empty, 5).await.unwrap().is_empty());
+        assert!(fs.tail_lines(&empty, 5).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_head_and_tail_without_trailing_newline() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("partial.log");
+        std::fs::write(&path, "first\nsecond\nlast").unwrap();
+
+        assert_eq!(
+            fs.tail_lines(&path, 2).await.unwrap(),
+            vec!["second", "last"]
+        );
+        assert_eq!(
+            fs.head_lines(&path, 5).await.unwrap(),
+            vec!["first", "second", "last"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_head_and_tail_cap_long_lines() {
+        let fs = LocalFileSystem::new();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("one_line.bin");
+        let mut content = vec![b'a'; LINE_PREVIEW_MAX_LINE_LEN];
+        content.resize(content.len() + 3 * LINE_PREVIEW_CHUNK_SIZE as usize, b'z');
+        content.extend_from_slice(b"\nshort\n");
+        std::fs::write(&path, &content).unwrap();
+
+        let capped = "a".repeat(LINE_PREVIEW_MAX_LINE_LEN);
+        assert_eq!(
+            fs.head_lines(&path, 5).await.unwrap(),
+            vec![capped.clone(), "short".to_string()]
+        );
+        assert_eq!(
+            fs.tail_lines(&path, 5).await.unwrap(),
+            vec![capped, "short".to_string()]
+        );
+        assert_eq!(fs.tail_lines(&path, 1).await.unwrap(), vec!["short"]);
+    }
 }
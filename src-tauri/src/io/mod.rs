@@ -1,3 +1,5 @@
+pub mod acl;
+pub mod ads;
 pub mod commands;
 pub mod error;
 pub mod fs;
@@ -6,5 +8,5 @@ pub mod types;
 
 pub use error::{FileSystemError, Result};
 pub use fs::{BackendType, FileSystem, FileSystemBuilder};
-pub use local::LocalFileSystem;
+pub use local::{atomic_write_sync, LocalFileSystem};
 pub use types::*;
@@ -1,6 +1,8 @@
+use crate::index::FileCategory;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -11,11 +13,98 @@ pub struct FileInfo {
     #[serde(rename = "type")]
     pub file_type: FileType,
     pub size: Option<u64>,
+    /// `size` formatted for display, e.g. "1.5 MB"
+    pub size_human: Option<String>,
     pub modified: Option<DateTime<Utc>>,
     pub created: Option<DateTime<Utc>>,
     pub accessed: Option<DateTime<Utc>>,
     pub permissions: Option<FilePermissions>,
+    /// Where a symlink points, as stored in the link (may be relative or dangling)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<PathBuf>,
     pub children: Option<Vec<FileInfo>>,
+    /// How many levels below the scanned or listed directory this entry is (0 = the root)
+    #[serde(default)]
+    pub depth: usize,
+    /// Why this entry could not be fully read (e.g. permission denied)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Non-fatal problems found while scanning this directory's entries (e.g. symlink loops)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl FileInfo {
+    /// Collect every entry in this tree that could not be read
+    pub fn scan_errors(&self) -> Vec<ScanError> {
+        let mut errors = Vec::new();
+        self.collect_scan_errors(&mut errors);
+        errors
+    }
+
+    fn collect_scan_errors(&self, errors: &mut Vec<ScanError>) {
+        if let Some(message) = &self.error {
+            errors.push(ScanError {
+                path: self.path.clone(),
+                message: message.clone(),
+            });
+        }
+        for (path, message) in &self.errors {
+            errors.push(ScanError {
+                path: path.clone(),
+                message: message.clone(),
+            });
+        }
+        for child in self.children.iter().flatten() {
+            child.collect_scan_errors(errors);
+        }
+    }
+}
+
+/// Format a byte count with SI units (1 KB = 1000 bytes)
+pub fn format_size_si(bytes: u64) -> String {
+    const KB: f64 = 1_000.0;
+    const MB: f64 = 1_000_000.0;
+    const GB: f64 = 1_000_000_000.0;
+
+    // Rounded before the unit is chosen, so 999_950 bytes reads "1.0 MB", not "1000.0 KB"
+    let n = bytes as f64;
+    let round_tenths = |value: f64| (value * 10.0).round() / 10.0;
+    if n < KB {
+        format!("{} B", bytes)
+    } else if round_tenths(n / KB) < 1_000.0 {
+        format!("{:.1} KB", round_tenths(n / KB))
+    } else if round_tenths(n / MB) < 1_000.0 {
+        format!("{:.1} MB", round_tenths(n / MB))
+    } else {
+        format!("{:.2} GB", n / GB)
+    }
+}
+
+/// Describe how long before now a moment was, e.g. "5 minutes ago" or "3 days ago"
+pub fn format_relative_time(then: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - then).num_seconds();
+    let (count, unit) = match seconds {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3_600 => (s / 60, "minute"),
+        s if s < 86_400 => (s / 3_600, "hour"),
+        s if s < 30 * 86_400 => (s / 86_400, "day"),
+        s if s < 365 * 86_400 => (s / (30 * 86_400), "month"),
+        s => (s / (365 * 86_400), "year"),
+    };
+    if count == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", count, unit)
+    }
+}
+
+/// An entry that could not be read during a directory scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -34,6 +123,20 @@ pub struct FilePermissions {
     pub can_read: bool,
     pub can_write: bool,
     pub can_execute: bool,
+    /// Owning user and group IDs (Unix only)
+    #[serde(default)]
+    pub uid: Option<u32>,
+    #[serde(default)]
+    pub gid: Option<u32>,
+    /// `uid`/`gid` resolved to names, when the accounts exist on this machine
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    /// POSIX ACL entries beyond the mode bits (Linux only), e.g. "user:alice:rw-"
+    /// Only filled in by `FileSystem::metadata`, not for directory listings and scans
+    #[serde(default)]
+    pub acl: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +150,10 @@ pub struct FileMetadata {
     pub is_file: bool,
     pub is_dir: bool,
     pub is_symlink: bool,
+    /// Where a symlink points; the other fields describe the target unless symlinks are
+    /// recorded (or the link dangles), in which case they describe the link itself
+    #[serde(default)]
+    pub symlink_target: Option<PathBuf>,
     pub permissions: FilePermissions,
     pub mime_type: Option<String>,
     pub extension: Option<String>,
@@ -67,6 +174,41 @@ pub struct FileHash {
     pub path: PathBuf,
     pub md5: String,
     pub sha256: String,
+    /// Keccak-256 as used by Ethereum and IPFS-adjacent tooling (not the NIST SHA3-256)
+    pub keccak256: String,
+}
+
+/// Progress of a streaming hash calculation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashProgress {
+    pub path: PathBuf,
+    pub bytes_processed: u64,
+    pub total_bytes: u64,
+    /// 0-100; the final report is always 100
+    pub percent: f64,
+    /// Rough time remaining from the throughput so far (None until there is any)
+    pub eta_ms: Option<u64>,
+}
+
+/// Byte-level comparison of two files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    pub are_identical: bool,
+    pub size_a: u64,
+    pub size_b: u64,
+    /// SHA-256 of each file; empty when the comparison stopped early
+    pub hash_a: String,
+    pub hash_b: String,
+    pub first_difference_offset: Option<u64>,
+    /// Bytes past the end of the shorter file count as different
+    pub different_byte_count: u64,
+    /// different_byte_count / max(size_a, size_b), 0 for two empty files
+    pub diff_ratio: f32,
+    /// True when more than max_diff_bytes bytes differ, so counting stopped early
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,8 +221,17 @@ pub struct SearchOptions {
     pub file_extensions: Option<Vec<String>>,
     pub max_depth: Option<usize>,
     pub max_results: Option<usize>,
+    /// Search file contents in parallel once more than this many files are found
+    #[serde(default)]
+    pub parallel_threshold: Option<usize>,
+    /// Lines of surrounding text included before and after each content match
+    #[serde(default)]
+    pub context_lines: usize,
 }
 
+/// File count above which content search switches to parallel mode by default
+pub const DEFAULT_PARALLEL_SEARCH_THRESHOLD: usize = 100;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResult {
@@ -89,6 +240,23 @@ pub struct SearchResult {
     pub column: usize,
     pub content: String,
     pub r#match: String,
+    pub context_snippet: ContextSnippet,
+}
+
+/// A content match with its surrounding lines and the file's metadata, so results can be
+/// shown without a get_metadata call per hit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextSnippet {
+    pub file_name: String,
+    pub file_size_human: String,
+    pub mime_type: Option<String>,
+    /// Relative to the time of the search, e.g. "3 days ago"
+    pub modified_human: String,
+    pub match_line: String,
+    /// Up to SearchOptions::context_lines lines on each side of the match
+    pub before_lines: Vec<String>,
+    pub after_lines: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,8 +264,23 @@ pub struct SearchResult {
 pub struct DirectoryScanOptions {
     pub max_depth: Option<usize>,
     pub include_hidden: bool,
+    /// Recurse through symlinks (the default); otherwise they are listed with their target
+    /// and not followed
     pub follow_symlinks: bool,
     pub parallel: bool,
+    /// Glob patterns matched against entry names (e.g. "node_modules", "*.tmp")
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// Fail the whole scan on the first unreadable entry instead of noting it and moving on
+    #[serde(default)]
+    pub abort_on_error: bool,
+    /// Order of the children at every level of the scanned tree
+    #[serde(default)]
+    pub sort: SortOrder,
+    /// Keep only files whose detected type is in one of these categories
+    /// Directories are kept (possibly empty) so the tree structure stays intact
+    #[serde(default)]
+    pub filter_categories: Option<Vec<FileCategory>>,
 }
 
 impl Default for DirectoryScanOptions {
@@ -105,8 +288,95 @@ impl Default for DirectoryScanOptions {
         Self {
             max_depth: None,
             include_hidden: false,
-            follow_symlinks: false,
+            follow_symlinks: true,
             parallel: true,
+            exclude_patterns: Vec::new(),
+            abort_on_error: false,
+            sort: SortOrder::default(),
+            filter_categories: None,
+        }
+    }
+}
+
+/// Entry attribute a directory listing is ordered by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortField {
+    #[default]
+    Name,
+    Size,
+    Modified,
+    Created,
+    Extension,
+    Type,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// How directory entries are ordered; defaults to name, ascending
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SortOrder {
+    pub field: SortField,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+impl SortOrder {
+    pub fn new(field: SortField, direction: SortDirection) -> Self {
+        Self { field, direction }
+    }
+
+    /// Compare two entries by the chosen field
+    /// Entries missing the field (e.g. directories by size) come first when ascending;
+    /// ties fall back to name so the order doesn't depend on read_dir order
+    pub fn compare(&self, a: &FileInfo, b: &FileInfo) -> Ordering {
+        let ordering = match self.field {
+            SortField::Name => Ordering::Equal,
+            SortField::Size => a.size.cmp(&b.size),
+            SortField::Modified => a.modified.cmp(&b.modified),
+            SortField::Created => a.created.cmp(&b.created),
+            SortField::Extension => extension_key(&a.name).cmp(&extension_key(&b.name)),
+            SortField::Type => type_rank(a.file_type).cmp(&type_rank(b.file_type)),
         }
+        .then_with(|| compare_names(&a.name, &b.name));
+
+        match self.direction {
+            SortDirection::Ascending => ordering,
+            SortDirection::Descending => ordering.reverse(),
+        }
+    }
+
+    pub fn sort(&self, entries: &mut [FileInfo]) {
+        entries.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+/// Case-insensitive name order, with the exact name breaking ties
+fn compare_names(a: &str, b: &str) -> Ordering {
+    a.to_lowercase()
+        .cmp(&b.to_lowercase())
+        .then_with(|| a.cmp(b))
+}
+
+fn extension_key(name: &str) -> Option<String> {
+    Path::new(name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// Directories group before files, then symlinks, then anything unrecognised
+fn type_rank(file_type: FileType) -> u8 {
+    match file_type {
+        FileType::Directory => 0,
+        FileType::File => 1,
+        FileType::Symlink => 2,
+        FileType::Unknown => 3,
     }
 }
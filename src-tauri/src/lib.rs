@@ -1,8 +1,10 @@
+mod audit;
 mod db;
 mod index;
 mod io;
+mod report;
 
-use db::DatabaseState;
+use db::{DatabaseState, SqliteState};
 use io::commands::FileSystemState;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -18,46 +20,107 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .manage(FileSystemState::new())
         .manage(DatabaseState::new())
+        .manage(SqliteState::new())
         .invoke_handler(tauri::generate_handler![
             greet,
             // File system commands
             io::commands::read_file,
             io::commands::read_file_as_string,
+            io::commands::read_file_as_string_lossy,
             io::commands::write_file,
             io::commands::exists,
             io::commands::is_file,
             io::commands::is_dir,
             io::commands::get_metadata,
+            io::commands::set_record_symlinks,
             io::commands::list_directory,
             io::commands::scan_directory,
+            io::commands::get_scan_errors,
+            io::commands::scan_for_ads,
             io::commands::delete_file,
             io::commands::delete_directory,
             io::commands::create_directory,
             io::commands::copy_file,
+            io::commands::copy_file_preserving,
+            io::commands::copy_directory,
             io::commands::move_path,
+            io::commands::create_symlink,
+            io::commands::create_hardlink,
             io::commands::calculate_hash,
+            io::commands::calculate_hash_with_progress,
+            io::commands::compare_files,
             io::commands::search_files,
             io::commands::search_content,
             io::commands::read_file_chunked,
             io::commands::get_file_size,
+            io::commands::get_recursive_size,
+            io::commands::get_recursive_file_count,
+            io::commands::head_lines,
+            io::commands::tail_lines,
             // Database commands
             db::commands::create_project_database,
+            db::commands::open_project_read_only,
+            db::commands::reload_project_index,
             db::commands::get_project_metadata,
             db::commands::index_directory,
+            db::commands::retry_file_extraction,
+            db::commands::search_in_file,
+            db::commands::lookup_keccak256,
+            db::commands::rebuild_index_from_scan,
+            db::commands::rebuild_index_from_cache,
+            db::commands::gc_index,
+            db::commands::backup_index,
+            db::commands::restore_index,
+            db::commands::export_files,
             db::commands::search_database,
+            db::commands::get_files_modified_between,
+            db::commands::get_files_created_between,
+            db::commands::search_database_streaming,
+            db::commands::get_structured_catalog,
+            db::commands::get_directory_breakdown,
+            db::commands::find_similar,
+            db::commands::get_thumbnail,
+            db::commands::suggest,
+            db::commands::export_index_to_sqlite,
             db::commands::query_sqlite_info,
+            db::commands::query_sqlite_full_schema,
             db::commands::query_sqlite_table,
+            db::commands::close_sqlite_connection,
+            db::commands::diff_sqlite,
             db::commands::query_leveldb_info,
+            db::commands::detect_file_type,
+            db::commands::get_supported_extractors,
+            db::commands::list_archive_entries,
+            db::commands::read_archive_entry,
+            db::commands::carve_files,
+            db::commands::recover_deleted_rows,
             db::commands::query_indexeddb_info,
             db::commands::create_group,
             db::commands::get_groups,
             db::commands::delete_group,
+            db::commands::add_file_to_group,
+            db::commands::remove_file_from_group,
+            // Case metadata commands
+            db::commands::set_case_metadata,
+            db::commands::get_case_metadata,
+            db::commands::export_auxiliary,
+            db::commands::import_auxiliary,
+            // Project settings commands
+            db::commands::update_archive_settings,
+            db::commands::update_preview_config,
             // Index status commands
             db::commands::is_path_indexed,
             db::commands::get_path_index_status,
+            // Tag commands
+            db::commands::add_tags_bulk,
+            db::commands::get_all_tags,
+            // Audit log commands
+            db::commands::get_audit_log,
+            db::commands::export_audit_log_csv,
+            // Report commands
+            db::commands::export_html_report,
             // db::commands::store_file_note,
             // db::commands::add_file_tag,
-            // db::commands::get_all_tags,
             // db::commands::get_database_stats,
         ])
         .run(tauri::generate_context!())
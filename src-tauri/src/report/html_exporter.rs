@@ -0,0 +1,332 @@
+use crate::db::CaseMetadata;
+use crate::index::{Query, QueryResult, TypedHit};
+use crate::io::{atomic_write_sync, format_size_si};
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use image::ImageFormat;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Report layout, rendered by Tera with HTML escaping enabled
+const REPORT_TEMPLATE: &str = include_str!("templates/court_report.html");
+
+/// Longest edge of the thumbnails embedded in the report
+const THUMBNAIL_SIZE: u32 = 160;
+
+/// What goes into an HTML report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportOptions {
+    /// Embed thumbnails of image hits as base64, keeping the report a single file
+    pub include_thumbnails: bool,
+
+    /// Hits past this are counted in the summary but left out of the table
+    pub max_hits_in_table: usize,
+
+    /// Image shown in the report header (e.g. the agency's logo)
+    pub custom_logo: Option<PathBuf>,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            include_thumbnails: true,
+            max_hits_in_table: 1000,
+            custom_logo: None,
+        }
+    }
+}
+
+/// One row of the hit table, pre-formatted for the template
+#[derive(Serialize)]
+struct ReportRow {
+    path: String,
+    category: String,
+    score: String,
+    snippet: String,
+    size: String,
+    modified: String,
+    thumbnail: Option<String>,
+}
+
+/// Render query results as a self-contained HTML report and write it to output_path
+/// The query is described in the report's search summary
+pub fn generate_html_report(
+    query: &Query,
+    query_result: &QueryResult,
+    case_metadata: &CaseMetadata,
+    output_path: &Path,
+    options: ReportOptions,
+) -> Result<()> {
+    let rows: Vec<ReportRow> = query_result
+        .hits
+        .iter()
+        .take(options.max_hits_in_table)
+        .map(|hit| report_row(hit, options.include_thumbnails))
+        .collect();
+    let logo = options
+        .custom_logo
+        .as_deref()
+        .map(logo_data_uri)
+        .transpose()?;
+
+    let mut context = tera::Context::new();
+    context.insert("case", case_metadata);
+    context.insert(
+        "acquisition_date",
+        &case_metadata
+            .acquisition_date
+            .format("%Y-%m-%d")
+            .to_string(),
+    );
+    context.insert("query_summary", &describe_query(query));
+    context.insert("hit_count", &query_result.total);
+    context.insert("query_time_ms", &query_result.query_time_ms);
+    context.insert("include_thumbnails", &options.include_thumbnails);
+    context.insert("omitted_hits", &(query_result.hits.len() - rows.len()));
+    context.insert("rows", &rows);
+    context.insert("logo", &logo);
+    context.insert("tool_version", env!("CARGO_PKG_VERSION"));
+    context.insert("generated_at", &format_timestamp(Utc::now()));
+
+    let html = tera::Tera::one_off(REPORT_TEMPLATE, &context, true)
+        .context("Failed to render HTML report")?;
+    atomic_write_sync(output_path, html.as_bytes())
+        .with_context(|| format!("Failed to write report to {:?}", output_path))?;
+
+    Ok(())
+}
+
+/// Table row for a hit; size and modification time are the values recorded at indexing,
+/// not whatever the file on disk says now
+fn report_row(hit: &TypedHit, include_thumbnails: bool) -> ReportRow {
+    let metadata = hit.metadata.as_ref();
+
+    ReportRow {
+        path: hit.path.to_string_lossy().to_string(),
+        category: format!("{:?}", hit.category),
+        score: format!("{:.3}", hit.score),
+        snippet: hit.snippet.clone(),
+        size: metadata
+            .map(|m| format!("{} ({} bytes)", format_size_si(m.size), m.size))
+            .unwrap_or_else(|| "-".to_string()),
+        modified: metadata
+            .map(|m| format_timestamp(m.modified))
+            .unwrap_or_else(|| "-".to_string()),
+        thumbnail: include_thumbnails
+            .then(|| thumbnail_data_uri(&hit.path))
+            .flatten(),
+    }
+}
+
+/// JPEG thumbnail of an image as a data URI, or None if the file isn't a readable image
+fn thumbnail_data_uri(path: &Path) -> Option<String> {
+    ImageFormat::from_path(path).ok()?;
+    let image = image::open(path).ok()?;
+
+    // JPEG has no alpha channel, so flatten to RGB first
+    let mut jpeg = Vec::new();
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8()
+        .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+        .ok()?;
+
+    Some(format!("data:image/jpeg;base64,{}", BASE64.encode(jpeg)))
+}
+
+/// Logo file as a data URI, typed by its extension
+fn logo_data_uri(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read logo {:?}", path))?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    let mime = match extension.as_deref() {
+        Some("svg") => "image/svg+xml",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "image/png",
+    };
+
+    Ok(format!("data:{};base64,{}", mime, BASE64.encode(bytes)))
+}
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+/// Plain-language description of a query for the search summary
+fn describe_query(query: &Query) -> String {
+    match query {
+        Query::FullText { query, .. } => format!("Full-text search for \"{}\"", query),
+        Query::Metadata {
+            category,
+            mime_type,
+            min_size,
+            max_size,
+            extension,
+            path_prefix,
+        } => {
+            let mut filters = Vec::new();
+            if let Some(category) = category {
+                filters.push(format!("category {:?}", category));
+            }
+            if let Some(mime_type) = mime_type {
+                filters.push(format!("MIME type {}", mime_type));
+            }
+            if let Some(extension) = extension {
+                filters.push(format!("extension .{}", extension));
+            }
+            if let Some(min_size) = min_size {
+                filters.push(format!("at least {} bytes", min_size));
+            }
+            if let Some(max_size) = max_size {
+                filters.push(format!("at most {} bytes", max_size));
+            }
+            if let Some(path_prefix) = path_prefix {
+                filters.push(format!("under {}", path_prefix));
+            }
+
+            if filters.is_empty() {
+                "All indexed files".to_string()
+            } else {
+                format!("Files with {}", filters.join(", "))
+            }
+        }
+        Query::Structured {
+            structured_type,
+            query,
+        } => format!(
+            "Structured search ({:?}) for \"{}\"",
+            structured_type, query
+        ),
+        Query::SimilarTo { doc_id, .. } => format!("Files similar to document {}", doc_id),
+        Query::FromArchive { archive_path } => format!("Files unpacked from {}", archive_path),
+        Query::LowConfidence { threshold } => {
+            format!("Files whose type confidence is below {:.2}", threshold)
+        }
+        Query::SizeRange { min, max, .. } => match (min, max) {
+            (Some(min), Some(max)) => format!("Files between {} and {} bytes", min, max),
+            (Some(min), None) => format!("Files of at least {} bytes", min),
+            (None, Some(max)) => format!("Files of at most {} bytes", max),
+            (None, None) => "Files of any size".to_string(),
+        },
+        Query::Owner { uid, gid, .. } => {
+            let owners: Vec<String> = [
+                uid.map(|uid| format!("uid {}", uid)),
+                gid.map(|gid| format!("gid {}", gid)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            format!("Files owned by {}", owners.join(" and "))
+        }
+        Query::Tagged { tag, query } => match query {
+            Some(inner) => format!("{}, tagged \"{}\"", describe_query(inner), tag),
+            None => format!("Files tagged \"{}\"", tag),
+        },
+        Query::Combined { metadata, fulltext } => {
+            format!(
+                "{}, among {}",
+                describe_query(fulltext),
+                describe_query(metadata)
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::{FileCategory, HitMetadata};
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn hit(path: &Path, category: FileCategory, snippet: &str) -> TypedHit {
+        TypedHit {
+            id: path.to_string_lossy().to_string(),
+            path: path.to_path_buf(),
+            category,
+            location: None,
+            snippet: snippet.to_string(),
+            score: 1.5,
+            schema: None,
+            offset: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_report_lists_hits_with_case_details_and_thumbnails() {
+        let temp_dir = TempDir::new().unwrap();
+        let photo = temp_dir.path().join("photo.png");
+        image::RgbaImage::from_pixel(400, 300, image::Rgba([200, 40, 40, 255]))
+            .save(&photo)
+            .unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        std::fs::write(&notes, "meet at <b>dawn</b>").unwrap();
+        let hidden = temp_dir.path().join("extra.txt");
+
+        let case_metadata = CaseMetadata {
+            case_number: "2024-CR-0117".to_string(),
+            case_name: "State v. Doe".to_string(),
+            examiner_name: "J. Smith".to_string(),
+            examiner_agency: "County Digital Forensics Unit".to_string(),
+            acquisition_date: Utc::now(),
+            notes: String::new(),
+            evidence_description: "Seized laptop image".to_string(),
+        };
+        let query = Query::FullText {
+            query: "dawn".to_string(),
+            limit: None,
+            boost_config: None,
+            fields: None,
+        };
+        let query_result = QueryResult {
+            hits: vec![
+                hit(&photo, FileCategory::Media, ""),
+                TypedHit {
+                    // Indexed before the file on disk was overwritten
+                    metadata: Some(HitMetadata {
+                        size: 2048,
+                        modified: Utc.with_ymd_and_hms(2021, 3, 4, 5, 6, 7).unwrap(),
+                        indexed_at: None,
+                    }),
+                    ..hit(&notes, FileCategory::Text, "meet at <b>dawn</b>")
+                },
+                hit(&hidden, FileCategory::Text, "not in the table"),
+            ],
+            total: 3,
+            query_time_ms: 4,
+        };
+
+        let output = temp_dir.path().join("report").join("report.html");
+        let options = ReportOptions {
+            max_hits_in_table: 2,
+            ..ReportOptions::default()
+        };
+        generate_html_report(&query, &query_result, &case_metadata, &output, options).unwrap();
+
+        let html = std::fs::read_to_string(&output).unwrap();
+        assert!(html.contains("2024-CR-0117"));
+        assert!(html.contains("J. Smith"));
+        assert!(html.contains("Full-text search for &quot;dawn&quot;"));
+        // Snippets are escaped rather than rendered as markup
+        assert!(html.contains("meet at &lt;b&gt;dawn&lt;&#x2F;b&gt;"));
+        assert!(html.contains("data:image/jpeg;base64,"));
+        assert!(html.contains("2.0 KB (2048 bytes)"));
+        assert!(html.contains(&format_timestamp(
+            Utc.with_ymd_and_hms(2021, 3, 4, 5, 6, 7).unwrap()
+        )));
+        assert!(!html.contains("19 bytes"));
+        assert!(!html.contains("not in the table"));
+        assert!(html.contains("Hits not listed in this table: 1"));
+        assert!(html.contains(env!("CARGO_PKG_VERSION")));
+    }
+}
@@ -0,0 +1,6 @@
+// Court-ready reports generated from search results
+// Reports are self-contained files so they can be attached to a case as-is
+
+mod html_exporter;
+
+pub use html_exporter::{generate_html_report, ReportOptions};
@@ -11,7 +11,7 @@ import { useFileSystemWithPanes } from './hooks/useFileSystemWithPanes';
 import { useKeyboardShortcuts } from './hooks/useKeyboardShortcuts';
 import { SplitLayout } from './components/layout/SplitLayoutEnhanced';
 import { FileInfo, AnalysisGroup, IndexStats } from './types';
-import {scanDirectory, createProjectDatabase, indexDirectory, createGroup, getGroups, deleteGroup} from './lib/tauri';
+import {scanDirectory, getScanErrors, createProjectDatabase, indexDirectory, createGroup, getGroups, deleteGroup} from './lib/tauri';
 
 function App() {
   const {
@@ -233,11 +233,15 @@ function App() {
       const tree = await scanDirectory(selected, {
         maxDepth: undefined, // Scan all levels
         includeHidden: true,
-        followSymlinks: false,
+        followSymlinks: true,
         parallel: true, // Enable parallel scanning for performance
       });
 
       console.log("Directory scanned successfully", tree)
+      const scanErrors = await getScanErrors(tree);
+      if (scanErrors.length > 0) {
+        console.warn(`${scanErrors.length} entries could not be read`, scanErrors);
+      }
       setScannedTree(tree);
       setScanning(false);
     } catch (error) {
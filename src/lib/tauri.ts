@@ -1,14 +1,18 @@
-import { invoke } from '@tauri-apps/api/core';
+import { Channel, invoke } from '@tauri-apps/api/core';
 import type {
     FileInfo,
     FileMetadata,
     FileHash,
+    FileDiff,
     SearchOptions,
     SearchResult,
     DirectoryScanOptions,
+    ScanError,
+    SortOrder,
     ProjectMetadata,
     FileRecord,
     IndexStats,
+    IndexedBatch,
     DatabaseStats, AnalysisGroup,
 } from '../types';
 
@@ -44,8 +48,12 @@ export async function getMetadata(path: string): Promise<FileMetadata> {
   return await invoke<FileMetadata>('get_metadata', { path });
 }
 
-export async function listDirectory(path: string): Promise<FileInfo[]> {
-  return await invoke<FileInfo[]>('list_directory', { path });
+export async function setRecordSymlinks(recordSymlinks: boolean): Promise<void> {
+  return await invoke<void>('set_record_symlinks', { recordSymlinks });
+}
+
+export async function listDirectory(path: string, sort?: SortOrder): Promise<FileInfo[]> {
+  return await invoke<FileInfo[]>('list_directory', { path, sort });
 }
 
 export async function scanDirectory(
@@ -55,6 +63,10 @@ export async function scanDirectory(
   return await invoke<FileInfo>('scan_directory', { path, options });
 }
 
+export async function getScanErrors(tree: FileInfo): Promise<ScanError[]> {
+  return await invoke<ScanError[]>('get_scan_errors', { tree });
+}
+
 export async function deleteFile(path: string): Promise<void> {
   await invoke('delete_file', { path });
 }
@@ -79,6 +91,14 @@ export async function calculateHash(path: string): Promise<FileHash> {
   return await invoke<FileHash>('calculate_hash', { path });
 }
 
+export async function compareFiles(
+  pathA: string,
+  pathB: string,
+  maxDiffBytes?: number
+): Promise<FileDiff> {
+  return await invoke<FileDiff>('compare_files', { pathA, pathB, maxDiffBytes });
+}
+
 export async function createGroup(name: string, color: string) {
     return await invoke<void>("create_group", {name, color})
 }
@@ -93,6 +113,14 @@ export async function getGroups(): Promise<AnalysisGroup[]> {
     return await invoke<AnalysisGroup[]>('get_groups')
 }
 
+export async function addFileToGroup(groupName: string, groupColor: string, fileId: string, filePath: string) {
+    return await invoke<void>("add_file_to_group", {groupName, groupColor, fileId, filePath})
+}
+
+export async function removeFileFromGroup(groupName: string, groupColor: string, fileId: string) {
+    return await invoke<void>("remove_file_from_group", {groupName, groupColor, fileId})
+}
+
 
 
 export async function searchFiles(
@@ -129,12 +157,27 @@ export async function createProjectDatabase(evidencePath: string): Promise<strin
   return await invoke<string>('create_project_database', { evidencePath });
 }
 
+export async function openProjectReadOnly(evidencePath: string): Promise<string> {
+  return await invoke<string>('open_project_read_only', { evidencePath });
+}
+
+export async function reloadProjectIndex(): Promise<void> {
+  return await invoke<void>('reload_project_index');
+}
+
 export async function getProjectMetadata(): Promise<ProjectMetadata | null> {
   return await invoke<ProjectMetadata | null>('get_project_metadata');
 }
 
-export async function indexDirectory(fileTree: FileInfo): Promise<IndexStats> {
-  return await invoke<IndexStats>('index_directory', { fileTree });
+export async function indexDirectory(
+  fileTree: FileInfo,
+  onBatch?: (batch: IndexedBatch) => void
+): Promise<IndexStats> {
+  const channel = new Channel<IndexedBatch>();
+  if (onBatch) {
+    channel.onmessage = onBatch;
+  }
+  return await invoke<IndexStats>('index_directory', { fileTree, onBatch: channel });
 }
 
 export async function searchDatabase(query: string): Promise<Array<[string, string]>> {
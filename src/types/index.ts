@@ -33,6 +33,17 @@ export interface SearchResult {
   column: number;
   content: string;
   match: string;
+  contextSnippet: ContextSnippet;
+}
+
+export interface ContextSnippet {
+  fileName: string;
+  fileSizeHuman: string;
+  mimeType?: string;
+  modifiedHuman: string;
+  matchLine: string;
+  beforeLines: string[];
+  afterLines: string[];
 }
 
 export interface AnalysisGroup {
@@ -48,11 +59,21 @@ export interface FileInfo {
   path: string;
   type: 'file' | 'directory' | 'symlink' | 'unknown';
   size?: number;
+  sizeHuman?: string;
   modified?: string; // ISO date string
   created?: string;
   accessed?: string;
   permissions?: FilePermissions;
+  symlinkTarget?: string;
   children?: FileInfo[];
+  depth: number;
+  error?: string;
+  errors?: [string, string][]; // [path, message] of non-fatal scan errors
+}
+
+export interface ScanError {
+  path: string;
+  message: string;
 }
 
 export interface FilePermissions {
@@ -60,6 +81,11 @@ export interface FilePermissions {
   canRead: boolean;
   canWrite: boolean;
   canExecute: boolean;
+  uid?: number;
+  gid?: number;
+  owner?: string;
+  group?: string;
+  acl?: string[]; // POSIX ACL entries beyond the mode bits, e.g. "user:alice:rw-"
 }
 
 export interface FileMetadata {
@@ -71,6 +97,7 @@ export interface FileMetadata {
   isFile: boolean;
   isDir: boolean;
   isSymlink: boolean;
+  symlinkTarget?: string;
   permissions: FilePermissions;
   mimeType?: string;
   extension?: string;
@@ -80,6 +107,19 @@ export interface FileHash {
   path: string;
   md5: string;
   sha256: string;
+  keccak256: string;
+}
+
+export interface FileDiff {
+  areIdentical: boolean;
+  sizeA: number;
+  sizeB: number;
+  hashA: string;
+  hashB: string;
+  firstDifferenceOffset: number | null;
+  differentByteCount: number;
+  diffRatio: number;
+  truncated: boolean;
 }
 
 export interface SearchOptions {
@@ -90,6 +130,8 @@ export interface SearchOptions {
   fileExtensions?: string[];
   maxDepth?: number;
   maxResults?: number;
+  parallelThreshold?: number;
+  contextLines?: number;
 }
 
 export interface DirectoryScanOptions {
@@ -97,6 +139,26 @@ export interface DirectoryScanOptions {
   includeHidden: boolean;
   followSymlinks: boolean;
   parallel: boolean;
+  excludePatterns?: string[];
+  abortOnError?: boolean;
+  sort?: SortOrder;
+  filterCategories?: FileCategory[];
+}
+
+export type FileCategory =
+  | 'database'
+  | 'structureddata'
+  | 'document'
+  | 'email'
+  | 'text'
+  | 'media'
+  | 'archive'
+  | 'binary'
+  | 'unknown';
+
+export interface SortOrder {
+  field: 'name' | 'size' | 'modified' | 'created' | 'extension' | 'type';
+  direction?: 'ascending' | 'descending';
 }
 
 // Database types
@@ -127,6 +189,13 @@ export interface IndexStats {
   totalSize: number;
   indexedFiles: number;
   durationMs: number;
+  skippedByFilter?: number;
+  unavailableFiles?: string[];
+}
+
+export interface IndexedBatch {
+  paths: string[];
+  priority: boolean;
 }
 
 export interface DatabaseStats {